@@ -0,0 +1,110 @@
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy_ecs::prelude::*;
+
+use renet2::{RenetClient, RenetServer};
+
+use crate::prelude::RenetReceive;
+
+/// Adds both [`RenetClientDiagnosticsPlugin`] and [`RenetServerDiagnosticsPlugin`].
+///
+/// Each set of diagnostics is only measured while its resource exists, so this can be added to apps that are
+/// clients, servers, or switch between the two.
+pub struct RenetDiagnosticsPlugin;
+
+impl Plugin for RenetDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((RenetClientDiagnosticsPlugin, RenetServerDiagnosticsPlugin));
+    }
+}
+
+/// Adds the network stats of the [`RenetClient`] resource to bevy's `DiagnosticsStore`.
+///
+/// Registers the following diagnostics, which are measured in `PreUpdate` after [`RenetReceive`]
+/// while a [`RenetClient`] exists:
+/// - [`Self::RTT`]: round-trip time in milliseconds.
+/// - [`Self::PACKET_LOSS`]: packet loss in percent.
+/// - [`Self::BYTES_SENT_PER_SEC`]: bytes sent per second.
+/// - [`Self::BYTES_RECEIVED_PER_SEC`]: bytes received per second.
+pub struct RenetClientDiagnosticsPlugin;
+
+impl Plugin for RenetClientDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::RTT).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::PACKET_LOSS).with_suffix("%"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_SENT_PER_SEC).with_suffix("B/s"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_RECEIVED_PER_SEC).with_suffix("B/s"))
+            .add_systems(
+                PreUpdate,
+                Self::diagnostic_system.after(RenetReceive).run_if(resource_exists::<RenetClient>),
+            );
+    }
+}
+
+impl RenetClientDiagnosticsPlugin {
+    /// Round-trip time in milliseconds.
+    pub const RTT: DiagnosticPath = DiagnosticPath::const_new("renet/client/rtt");
+    /// Packet loss in percent.
+    pub const PACKET_LOSS: DiagnosticPath = DiagnosticPath::const_new("renet/client/packet_loss");
+    /// Bytes sent per second.
+    pub const BYTES_SENT_PER_SEC: DiagnosticPath = DiagnosticPath::const_new("renet/client/bytes_sent_per_sec");
+    /// Bytes received per second.
+    pub const BYTES_RECEIVED_PER_SEC: DiagnosticPath = DiagnosticPath::const_new("renet/client/bytes_received_per_sec");
+
+    pub fn diagnostic_system(mut diagnostics: Diagnostics, client: Res<RenetClient>) {
+        let info = client.network_info();
+        diagnostics.add_measurement(&Self::RTT, || info.rtt * 1000.0);
+        diagnostics.add_measurement(&Self::PACKET_LOSS, || info.packet_loss * 100.0);
+        diagnostics.add_measurement(&Self::BYTES_SENT_PER_SEC, || info.bytes_sent_per_second);
+        diagnostics.add_measurement(&Self::BYTES_RECEIVED_PER_SEC, || info.bytes_received_per_second);
+    }
+}
+
+/// Adds the network stats of the [`RenetServer`] resource to bevy's `DiagnosticsStore`.
+///
+/// Registers the following diagnostics, which are measured in `PreUpdate` after [`RenetReceive`]
+/// while a [`RenetServer`] exists:
+/// - [`Self::CONNECTED_CLIENTS`]: number of connected clients.
+/// - [`Self::RTT`]: average round-trip time of the connected clients in milliseconds.
+/// - [`Self::PACKET_LOSS`]: average packet loss of the connected clients in percent.
+/// - [`Self::BYTES_SENT_PER_SEC`]: bytes sent per second to all clients.
+/// - [`Self::BYTES_RECEIVED_PER_SEC`]: bytes received per second from all clients.
+///
+/// See [`RenetServer::aggregate_network_info`].
+pub struct RenetServerDiagnosticsPlugin;
+
+impl Plugin for RenetServerDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::CONNECTED_CLIENTS))
+            .register_diagnostic(Diagnostic::new(Self::RTT).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::PACKET_LOSS).with_suffix("%"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_SENT_PER_SEC).with_suffix("B/s"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_RECEIVED_PER_SEC).with_suffix("B/s"))
+            .add_systems(
+                PreUpdate,
+                Self::diagnostic_system.after(RenetReceive).run_if(resource_exists::<RenetServer>),
+            );
+    }
+}
+
+impl RenetServerDiagnosticsPlugin {
+    /// Number of connected clients.
+    pub const CONNECTED_CLIENTS: DiagnosticPath = DiagnosticPath::const_new("renet/server/connected_clients");
+    /// Average round-trip time of the connected clients in milliseconds.
+    pub const RTT: DiagnosticPath = DiagnosticPath::const_new("renet/server/rtt");
+    /// Average packet loss of the connected clients in percent.
+    pub const PACKET_LOSS: DiagnosticPath = DiagnosticPath::const_new("renet/server/packet_loss");
+    /// Bytes sent per second to all clients.
+    pub const BYTES_SENT_PER_SEC: DiagnosticPath = DiagnosticPath::const_new("renet/server/bytes_sent_per_sec");
+    /// Bytes received per second from all clients.
+    pub const BYTES_RECEIVED_PER_SEC: DiagnosticPath = DiagnosticPath::const_new("renet/server/bytes_received_per_sec");
+
+    pub fn diagnostic_system(mut diagnostics: Diagnostics, server: Res<RenetServer>) {
+        let info = server.aggregate_network_info();
+        diagnostics.add_measurement(&Self::CONNECTED_CLIENTS, || info.client_count as f64);
+        diagnostics.add_measurement(&Self::RTT, || info.avg_rtt * 1000.0);
+        diagnostics.add_measurement(&Self::PACKET_LOSS, || info.avg_packet_loss * 100.0);
+        diagnostics.add_measurement(&Self::BYTES_SENT_PER_SEC, || info.total_sent_bps);
+        diagnostics.add_measurement(&Self::BYTES_RECEIVED_PER_SEC, || info.total_received_bps);
+    }
+}
@@ -6,10 +6,12 @@ pub mod netcode;
 #[cfg(feature = "steam")]
 pub mod steam;
 
+mod diagnostics;
 mod renet2;
 mod run_conditions;
 
 pub mod prelude {
+    pub use crate::diagnostics::*;
     pub use crate::renet2::*;
     pub use crate::run_conditions::*;
 }
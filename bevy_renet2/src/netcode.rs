@@ -6,7 +6,14 @@ use bevy_app::{prelude::*, AppExit};
 use bevy_ecs::prelude::*;
 use bevy_time::prelude::*;
 
-use crate::prelude::{client_should_update, RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin};
+use crate::prelude::{client_should_update, RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin, ServerInstanceId};
+
+/// A [`NetcodeTransportError`] emitted by the transport of a server instance.
+#[derive(Message, Debug)]
+pub struct NetcodeInstanceTransportError {
+    pub instance: ServerInstanceId,
+    pub error: NetcodeTransportError,
+}
 
 pub struct NetcodeServerPlugin;
 
@@ -15,6 +22,7 @@ pub struct NetcodeClientPlugin;
 impl Plugin for NetcodeServerPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<NetcodeTransportError>();
+        app.add_message::<NetcodeInstanceTransportError>();
 
         app.add_systems(
             PreUpdate,
@@ -38,6 +46,16 @@ impl Plugin for NetcodeServerPlugin {
                 .run_if(resource_exists::<NetcodeServerTransport>)
                 .run_if(resource_exists::<RenetServer>),
         );
+
+        app.add_systems(
+            PreUpdate,
+            Self::update_instances_system
+                .in_set(RenetReceive)
+                .after(RenetServerPlugin::update_instances_system)
+                .before(RenetServerPlugin::emit_instance_events_system),
+        )
+        .add_systems(PostUpdate, Self::send_instance_packets.in_set(RenetSend))
+        .add_systems(Last, Self::disconnect_instances_on_exit);
     }
 }
 
@@ -69,6 +87,41 @@ impl NetcodeServerPlugin {
             transport.disconnect_all(&mut server);
         }
     }
+
+    pub fn update_instances_system(
+        mut instances: Query<(&ServerInstanceId, &mut NetcodeServerTransport, &mut RenetServer)>,
+        time: Res<Time<Real>>,
+        mut transport_errors: MessageWriter<NetcodeInstanceTransportError>,
+    ) {
+        for (instance, mut transport, mut server) in instances.iter_mut() {
+            if let Err(e) = transport.update(time.delta(), &mut server) {
+                for error in e {
+                    transport_errors.write(NetcodeInstanceTransportError {
+                        instance: *instance,
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn send_instance_packets(mut instances: Query<(&mut NetcodeServerTransport, &mut RenetServer), With<ServerInstanceId>>) {
+        for (mut transport, mut server) in instances.iter_mut() {
+            transport.send_packets(&mut server);
+        }
+    }
+
+    pub fn disconnect_instances_on_exit(
+        exit: MessageReader<AppExit>,
+        mut instances: Query<(&mut NetcodeServerTransport, &mut RenetServer), With<ServerInstanceId>>,
+    ) {
+        if exit.is_empty() {
+            return;
+        }
+        for (mut transport, mut server) in instances.iter_mut() {
+            transport.disconnect_all(&mut server);
+        }
+    }
 }
 
 impl Plugin for NetcodeClientPlugin {
@@ -24,6 +24,21 @@ pub struct RenetReceive;
 #[derive(Debug, SystemSet, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RenetSend;
 
+/// Identifies a server instance when hosting multiple isolated servers in one app.
+///
+/// Each instance is an entity with this component, a [`RenetServer`], and a transport component
+/// (e.g. `NetcodeServerTransport`). The server plugins update every instance entity alongside the
+/// [`RenetServer`] resource, which remains the simplest way to run a single server.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ServerInstanceId(pub u64);
+
+/// A [`ServerEvent`] emitted by a server instance.
+#[derive(Message, Debug)]
+pub struct ServerInstanceEvent {
+    pub instance: ServerInstanceId,
+    pub event: ServerEvent,
+}
+
 pub struct RenetServerPlugin;
 
 pub struct RenetClientPlugin;
@@ -31,6 +46,7 @@ pub struct RenetClientPlugin;
 impl Plugin for RenetServerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Messages<ServerEvent>>();
+        app.add_message::<ServerInstanceEvent>();
         app.add_systems(PreUpdate, Self::update_system.run_if(resource_exists::<RenetServer>));
         app.add_systems(
             PreUpdate,
@@ -39,6 +55,13 @@ impl Plugin for RenetServerPlugin {
                 .run_if(resource_exists::<RenetServer>)
                 .after(Self::update_system),
         );
+        app.add_systems(PreUpdate, Self::update_instances_system);
+        app.add_systems(
+            PreUpdate,
+            Self::emit_instance_events_system
+                .in_set(RenetReceive)
+                .after(Self::update_instances_system),
+        );
     }
 }
 
@@ -52,6 +75,26 @@ impl RenetServerPlugin {
             server_events.write(event);
         }
     }
+
+    pub fn update_instances_system(mut servers: Query<&mut RenetServer, With<ServerInstanceId>>, time: Res<Time<Real>>) {
+        for mut server in servers.iter_mut() {
+            server.update(time.delta());
+        }
+    }
+
+    pub fn emit_instance_events_system(
+        mut servers: Query<(&ServerInstanceId, &mut RenetServer)>,
+        mut server_events: MessageWriter<ServerInstanceEvent>,
+    ) {
+        for (instance, mut server) in servers.iter_mut() {
+            while let Some(event) = server.get_event() {
+                server_events.write(ServerInstanceEvent {
+                    instance: *instance,
+                    event,
+                });
+            }
+        }
+    }
 }
 
 impl Plugin for RenetClientPlugin {
@@ -11,6 +11,10 @@ pub struct SteamServerPlugin;
 
 pub struct SteamClientPlugin;
 
+/// Error from [`SteamClientTransport::send_packets`], written by [`SteamClientPlugin::send_packets`].
+///
+/// At most one error is written per frame. Errors that persist across frames (e.g. [`SteamError::NoConnection`]
+/// after the client disconnects) are written again every frame rather than being dropped as duplicates.
 #[derive(Debug, Message)]
 pub struct SteamTransportError(pub SteamError);
 
@@ -1,11 +1,17 @@
+use std::io::ErrorKind;
 use std::time::SystemTime;
 
+use bevy::diagnostic::{DiagnosticPath, DiagnosticsPlugin, DiagnosticsStore};
 use bevy::prelude::*;
 use bevy_renet2::netcode::{
-    in_memory_server_addr, new_memory_sockets, ClientAuthentication, MemorySocketClient, NetcodeClientPlugin, NetcodeClientTransport,
-    NetcodeServerPlugin, NetcodeServerTransport, ServerAuthentication, ServerSetupConfig,
+    in_memory_server_addr, new_memory_sockets, CallbackSocket, ClientAuthentication, MemorySocketClient, NetcodeClientPlugin,
+    NetcodeClientTransport, NetcodeInstanceTransportError, NetcodeServerPlugin, NetcodeServerTransport, ServerAuthentication,
+    ServerSetupConfig,
+};
+use bevy_renet2::prelude::{
+    ConnectionConfig, DefaultChannel, RenetClient, RenetClientDiagnosticsPlugin, RenetClientPlugin, RenetDiagnosticsPlugin, RenetServer,
+    RenetServerDiagnosticsPlugin, RenetServerPlugin, ServerEvent, ServerInstanceEvent, ServerInstanceId,
 };
-use bevy_renet2::prelude::{ConnectionConfig, DefaultChannel, RenetClient, RenetClientPlugin, RenetServer, RenetServerPlugin};
 
 #[derive(Debug, Default, Resource, PartialEq, Eq, Deref, DerefMut)]
 pub struct ServerReceived(Vec<(u64, Vec<u8>)>);
@@ -13,6 +19,9 @@ pub struct ServerReceived(Vec<(u64, Vec<u8>)>);
 #[derive(Debug, Default, Resource, PartialEq, Eq, Deref, DerefMut)]
 pub struct ClientReceived(Vec<Vec<u8>>);
 
+#[derive(Debug, Default, Resource, PartialEq, Eq, Deref, DerefMut)]
+pub struct InstanceConnections(Vec<(u64, u64)>);
+
 const PROTOCOL_ID: u64 = 0;
 
 fn create_server_transport(num_clients: usize) -> (NetcodeServerTransport, Vec<MemorySocketClient>) {
@@ -24,7 +33,7 @@ fn create_server_transport(num_clients: usize) -> (NetcodeServerTransport, Vec<M
         protocol_id: PROTOCOL_ID,
         authentication: ServerAuthentication::Unsecure,
     };
-    let (server_socket, client_sockets) = new_memory_sockets((1..=num_clients as u16).collect(), false, false);
+    let (server_socket, client_sockets) = new_memory_sockets((1..=num_clients as u16).collect(), false, false, None);
 
     (NetcodeServerTransport::new(server_config, server_socket).unwrap(), client_sockets)
 }
@@ -320,3 +329,137 @@ fn no_transport() {
     server.update();
     client.update();
 }
+
+#[test]
+fn multiple_server_instances() {
+    let mut server = App::new();
+    server
+        .add_plugins((MinimalPlugins, RenetServerPlugin, NetcodeServerPlugin))
+        .init_resource::<InstanceConnections>()
+        .add_systems(
+            Update,
+            |mut events: MessageReader<ServerInstanceEvent>, mut connections: ResMut<InstanceConnections>| {
+                for ServerInstanceEvent { instance, event } in events.read() {
+                    if let ServerEvent::ClientConnected { client_id } = event {
+                        connections.push((instance.0, *client_id));
+                    }
+                }
+            },
+        );
+
+    let mut clients = Vec::new();
+    for instance in 0..2 {
+        let (transport, mut client_sockets) = create_server_transport(1);
+        server
+            .world_mut()
+            .spawn((ServerInstanceId(instance), RenetServer::new(ConnectionConfig::test()), transport));
+        clients.push(create_client_app(client_sockets.pop().unwrap()));
+    }
+
+    for client in clients.iter_mut() {
+        while !client.world().resource::<RenetClient>().is_connected() {
+            server.update();
+            client.update();
+        }
+    }
+    assert_eq!(server.world().resource::<InstanceConnections>().0, [(0, 1), (1, 1)]);
+
+    // Each instance only sends to its own clients.
+    server.add_systems(Update, |mut servers: Query<(&ServerInstanceId, &mut RenetServer)>| {
+        for (instance, mut server) in servers.iter_mut() {
+            server.broadcast_message(DefaultChannel::ReliableOrdered, vec![instance.0 as u8]);
+        }
+    });
+    server.update();
+    for client in clients.iter_mut() {
+        client.update();
+    }
+
+    assert_eq!(client_received(&clients[0]), [[0]]);
+    assert_eq!(client_received(&clients[1]), [[1]]);
+}
+
+#[test]
+fn instance_transport_errors() {
+    #[derive(Debug, Default, Resource, Deref, DerefMut)]
+    struct InstanceErrors(Vec<u64>);
+
+    let mut server = App::new();
+    server
+        .add_plugins((MinimalPlugins, RenetServerPlugin, NetcodeServerPlugin))
+        .init_resource::<InstanceErrors>()
+        .add_systems(
+            Update,
+            |mut errors: MessageReader<NetcodeInstanceTransportError>, mut instances: ResMut<InstanceErrors>| {
+                instances.extend(errors.read().map(|error| error.instance.0));
+            },
+        );
+
+    for instance in 0..2 {
+        // Only the second instance's socket fails.
+        let mut failed = instance == 0;
+        let socket = CallbackSocket::new(
+            in_memory_server_addr(),
+            |_, _| Ok(()),
+            move |_| match std::mem::replace(&mut failed, true) {
+                true => Err(ErrorKind::WouldBlock.into()),
+                false => Err(ErrorKind::Other.into()),
+            },
+        );
+        let server_config = ServerSetupConfig {
+            socket_addresses: vec![vec![in_memory_server_addr()]],
+            current_time: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap(),
+            max_clients: 1,
+            protocol_id: PROTOCOL_ID,
+            authentication: ServerAuthentication::Unsecure,
+        };
+        let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
+        server
+            .world_mut()
+            .spawn((ServerInstanceId(instance), RenetServer::new(ConnectionConfig::test()), transport));
+    }
+
+    server.update();
+    assert_eq!(server.world().resource::<InstanceErrors>().0, [1]);
+}
+
+#[test]
+fn diagnostics() {
+    fn measurement(app: &App, path: &DiagnosticPath) -> Option<f64> {
+        app.world().resource::<DiagnosticsStore>().get_measurement(path).map(|m| m.value)
+    }
+
+    let (mut server, mut clients) = create_and_connect_apps(2);
+    server.add_plugins((DiagnosticsPlugin, RenetServerDiagnosticsPlugin));
+    let client = &mut clients[0];
+    client.add_plugins((DiagnosticsPlugin, RenetClientDiagnosticsPlugin));
+
+    server.update();
+    client.update();
+
+    assert_eq!(measurement(&server, &RenetServerDiagnosticsPlugin::CONNECTED_CLIENTS), Some(2.0));
+    assert!(measurement(&server, &RenetServerDiagnosticsPlugin::BYTES_RECEIVED_PER_SEC).is_some());
+    assert!(measurement(client, &RenetClientDiagnosticsPlugin::RTT).is_some());
+    assert!(measurement(client, &RenetClientDiagnosticsPlugin::PACKET_LOSS).is_some());
+}
+
+#[test]
+fn combined_diagnostics() {
+    fn measurement(app: &App, path: &DiagnosticPath) -> Option<f64> {
+        app.world().resource::<DiagnosticsStore>().get_measurement(path).map(|m| m.value)
+    }
+
+    let (mut server, mut clients) = create_and_connect_apps(1);
+    server.add_plugins((DiagnosticsPlugin, RenetDiagnosticsPlugin));
+    let client = &mut clients[0];
+    client.add_plugins((DiagnosticsPlugin, RenetDiagnosticsPlugin));
+
+    server.update();
+    client.update();
+
+    // Only the diagnostics of existing resources are measured.
+    assert_eq!(measurement(&server, &RenetServerDiagnosticsPlugin::CONNECTED_CLIENTS), Some(1.0));
+    assert!(measurement(&server, &RenetClientDiagnosticsPlugin::RTT).is_none());
+    assert!(measurement(client, &RenetClientDiagnosticsPlugin::RTT).is_some());
+    assert!(measurement(client, &RenetServerDiagnosticsPlugin::CONNECTED_CLIENTS).is_none());
+}
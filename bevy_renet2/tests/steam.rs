@@ -0,0 +1,25 @@
+#![cfg(feature = "steam")]
+
+use bevy::prelude::*;
+use bevy_renet2::prelude::{ConnectionConfig, RenetClient, RenetClientPlugin, RenetServer, RenetServerPlugin};
+use bevy_renet2::steam::{SteamClientPlugin, SteamServerPlugin, SteamTransportError};
+
+#[test]
+fn headless_plugins() {
+    let mut client = App::new();
+    client
+        .add_plugins((MinimalPlugins, RenetClientPlugin, SteamClientPlugin))
+        .insert_resource(RenetClient::new(ConnectionConfig::test(), false));
+
+    let mut server = App::new();
+    server
+        .add_plugins((MinimalPlugins, RenetServerPlugin, SteamServerPlugin))
+        .insert_resource(RenetServer::new(ConnectionConfig::test()));
+
+    // The transport systems are skipped without a steam transport.
+    for _ in 0..3 {
+        client.update();
+        server.update();
+    }
+    assert!(client.world().resource::<Messages<SteamTransportError>>().is_empty());
+}
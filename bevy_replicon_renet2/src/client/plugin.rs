@@ -64,6 +64,16 @@ fn receive_packets(
     mut stats: ResMut<ClientStats>,
 ) {
     for channel_id in 0..channels.server_channels().len() as u8 {
+        if !client.has_receive_channel(channel_id) {
+            error!(
+                "disconnecting: replicon has {} server channels, but the renet2 client has {} receive channels \
+                (missing channel {channel_id}); the client's `ConnectionConfig` must be built from `RepliconChannels`",
+                channels.server_channels().len(),
+                client.receive_channel_count(),
+            );
+            client.disconnect();
+            return;
+        }
         while let Some(message) = client.receive_message(channel_id) {
             trace!("forwarding {} received bytes over channel {channel_id}", message.len());
             messages.insert_received(channel_id, message);
@@ -94,6 +94,9 @@ fn create_configs(channels: &[Channel]) -> Vec<ChannelConfig> {
         let config = ChannelConfig {
             channel_id: index as u8,
             max_memory_usage_bytes: 5 * 1024 * 1024,
+            initial_burst_limit: None,
+            dedup_window: None,
+            max_bytes_per_sec: None,
             send_type,
         };
 
@@ -75,6 +75,18 @@ Channels need to be obtained only **after** registering all replication componen
 
 </div>
 
+## Reliable transports
+
+Some transports are already reliable and ordered (e.g. WebSockets and the in-memory transport). On these
+transports renet2 automatically downgrades Replicon's reliable channels to unreliable channels with
+`ordered_reliable_substrate` enabled, so messages are not acked or resent twice. No extra configuration is
+needed, and [`RenetClient::has_reliable_socket`](renet2::RenetClient::has_reliable_socket) reports whether
+this applies to a client.
+
+Both ends must agree on whether a connection is reliable, otherwise the channel formats won't match. The client
+uses its socket's `is_reliable()`, and the server uses `is_reliable()` of the socket the client connected
+through, so custom socket implementations must report the same value on both sides.
+
 ## Replicon conditions
 
 The crate updates the running state of [`RepliconServer`](bevy_replicon::prelude::RepliconServer) and connection state of [`RepliconClient`](bevy_replicon::prelude::RepliconClient)
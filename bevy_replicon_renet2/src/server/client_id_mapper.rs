@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy_renet2::prelude::ClientId;
+use bevy_replicon::shared::backend::connected_client::NetworkId;
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Maps renet2 client ids to the [`NetworkId`]s of replicon's connected client entities.
+///
+/// Use it to give clients a stable identity (e.g. an account id) instead of their transient renet2 client ids.
+///
+/// The mapping must be unique: two connected clients must never map to the same [`NetworkId`].
+pub trait ClientIdMapper: Send + Sync + 'static {
+    /// Returns the network id for a newly connected client.
+    fn network_id(&self, client_id: ClientId) -> NetworkId;
+}
+
+/// Maps renet2 client ids 1:1 to network ids.
+///
+/// This is the default behavior when [`RenetClientIdMapper`] is not present.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IdentityClientIdMapper;
+
+impl ClientIdMapper for IdentityClientIdMapper {
+    fn network_id(&self, client_id: ClientId) -> NetworkId {
+        NetworkId::new(client_id)
+    }
+}
+
+/// Resource that customizes how [`RepliconRenetServerPlugin`](crate::RepliconRenetServerPlugin) assigns
+/// [`NetworkId`]s to connected clients.
+///
+/// If this resource doesn't exist then client ids are mapped with [`IdentityClientIdMapper`].
+#[derive(Resource)]
+pub struct RenetClientIdMapper(pub Box<dyn ClientIdMapper>);
+
+impl RenetClientIdMapper {
+    /// Makes a new mapper resource.
+    pub fn new(mapper: impl ClientIdMapper) -> Self {
+        Self(Box::new(mapper))
+    }
+}
+
+impl Default for RenetClientIdMapper {
+    fn default() -> Self {
+        Self::new(IdentityClientIdMapper)
+    }
+}
+
+/// The renet2 client id of a connected client entity.
+///
+/// Inserted alongside [`NetworkId`] when a client connects. If a [`RenetClientIdMapper`] is used then this may
+/// differ from the entity's [`NetworkId`].
+#[derive(Component, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RenetClientId(pub ClientId);
+
+impl RenetClientId {
+    /// Returns the renet2 client id.
+    pub fn get(&self) -> ClientId {
+        self.0
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
@@ -1,3 +1,5 @@
+mod client_id_mapper;
 mod plugin;
 
+pub use client_id_mapper::*;
 pub use plugin::*;
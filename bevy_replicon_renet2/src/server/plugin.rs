@@ -1,9 +1,8 @@
 use bevy::prelude::*;
 use bevy_renet2::prelude::{RenetReceive, RenetSend, RenetServer, RenetServerPlugin, ServerEvent};
-use bevy_replicon::{
-    prelude::*,
-    shared::backend::connected_client::{NetworkId, NetworkIdMap},
-};
+use bevy_replicon::prelude::*;
+
+use crate::{ClientIdMapper, IdentityClientIdMapper, RenetClientId, RenetClientIdMapper};
 
 /// Adds Renet as the server messaging backend.
 ///
@@ -52,28 +51,37 @@ fn set_stopped(mut state: ResMut<NextState<ServerState>>) {
     state.set(ServerState::Stopped);
 }
 
-fn process_server_events(mut commands: Commands, mut server_events: MessageReader<ServerEvent>, network_map: Res<NetworkIdMap>) {
+fn process_server_events(
+    mut commands: Commands,
+    mut server_events: MessageReader<ServerEvent>,
+    server: Res<RenetServer>,
+    mapper: Option<Res<RenetClientIdMapper>>,
+    clients: Query<(Entity, &RenetClientId)>,
+) {
     for event in server_events.read() {
         match event {
             ServerEvent::ClientConnected { client_id } => {
-                let network_id = NetworkId::new(*client_id);
+                let network_id = match &mapper {
+                    Some(mapper) => mapper.0.network_id(*client_id),
+                    None => IdentityClientIdMapper.network_id(*client_id),
+                };
                 let client_entity = commands
                     .spawn((
                         ConnectedClient {
-                            // From https://github.com/lucaspoffo/renet/blob/master/renet/src/packet.rs#L7
-                            max_size: 1200,
+                            // Larger messages are sliced by renet2.
+                            max_size: server.slice_size(),
                         },
                         network_id,
+                        RenetClientId(*client_id),
                     ))
                     .id();
-                debug!("spawning client `{client_entity}` with `{network_id:?}`");
+                debug!("spawning client `{client_entity}` with `{network_id:?}` (renet2 client id {client_id})");
             }
             ServerEvent::ClientDisconnected { client_id, reason } => {
-                let network_id = NetworkId::new(*client_id);
-                if let Some(&client_entity) = network_map.get(&network_id) {
+                if let Some((client_entity, _)) = clients.iter().find(|(_, id)| id.get() == *client_id) {
                     // Entity could have been despawned by user.
                     commands.entity(client_entity).despawn();
-                    debug!("despawning client `{client_entity}` with `{network_id:?}`: {reason}");
+                    debug!("despawning client `{client_entity}` with renet2 client id {client_id}: {reason}");
                 }
             }
         }
@@ -81,21 +89,43 @@ fn process_server_events(mut commands: Commands, mut server_events: MessageReade
 }
 
 fn receive_packets(
+    mut channel_mismatch_logged: Local<bool>,
     channels: Res<RepliconChannels>,
     mut server: ResMut<RenetServer>,
     mut messages: ResMut<ServerMessages>,
-    mut clients: Query<(Entity, &NetworkId, &mut ConnectedClientStats)>,
+    mut clients: Query<(Entity, &RenetClientId, &mut ConnectedClientStats)>,
 ) {
-    for (client_entity, network_id, mut stats) in &mut clients {
-        for channel_id in 0..channels.client_channels().len() as u8 {
-            while let Some(message) = server.receive_message(network_id.get(), channel_id) {
+    // Messages on channels that renet2 doesn't know about can't be received.
+    let channel_count = channels.client_channels().len() as u8;
+    if let Some(channel_id) = (0..channel_count).find(|channel_id| !server.has_receive_channel(*channel_id)) {
+        if !*channel_mismatch_logged {
+            error!(
+                "replicon has {} client channels, but the renet2 server has {} receive channels (missing channel \
+                {channel_id}); the server's `ConnectionConfig` must be built from `RepliconChannels`",
+                channel_count,
+                server.receive_channel_count(),
+            );
+            *channel_mismatch_logged = true;
+        }
+    }
+
+    for (client_entity, client_id, mut stats) in &mut clients {
+        // Renet events reading runs in parallel, so the client might have been disconnected.
+        if !server.is_connected(client_id.get()) {
+            continue;
+        }
+
+        for channel_id in 0..channel_count {
+            if !server.has_receive_channel(channel_id) {
+                continue;
+            }
+            while let Some(message) = server.receive_message(client_id.get(), channel_id) {
                 trace!("forwarding {} received bytes over channel {channel_id}", message.len());
                 messages.insert_received(client_entity, channel_id, message);
             }
         }
 
-        // Renet events reading runs in parallel, so the client might have been disconnected.
-        if let Ok(info) = server.network_info(network_id.get()) {
+        if let Ok(info) = server.network_info(client_id.get()) {
             stats.rtt = info.rtt;
             stats.packet_loss = info.packet_loss;
             stats.sent_bps = info.bytes_sent_per_second;
@@ -104,13 +134,13 @@ fn receive_packets(
     }
 }
 
-fn send_packets(mut server: ResMut<RenetServer>, mut messages: ResMut<ServerMessages>, clients: Query<&NetworkId>) {
+fn send_packets(mut server: ResMut<RenetServer>, mut messages: ResMut<ServerMessages>, clients: Query<&RenetClientId>) {
     for (client_entity, channel_id, message) in messages.drain_sent() {
         trace!("forwarding {} sent bytes over channel {channel_id}", message.len());
-        let network_id = clients
+        let client_id = clients
             .get(client_entity)
             .expect("messages should be sent only to connected clients");
-        server.send_message(network_id.get(), channel_id as u8, message)
+        server.send_message(client_id.get(), channel_id as u8, message)
     }
 }
 
@@ -121,11 +151,11 @@ fn disconnect_by_request(mut commands: Commands, mut disconnects: MessageReader<
     }
 }
 
-fn disconnect_client(remove: On<Remove, ConnectedClient>, server: Option<ResMut<RenetServer>>, clients: Query<&NetworkId>) {
+fn disconnect_client(remove: On<Remove, ConnectedClient>, server: Option<ResMut<RenetServer>>, clients: Query<&RenetClientId>) {
     if let Some(mut server) = server {
         debug!("disconnecting despawned client `{}`", remove.entity);
 
-        let network_id = clients.get(remove.entity).expect("inserted on connection");
-        server.disconnect(network_id.get());
+        let client_id = clients.get(remove.entity).expect("inserted on connection");
+        server.disconnect(client_id.get());
     }
 }
@@ -256,6 +256,39 @@ fn client_message() {
     assert_eq!(messages.len(), 1);
 }
 
+#[test]
+fn client_stats() {
+    let mut server_app = App::new();
+    let mut client_app = App::new();
+    for app in [&mut server_app, &mut client_app] {
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            RepliconPlugins.set(ServerPlugin::new(PostUpdate)),
+            RepliconRenetPlugins,
+        ))
+        .add_client_message::<Test>(Channel::Ordered)
+        .add_server_message::<Test>(Channel::Ordered)
+        .finish();
+    }
+
+    setup(&mut server_app, &mut client_app);
+
+    for _ in 0..10 {
+        client_app.world_mut().write_message(Test);
+        server_app.world_mut().write_message(ToClients {
+            targets: SendTargets::All,
+            message: Test,
+        });
+        client_app.update();
+        server_app.update();
+    }
+
+    let stats = client_app.world().resource::<ClientStats>();
+    assert!(stats.sent_bps > 0.0);
+    assert!(stats.received_bps > 0.0);
+}
+
 fn setup(server_app: &mut App, client_app: &mut App) {
     const CLIENT_ID: u64 = 1;
     let port = setup_server(server_app, 1);
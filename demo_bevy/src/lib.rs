@@ -67,6 +67,9 @@ impl ClientChannel {
             ChannelConfig {
                 channel_id: Self::Input.into(),
                 max_memory_usage_bytes: 5 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::ReliableOrdered {
                     resend_time: Duration::ZERO,
                 },
@@ -74,6 +77,9 @@ impl ClientChannel {
             ChannelConfig {
                 channel_id: Self::Command.into(),
                 max_memory_usage_bytes: 5 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::ReliableOrdered {
                     resend_time: Duration::ZERO,
                 },
@@ -97,6 +103,9 @@ impl ServerChannel {
             ChannelConfig {
                 channel_id: Self::NetworkedEntities.into(),
                 max_memory_usage_bytes: 10 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::Unreliable {
                     ordered_reliable_substrate: false,
                 },
@@ -104,6 +113,9 @@ impl ServerChannel {
             ChannelConfig {
                 channel_id: Self::ServerMessages.into(),
                 max_memory_usage_bytes: 10 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::ReliableOrdered {
                     resend_time: Duration::from_millis(200),
                 },
@@ -115,8 +127,7 @@ impl ServerChannel {
 pub fn connection_config() -> ConnectionConfig {
     ConnectionConfig {
         available_bytes_per_tick: 1024 * 1024,
-        client_channels_config: ClientChannel::channels_config(),
-        server_channels_config: ServerChannel::channels_config(),
+        ..ConnectionConfig::from_channels(ServerChannel::channels_config(), ClientChannel::channels_config())
     }
 }
 
@@ -62,9 +62,7 @@ impl ChatApplication {
                 tracing::warn!("webtransport with cert hashes is not supported on this platform, falling back \
                     to websockets");
                 tracing::info!("setting up websocket client (server = {:?})", ws_server_url.as_str());
-                let socket_config = WebSocketClientConfig {
-                    server_url: ws_server_url,
-                };
+                let socket_config = WebSocketClientConfig::new(ws_server_url);
 
                 let socket = WebSocketClient::new(socket_config).unwrap();
                 let client = RenetClient::new(connection_config, socket.is_reliable());
@@ -0,0 +1,95 @@
+//! Compares allocations made while receiving messages with [`StandardBufferPool`] and [`ArenaBufferPool`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use renet2::{ArenaBufferPool, BufferPool, ConnectionConfig, DefaultChannel, RenetClient, StandardBufferPool};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const MESSAGES_PER_PACKET: usize = 20;
+
+/// Makes packets containing small messages on the unreliable channel.
+fn make_packets() -> Vec<Vec<u8>> {
+    let mut sender = RenetClient::new(ConnectionConfig::test(), false);
+    sender.set_connected();
+    for _ in 0..MESSAGES_PER_PACKET {
+        sender.send_message(DefaultChannel::Unreliable, vec![7u8; 32]);
+    }
+    sender.get_packets_to_send()
+}
+
+fn make_receiver(pool: Arc<dyn BufferPool>) -> RenetClient {
+    let mut config = ConnectionConfig::test();
+    config.buffer_pool = pool;
+    let mut receiver = RenetClient::new(config, false);
+    receiver.set_connected();
+    receiver
+}
+
+/// Receives and drops all messages in `packets`.
+fn receive(receiver: &mut RenetClient, packets: &[Vec<u8>]) {
+    for packet in packets {
+        receiver.process_packet(packet);
+    }
+    while let Some(message) = receiver.receive_message(DefaultChannel::Unreliable) {
+        std::hint::black_box(message);
+    }
+    receiver.update(Duration::from_millis(16));
+}
+
+/// Counts allocations made while receiving `packets` once the receiver has warmed up.
+fn count_allocations(receiver: &mut RenetClient, packets: &[Vec<u8>]) -> usize {
+    receive(receiver, packets);
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    receive(receiver, packets);
+    ALLOCATIONS.load(Ordering::Relaxed) - start
+}
+
+fn buffer_pool(c: &mut Criterion) {
+    let packets = make_packets();
+    let pools: [(&str, Arc<dyn BufferPool>); 2] = [
+        ("standard", Arc::new(StandardBufferPool)),
+        ("arena", Arc::new(ArenaBufferPool::default())),
+    ];
+
+    // The arena pool reuses message buffers once warmed up, so it must allocate less than the standard pool.
+    let [standard, arena] = pools
+        .each_ref()
+        .map(|(_, pool)| count_allocations(&mut make_receiver(pool.clone()), &packets));
+    assert!(
+        arena < standard,
+        "arena pool made {arena} allocations and standard pool made {standard} to receive {MESSAGES_PER_PACKET} messages"
+    );
+
+    let mut group = c.benchmark_group("receive_messages");
+    for (name, pool) in pools {
+        let mut receiver = make_receiver(pool);
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(|| packets.clone(), |packets| receive(&mut receiver, packets), BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, buffer_pool);
+criterion_main!(benches);
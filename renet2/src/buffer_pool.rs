@@ -0,0 +1,103 @@
+use bytes::{Bytes, BytesMut};
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+/// Source of the buffers that received messages are stored in.
+///
+/// Set on [`ConnectionConfig::buffer_pool`](crate::ConnectionConfig::buffer_pool). The default
+/// [`StandardBufferPool`] allocates a fresh buffer for every message, while [`ArenaBufferPool`] reuses memory
+/// once messages are dropped. Custom pools can be used to further reduce allocations in servers under load.
+pub trait BufferPool: Debug + Send + Sync + 'static {
+    /// Gets an empty buffer with space for at least `capacity` bytes.
+    fn take(&self, capacity: usize) -> BytesMut;
+
+    /// Copies `data` into a buffer from the pool.
+    fn copy_from_slice(&self, data: &[u8]) -> Bytes {
+        let mut buffer = self.take(data.len());
+        buffer.extend_from_slice(data);
+        buffer.freeze()
+    }
+}
+
+/// [`BufferPool`] that allocates a new buffer every time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StandardBufferPool;
+
+impl BufferPool for StandardBufferPool {
+    fn take(&self, capacity: usize) -> BytesMut {
+        BytesMut::with_capacity(capacity)
+    }
+}
+
+/// [`BufferPool`] that hands out buffers from large shared chunks of memory.
+///
+/// A chunk is reused once every buffer taken from it has been dropped, so allocations only happen when
+/// messages are held onto for a long time (or are larger than the chunk size).
+#[derive(Debug)]
+pub struct ArenaBufferPool {
+    chunk_size: usize,
+    arena: Mutex<BytesMut>,
+}
+
+impl ArenaBufferPool {
+    /// Makes a new pool that allocates memory in chunks of `chunk_size` bytes.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            arena: Mutex::new(BytesMut::with_capacity(chunk_size)),
+        }
+    }
+}
+
+impl Default for ArenaBufferPool {
+    fn default() -> Self {
+        Self::new(64 * 1024)
+    }
+}
+
+impl BufferPool for ArenaBufferPool {
+    fn take(&self, capacity: usize) -> BytesMut {
+        let mut arena = self.arena.lock().unwrap_or_else(|err| err.into_inner());
+        if arena.capacity() < capacity {
+            // Reclaims the current chunk if all buffers taken from it were dropped, otherwise allocates a new chunk.
+            arena.reserve(capacity.max(self.chunk_size));
+        }
+        let remainder = arena.split_off(capacity);
+        std::mem::replace(&mut *arena, remainder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_reuses_memory() {
+        let pool = ArenaBufferPool::new(1024);
+
+        let first = pool.copy_from_slice(&[1; 600]);
+        let first_ptr = first.as_ptr();
+        assert_eq!(&first[..], &[1; 600]);
+
+        // The rest of the chunk is used while the first buffer is alive.
+        let second = pool.copy_from_slice(&[2; 400]);
+        assert_eq!(second.as_ptr(), first_ptr.wrapping_add(600));
+
+        // A new chunk is needed when the current one is full.
+        let third = pool.copy_from_slice(&[3; 600]);
+        let third_ptr = third.as_ptr();
+        assert_eq!(&third[..], &[3; 600]);
+        assert_ne!(third.as_ptr(), first_ptr);
+        assert_eq!(&first[..], &[1; 600]);
+        assert_eq!(&second[..], &[2; 400]);
+        drop(first);
+        drop(second);
+
+        // The chunk is reclaimed once all its buffers are dropped.
+        drop(third);
+        let fourth = pool.copy_from_slice(&[4; 1000]);
+        assert_eq!(fourth.as_ptr(), third_ptr);
+        assert_eq!(&fourth[..], &[4; 1000]);
+    }
+}
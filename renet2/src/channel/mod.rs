@@ -32,10 +32,82 @@ pub struct ChannelConfig {
     /// Unreliable channels will drop new messages when this value is reached.
     /// Reliable channels will cause a disconnect when this value is reached.
     pub max_memory_usage_bytes: usize,
+    /// Limits how many bytes a reliable channel can send per tick right after the connection is made.
+    ///
+    /// The limit grows by the number of bytes acked (slow-start, like in TCP), and stops applying once it exceeds
+    /// the bytes available to the channel in a tick. This avoids flooding a slow client with a large backlog of reliable
+    /// messages as soon as it connects. Values smaller than one packet are raised to fit one packet.
+    ///
+    /// Resent messages count toward the limit, but only acks make it grow. If the `resend_time` is shorter than the
+    /// round-trip time, messages will be resent before they can be acked and use up the limit, which slows the ramp-up.
+    ///
+    /// Ignored by unreliable channels. Defaults to `None` (no limit).
+    pub initial_burst_limit: Option<usize>,
+    /// Number of recent message keys an unreliable channel remembers to drop duplicate messages.
+    ///
+    /// When set, messages sent with `send_message_keyed` are dropped by the receiver if a message with the same key
+    /// was received within the last `dedup_window` keyed messages. This is meant for application-level retries and is
+    /// separate from packet-level replay protection.
+    ///
+    /// Enabling this adds 1 byte to every message on the channel, and 8 more bytes to keyed messages. The receiver
+    /// stores up to `dedup_window` keys (roughly 24 bytes per key). The server and client configs must match.
+    ///
+    /// Ignored by reliable channels, unless they are downgraded to unreliable channels because the connection has a
+    /// reliable socket (see [`RenetClient::new`](crate::RenetClient::new)), in which case keyed messages are deduped
+    /// too. Defaults to `None` (disabled).
+    pub dedup_window: Option<usize>,
+    /// Maximum number of bytes per second the channel may send.
+    ///
+    /// Bytes the channel doesn't use accumulate up to one second's worth, so the channel can burst after being idle.
+    /// Messages over the limit are deferred to later ticks instead of being dropped, and count toward
+    /// `max_memory_usage_bytes` while they wait. A message (or slice) larger than the limit is sent once the channel
+    /// has been idle for a second, and the channel then stays idle until the excess bytes are paid back. Reliable
+    /// resends count toward the limit.
+    ///
+    /// Must be greater than 0, see [`ConnectionConfig::validate`](crate::ConnectionConfig::validate).
+    ///
+    /// The rate is measured with the time passed to `update`. Defaults to `None` (no limit).
+    pub max_bytes_per_sec: Option<usize>,
     /// Delivery guarantee of the channel.
     pub send_type: SendType,
 }
 
+/// Token bucket enforcing [`ChannelConfig::max_bytes_per_sec`].
+#[derive(Debug)]
+pub(crate) struct ChannelRateLimit {
+    max_bytes_per_sec: f64,
+    allowance: f64,
+    last_refill: Duration,
+}
+
+impl ChannelRateLimit {
+    pub fn new(max_bytes_per_sec: usize) -> Self {
+        Self {
+            max_bytes_per_sec: max_bytes_per_sec as f64,
+            allowance: max_bytes_per_sec as f64,
+            last_refill: Duration::ZERO,
+        }
+    }
+
+    /// Returns the number of bytes the channel may send at `current_time`.
+    ///
+    /// When the allowance is full, at least `next_send_bytes` may be sent so messages larger than the limit don't get
+    /// stuck. The allowance then goes negative, and the channel waits until it is paid back.
+    pub fn available_bytes(&mut self, current_time: Duration, next_send_bytes: u64) -> u64 {
+        let elapsed = current_time.saturating_sub(self.last_refill);
+        self.last_refill = current_time;
+        self.allowance = (self.allowance + elapsed.as_secs_f64() * self.max_bytes_per_sec).min(self.max_bytes_per_sec);
+        if self.allowance >= self.max_bytes_per_sec {
+            return (self.allowance as u64).max(next_send_bytes);
+        }
+        self.allowance.max(0.0) as u64
+    }
+
+    pub fn consume(&mut self, bytes: u64) {
+        self.allowance -= bytes as f64;
+    }
+}
+
 /// Utility enumerator when using the default channels configuration.
 /// The default configuration has 3 channels: unreliable, reliable ordered, and reliable unordered.
 pub enum DefaultChannel {
@@ -60,6 +132,9 @@ impl DefaultChannel {
             ChannelConfig {
                 channel_id: 0,
                 max_memory_usage_bytes: 5 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::Unreliable {
                     ordered_reliable_substrate: false,
                 },
@@ -67,6 +142,9 @@ impl DefaultChannel {
             ChannelConfig {
                 channel_id: 1,
                 max_memory_usage_bytes: 5 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::ReliableUnordered {
                     resend_time: Duration::from_millis(300),
                 },
@@ -74,6 +152,9 @@ impl DefaultChannel {
             ChannelConfig {
                 channel_id: 2,
                 max_memory_usage_bytes: 5 * 1024 * 1024,
+                initial_burst_limit: None,
+                dedup_window: None,
+                max_bytes_per_sec: None,
                 send_type: SendType::ReliableOrdered {
                     resend_time: Duration::from_millis(300),
                 },
@@ -6,7 +6,10 @@ use std::{
 use bytes::Bytes;
 
 use super::SliceConstructor;
+#[cfg(feature = "debug_tools")]
+use crate::message_tracker::{MessageStatus, MessageTracker};
 use crate::{
+    buffer_pool::BufferPool,
     error::ChannelError,
     packet::{Packet, Slice, SLICE_SIZE},
 };
@@ -15,6 +18,7 @@ use crate::{
 enum UnackedMessage {
     Small {
         message: Bytes,
+        first_sent: Option<Duration>,
         last_sent: Option<Duration>,
     },
     Sliced {
@@ -23,6 +27,7 @@ enum UnackedMessage {
         num_acked_slices: usize,
         next_slice_to_send: usize,
         acked: Vec<bool>,
+        first_sent: Option<Duration>,
         last_sent: Vec<Option<Duration>>,
     },
 }
@@ -35,6 +40,11 @@ pub struct SendChannelReliable {
     resend_time: Duration,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    effective_latency: Option<Duration>,
+    burst_limit: Option<u64>,
+    slice_size: usize,
+    #[cfg(feature = "debug_tools")]
+    tracker: MessageTracker,
 }
 
 #[derive(Debug)]
@@ -54,11 +64,12 @@ pub struct ReceiveChannelReliable {
     reliable_order: ReliableOrder,
     memory_usage_bytes: usize,
     max_memory_usage_bytes: usize,
+    slice_size: usize,
 }
 
 impl UnackedMessage {
-    fn new_sliced(payload: Bytes) -> Self {
-        let num_slices = payload.len().div_ceil(SLICE_SIZE);
+    fn new_sliced(payload: Bytes, slice_size: usize) -> Self {
+        let num_slices = payload.len().div_ceil(slice_size);
 
         Self::Sliced {
             message: payload,
@@ -66,13 +77,14 @@ impl UnackedMessage {
             num_acked_slices: 0,
             next_slice_to_send: 0,
             acked: vec![false; num_slices],
+            first_sent: None,
             last_sent: vec![None; num_slices],
         }
     }
 }
 
 impl SendChannelReliable {
-    pub fn new(channel_id: u8, resend_time: Duration, max_memory_usage_bytes: usize) -> Self {
+    pub fn new(channel_id: u8, resend_time: Duration, max_memory_usage_bytes: usize, initial_burst_limit: Option<usize>) -> Self {
         Self {
             channel_id,
             unacked_messages: BTreeMap::new(),
@@ -80,18 +92,155 @@ impl SendChannelReliable {
             resend_time,
             max_memory_usage_bytes,
             memory_usage_bytes: 0,
+            effective_latency: None,
+            // The limit must fit at least one full packet, otherwise large messages could never be sent.
+            burst_limit: initial_burst_limit.map(|limit| limit.max(SLICE_SIZE) as u64),
+            slice_size: SLICE_SIZE,
+            #[cfg(feature = "debug_tools")]
+            tracker: MessageTracker::new(channel_id, true),
         }
     }
 
+    /// Starts tracking the next message sent on this channel, returning its id.
+    #[cfg(feature = "debug_tools")]
+    pub fn track_next_message(&mut self) -> u64 {
+        self.tracker.track(self.next_reliable_message_id);
+        self.next_reliable_message_id
+    }
+
+    #[cfg(feature = "debug_tools")]
+    pub fn message_status(&self, message_id: u64) -> MessageStatus {
+        self.tracker.status(message_id)
+    }
+
+    /// Marks tracked messages that were not acked yet as dropped.
+    #[cfg(feature = "debug_tools")]
+    pub fn drop_tracked_messages(&mut self) {
+        self.tracker.drop_pending();
+    }
+
+    /// Current slow-start limit on bytes sent per tick, or `None` once slow-start is over.
+    #[cfg(test)]
+    fn burst_limit(&self) -> Option<u64> {
+        self.burst_limit
+    }
+
+    fn grow_burst_limit(&mut self, acked_bytes: usize) {
+        if let Some(limit) = &mut self.burst_limit {
+            *limit += acked_bytes as u64;
+        }
+    }
+
+    /// Smoothed time from first sending a message until it is fully acked, including any resends.
+    pub fn effective_latency(&self) -> Option<Duration> {
+        self.effective_latency
+    }
+
+    fn update_effective_latency(&mut self, first_sent: Option<Duration>, current_time: Duration) {
+        let Some(first_sent) = first_sent else {
+            return;
+        };
+        let latency = current_time.saturating_sub(first_sent);
+        self.effective_latency = Some(match self.effective_latency {
+            None => latency,
+            Some(effective_latency) => effective_latency.mul_f64(0.875) + latency.mul_f64(0.125),
+        });
+    }
+
     pub fn available_memory(&self) -> usize {
-        self.max_memory_usage_bytes - self.memory_usage_bytes
+        self.max_memory_usage_bytes.saturating_sub(self.memory_usage_bytes)
+    }
+
+    pub fn set_max_memory_usage_bytes(&mut self, max_memory_usage_bytes: usize) {
+        self.max_memory_usage_bytes = max_memory_usage_bytes;
+    }
+
+    /// Sets the size messages are sliced into, see [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
+    pub fn set_slice_size(&mut self, slice_size: usize) {
+        self.slice_size = slice_size;
+    }
+
+    pub fn slice_size(&self) -> usize {
+        self.slice_size
     }
 
     pub fn can_send_message(&self, size_bytes: usize) -> bool {
         size_bytes + self.memory_usage_bytes <= self.max_memory_usage_bytes
     }
 
+    /// Number of messages that are not fully acked yet, including messages that were sent but not acked.
+    pub fn pending_message_count(&self) -> usize {
+        self.unacked_messages.len()
+    }
+
+    /// Returns true if some message has parts that were never sent.
+    pub fn has_unsent_messages(&self) -> bool {
+        self.unacked_messages.values().any(|unacked_message| match unacked_message {
+            UnackedMessage::Small { last_sent, .. } => last_sent.is_none(),
+            UnackedMessage::Sliced { last_sent, .. } => last_sent.iter().any(Option::is_none),
+        })
+    }
+
+    /// Bytes of the messages and slices that were never sent.
+    pub fn unsent_bytes(&self) -> usize {
+        let slice_size = self.slice_size;
+        self.unacked_messages
+            .values()
+            .map(|unacked_message| match unacked_message {
+                UnackedMessage::Small { message, last_sent, .. } => last_sent.map_or(message.len(), |_| 0),
+                UnackedMessage::Sliced { message, last_sent, .. } => last_sent
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, last_sent)| last_sent.is_none())
+                    .map(|(i, _)| slice_size.min(message.len() - i * slice_size))
+                    .sum(),
+            })
+            .sum()
+    }
+
+    /// Returns true if some message or slice was sent but not acked within the resend time.
+    pub fn has_resends_due(&self, current_time: Duration) -> bool {
+        let due = |last_sent: &Option<Duration>| last_sent.is_some_and(|last_sent| current_time - last_sent >= self.resend_time);
+        self.unacked_messages.values().any(|unacked_message| match unacked_message {
+            UnackedMessage::Small { last_sent, .. } => due(last_sent),
+            UnackedMessage::Sliced { acked, last_sent, .. } => {
+                acked.iter().zip(last_sent).any(|(acked, last_sent)| !acked && due(last_sent))
+            }
+        })
+    }
+
+    /// Drops all messages that are not fully acked yet, returning the number of messages dropped.
+    ///
+    /// Dropped message ids are never delivered, so an ordered receiver will stop delivering messages.
+    pub fn clear(&mut self) -> usize {
+        #[cfg(feature = "debug_tools")]
+        for message_id in self.unacked_messages.keys() {
+            self.tracker.set_status(*message_id, MessageStatus::Dropped);
+        }
+        let count = self.unacked_messages.len();
+        self.unacked_messages.clear();
+        self.memory_usage_bytes = 0;
+        count
+    }
+
     pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64, current_time: Duration) -> Vec<Packet> {
+        let Some(burst_limit) = self.burst_limit else {
+            return self.generate_packets(packet_sequence, available_bytes, current_time);
+        };
+        if burst_limit >= *available_bytes {
+            // Slow-start ends once the limit no longer constrains the channel.
+            self.burst_limit = None;
+            return self.generate_packets(packet_sequence, available_bytes, current_time);
+        }
+
+        let mut burst_bytes = burst_limit;
+        let packets = self.generate_packets(packet_sequence, &mut burst_bytes, current_time);
+        *available_bytes -= burst_limit - burst_bytes;
+        packets
+    }
+
+    fn generate_packets(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64, current_time: Duration) -> Vec<Packet> {
+        let slice_size = self.slice_size;
         if self.unacked_messages.is_empty() {
             return vec![];
         }
@@ -103,7 +252,11 @@ impl SendChannelReliable {
 
         'messages: for (&message_id, unacked_message) in self.unacked_messages.iter_mut() {
             match unacked_message {
-                UnackedMessage::Small { message, last_sent } => {
+                UnackedMessage::Small {
+                    message,
+                    first_sent,
+                    last_sent,
+                } => {
                     if *available_bytes < message.len() as u64 {
                         // Skip message, no bytes available to send this message
                         continue;
@@ -119,7 +272,7 @@ impl SendChannelReliable {
 
                     // Generate packet with small messages if you cannot fit
                     let serialized_size = message.len() + octets::varint_len(message.len() as u64) + octets::varint_len(message_id);
-                    if small_messages_bytes + serialized_size > SLICE_SIZE {
+                    if small_messages_bytes + serialized_size > slice_size {
                         packets.push(Packet::SmallReliable {
                             sequence: *packet_sequence,
                             channel_id: self.channel_id,
@@ -131,7 +284,10 @@ impl SendChannelReliable {
 
                     small_messages_bytes += serialized_size;
                     small_messages.push((message_id, message.clone()));
+                    first_sent.get_or_insert(current_time);
                     *last_sent = Some(current_time);
+                    #[cfg(feature = "debug_tools")]
+                    self.tracker.set_status(message_id, MessageStatus::Sent);
 
                     continue;
                 }
@@ -139,13 +295,14 @@ impl SendChannelReliable {
                     message,
                     num_slices,
                     acked,
+                    first_sent,
                     last_sent,
                     next_slice_to_send,
                     ..
                 } => {
                     let start_index = *next_slice_to_send;
                     for i in 0..*num_slices {
-                        if *available_bytes < SLICE_SIZE as u64 {
+                        if *available_bytes < slice_size as u64 {
                             // Skip message, no bytes available to send a slice
                             continue 'messages;
                         }
@@ -161,8 +318,8 @@ impl SendChannelReliable {
                             }
                         }
 
-                        let start = i * SLICE_SIZE;
-                        let end = if i == *num_slices - 1 { message.len() } else { (i + 1) * SLICE_SIZE };
+                        let start = i * slice_size;
+                        let end = if i == *num_slices - 1 { message.len() } else { (i + 1) * slice_size };
 
                         let payload = message.slice(start..end);
                         *available_bytes -= payload.len() as u64;
@@ -181,8 +338,17 @@ impl SendChannelReliable {
                         });
 
                         *packet_sequence += 1;
+                        first_sent.get_or_insert(current_time);
                         last_sent[i] = Some(current_time);
                         *next_slice_to_send = i + 1 % *num_slices;
+                        #[cfg(feature = "debug_tools")]
+                        self.tracker.set_status(
+                            message_id,
+                            match last_sent.iter().all(Option::is_some) {
+                                true => MessageStatus::Sent,
+                                false => MessageStatus::Packetized,
+                            },
+                        );
                     }
                 }
             }
@@ -207,10 +373,14 @@ impl SendChannelReliable {
         }
 
         self.memory_usage_bytes += message.len();
-        let unacked_message = if message.len() > SLICE_SIZE {
-            UnackedMessage::new_sliced(message)
+        let unacked_message = if message.len() > self.slice_size {
+            UnackedMessage::new_sliced(message, self.slice_size)
         } else {
-            UnackedMessage::Small { message, last_sent: None }
+            UnackedMessage::Small {
+                message,
+                first_sent: None,
+                last_sent: None,
+            }
         };
 
         self.unacked_messages.insert(self.next_reliable_message_id, unacked_message);
@@ -219,17 +389,27 @@ impl SendChannelReliable {
         Ok(())
     }
 
-    pub fn process_message_ack(&mut self, message_id: u64) {
+    pub fn process_message_ack(&mut self, message_id: u64, current_time: Duration) {
         if self.unacked_messages.contains_key(&message_id) {
             let unacked_message = self.unacked_messages.remove(&message_id).unwrap();
-            let UnackedMessage::Small { message: payload, .. } = unacked_message else {
+            let UnackedMessage::Small {
+                message: payload,
+                first_sent,
+                ..
+            } = unacked_message
+            else {
                 unreachable!("called ack on small message but found sliced");
             };
             self.memory_usage_bytes -= payload.len();
+            self.update_effective_latency(first_sent, current_time);
+            self.grow_burst_limit(payload.len());
+            #[cfg(feature = "debug_tools")]
+            self.tracker.set_status(message_id, MessageStatus::Acked);
         }
     }
 
-    pub fn process_slice_message_ack(&mut self, message_id: u64, slice_index: usize) {
+    pub fn process_slice_message_ack(&mut self, message_id: u64, slice_index: usize, current_time: Duration) {
+        let slice_size = self.slice_size;
         let Some(unacked_message) = self.unacked_messages.get_mut(&message_id) else {
             return;
         };
@@ -239,6 +419,7 @@ impl SendChannelReliable {
             num_slices,
             num_acked_slices,
             acked,
+            first_sent,
             ..
         } = unacked_message
         else {
@@ -251,11 +432,17 @@ impl SendChannelReliable {
 
         acked[slice_index] = true;
         *num_acked_slices += 1;
+        let slice_bytes = slice_size.min(message.len() - slice_index * slice_size);
 
         if *num_acked_slices == *num_slices {
+            let first_sent = *first_sent;
             self.memory_usage_bytes -= message.len();
             self.unacked_messages.remove(&message_id);
+            self.update_effective_latency(first_sent, current_time);
+            #[cfg(feature = "debug_tools")]
+            self.tracker.set_status(message_id, MessageStatus::Acked);
         }
+        self.grow_burst_limit(slice_bytes);
     }
 }
 
@@ -273,6 +460,7 @@ impl ReceiveChannelReliable {
             messages: BTreeMap::new(),
             oldest_pending_message_id: 0,
             reliable_order,
+            slice_size: SLICE_SIZE,
             memory_usage_bytes: 0,
             max_memory_usage_bytes,
         }
@@ -318,14 +506,31 @@ impl ReceiveChannelReliable {
         Ok(())
     }
 
-    pub fn process_slice(&mut self, slice: Slice) -> Result<(), ChannelError> {
+    /// Sets the size of the slices sent by the peer, see
+    /// [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
+    pub fn set_slice_size(&mut self, slice_size: usize) {
+        self.slice_size = slice_size;
+    }
+
+    /// Returns the number of sliced messages being reassembled.
+    pub fn num_reassemblies(&self) -> usize {
+        self.slices.len()
+    }
+
+    /// Returns whether a slice of the given message would start a new reassembly.
+    pub fn starts_reassembly(&self, message_id: u64) -> bool {
+        !self.messages.contains_key(&message_id) && message_id >= self.oldest_pending_message_id && !self.slices.contains_key(&message_id)
+    }
+
+    pub fn process_slice(&mut self, slice: Slice, pool: &dyn BufferPool) -> Result<(), ChannelError> {
+        let slice_size = self.slice_size;
         if self.messages.contains_key(&slice.message_id) || slice.message_id < self.oldest_pending_message_id {
             // Message already assembled
             return Ok(());
         }
 
         if !self.slices.contains_key(&slice.message_id) {
-            let message_len = slice.num_slices * SLICE_SIZE;
+            let message_len = slice.num_slices * slice_size;
             if self.memory_usage_bytes + message_len > self.max_memory_usage_bytes {
                 return Err(ChannelError::ReliableChannelMaxMemoryReached);
             }
@@ -335,11 +540,11 @@ impl ReceiveChannelReliable {
         let slice_constructor = self
             .slices
             .entry(slice.message_id)
-            .or_insert_with(|| SliceConstructor::new(slice.message_id, slice.num_slices));
+            .or_insert_with(|| SliceConstructor::new(slice.message_id, slice.num_slices, slice_size, pool));
 
         if let Some(message) = slice_constructor.process_slice(slice.slice_index, &slice.payload)? {
             // Memory usage is re-added with the exactly message size
-            self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+            self.memory_usage_bytes -= slice.num_slices * slice_size;
             self.process_message(message, slice.message_id)?;
             self.slices.remove(&slice.message_id);
         }
@@ -380,6 +585,7 @@ mod tests {
     use octets::OctetsMut;
 
     use super::*;
+    use crate::buffer_pool::StandardBufferPool;
 
     #[test]
     fn small_packet() {
@@ -389,7 +595,7 @@ mod tests {
         let mut current_time: Duration = Duration::ZERO;
         let resend_time = Duration::from_millis(100);
         let mut recv = ReceiveChannelReliable::new(max_memory, true);
-        let mut send = SendChannelReliable::new(0, resend_time, max_memory);
+        let mut send = SendChannelReliable::new(0, resend_time, max_memory, None);
 
         let message1 = vec![1, 2, 3];
         let message2 = vec![3, 4, 5];
@@ -429,8 +635,8 @@ mod tests {
 
         // Should not resend after ack
         current_time += resend_time;
-        send.process_message_ack(0);
-        send.process_message_ack(1);
+        send.process_message_ack(0, current_time);
+        send.process_message_ack(1, current_time);
 
         let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
         assert!(packets.is_empty());
@@ -444,7 +650,7 @@ mod tests {
         let mut current_time: Duration = Duration::ZERO;
         let resend_time = Duration::from_millis(100);
         let mut recv = ReceiveChannelReliable::new(max_memory, false);
-        let mut send = SendChannelReliable::new(0, resend_time, max_memory);
+        let mut send = SendChannelReliable::new(0, resend_time, max_memory, None);
 
         let message1 = vec![1, 2, 3];
         let message2 = vec![3, 4, 5];
@@ -498,9 +704,9 @@ mod tests {
 
         // Should not resend after ack
         current_time += resend_time;
-        send.process_message_ack(0);
-        send.process_message_ack(1);
-        send.process_message_ack(2);
+        send.process_message_ack(0, current_time);
+        send.process_message_ack(1, current_time);
+        send.process_message_ack(2, current_time);
 
         let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
         assert!(packets.is_empty());
@@ -514,7 +720,7 @@ mod tests {
         let mut current_time: Duration = Duration::ZERO;
         let resend_time = Duration::from_millis(100);
         let mut recv = ReceiveChannelReliable::new(max_memory, true);
-        let mut send = SendChannelReliable::new(0, resend_time, max_memory);
+        let mut send = SendChannelReliable::new(0, resend_time, max_memory, None);
 
         let message = vec![5; SLICE_SIZE * 3];
 
@@ -525,7 +731,7 @@ mod tests {
             let Packet::ReliableSlice { channel_id: 0, slice, .. } = packet else {
                 unreachable!();
             };
-            recv.process_slice(slice).unwrap();
+            recv.process_slice(slice, &StandardBufferPool).unwrap();
         }
 
         let new_message = recv.receive_message().unwrap();
@@ -542,14 +748,75 @@ mod tests {
 
         // Should not resend after ack
         current_time += resend_time;
-        send.process_slice_message_ack(0, 0);
-        send.process_slice_message_ack(0, 1);
-        send.process_slice_message_ack(0, 2);
+        send.process_slice_message_ack(0, 0, current_time);
+        send.process_slice_message_ack(0, 1, current_time);
+        send.process_slice_message_ack(0, 2, current_time);
 
         let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
         assert!(packets.is_empty());
     }
 
+    #[test]
+    fn effective_latency() {
+        let mut available_bytes = u64::MAX;
+        let mut sequence: u64 = 0;
+        let mut current_time: Duration = Duration::ZERO;
+        let resend_time = Duration::from_millis(100);
+        let mut send = SendChannelReliable::new(0, resend_time, 10000, None);
+        assert_eq!(send.effective_latency(), None);
+
+        // Latency is measured from the first send, including resends.
+        send.send_message(vec![1, 2, 3].into()).unwrap();
+        assert_eq!(send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time).len(), 1);
+        current_time += resend_time;
+        assert_eq!(send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time).len(), 1);
+        current_time += Duration::from_millis(150);
+        send.process_message_ack(0, current_time);
+        assert_eq!(send.effective_latency(), Some(Duration::from_millis(250)));
+
+        // Sliced messages are measured when the last slice is acked.
+        send.send_message(vec![5; SLICE_SIZE * 2].into()).unwrap();
+        assert_eq!(send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time).len(), 2);
+        current_time += Duration::from_millis(50);
+        send.process_slice_message_ack(1, 0, current_time);
+        assert_eq!(send.effective_latency(), Some(Duration::from_millis(250)));
+        send.process_slice_message_ack(1, 1, current_time);
+        let expected = Duration::from_millis(250).mul_f64(0.875) + Duration::from_millis(50).mul_f64(0.125);
+        assert_eq!(send.effective_latency(), Some(expected));
+    }
+
+    #[test]
+    fn initial_burst_limit() {
+        let mut sequence: u64 = 0;
+        let current_time: Duration = Duration::ZERO;
+        let resend_time = Duration::from_millis(100);
+        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX, Some(SLICE_SIZE * 2));
+        for _ in 0..10 {
+            send.send_message(vec![1; 1000].into()).unwrap();
+        }
+
+        // Only two messages fit in the initial limit.
+        let mut available_bytes = 100_000;
+        send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
+        assert_eq!(available_bytes, 100_000 - 2000);
+
+        // Acks grow the limit.
+        send.process_message_ack(0, current_time);
+        assert_eq!(send.burst_limit(), Some(SLICE_SIZE as u64 * 2 + 1000));
+        let mut available_bytes = 100_000;
+        send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
+        assert_eq!(available_bytes, 100_000 - 3000);
+
+        // Slow-start ends when the limit exceeds the available bytes.
+        for message_id in 1..5 {
+            send.process_message_ack(message_id, current_time);
+        }
+        let mut available_bytes = 5000;
+        send.get_packets_to_send(&mut sequence, &mut available_bytes, current_time);
+        assert_eq!(send.burst_limit(), None);
+        assert_eq!(available_bytes, 0);
+    }
+
     #[test]
     fn max_memory() {
         let mut available_bytes = u64::MAX;
@@ -557,7 +824,7 @@ mod tests {
         let current_time: Duration = Duration::ZERO;
         let resend_time = Duration::from_millis(100);
         let mut recv = ReceiveChannelReliable::new(99, true);
-        let mut send = SendChannelReliable::new(0, resend_time, 101);
+        let mut send = SendChannelReliable::new(0, resend_time, 101, None);
 
         let message = vec![5; 100];
 
@@ -593,7 +860,7 @@ mod tests {
         let mut sequence: u64 = 0;
         let current_time: Duration = Duration::ZERO;
         let resend_time = Duration::from_millis(100);
-        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX);
+        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX, None);
 
         let message: Bytes = vec![0u8; 100].into();
         send.send_message(message.clone()).unwrap();
@@ -626,7 +893,7 @@ mod tests {
         let current_time: Duration = Duration::ZERO;
         let mut available_bytes = u64::MAX;
         let resend_time = Duration::from_millis(100);
-        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX);
+        let mut send = SendChannelReliable::new(0, resend_time, usize::MAX, None);
 
         // 4 bytes
         let message: Bytes = vec![0, 1, 2, 3].into();
@@ -1,6 +1,6 @@
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 
-use crate::{error::ChannelError, packet::SLICE_SIZE};
+use crate::{buffer_pool::BufferPool, error::ChannelError};
 
 #[derive(Debug, Clone)]
 pub struct SliceConstructor {
@@ -8,33 +8,42 @@ pub struct SliceConstructor {
     pub num_slices: usize,
     num_received_slices: usize,
     received: Vec<bool>,
-    sliced_data: Vec<u8>,
+    sliced_data: BytesMut,
+    slice_size: usize,
 }
 
 impl SliceConstructor {
-    pub fn new(message_id: u64, num_slices: usize) -> Self {
+    pub fn new(message_id: u64, num_slices: usize, slice_size: usize, pool: &dyn BufferPool) -> Self {
+        let mut sliced_data = pool.take(num_slices * slice_size);
+        sliced_data.resize(num_slices * slice_size, 0);
+
         SliceConstructor {
             message_id,
             num_slices,
             num_received_slices: 0,
             received: vec![false; num_slices],
-            sliced_data: vec![0; num_slices * SLICE_SIZE],
+            sliced_data,
+            slice_size,
         }
     }
 
     pub fn process_slice(&mut self, slice_index: usize, bytes: &[u8]) -> Result<Option<Bytes>, ChannelError> {
         let is_last_slice = slice_index == self.num_slices - 1;
         if is_last_slice {
-            if bytes.len() > SLICE_SIZE {
+            if bytes.len() > self.slice_size {
                 log::error!(
                     "Invalid last slice_size for SliceMessage, got {}, expected less than {}.",
                     bytes.len(),
-                    SLICE_SIZE,
+                    self.slice_size,
                 );
                 return Err(ChannelError::InvalidSliceMessage);
             }
-        } else if bytes.len() != SLICE_SIZE {
-            log::error!("Invalid slice_size for SliceMessage, got {}, expected {}.", bytes.len(), SLICE_SIZE);
+        } else if bytes.len() != self.slice_size {
+            log::error!(
+                "Invalid slice_size for SliceMessage, got {}, expected {}.",
+                bytes.len(),
+                self.slice_size
+            );
             return Err(ChannelError::InvalidSliceMessage);
         }
 
@@ -43,15 +52,15 @@ impl SliceConstructor {
             self.num_received_slices += 1;
 
             if is_last_slice {
-                let len = (self.num_slices - 1) * SLICE_SIZE + bytes.len();
+                let len = (self.num_slices - 1) * self.slice_size + bytes.len();
                 self.sliced_data.resize(len, 0);
             }
 
-            let start = slice_index * SLICE_SIZE;
+            let start = slice_index * self.slice_size;
             let end = if slice_index == self.num_slices - 1 {
-                (self.num_slices - 1) * SLICE_SIZE + bytes.len()
+                (self.num_slices - 1) * self.slice_size + bytes.len()
             } else {
-                (slice_index + 1) * SLICE_SIZE
+                (slice_index + 1) * self.slice_size
             };
 
             self.sliced_data[start..end].copy_from_slice(bytes);
@@ -67,7 +76,7 @@ impl SliceConstructor {
         if self.num_received_slices == self.num_slices {
             log::trace!("Received all slices for message {}.", self.message_id);
             let payload = std::mem::take(&mut self.sliced_data);
-            return Ok(Some(payload.into()));
+            return Ok(Some(payload.freeze()));
         }
 
         Ok(None)
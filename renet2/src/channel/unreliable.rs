@@ -1,11 +1,14 @@
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, HashSet, VecDeque},
     time::Duration,
 };
 
 use bytes::Bytes;
 
+#[cfg(feature = "debug_tools")]
+use crate::message_tracker::{MessageStatus, MessageTracker};
 use crate::{
+    buffer_pool::BufferPool,
     channel::SliceConstructor,
     error::ChannelError,
     packet::{Packet, Slice, SLICE_SIZE},
@@ -14,15 +17,66 @@ use crate::{
 #[derive(Debug)]
 pub struct SendChannelUnreliable {
     channel_id: u8,
-    unreliable_messages: VecDeque<Bytes>,
+    // Messages are stored with an id that is only used to track their status, and their priority. Messages are
+    // kept sorted by descending priority, so higher-priority messages are sent first.
+    unreliable_messages: VecDeque<(u64, u8, Bytes)>,
+    next_message_id: u64,
     sliced_message_id: u64,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
     // If the underlying networking substrate is ordered-reliable (e.g. WebSockets), then renet2
     // can use unreliable channels to avoid redundant message acking. This setting ensures
     // unreliable channels will behave like reliable channels by not dropping messages when
-    // there are too many bytes to send in one tick.
-    ordered_reliable_substrate: bool,
+    // there are too many bytes to send in one tick. Also used by rate-limited channels, which
+    // defer excess messages to later ticks.
+    defer_overflow: bool,
+    // If set, messages are prefixed with a dedup header (see `DEDUP_UNKEYED`/`DEDUP_KEYED`).
+    dedup: bool,
+    // If set, message priorities are ignored so messages are sent in order (e.g. when the channel replaces a
+    // reliable ordered channel on an ordered-reliable substrate).
+    ordered: bool,
+    slice_size: usize,
+    #[cfg(feature = "debug_tools")]
+    tracker: MessageTracker,
+}
+
+// Dedup header tags, followed by a little-endian `u64` key for keyed messages.
+const DEDUP_UNKEYED: u8 = 0;
+const DEDUP_KEYED: u8 = 1;
+
+/// Remembers the most recent message keys received on a channel.
+#[derive(Debug)]
+struct DedupWindow {
+    capacity: usize,
+    order: VecDeque<u64>,
+    keys: HashSet<u64>,
+}
+
+impl DedupWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            keys: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Returns `false` if the key was recently seen.
+    fn insert(&mut self, key: u64) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.keys.insert(key) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.order.push_back(key);
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -33,29 +87,92 @@ pub struct ReceiveChannelUnreliable {
     slices_last_received: BTreeMap<u64, Duration>,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    dedup: Option<DedupWindow>,
+    slice_size: usize,
 }
 
 impl SendChannelUnreliable {
-    pub fn new(channel_id: u8, max_memory_usage_bytes: usize, ordered_reliable_substrate: bool) -> Self {
+    pub fn new(channel_id: u8, max_memory_usage_bytes: usize, defer_overflow: bool, dedup: bool, ordered: bool) -> Self {
         Self {
             channel_id,
             unreliable_messages: VecDeque::new(),
+            next_message_id: 0,
             sliced_message_id: 0,
             max_memory_usage_bytes,
             memory_usage_bytes: 0,
-            ordered_reliable_substrate,
+            defer_overflow,
+            dedup,
+            ordered,
+            slice_size: SLICE_SIZE,
+            #[cfg(feature = "debug_tools")]
+            tracker: MessageTracker::new(channel_id, false),
         }
     }
 
+    /// Starts tracking the next message sent on this channel, returning its id.
+    #[cfg(feature = "debug_tools")]
+    pub fn track_next_message(&mut self) -> u64 {
+        self.tracker.track(self.next_message_id);
+        self.next_message_id
+    }
+
+    #[cfg(feature = "debug_tools")]
+    pub fn message_status(&self, message_id: u64) -> MessageStatus {
+        self.tracker.status(message_id)
+    }
+
+    /// Marks tracked messages that were not sent yet as dropped.
+    #[cfg(feature = "debug_tools")]
+    pub fn drop_tracked_messages(&mut self) {
+        self.tracker.drop_pending();
+    }
+
     pub fn can_send_message(&self, size_bytes: usize) -> bool {
         size_bytes + self.memory_usage_bytes <= self.max_memory_usage_bytes
     }
 
     pub fn available_memory(&self) -> usize {
-        self.max_memory_usage_bytes - self.memory_usage_bytes
+        self.max_memory_usage_bytes.saturating_sub(self.memory_usage_bytes)
+    }
+
+    pub fn set_max_memory_usage_bytes(&mut self, max_memory_usage_bytes: usize) {
+        self.max_memory_usage_bytes = max_memory_usage_bytes;
+    }
+
+    /// Sets the size messages are sliced into, see [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
+    pub fn set_slice_size(&mut self, slice_size: usize) {
+        self.slice_size = slice_size;
+    }
+
+    /// Number of messages waiting to be sent.
+    pub fn pending_message_count(&self) -> usize {
+        self.unreliable_messages.len()
+    }
+
+    /// Bytes of the messages waiting to be sent.
+    pub fn unsent_bytes(&self) -> usize {
+        self.memory_usage_bytes
+    }
+
+    /// Size of the next message to send, or 0 if there are no messages waiting.
+    pub fn next_message_len(&self) -> usize {
+        self.unreliable_messages.front().map_or(0, |(_, _, message)| message.len())
+    }
+
+    /// Drops all messages waiting to be sent, returning the number of messages dropped.
+    pub fn clear(&mut self) -> usize {
+        #[cfg(feature = "debug_tools")]
+        for (message_id, _, _) in self.unreliable_messages.iter() {
+            self.tracker.set_status(*message_id, MessageStatus::Dropped);
+        }
+        let count = self.unreliable_messages.len();
+        self.unreliable_messages.clear();
+        self.memory_usage_bytes = 0;
+        count
     }
 
     pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64) -> Vec<Packet> {
+        let slice_size = self.slice_size;
         let mut packets: Vec<Packet> = vec![];
         let mut small_messages: Vec<Bytes> = vec![];
         let mut small_messages_bytes = 0;
@@ -63,25 +180,31 @@ impl SendChannelUnreliable {
         let mut overflow_messages = vec![];
         let mut overflow_trigger = false;
 
-        while let Some(message) = self.unreliable_messages.pop_front() {
+        while let Some((message_id, priority, message)) = self.unreliable_messages.pop_front() {
             self.memory_usage_bytes -= message.len();
             if *available_bytes < message.len() as u64 || overflow_trigger {
                 // Drop or save message, no available bytes to send.
-                if self.ordered_reliable_substrate {
-                    overflow_messages.push(message);
+                if self.defer_overflow {
+                    overflow_messages.push((message_id, priority, message));
                     // Once this is triggered, we stop sending messages so the 'ordered' setting can be maintained.
                     overflow_trigger = true;
+                } else {
+                    #[cfg(feature = "debug_tools")]
+                    self.tracker.set_status(message_id, MessageStatus::Dropped);
                 }
                 continue;
             }
 
+            #[cfg(feature = "debug_tools")]
+            self.tracker.set_status(message_id, MessageStatus::Sent);
+
             *available_bytes -= message.len() as u64;
-            if message.len() > SLICE_SIZE {
-                let num_slices = message.len().div_ceil(SLICE_SIZE);
+            if message.len() > slice_size {
+                let num_slices = message.len().div_ceil(slice_size);
 
                 for slice_index in 0..num_slices {
-                    let start = slice_index * SLICE_SIZE;
-                    let end = if slice_index == num_slices - 1 { message.len() } else { (slice_index + 1) * SLICE_SIZE };
+                    let start = slice_index * slice_size;
+                    let end = if slice_index == num_slices - 1 { message.len() } else { (slice_index + 1) * slice_size };
                     let payload = message.slice(start..end);
 
                     let slice = Slice {
@@ -102,7 +225,7 @@ impl SendChannelUnreliable {
                 self.sliced_message_id += 1;
             } else {
                 let serialized_size = message.len() + octets::varint_len(message.len() as u64);
-                if small_messages_bytes + serialized_size > SLICE_SIZE {
+                if small_messages_bytes + serialized_size > slice_size {
                     packets.push(Packet::SmallUnreliable {
                         sequence: *packet_sequence,
                         channel_id: self.channel_id,
@@ -117,9 +240,9 @@ impl SendChannelUnreliable {
             }
         }
 
-        for message in overflow_messages {
+        for (message_id, priority, message) in overflow_messages.into_iter().rev() {
             self.memory_usage_bytes += message.len();
-            self.unreliable_messages.push_front(message);
+            self.unreliable_messages.push_front((message_id, priority, message));
         }
 
         // Generate final packet for remaining small messages
@@ -135,16 +258,57 @@ impl SendChannelUnreliable {
         packets
     }
 
+    #[cfg(test)]
     pub fn send_message(&mut self, message: Bytes) {
+        self.send_message_prioritized(0, message);
+    }
+
+    /// Sends a message that is sent before messages with a lower priority that are waiting to be sent.
+    ///
+    /// The priority is ignored if the channel is ordered.
+    pub fn send_message_prioritized(&mut self, priority: u8, message: Bytes) {
+        let message = match self.dedup {
+            true => Self::with_header(&[DEDUP_UNKEYED], &message),
+            false => message,
+        };
+        self.push_message(priority, message);
+    }
+
+    /// Sends a message with a key that the receiver uses to drop duplicates.
+    ///
+    /// The key is ignored if dedup is not enabled for this channel.
+    pub fn send_message_keyed(&mut self, key: u64, message: Bytes) {
+        if !self.dedup {
+            return self.push_message(0, message);
+        }
+
+        let mut header = [DEDUP_KEYED; 9];
+        header[1..].copy_from_slice(&key.to_le_bytes());
+        self.push_message(0, Self::with_header(&header, &message));
+    }
+
+    fn with_header(header: &[u8], message: &[u8]) -> Bytes {
+        let mut buffer = Vec::with_capacity(header.len() + message.len());
+        buffer.extend_from_slice(header);
+        buffer.extend_from_slice(message);
+        buffer.into()
+    }
+
+    fn push_message(&mut self, priority: u8, message: Bytes) {
+        let message_id = self.next_message_id;
+        self.next_message_id += 1;
+
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             log::warn!(
                 "dropped unreliable message sent because channel {} is memory limited",
                 self.channel_id
             );
+            #[cfg(feature = "debug_tools")]
+            self.tracker.set_status(message_id, MessageStatus::Dropped);
             return;
         }
 
-        let num_fragments = message.len() / SLICE_SIZE;
+        let num_fragments = message.len() / self.slice_size;
         if num_fragments > 20 {
             log::warn!(
                 "Sending an unreliable message with {num_fragments} fragments, messages with this many fragments are susceptible to packet loss. \
@@ -152,12 +316,19 @@ impl SendChannelUnreliable {
         }
 
         self.memory_usage_bytes += message.len();
-        self.unreliable_messages.push_back(message);
+        let priority = if self.ordered { 0 } else { priority };
+        // Insert after the messages with the same or a higher priority.
+        let index = self
+            .unreliable_messages
+            .iter()
+            .rposition(|(_, queued_priority, _)| *queued_priority >= priority)
+            .map_or(0, |index| index + 1);
+        self.unreliable_messages.insert(index, (message_id, priority, message));
     }
 }
 
 impl ReceiveChannelUnreliable {
-    pub fn new(channel_id: u8, max_memory_usage_bytes: usize) -> Self {
+    pub fn new(channel_id: u8, max_memory_usage_bytes: usize, dedup_window: Option<usize>) -> Self {
         Self {
             channel_id,
             slices: BTreeMap::new(),
@@ -165,6 +336,8 @@ impl ReceiveChannelUnreliable {
             messages: VecDeque::new(),
             memory_usage_bytes: 0,
             max_memory_usage_bytes,
+            dedup: dedup_window.map(DedupWindow::new),
+            slice_size: SLICE_SIZE,
         }
     }
 
@@ -181,9 +354,36 @@ impl ReceiveChannelUnreliable {
         self.messages.push_back(message);
     }
 
-    pub fn process_slice(&mut self, slice: Slice, current_time: Duration) -> Result<(), ChannelError> {
+    /// Sets the size of the slices sent by the peer, see
+    /// [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
+    pub fn set_slice_size(&mut self, slice_size: usize) {
+        self.slice_size = slice_size;
+    }
+
+    /// Returns the number of sliced messages being reassembled.
+    pub fn num_reassemblies(&self) -> usize {
+        self.slices.len()
+    }
+
+    /// Returns whether a slice of the given message would start a new reassembly.
+    pub fn starts_reassembly(&self, message_id: u64) -> bool {
+        !self.slices.contains_key(&message_id)
+    }
+
+    /// Discards the oldest incomplete sliced message, returns false if no message was being reassembled.
+    pub fn discard_oldest_reassembly(&mut self) -> bool {
+        let Some((message_id, slice)) = self.slices.pop_first() else {
+            return false;
+        };
+        self.slices_last_received.remove(&message_id);
+        self.memory_usage_bytes -= slice.num_slices * self.slice_size;
+        true
+    }
+
+    pub fn process_slice(&mut self, slice: Slice, current_time: Duration, pool: &dyn BufferPool) -> Result<(), ChannelError> {
+        let slice_size = self.slice_size;
         if !self.slices.contains_key(&slice.message_id) {
-            let message_len = slice.num_slices * SLICE_SIZE;
+            let message_len = slice.num_slices * slice_size;
             if self.memory_usage_bytes + message_len > self.max_memory_usage_bytes {
                 log::warn!(
                     "dropped unreliable slice message received because channel {} is memory limited",
@@ -198,12 +398,12 @@ impl ReceiveChannelUnreliable {
         let slice_constructor = self
             .slices
             .entry(slice.message_id)
-            .or_insert_with(|| SliceConstructor::new(slice.message_id, slice.num_slices));
+            .or_insert_with(|| SliceConstructor::new(slice.message_id, slice.num_slices, slice_size, pool));
 
         if let Some(message) = slice_constructor.process_slice(slice.slice_index, &slice.payload)? {
             self.slices.remove(&slice.message_id);
             self.slices_last_received.remove(&slice.message_id);
-            self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+            self.memory_usage_bytes -= slice.num_slices * slice_size;
             self.memory_usage_bytes += message.len();
             self.messages.push_back(message);
         } else {
@@ -229,15 +429,32 @@ impl ReceiveChannelUnreliable {
         for message_id in lost_messages.iter() {
             self.slices_last_received.remove(message_id);
             let slice = self.slices.remove(message_id).expect("discarded slice should exist");
-            self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
+            self.memory_usage_bytes -= slice.num_slices * self.slice_size;
         }
     }
 
     pub fn receive_message(&mut self) -> Option<Bytes> {
-        if let Some(message) = self.messages.pop_front() {
+        while let Some(message) = self.messages.pop_front() {
             self.memory_usage_bytes -= message.len();
-            return Some(message);
-        };
+            let Some(dedup) = &mut self.dedup else {
+                return Some(message);
+            };
+
+            match message.first() {
+                Some(&DEDUP_UNKEYED) => return Some(message.slice(1..)),
+                Some(&DEDUP_KEYED) if message.len() >= 9 => {
+                    let key = u64::from_le_bytes(message[1..9].try_into().unwrap());
+                    if dedup.insert(key) {
+                        return Some(message.slice(9..));
+                    }
+                    log::trace!("dropped duplicate unreliable message with key {key} on channel {}", self.channel_id);
+                }
+                _ => log::warn!(
+                    "dropped unreliable message with invalid dedup header on channel {}",
+                    self.channel_id
+                ),
+            }
+        }
 
         None
     }
@@ -248,14 +465,15 @@ mod tests {
     use octets::OctetsMut;
 
     use super::*;
+    use crate::buffer_pool::StandardBufferPool;
 
     #[test]
     fn small_packet() {
         let max_memory: usize = 10000;
         let mut available_bytes = u64::MAX;
         let mut sequence: u64 = 0;
-        let mut recv = ReceiveChannelUnreliable::new(0, max_memory);
-        let mut send = SendChannelUnreliable::new(0, max_memory, false);
+        let mut recv = ReceiveChannelUnreliable::new(0, max_memory, None);
+        let mut send = SendChannelUnreliable::new(0, max_memory, false, false, false);
 
         let message1 = vec![1, 2, 3];
         let message2 = vec![3, 4, 5];
@@ -284,14 +502,39 @@ mod tests {
         assert!(packets.is_empty());
     }
 
+    #[test]
+    fn deferred_messages_keep_order() {
+        let max_memory: usize = 10000;
+        let mut sequence: u64 = 0;
+        let mut send = SendChannelUnreliable::new(0, max_memory, true, false, false);
+
+        for i in 0..4u8 {
+            send.send_message(vec![i; 10].into());
+        }
+
+        // Only the first message fits, the rest are deferred.
+        let mut available_bytes = 10;
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(send.pending_message_count(), 3);
+
+        let mut available_bytes = u64::MAX;
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes);
+        let Packet::SmallUnreliable { messages, .. } = &packets[0] else {
+            unreachable!();
+        };
+        let firsts: Vec<u8> = messages.iter().map(|message| message[0]).collect();
+        assert_eq!(firsts, vec![1, 2, 3]);
+    }
+
     #[test]
     fn slice_packet() {
         let max_memory: usize = 10000;
         let mut available_bytes = u64::MAX;
         let mut sequence: u64 = 0;
         let current_time = Duration::ZERO;
-        let mut recv = ReceiveChannelUnreliable::new(0, max_memory);
-        let mut send = SendChannelUnreliable::new(0, max_memory, false);
+        let mut recv = ReceiveChannelUnreliable::new(0, max_memory, None);
+        let mut send = SendChannelUnreliable::new(0, max_memory, false, false, false);
 
         let message = vec![5; SLICE_SIZE * 3];
 
@@ -302,7 +545,7 @@ mod tests {
             let Packet::UnreliableSlice { slice, .. } = packet else {
                 unreachable!();
             };
-            recv.process_slice(slice, current_time).unwrap();
+            recv.process_slice(slice, current_time, &StandardBufferPool).unwrap();
         }
 
         let new_message = recv.receive_message().unwrap();
@@ -314,12 +557,87 @@ mod tests {
         assert!(packets.is_empty());
     }
 
+    #[test]
+    fn priority() {
+        let mut sequence: u64 = 0;
+        let mut recv = ReceiveChannelUnreliable::new(0, usize::MAX, None);
+        let mut send = SendChannelUnreliable::new(0, usize::MAX, false, false, false);
+
+        send.send_message(vec![0; 10].into());
+        send.send_message_prioritized(1, vec![1; 10].into());
+        send.send_message_prioritized(2, vec![2; 10].into());
+        send.send_message_prioritized(1, vec![3; 10].into());
+
+        // Only two messages fit, the others are dropped.
+        let mut available_bytes = 20;
+        for packet in send.get_packets_to_send(&mut sequence, &mut available_bytes) {
+            let Packet::SmallUnreliable { messages, .. } = packet else {
+                unreachable!();
+            };
+            for message in messages {
+                recv.process_message(message);
+            }
+        }
+        assert_eq!(recv.receive_message().unwrap(), vec![2; 10]);
+        assert_eq!(recv.receive_message().unwrap(), vec![1; 10]);
+        assert!(recv.receive_message().is_none());
+        assert_eq!(send.pending_message_count(), 0);
+
+        // Ordered channels ignore priorities.
+        let mut send = SendChannelUnreliable::new(0, usize::MAX, true, false, true);
+        send.send_message(vec![0; 10].into());
+        send.send_message_prioritized(1, vec![1; 10].into());
+        let mut available_bytes = 10;
+        for packet in send.get_packets_to_send(&mut sequence, &mut available_bytes) {
+            let Packet::SmallUnreliable { messages, .. } = packet else {
+                unreachable!();
+            };
+            assert_eq!(messages, vec![Bytes::from(vec![0; 10])]);
+        }
+        assert_eq!(send.pending_message_count(), 1);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut available_bytes = u64::MAX;
+        let mut sequence: u64 = 0;
+        let mut recv = ReceiveChannelUnreliable::new(0, usize::MAX, Some(2));
+        let mut send = SendChannelUnreliable::new(0, usize::MAX, false, true, false);
+
+        send.send_message_keyed(1, vec![1].into());
+        send.send_message_keyed(1, vec![1].into());
+        send.send_message(vec![0].into());
+        send.send_message(vec![0].into());
+        send.send_message_keyed(2, vec![2].into());
+        send.send_message_keyed(3, vec![3].into());
+        // Key 1 was pushed out of the window.
+        send.send_message_keyed(1, vec![1].into());
+        send.send_message_keyed(3, vec![3].into());
+
+        let packets = send.get_packets_to_send(&mut sequence, &mut available_bytes);
+        for packet in packets {
+            let Packet::SmallUnreliable { messages, .. } = packet else {
+                unreachable!();
+            };
+            for message in messages {
+                recv.process_message(message);
+            }
+        }
+
+        let mut received = vec![];
+        while let Some(message) = recv.receive_message() {
+            received.push(message[0]);
+        }
+        assert_eq!(received, vec![1, 0, 0, 2, 3, 1]);
+        assert_eq!(recv.memory_usage_bytes, 0);
+    }
+
     #[test]
     fn max_memory() {
         let mut sequence: u64 = 0;
         let mut available_bytes = u64::MAX;
-        let mut recv = ReceiveChannelUnreliable::new(0, 50);
-        let mut send = SendChannelUnreliable::new(0, 40, false);
+        let mut recv = ReceiveChannelUnreliable::new(0, 50, None);
+        let mut send = SendChannelUnreliable::new(0, 40, false, false, false);
 
         let message = vec![5; 50];
 
@@ -346,7 +664,7 @@ mod tests {
     #[test]
     fn available_bytes() {
         let mut sequence: u64 = 0;
-        let mut send = SendChannelUnreliable::new(0, usize::MAX, false);
+        let mut send = SendChannelUnreliable::new(0, usize::MAX, false, false, false);
 
         let message: Bytes = vec![0u8; 100].into();
         send.send_message(message.clone());
@@ -379,7 +697,7 @@ mod tests {
     fn small_packet_max_size() {
         let mut sequence: u64 = 0;
         let mut available_bytes = u64::MAX;
-        let mut send = SendChannelUnreliable::new(0, usize::MAX, false);
+        let mut send = SendChannelUnreliable::new(0, usize::MAX, false, false, false);
 
         // 4 bytes
         let message: Bytes = vec![0, 1, 2, 3].into();
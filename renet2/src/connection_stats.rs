@@ -1,9 +1,23 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
+use crate::remote_connection::ConnectionTrend;
+
 const RESOLUTION: Duration = Duration::from_millis(300);
 const WINDOW: Duration = Duration::from_millis(6000);
 const SIZE: usize = (WINDOW.as_millis() / RESOLUTION.as_millis()) as usize;
 
+/// Time between the samples used to compute the [`ConnectionTrend`].
+const TREND_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+/// Number of samples used to compute the [`ConnectionTrend`], the newer half is compared to the older half.
+const TREND_SAMPLES: usize = 10;
+/// Relative change in rtt between the two halves that counts as a trend.
+const TREND_RTT_RATIO: f64 = 0.2;
+/// Smallest change in rtt (in seconds) that counts as a trend, so jitter on fast connections is ignored.
+const TREND_MIN_RTT_CHANGE: f64 = 0.005;
+/// Change in packet loss between the two halves that counts as a trend.
+const TREND_PACKET_LOSS_CHANGE: f64 = 0.02;
+
 #[derive(Debug, Default)]
 pub struct ConnectionStats {
     packets_sent: [u64; SIZE],
@@ -59,28 +73,11 @@ impl ConnectionStats {
     }
 
     pub fn bytes_sent_per_second(&self, current_time: Duration) -> f64 {
-        let mut total_bytes: u64 = self.bytes_sent.iter().sum();
-
-        if current_time < WINDOW {
-            return total_bytes as f64 / current_time.as_secs_f64();
-        }
-
-        // Ignore the current incomplete resolution
-        total_bytes -= self.bytes_sent[self.current_index];
-
-        total_bytes as f64 / (WINDOW - RESOLUTION).as_secs_f64()
+        bytes_per_second(&self.bytes_sent, self.current_index, current_time)
     }
 
     pub fn bytes_received_per_second(&self, current_time: Duration) -> f64 {
-        let mut total_bytes: u64 = self.bytes_received.iter().sum();
-
-        if current_time < WINDOW {
-            return total_bytes as f64 / current_time.as_secs_f64();
-        }
-
-        // Ignore the current incomplete resolution
-        total_bytes -= self.bytes_received[self.current_index];
-        total_bytes as f64 / (WINDOW - RESOLUTION).as_secs_f64()
+        bytes_per_second(&self.bytes_received, self.current_index, current_time)
     }
 
     pub fn packet_loss(&self) -> f64 {
@@ -111,6 +108,113 @@ impl ConnectionStats {
     }
 }
 
+fn bytes_per_second(bytes: &[u64; SIZE], current_index: usize, current_time: Duration) -> f64 {
+    let mut total_bytes: u64 = bytes.iter().sum();
+
+    if current_time < WINDOW {
+        return total_bytes as f64 / current_time.as_secs_f64();
+    }
+
+    // Ignore the current incomplete resolution
+    total_bytes -= bytes[current_index];
+    total_bytes as f64 / (WINDOW - RESOLUTION).as_secs_f64()
+}
+
+/// Bytes sent and received on a single channel, both in total and over the stats window.
+#[derive(Debug, Default)]
+pub struct ChannelStats {
+    total_bytes_sent: u64,
+    total_bytes_received: u64,
+    bytes_sent: [u64; SIZE],
+    bytes_received: [u64; SIZE],
+    current_index: usize,
+}
+
+impl ChannelStats {
+    pub fn update(&mut self, current_time: Duration) {
+        let i = ConnectionStats::index(current_time);
+        if self.current_index != i {
+            self.current_index = i;
+            self.bytes_sent[i] = 0;
+            self.bytes_received[i] = 0;
+        }
+    }
+
+    pub fn sent_bytes(&mut self, bytes: u64) {
+        self.total_bytes_sent += bytes;
+        self.bytes_sent[self.current_index] += bytes;
+    }
+
+    pub fn received_bytes(&mut self, bytes: u64) {
+        self.total_bytes_received += bytes;
+        self.bytes_received[self.current_index] += bytes;
+    }
+
+    pub fn total_bytes_sent(&self) -> u64 {
+        self.total_bytes_sent
+    }
+
+    pub fn total_bytes_received(&self) -> u64 {
+        self.total_bytes_received
+    }
+
+    pub fn bytes_sent_per_second(&self, current_time: Duration) -> f64 {
+        bytes_per_second(&self.bytes_sent, self.current_index, current_time)
+    }
+
+    pub fn bytes_received_per_second(&self, current_time: Duration) -> f64 {
+        bytes_per_second(&self.bytes_received, self.current_index, current_time)
+    }
+}
+
+/// Recent samples of the rtt and packet loss of a connection.
+#[derive(Debug, Default)]
+pub struct TrendTracker {
+    samples: VecDeque<(f64, f64)>,
+    next_sample_at: Duration,
+}
+
+impl TrendTracker {
+    pub fn update(&mut self, current_time: Duration, rtt: f64, packet_loss: f64) {
+        if current_time < self.next_sample_at {
+            return;
+        }
+
+        self.next_sample_at = current_time + TREND_SAMPLE_INTERVAL;
+        if self.samples.len() == TREND_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((rtt, packet_loss));
+    }
+
+    pub fn trend(&self) -> ConnectionTrend {
+        if self.samples.len() < TREND_SAMPLES {
+            return ConnectionTrend::Stable;
+        }
+
+        let half = TREND_SAMPLES / 2;
+        let mean = |samples: &mut dyn Iterator<Item = &(f64, f64)>| {
+            let (rtt, packet_loss) = samples.fold((0.0, 0.0), |(rtt, loss), sample| (rtt + sample.0, loss + sample.1));
+            (rtt / half as f64, packet_loss / half as f64)
+        };
+        let (old_rtt, old_packet_loss) = mean(&mut self.samples.iter().take(half));
+        let (new_rtt, new_packet_loss) = mean(&mut self.samples.iter().skip(half));
+
+        let rtt_threshold = (old_rtt * TREND_RTT_RATIO).max(TREND_MIN_RTT_CHANGE);
+        let rtt_change = new_rtt - old_rtt;
+        let packet_loss_change = new_packet_loss - old_packet_loss;
+
+        // A worse rtt or packet loss takes precedence over an improvement in the other.
+        if rtt_change > rtt_threshold || packet_loss_change > TREND_PACKET_LOSS_CHANGE {
+            ConnectionTrend::Degrading
+        } else if rtt_change < -rtt_threshold || packet_loss_change < -TREND_PACKET_LOSS_CHANGE {
+            ConnectionTrend::Improving
+        } else {
+            ConnectionTrend::Stable
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +245,24 @@ mod tests {
         assert_eq!(window.bytes_sent_per_second(current_time), 1000.);
     }
 
+    #[test]
+    fn channel_bytes_per_sec() {
+        let mut current_time = Duration::ZERO;
+        let mut stats = ChannelStats::default();
+
+        for _ in 0..60 {
+            stats.update(current_time);
+            stats.sent_bytes(100);
+            stats.received_bytes(50);
+            current_time += Duration::from_millis(100);
+        }
+
+        assert_eq!(stats.total_bytes_sent(), 6000);
+        assert_eq!(stats.total_bytes_received(), 3000);
+        assert_eq!(stats.bytes_sent_per_second(current_time), 1000.);
+        assert_eq!(stats.bytes_received_per_second(current_time), 500.);
+    }
+
     #[test]
     fn packet_loss() {
         let mut current_time = Duration::ZERO;
@@ -169,4 +291,49 @@ mod tests {
         assert_eq!(window.packets_acked, [3; SIZE]);
         assert_eq!(window.packet_loss(), 0.5);
     }
+
+    #[test]
+    fn trend() {
+        let mut current_time = Duration::ZERO;
+        let mut tracker = TrendTracker::default();
+        let mut sample = |tracker: &mut TrendTracker, rtt: f64, packet_loss: f64| {
+            tracker.update(current_time, rtt, packet_loss);
+            current_time += TREND_SAMPLE_INTERVAL;
+        };
+
+        // Not enough samples yet.
+        for _ in 0..TREND_SAMPLES - 1 {
+            sample(&mut tracker, 0.1, 0.0);
+        }
+        assert_eq!(tracker.trend(), ConnectionTrend::Stable);
+        sample(&mut tracker, 0.1, 0.0);
+        assert_eq!(tracker.trend(), ConnectionTrend::Stable);
+
+        // Small jitter is ignored.
+        for _ in 0..TREND_SAMPLES / 2 {
+            sample(&mut tracker, 0.11, 0.01);
+        }
+        assert_eq!(tracker.trend(), ConnectionTrend::Stable);
+
+        for _ in 0..TREND_SAMPLES / 2 {
+            sample(&mut tracker, 0.2, 0.01);
+        }
+        assert_eq!(tracker.trend(), ConnectionTrend::Degrading);
+
+        for _ in 0..TREND_SAMPLES / 2 {
+            sample(&mut tracker, 0.1, 0.01);
+        }
+        assert_eq!(tracker.trend(), ConnectionTrend::Improving);
+
+        // Higher packet loss outweighs a lower rtt.
+        for _ in 0..TREND_SAMPLES / 2 {
+            sample(&mut tracker, 0.05, 0.1);
+        }
+        assert_eq!(tracker.trend(), ConnectionTrend::Degrading);
+
+        // Updates between sample intervals are ignored.
+        tracker.update(current_time - TREND_SAMPLE_INTERVAL / 2, 1.0, 1.0);
+        assert_eq!(tracker.trend(), ConnectionTrend::Degrading);
+        assert_eq!(tracker.samples.back(), Some(&(0.05, 0.1)));
+    }
 }
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::packet::SerializationError;
+use crate::packet::{SerializationError, MIN_SLICE_SIZE, SLICE_SIZE};
 
 /// Possible reasons for a disconnection.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -32,6 +32,8 @@ pub enum ChannelError {
     ReliableChannelMaxMemoryReached,
     /// Received an invalid slice message in the channel.
     InvalidSliceMessage,
+    /// Received a reliable slice that would exceed [`ConnectionConfig::max_concurrent_reassemblies`](crate::ConnectionConfig::max_concurrent_reassemblies).
+    TooManyReassemblies,
 }
 
 impl fmt::Display for ChannelError {
@@ -41,6 +43,7 @@ impl fmt::Display for ChannelError {
         match *self {
             ReliableChannelMaxMemoryReached => write!(fmt, "reliable channel memory usage was exhausted"),
             InvalidSliceMessage => write!(fmt, "received an invalid slice packet"),
+            TooManyReassemblies => write!(fmt, "too many sliced messages are being reassembled"),
         }
     }
 }
@@ -64,6 +67,34 @@ impl fmt::Display for DisconnectReason {
 
 impl std::error::Error for ChannelError {}
 
+/// Invalid setting in a [`ConnectionConfig`](crate::ConnectionConfig), see
+/// [`ConnectionConfig::validate`](crate::ConnectionConfig::validate).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionConfigError {
+    /// [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size) is not between [`MIN_SLICE_SIZE`] and
+    /// [`SLICE_SIZE`].
+    InvalidSliceSize(usize),
+    /// [`ConnectionConfig::max_concurrent_reassemblies`](crate::ConnectionConfig::max_concurrent_reassemblies) is 0.
+    ZeroMaxConcurrentReassemblies,
+    /// The [`ChannelConfig::max_bytes_per_sec`](crate::ChannelConfig::max_bytes_per_sec) of the channel with this id
+    /// is `Some(0)`.
+    ZeroChannelRateLimit(u8),
+}
+
+impl fmt::Display for ConnectionConfigError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use ConnectionConfigError::*;
+
+        match *self {
+            InvalidSliceSize(slice_size) => write!(fmt, "slice size {slice_size} is not between {MIN_SLICE_SIZE} and {SLICE_SIZE}"),
+            ZeroMaxConcurrentReassemblies => write!(fmt, "max concurrent reassemblies is 0"),
+            ZeroChannelRateLimit(channel_id) => write!(fmt, "channel {channel_id} has a max_bytes_per_sec of 0"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionConfigError {}
+
 #[derive(Debug)]
 pub struct ClientNotFound;
 
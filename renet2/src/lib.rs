@@ -1,17 +1,27 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+mod buffer_pool;
 mod channel;
 mod connection_stats;
 mod error;
+#[cfg(feature = "debug_tools")]
+mod message_tracker;
 mod packet;
 mod remote_connection;
+#[cfg(any(test, feature = "test_utils"))]
+mod semantics;
 mod server;
 
+pub use buffer_pool::{ArenaBufferPool, BufferPool, StandardBufferPool};
 pub use channel::{ChannelConfig, DefaultChannel, SendType};
-pub use error::{ChannelError, ClientNotFound, DisconnectReason};
-pub use packet::Payload;
-pub use remote_connection::{ConnectionConfig, NetworkInfo, RenetClient, RenetConnectionStatus};
-pub use server::{RenetServer, ServerEvent};
+pub use error::{ChannelError, ClientNotFound, ConnectionConfigError, DisconnectReason};
+#[cfg(feature = "debug_tools")]
+pub use message_tracker::{MessageId, MessageStatus};
+pub use packet::{Payload, MIN_SLICE_SIZE, SLICE_SIZE};
+pub use remote_connection::{ConnectionConfig, ConnectionTrend, NetworkInfo, RenetClient, RenetConnectionStatus};
+#[cfg(any(test, feature = "test_utils"))]
+pub use semantics::{verify_channel_semantics, ChannelSemanticsReport, SemanticsReport, SemanticsScenario};
+pub use server::{AggregateNetworkInfo, RenetServer, ServerEvent};
 
 pub use bytes::Bytes;
 
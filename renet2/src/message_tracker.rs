@@ -0,0 +1,117 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Number of finished messages per channel whose status is remembered.
+const MAX_FINISHED_MESSAGES: usize = 1024;
+
+/// Identifies a message sent with [`RenetClient::send_message_tracked`](crate::RenetClient::send_message_tracked).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageId {
+    pub(crate) channel_id: u8,
+    pub(crate) message_id: u64,
+}
+
+impl MessageId {
+    /// Returns the channel the message was sent on.
+    pub fn channel_id(&self) -> u8 {
+        self.channel_id
+    }
+}
+
+/// Lifecycle stage of a tracked message.
+///
+/// Messages go through `Enqueued -> Packetized -> Sent`, then reliable messages become `Acked` once the peer
+/// acknowledges every part of them. Messages can be `Dropped` at any point before they are finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatus {
+    /// The message is not tracked, or it finished too long ago to be remembered.
+    Unknown,
+    /// The message is waiting in its channel to be sent.
+    Enqueued,
+    /// Some slices of a sliced message were written to packets, but not all of them yet.
+    Packetized,
+    /// Every part of the message was written to packets handed to the transport.
+    ///
+    /// This is final for unreliable messages. Reliable messages may be resent until they are acked.
+    Sent,
+    /// The peer acknowledged the full message. Only used for reliable channels.
+    Acked,
+    /// The message was dropped before it was finished, e.g. because the channel ran out of memory or bandwidth,
+    /// the channel was cleared, or the connection was closed.
+    Dropped,
+}
+
+/// Records the status of tracked messages sent on a channel.
+#[derive(Debug)]
+pub(crate) struct MessageTracker {
+    channel_id: u8,
+    reliable: bool,
+    statuses: HashMap<u64, MessageStatus>,
+    finished: VecDeque<u64>,
+}
+
+impl MessageTracker {
+    pub fn new(channel_id: u8, reliable: bool) -> Self {
+        Self {
+            channel_id,
+            reliable,
+            statuses: HashMap::new(),
+            finished: VecDeque::new(),
+        }
+    }
+
+    /// Starts tracking a message that is about to be enqueued.
+    pub fn track(&mut self, message_id: u64) {
+        log::debug!("tracked message {message_id} on channel {} enqueued", self.channel_id);
+        self.statuses.insert(message_id, MessageStatus::Enqueued);
+    }
+
+    pub fn status(&self, message_id: u64) -> MessageStatus {
+        self.statuses.get(&message_id).copied().unwrap_or(MessageStatus::Unknown)
+    }
+
+    /// Updates the status of a message. Does nothing if the message is not tracked or already finished.
+    pub fn set_status(&mut self, message_id: u64, status: MessageStatus) {
+        let Some(current) = self.statuses.get(&message_id).copied() else {
+            return;
+        };
+        if current == status || self.is_finished(current) {
+            return;
+        }
+
+        log::debug!(
+            "tracked message {message_id} on channel {}: {current:?} -> {status:?}",
+            self.channel_id
+        );
+        self.statuses.insert(message_id, status);
+
+        if self.is_finished(status) {
+            self.finished.push_back(message_id);
+            if self.finished.len() > MAX_FINISHED_MESSAGES {
+                if let Some(oldest) = self.finished.pop_front() {
+                    self.statuses.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Marks all messages that are not finished as dropped.
+    pub fn drop_pending(&mut self) {
+        let pending: Vec<u64> = self
+            .statuses
+            .iter()
+            .filter(|(_, status)| !self.is_finished(**status))
+            .map(|(message_id, _)| *message_id)
+            .collect();
+        for message_id in pending {
+            self.set_status(message_id, MessageStatus::Dropped);
+        }
+    }
+
+    fn is_finished(&self, status: MessageStatus) -> bool {
+        match status {
+            MessageStatus::Acked | MessageStatus::Dropped => true,
+            MessageStatus::Sent => !self.reliable,
+            MessageStatus::Unknown | MessageStatus::Enqueued | MessageStatus::Packetized => false,
+        }
+    }
+}
@@ -1,11 +1,22 @@
+use crate::buffer_pool::BufferPool;
 use bytes::Bytes;
 use std::{fmt, ops::Range};
 
 pub type Payload = Vec<u8>;
 
-// Sliced messages are split into SLICE_SIZE bytes chunks
+/// Messages larger than this are split into `SLICE_SIZE`-byte slices, i.e. it is the largest message that can be
+/// sent without fragmentation.
+///
+/// This is the default and maximum of [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
 pub const SLICE_SIZE: usize = 1200;
 
+/// Minimum of [`ConnectionConfig::slice_size`](crate::ConnectionConfig::slice_size).
+pub const MIN_SLICE_SIZE: usize = 64;
+
+/// Size of the checksum appended to packets when
+/// [`ConnectionConfig::packet_checksum`](crate::ConnectionConfig::packet_checksum) is set.
+pub(crate) const CHECKSUM_BYTES: usize = 4;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Slice {
     pub message_id: u64,
@@ -56,6 +67,8 @@ pub enum SerializationError {
     EmptySlice,
     InvalidAckRange,
     InvalidPacketType,
+    /// The packet's checksum doesn't match its contents, it was corrupted or sent with a different protocol id.
+    ChecksumMismatch,
 }
 
 impl std::error::Error for SerializationError {}
@@ -71,6 +84,7 @@ impl fmt::Display for SerializationError {
             InvalidPacketType => write!(fmt, "invalid packet type"),
             SliceSizeAboveLimit => write!(fmt, "invalid slice size, it's above the limit of {} bytes", SLICE_SIZE),
             EmptySlice => write!(fmt, "invalid slice, slices cannot be empty"),
+            ChecksumMismatch => write!(fmt, "packet checksum mismatch"),
         }
     }
 }
@@ -81,6 +95,62 @@ impl From<octets::BufferTooShortError> for SerializationError {
     }
 }
 
+/// Appends the checksum of the packet in `buffer[..len]` and `protocol_id`, returns the new packet length.
+pub(crate) fn write_checksum(buffer: &mut [u8], len: usize, protocol_id: u64) -> Result<usize, SerializationError> {
+    let end = len + CHECKSUM_BYTES;
+    if buffer.len() < end {
+        return Err(SerializationError::BufferTooShort);
+    }
+    let checksum = checksum(&buffer[..len], protocol_id);
+    buffer[len..end].copy_from_slice(&checksum.to_le_bytes());
+
+    Ok(end)
+}
+
+/// Verifies the checksum appended by [`write_checksum`], returns the packet without the checksum.
+pub(crate) fn verify_checksum(packet: &[u8], protocol_id: u64) -> Result<&[u8], SerializationError> {
+    let Some((packet, expected)) = packet.split_last_chunk::<CHECKSUM_BYTES>() else {
+        return Err(SerializationError::BufferTooShort);
+    };
+    if checksum(packet, protocol_id) != u32::from_le_bytes(*expected) {
+        return Err(SerializationError::ChecksumMismatch);
+    }
+
+    Ok(packet)
+}
+
+/// CRC32 (IEEE) of the packet with the protocol id appended.
+fn checksum(packet: &[u8], protocol_id: u64) -> u32 {
+    let crc = crc32_update(!0, packet);
+    !crc32_update(crc, &protocol_id.to_le_bytes())
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB88320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
 impl Packet {
     pub fn sequence(&self) -> u64 {
         match self {
@@ -92,6 +162,16 @@ impl Packet {
         }
     }
 
+    pub fn channel_id(&self) -> Option<u8> {
+        match self {
+            Packet::SmallReliable { channel_id, .. }
+            | Packet::SmallUnreliable { channel_id, .. }
+            | Packet::UnreliableSlice { channel_id, .. }
+            | Packet::ReliableSlice { channel_id, .. } => Some(*channel_id),
+            Packet::Ack { .. } => None,
+        }
+    }
+
     pub fn to_bytes(&self, b: &mut octets::OctetsMut) -> Result<usize, SerializationError> {
         let before = b.cap();
 
@@ -205,7 +285,8 @@ impl Packet {
         Ok(before - b.cap())
     }
 
-    pub fn from_bytes(b: &mut octets::Octets) -> Result<Packet, SerializationError> {
+    /// Deserializes a packet, copying message payloads into buffers from `pool`.
+    pub fn from_bytes(b: &mut octets::Octets, pool: &dyn BufferPool) -> Result<Packet, SerializationError> {
         let packet_type = b.get_u8()?;
         match packet_type {
             0 => {
@@ -218,7 +299,7 @@ impl Packet {
                     let message_id = b.get_varint()?;
                     let payload = b.get_bytes_with_varint_length()?;
 
-                    messages.push((message_id, payload.to_vec().into()));
+                    messages.push((message_id, pool.copy_from_slice(payload.buf())));
                 }
 
                 Ok(Packet::SmallReliable {
@@ -235,7 +316,7 @@ impl Packet {
                 let mut messages: Vec<Bytes> = Vec::with_capacity(64);
                 for _ in 0..messages_len {
                     let payload = b.get_bytes_with_varint_length()?;
-                    messages.push(payload.to_vec().into());
+                    messages.push(pool.copy_from_slice(payload.buf()));
                 }
 
                 Ok(Packet::SmallUnreliable {
@@ -269,7 +350,7 @@ impl Packet {
                     message_id,
                     slice_index,
                     num_slices,
-                    payload: payload.to_vec().into(),
+                    payload: pool.copy_from_slice(payload.buf()),
                 };
                 Ok(Packet::ReliableSlice {
                     sequence,
@@ -294,7 +375,7 @@ impl Packet {
                     message_id,
                     slice_index,
                     num_slices,
-                    payload: payload.to_vec().into(),
+                    payload: pool.copy_from_slice(payload.buf()),
                 };
                 Ok(Packet::UnreliableSlice {
                     sequence,
@@ -354,6 +435,7 @@ impl Packet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buffer_pool::StandardBufferPool;
 
     #[test]
     fn serialize_small_reliable_packet() {
@@ -368,10 +450,40 @@ mod tests {
         packet.to_bytes(&mut b).unwrap();
 
         let mut b = octets::Octets::with_slice(&buffer);
-        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
         assert_eq!(packet, recv_packet);
     }
 
+    #[test]
+    fn crc32() {
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn checksum_packet() {
+        let mut buffer = [0u8; 1300];
+        let packet = Packet::SmallReliable {
+            sequence: 0,
+            channel_id: 0,
+            messages: vec![(0, vec![0, 0, 0].into()), (1, vec![1, 1, 1].into()), (2, vec![2, 2, 2].into())],
+        };
+
+        let mut b = octets::OctetsMut::with_slice(&mut buffer);
+        let len = packet.to_bytes(&mut b).unwrap();
+        let len = write_checksum(&mut buffer, len, 7).unwrap();
+
+        let verified = verify_checksum(&buffer[..len], 7).unwrap();
+        let mut b = octets::Octets::with_slice(verified);
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
+        assert_eq!(packet, recv_packet);
+
+        // Packets from a different protocol or that were corrupted are rejected.
+        assert_eq!(verify_checksum(&buffer[..len], 8), Err(SerializationError::ChecksumMismatch));
+        buffer[3] ^= 1;
+        assert_eq!(verify_checksum(&buffer[..len], 7), Err(SerializationError::ChecksumMismatch));
+        assert_eq!(verify_checksum(&buffer[..3], 7), Err(SerializationError::BufferTooShort));
+    }
+
     #[test]
     fn serialize_small_unreliable_packet() {
         let mut buffer = [0u8; 1300];
@@ -385,7 +497,7 @@ mod tests {
         packet.to_bytes(&mut b).unwrap();
 
         let mut b = octets::Octets::with_slice(&buffer);
-        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
         assert_eq!(packet, recv_packet);
     }
 
@@ -408,7 +520,7 @@ mod tests {
         packet.to_bytes(&mut b).unwrap();
 
         let mut b = octets::Octets::with_slice(&buffer);
-        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
         assert_eq!(packet, recv_packet);
     }
 
@@ -431,7 +543,7 @@ mod tests {
         packet.to_bytes(&mut b).unwrap();
 
         let mut b = octets::Octets::with_slice(&buffer);
-        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
         assert_eq!(packet, recv_packet);
     }
 
@@ -448,7 +560,7 @@ mod tests {
         packet.to_bytes(&mut b).unwrap();
 
         let mut b = octets::Octets::with_slice(&buffer);
-        let recv_packet = Packet::from_bytes(&mut b).unwrap();
+        let recv_packet = Packet::from_bytes(&mut b, &StandardBufferPool).unwrap();
         assert_eq!(packet, recv_packet);
     }
 }
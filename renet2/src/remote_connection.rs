@@ -1,14 +1,19 @@
+use crate::buffer_pool::{BufferPool, StandardBufferPool};
 use crate::channel::reliable::{ReceiveChannelReliable, SendChannelReliable};
 use crate::channel::unreliable::{ReceiveChannelUnreliable, SendChannelUnreliable};
-use crate::channel::{ChannelConfig, DefaultChannel, SendType};
-use crate::connection_stats::ConnectionStats;
-use crate::error::DisconnectReason;
-use crate::packet::{Packet, Payload};
+use crate::channel::{ChannelConfig, ChannelRateLimit, DefaultChannel, SendType};
+use crate::connection_stats::{ChannelStats, ConnectionStats, TrendTracker};
+use crate::error::{ChannelError, ConnectionConfigError, DisconnectReason};
+#[cfg(feature = "debug_tools")]
+use crate::message_tracker::{MessageId, MessageStatus};
+use crate::packet::{verify_checksum, write_checksum, Packet, Payload, MIN_SLICE_SIZE, SLICE_SIZE};
+use crate::ClientId;
 use bytes::Bytes;
 use octets::OctetsMut;
 
 use std::collections::BTreeMap;
 use std::ops::Range;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Configuration for a renet connection and its channels.
@@ -27,6 +32,53 @@ pub struct ConnectionConfig {
     /// Each tick, the first channel can consume up to `available_bytes_per_tick`,
     /// used bytes are removed from it and passed to the next channel
     pub client_channels_config: Vec<ChannelConfig>,
+    /// Pool that received message buffers are drawn from.
+    ///
+    /// Default: [`StandardBufferPool`], which allocates a new buffer for each message.
+    pub buffer_pool: Arc<dyn BufferPool>,
+    /// How long new messages are held before being written to packets, so that messages sent in quick succession
+    /// share packets.
+    ///
+    /// When set, [`RenetClient::get_packets_to_send`] holds back all channels until the oldest unsent message has
+    /// waited at least this long, measured with the time passed to [`RenetClient::update`]. This trades latency for
+    /// fewer packets: every message is delayed by up to `coalesce_delay` (in practice rounded up to the next update
+    /// tick), while acks are still sent immediately. Nothing is held back once the unsent messages would fill a
+    /// packet ([`Self::slice_size`]), while reliable messages are waiting to be resent, or while messages are left
+    /// over from the previous send (e.g. because of rate limits), since waiting would only add latency.
+    ///
+    /// Default: `None`, messages are written to packets on the next call to `get_packets_to_send`.
+    pub coalesce_delay: Option<Duration>,
+    /// The maximum number of sliced messages received from the peer that can be reassembled at the same time,
+    /// summed over all channels.
+    ///
+    /// Each message larger than a single packet is reassembled from slices, and a buffer for the whole message is
+    /// allocated when its first slice arrives. This limits how many of those buffers a peer can open by sending the
+    /// first slices of many messages. When a slice would start a reassembly beyond this limit, the oldest incomplete
+    /// message of an unreliable channel is discarded (or the slice is dropped if that channel has none), and a
+    /// reliable channel disconnects the connection with
+    /// [`ChannelError::TooManyReassemblies`](crate::ChannelError::TooManyReassemblies).
+    ///
+    /// Must be greater than 0, otherwise no sliced message could be received.
+    ///
+    /// Default: 256
+    pub max_concurrent_reassemblies: usize,
+    /// When set, a CRC32 of each packet and this protocol id is appended to sent packets and verified on received
+    /// packets. Received packets that fail verification are dropped.
+    ///
+    /// This catches corrupted packets and packets from a different protocol that weren't rejected by the transport,
+    /// e.g. with an unencrypted UDP transport behind NATs. It changes the wire format, so the client and server must
+    /// use the same setting.
+    ///
+    /// Default: `None`
+    pub packet_checksum: Option<u64>,
+    /// Size of the slices that messages larger than this are split into.
+    ///
+    /// Lower this when the effective MTU of the path is smaller than usual (e.g. when tunneling through a proxy),
+    /// to avoid IP fragmentation. It must be between [`MIN_SLICE_SIZE`] and [`SLICE_SIZE`] (see [`Self::validate`]),
+    /// and the client and server must use the same value.
+    ///
+    /// Default: [`SLICE_SIZE`]
+    pub slice_size: usize,
 }
 
 impl ConnectionConfig {
@@ -37,6 +89,11 @@ impl ConnectionConfig {
             available_bytes_per_tick: 60_000,
             server_channels_config: server,
             client_channels_config: client,
+            buffer_pool: Arc::new(StandardBufferPool),
+            coalesce_delay: None,
+            max_concurrent_reassemblies: 256,
+            packet_checksum: None,
+            slice_size: SLICE_SIZE,
         }
     }
 
@@ -50,6 +107,26 @@ impl ConnectionConfig {
         Self::from_shared_channels(DefaultChannel::config())
     }
 
+    /// Checks that the settings of the config are in range.
+    ///
+    /// [`RenetClient::new`] and [`RenetServer::new`](crate::RenetServer::new) panic if the config is invalid.
+    pub fn validate(&self) -> Result<(), ConnectionConfigError> {
+        if !(MIN_SLICE_SIZE..=SLICE_SIZE).contains(&self.slice_size) {
+            return Err(ConnectionConfigError::InvalidSliceSize(self.slice_size));
+        }
+        if self.max_concurrent_reassemblies == 0 {
+            return Err(ConnectionConfigError::ZeroMaxConcurrentReassemblies);
+        }
+        let channels = self.server_channels_config.iter().chain(self.client_channels_config.iter());
+        for channel_config in channels {
+            if channel_config.max_bytes_per_sec == Some(0) {
+                return Err(ConnectionConfigError::ZeroChannelRateLimit(channel_config.channel_id));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Downgrades all reliable channels to [`SendType::Unreliable`] with `ordered_reliable_substrate = true`.
     ///
     /// Used when setting up a client that has a socket with built-in reliability (such as WebSockets).
@@ -112,6 +189,33 @@ enum SendChannel {
     Reliable(SendChannelReliable),
 }
 
+impl SendChannel {
+    /// Returns the bytes needed to send the next message or slice of the channel.
+    fn next_send_bytes(&self) -> u64 {
+        match self {
+            SendChannel::Empty => 0,
+            SendChannel::Unreliable(channel) => channel.next_message_len() as u64,
+            // Small messages are packed up to the slice size, and larger messages are sent one slice at a time.
+            SendChannel::Reliable(channel) => channel.slice_size() as u64,
+        }
+    }
+
+    fn unsent_bytes(&self) -> usize {
+        match self {
+            SendChannel::Empty => 0,
+            SendChannel::Unreliable(channel) => channel.unsent_bytes(),
+            SendChannel::Reliable(channel) => channel.unsent_bytes(),
+        }
+    }
+
+    fn has_resends_due(&self, current_time: Duration) -> bool {
+        match self {
+            SendChannel::Reliable(channel) => channel.has_resends_due(current_time),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ReceiveChannel {
     Empty,
@@ -120,6 +224,7 @@ enum ReceiveChannel {
 }
 
 /// Describes the stats of a connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NetworkInfo {
     /// Round-trip Time
     pub rtt: f64,
@@ -128,6 +233,17 @@ pub struct NetworkInfo {
     pub bytes_received_per_second: f64,
 }
 
+/// Whether the quality of a connection is getting better or worse, see [`RenetClient::connection_trend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionTrend {
+    /// The rtt or packet loss went down.
+    Improving,
+    /// The rtt and packet loss are stable.
+    Stable,
+    /// The rtt or packet loss went up.
+    Degrading,
+}
+
 /// The connection status of a [`RenetClient`].
 #[derive(Debug)]
 pub enum RenetConnectionStatus {
@@ -146,10 +262,30 @@ pub struct RenetClient {
     pending_acks: Vec<Range<u64>>,
     channel_send_order: Vec<ChannelOrder>,
     send_channels: Vec<SendChannel>,
+    send_rate_limits: Vec<Option<ChannelRateLimit>>,
     receive_channels: Vec<ReceiveChannel>,
     stats: ConnectionStats,
+    /// Stats of each channel, indexed by channel id.
+    channel_stats: Vec<ChannelStats>,
+    trend: TrendTracker,
     available_bytes_per_tick: u64,
     connection_status: RenetConnectionStatus,
+    client_id: Option<ClientId>,
+    buffer_pool: Arc<dyn BufferPool>,
+    coalesce_delay: Option<Duration>,
+    coalesce_started_at: Option<Duration>,
+    /// Whether the last send left messages unsent, e.g. because of the send budget or rate limits.
+    send_backlog: bool,
+    slice_size: usize,
+    max_concurrent_reassemblies: usize,
+    packet_checksum: Option<u64>,
+    /// Bytes this connection may send in the next call to `get_packets_to_send`, assigned by the server when it
+    /// has a shared send budget.
+    send_budget: Option<u64>,
+    /// Part of the last send budget that could not be used while messages were waiting, added to the next budget.
+    send_budget_carry: u64,
+    /// Number of consecutive sends in which the connection was held back by its share of the server's send budget.
+    send_starvation: f32,
     rtt: f64,
 }
 
@@ -160,6 +296,9 @@ impl RenetClient {
     ///
     /// See `ClientSocket::is_reliable` in `renet2_netcode`.
     pub fn new(mut config: ConnectionConfig, has_reliable_socket: bool) -> Self {
+        if let Err(err) = config.validate() {
+            panic!("invalid connection config: {err}");
+        }
         if has_reliable_socket {
             config.downgrade_to_unreliable();
         }
@@ -169,12 +308,20 @@ impl RenetClient {
             config.available_bytes_per_tick,
             config.client_channels_config,
             config.server_channels_config,
+            config.buffer_pool,
+            config.coalesce_delay,
+            config.max_concurrent_reassemblies,
+            config.packet_checksum,
+            config.slice_size,
         )
     }
 
     // When creating a client from the server, the server_channels_config are used as send channels,
     // and the client_channels_config is used as recv channels.
     pub(crate) fn new_from_server(mut config: ConnectionConfig, has_reliable_socket: bool) -> Self {
+        if let Err(err) = config.validate() {
+            panic!("invalid connection config: {err}");
+        }
         if has_reliable_socket {
             config.downgrade_to_unreliable();
         }
@@ -184,20 +331,33 @@ impl RenetClient {
             config.available_bytes_per_tick,
             config.server_channels_config,
             config.client_channels_config,
+            config.buffer_pool,
+            config.coalesce_delay,
+            config.max_concurrent_reassemblies,
+            config.packet_checksum,
+            config.slice_size,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn from_channels(
         has_reliable_socket: bool,
         available_bytes_per_tick: u64,
         send_channels_config: Vec<ChannelConfig>,
         receive_channels_config: Vec<ChannelConfig>,
+        buffer_pool: Arc<dyn BufferPool>,
+        coalesce_delay: Option<Duration>,
+        max_concurrent_reassemblies: usize,
+        packet_checksum: Option<u64>,
+        slice_size: usize,
     ) -> Self {
         let max_send_channel = send_channels_config.iter().map(|c| c.channel_id).max().unwrap_or_default();
         let max_receive_channel = receive_channels_config.iter().map(|c| c.channel_id).max().unwrap_or_default();
 
         let mut send_channels = Vec::new();
         send_channels.resize_with(max_send_channel as usize + 1, || SendChannel::Empty);
+        let mut send_rate_limits = Vec::new();
+        send_rate_limits.resize_with(max_send_channel as usize + 1, || None);
         let mut channel_send_order: Vec<ChannelOrder> = Vec::with_capacity(send_channels_config.len());
         for channel_config in send_channels_config.iter() {
             let send_channel = &mut send_channels[channel_config.channel_id as usize];
@@ -206,27 +366,41 @@ impl RenetClient {
                 "already exists send channel {}",
                 channel_config.channel_id
             );
+            send_rate_limits[channel_config.channel_id as usize] = channel_config.max_bytes_per_sec.map(ChannelRateLimit::new);
 
             match channel_config.send_type {
                 SendType::Unreliable {
                     ordered_reliable_substrate,
                 } => {
                     channel_send_order.push(ChannelOrder::Unreliable(channel_config.channel_id));
-                    let channel = SendChannelUnreliable::new(
+                    let mut channel = SendChannelUnreliable::new(
                         channel_config.channel_id,
                         channel_config.max_memory_usage_bytes,
+                        // Rate-limited channels defer messages instead of dropping them.
+                        ordered_reliable_substrate || channel_config.max_bytes_per_sec.is_some(),
+                        channel_config.dedup_window.is_some(),
                         ordered_reliable_substrate,
                     );
+                    channel.set_slice_size(slice_size);
                     *send_channel = SendChannel::Unreliable(channel);
                 }
                 SendType::ReliableOrdered { resend_time } | SendType::ReliableUnordered { resend_time } => {
                     channel_send_order.push(ChannelOrder::Reliable(channel_config.channel_id));
-                    let channel = SendChannelReliable::new(channel_config.channel_id, resend_time, channel_config.max_memory_usage_bytes);
+                    let mut channel = SendChannelReliable::new(
+                        channel_config.channel_id,
+                        resend_time,
+                        channel_config.max_memory_usage_bytes,
+                        channel_config.initial_burst_limit,
+                    );
+                    channel.set_slice_size(slice_size);
                     *send_channel = SendChannel::Reliable(channel);
                 }
             }
         }
 
+        let mut channel_stats = Vec::new();
+        channel_stats.resize_with(max_send_channel.max(max_receive_channel) as usize + 1, ChannelStats::default);
+
         let mut receive_channels = Vec::new();
         receive_channels.resize_with(max_receive_channel as usize + 1, || ReceiveChannel::Empty);
         for channel_config in receive_channels_config.iter() {
@@ -239,15 +413,22 @@ impl RenetClient {
 
             match channel_config.send_type {
                 SendType::Unreliable { .. } => {
-                    let channel = ReceiveChannelUnreliable::new(channel_config.channel_id, channel_config.max_memory_usage_bytes);
+                    let mut channel = ReceiveChannelUnreliable::new(
+                        channel_config.channel_id,
+                        channel_config.max_memory_usage_bytes,
+                        channel_config.dedup_window,
+                    );
+                    channel.set_slice_size(slice_size);
                     *receive_channel = ReceiveChannel::Unreliable(channel);
                 }
                 SendType::ReliableOrdered { .. } => {
-                    let channel = ReceiveChannelReliable::new(channel_config.max_memory_usage_bytes, true);
+                    let mut channel = ReceiveChannelReliable::new(channel_config.max_memory_usage_bytes, true);
+                    channel.set_slice_size(slice_size);
                     *receive_channel = ReceiveChannel::Reliable(channel);
                 }
                 SendType::ReliableUnordered { .. } => {
-                    let channel = ReceiveChannelReliable::new(channel_config.max_memory_usage_bytes, false);
+                    let mut channel = ReceiveChannelReliable::new(channel_config.max_memory_usage_bytes, false);
+                    channel.set_slice_size(slice_size);
                     *receive_channel = ReceiveChannel::Reliable(channel);
                 }
             }
@@ -261,14 +442,42 @@ impl RenetClient {
             pending_acks: Vec::new(),
             channel_send_order,
             send_channels,
+            send_rate_limits,
             receive_channels,
             stats: ConnectionStats::new(),
+            channel_stats,
+            trend: TrendTracker::default(),
             rtt: 0.0,
             available_bytes_per_tick,
             connection_status: RenetConnectionStatus::Connecting,
+            client_id: None,
+            buffer_pool,
+            coalesce_delay,
+            coalesce_started_at: None,
+            send_backlog: false,
+            slice_size,
+            max_concurrent_reassemblies,
+            packet_checksum,
+            send_budget: None,
+            send_budget_carry: 0,
+            send_starvation: 0.0,
         }
     }
 
+    /// Returns the id the server knows this client by.
+    ///
+    /// This is set by the transport once the client has connected, and is `None` before that.
+    pub fn client_id(&self) -> Option<ClientId> {
+        self.client_id
+    }
+
+    /// Sets the id the server knows this client by.
+    ///
+    /// Transports should call this when the client connects, so users don't need to access the transport to learn the id.
+    pub fn set_client_id(&mut self, client_id: ClientId) {
+        self.client_id = Some(client_id);
+    }
+
     /// Returns whether this client uses a reliable underlying socket.
     pub fn has_reliable_socket(&self) -> bool {
         self.has_reliable_socket
@@ -294,6 +503,40 @@ impl RenetClient {
         self.stats.bytes_received_per_second(self.current_time)
     }
 
+    /// Returns the total bytes sent on a channel, or 0 for invalid channels.
+    ///
+    /// Packet overhead is included, but acks are not sent on a channel so they aren't counted.
+    pub fn channel_bytes_sent<I: Into<u8>>(&self, channel_id: I) -> u64 {
+        self.channel_stats
+            .get(channel_id.into() as usize)
+            .map_or(0, |stats| stats.total_bytes_sent())
+    }
+
+    /// Returns the total bytes received on a channel, or 0 for invalid channels.
+    ///
+    /// Packet overhead is included, but acks are not sent on a channel so they aren't counted.
+    pub fn channel_bytes_received<I: Into<u8>>(&self, channel_id: I) -> u64 {
+        self.channel_stats
+            .get(channel_id.into() as usize)
+            .map_or(0, |stats| stats.total_bytes_received())
+    }
+
+    /// Returns the bytes sent per second on a channel, or 0.0 for invalid channels.
+    pub fn channel_bytes_sent_per_sec<I: Into<u8>>(&self, channel_id: I) -> f64 {
+        match self.channel_stats.get(channel_id.into() as usize) {
+            Some(stats) => stats.bytes_sent_per_second(self.current_time),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the bytes received per second on a channel, or 0.0 for invalid channels.
+    pub fn channel_bytes_received_per_sec<I: Into<u8>>(&self, channel_id: I) -> f64 {
+        match self.channel_stats.get(channel_id.into() as usize) {
+            Some(stats) => stats.bytes_received_per_second(self.current_time),
+            None => 0.0,
+        }
+    }
+
     /// Returns all network information for the connection.
     pub fn network_info(&self) -> NetworkInfo {
         NetworkInfo {
@@ -304,6 +547,28 @@ impl RenetClient {
         }
     }
 
+    /// Returns whether the connection quality is improving, stable, or degrading.
+    ///
+    /// The rtt and packet loss are sampled every 500ms in [`Self::update`], and the average of the newest 2.5 seconds
+    /// of samples is compared to the average of the 2.5 seconds before that. The trend is degrading if the rtt grew by
+    /// more than 20% (and at least 5ms) or the packet loss grew by more than 2 percentage points, and improving if either
+    /// shrank by as much. A connection younger than 5 seconds is always stable.
+    pub fn connection_trend(&self) -> ConnectionTrend {
+        self.trend.trend()
+    }
+
+    /// Returns the smoothed time between first sending a message on a reliable channel and receiving its ack,
+    /// including any resends.
+    ///
+    /// This is at least the round-trip time, and can be used to tune [`SendType`] resend times. Returns `None` for
+    /// unreliable or invalid channels, and for reliable channels that have not had any messages acked yet.
+    pub fn channel_effective_latency<I: Into<u8>>(&self, channel_id: I) -> Option<Duration> {
+        match self.send_channels.get(channel_id.into() as usize) {
+            Some(SendChannel::Reliable(channel)) => channel.effective_latency(),
+            _ => None,
+        }
+    }
+
     /// Returns whether the client is connected.
     #[inline]
     pub fn is_connected(&self) -> bool {
@@ -386,6 +651,22 @@ impl RenetClient {
         }
     }
 
+    /// Overrides [`ChannelConfig::max_memory_usage_bytes`] for messages sent on the given channel.
+    ///
+    /// Lowering the limit below the current memory usage does not drop messages that are already queued, but new
+    /// messages won't fit until enough memory is freed. Unreliable messages that don't fit are dropped, while
+    /// reliable channels disconnect with [`ChannelError::ReliableChannelMaxMemoryReached`](crate::ChannelError::ReliableChannelMaxMemoryReached).
+    pub fn set_channel_memory<I: Into<u8>>(&mut self, channel_id: I, max_memory_usage_bytes: usize) {
+        let channel_id = channel_id.into();
+        match self.send_channels.get_mut(channel_id as usize) {
+            None | Some(SendChannel::Empty) => {
+                panic!("Called 'set_channel_memory' with invalid channel {channel_id}");
+            }
+            Some(SendChannel::Reliable(reliable_channel)) => reliable_channel.set_max_memory_usage_bytes(max_memory_usage_bytes),
+            Some(SendChannel::Unreliable(unreliable_channel)) => unreliable_channel.set_max_memory_usage_bytes(max_memory_usage_bytes),
+        }
+    }
+
     /// Checks if the channel can send a message with the given size in bytes.
     pub fn can_send_message<I: Into<u8>>(&self, channel_id: I, size_bytes: usize) -> bool {
         let channel_id = channel_id.into();
@@ -398,29 +679,156 @@ impl RenetClient {
         }
     }
 
+    /// Returns the number of messages on a channel that are waiting to be sent.
+    ///
+    /// For reliable channels this includes messages that were sent but not acked yet.
+    pub fn pending_message_count<I: Into<u8>>(&self, channel_id: I) -> usize {
+        let channel_id = channel_id.into();
+        match self.send_channels.get(channel_id as usize) {
+            None | Some(SendChannel::Empty) => {
+                panic!("Called 'pending_message_count' with invalid channel {channel_id}");
+            }
+            Some(SendChannel::Reliable(reliable_channel)) => reliable_channel.pending_message_count(),
+            Some(SendChannel::Unreliable(unreliable_channel)) => unreliable_channel.pending_message_count(),
+        }
+    }
+
+    /// Drops all messages on a channel that are waiting to be sent, returning the number of messages dropped.
+    ///
+    /// This is intended for unreliable channels, e.g. to cancel queued updates that are no longer relevant.
+    ///
+    /// **Warning**: clearing a reliable channel drops messages that were sent but not acked yet. The receiver will
+    /// never get those messages, so an ordered channel will stop delivering messages and the peers will desync.
+    pub fn clear_channel<I: Into<u8>>(&mut self, channel_id: I) -> usize {
+        let channel_id = channel_id.into();
+        match self.send_channels.get_mut(channel_id as usize) {
+            None | Some(SendChannel::Empty) => {
+                panic!("Called 'clear_channel' with invalid channel {channel_id}");
+            }
+            Some(SendChannel::Reliable(reliable_channel)) => reliable_channel.clear(),
+            Some(SendChannel::Unreliable(unreliable_channel)) => unreliable_channel.clear(),
+        }
+    }
+
     /// Send a message to the server over a channel.
     pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) {
+        self.send_message_with("send_message", channel_id.into(), 0, None, message.into());
+    }
+
+    /// Send a message to the server over a channel, with a priority.
+    ///
+    /// On unreliable channels, messages with a higher priority are sent before the messages with a lower priority
+    /// that are waiting to be sent, so they are the last to be dropped when the channel can't send everything in a
+    /// tick. Messages sent with [`Self::send_message`] have priority 0.
+    ///
+    /// Reliable channels, and unreliable channels on an ordered-reliable substrate, ignore the priority to preserve
+    /// the order of messages.
+    pub fn send_message_prioritized<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, priority: u8, message: B) {
+        self.send_message_with("send_message_prioritized", channel_id.into(), priority, None, message.into());
+    }
+
+    /// Send a message to the server over a channel, with a key used to drop duplicates.
+    ///
+    /// If the channel is unreliable (including reliable channels downgraded on a reliable socket) and has a
+    /// [`ChannelConfig::dedup_window`], the receiver will drop this message if it recently received another message
+    /// with the same key. Otherwise the key is ignored.
+    pub fn send_message_keyed<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, key: u64, message: B) {
+        self.send_message_with("send_message_keyed", channel_id.into(), 0, Some(key), message.into());
+    }
+
+    /// Sends a message with the options of the public send methods, `method` is the name used in panics.
+    fn send_message_with(&mut self, method: &str, channel_id: u8, priority: u8, key: Option<u64>, message: Bytes) {
         if self.is_disconnected() {
             return;
         }
 
-        let channel_id = channel_id.into();
         match self.send_channels.get_mut(channel_id as usize) {
             None | Some(SendChannel::Empty) => {
-                panic!("Called 'send_message' with invalid channel {channel_id}");
+                panic!("Called '{method}' with invalid channel {channel_id}");
             }
             Some(SendChannel::Reliable(reliable_channel)) => {
-                if let Err(error) = reliable_channel.send_message(message.into()) {
+                if let Err(error) = reliable_channel.send_message(message) {
                     self.disconnect_with_reason(DisconnectReason::SendChannelError { channel_id, error });
                 }
             }
-            Some(SendChannel::Unreliable(unreliable_channel)) => {
-                unreliable_channel.send_message(message.into());
+            Some(SendChannel::Unreliable(unreliable_channel)) => match key {
+                Some(key) => unreliable_channel.send_message_keyed(key, message),
+                None => unreliable_channel.send_message_prioritized(priority, message),
+            },
+        }
+    }
+
+    /// Send a message to the server over a channel, and start tracking its status.
+    ///
+    /// Use [`Self::message_status`] to see how far the message got, e.g. when diagnosing lost messages. Status
+    /// changes are logged at the `debug` level.
+    #[cfg(feature = "debug_tools")]
+    pub fn send_message_tracked<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) -> MessageId {
+        let channel_id = channel_id.into();
+        let message_id = match self.send_channels.get_mut(channel_id as usize) {
+            None | Some(SendChannel::Empty) => {
+                panic!("Called 'send_message_tracked' with invalid channel {channel_id}");
+            }
+            Some(SendChannel::Reliable(reliable_channel)) => reliable_channel.track_next_message(),
+            Some(SendChannel::Unreliable(unreliable_channel)) => unreliable_channel.track_next_message(),
+        };
+
+        self.send_message(channel_id, message);
+        if self.is_disconnected() {
+            self.drop_tracked_messages();
+        }
+
+        MessageId { channel_id, message_id }
+    }
+
+    /// Returns the status of a message sent with [`Self::send_message_tracked`].
+    ///
+    /// Only the most recent finished messages of each channel are remembered, older ones are
+    /// [`MessageStatus::Unknown`].
+    #[cfg(feature = "debug_tools")]
+    pub fn message_status(&self, message_id: MessageId) -> MessageStatus {
+        match self.send_channels.get(message_id.channel_id as usize) {
+            None | Some(SendChannel::Empty) => MessageStatus::Unknown,
+            Some(SendChannel::Reliable(reliable_channel)) => reliable_channel.message_status(message_id.message_id),
+            Some(SendChannel::Unreliable(unreliable_channel)) => unreliable_channel.message_status(message_id.message_id),
+        }
+    }
+
+    #[cfg(feature = "debug_tools")]
+    fn drop_tracked_messages(&mut self) {
+        for send_channel in self.send_channels.iter_mut() {
+            match send_channel {
+                SendChannel::Empty => (),
+                SendChannel::Reliable(reliable_channel) => reliable_channel.drop_tracked_messages(),
+                SendChannel::Unreliable(unreliable_channel) => unreliable_channel.drop_tracked_messages(),
             }
         }
     }
 
+    /// Returns the number of channels configured to receive messages from the server.
+    pub fn receive_channel_count(&self) -> usize {
+        self.receive_channels
+            .iter()
+            .filter(|channel| !matches!(channel, ReceiveChannel::Empty))
+            .count()
+    }
+
+    /// Returns `true` if a channel is configured to receive messages from the server.
+    ///
+    /// [`Self::receive_message`] panics for channels that aren't configured, e.g. if the client and server were
+    /// set up with different channels.
+    pub fn has_receive_channel<I: Into<u8>>(&self, channel_id: I) -> bool {
+        !matches!(
+            self.receive_channels.get(channel_id.into() as usize),
+            None | Some(ReceiveChannel::Empty)
+        )
+    }
+
     /// Receive a message from the server over a channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel is not configured, see [`Self::has_receive_channel`].
     pub fn receive_message<I: Into<u8>>(&mut self, channel_id: I) -> Option<Bytes> {
         if self.is_disconnected() {
             return None;
@@ -436,11 +844,42 @@ impl RenetClient {
         }
     }
 
+    /// Drops all messages that were received but not consumed yet, on every channel, returning the number of
+    /// messages dropped. The connection is not affected.
+    ///
+    /// Useful to discard data from an old context after a state reset (e.g. when entering a new level). This only
+    /// drops messages that [`Self::receive_message`] would return right now: messages that are still in flight, or
+    /// partially received (e.g. message slices, or reliable ordered messages waiting on an earlier message), are
+    /// received normally later.
+    pub fn clear_received(&mut self) -> usize {
+        let mut count = 0;
+        for receive_channel in self.receive_channels.iter_mut() {
+            match receive_channel {
+                ReceiveChannel::Empty => (),
+                ReceiveChannel::Reliable(reliable_channel) => {
+                    while reliable_channel.receive_message().is_some() {
+                        count += 1;
+                    }
+                }
+                ReceiveChannel::Unreliable(unreliable_channel) => {
+                    while unreliable_channel.receive_message().is_some() {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
     /// Advances the client by the duration.
     /// Should be called every tick
     pub fn update(&mut self, duration: Duration) {
         self.current_time += duration;
         self.stats.update(self.current_time);
+        for channel_stats in self.channel_stats.iter_mut() {
+            channel_stats.update(self.current_time);
+        }
+        self.trend.update(self.current_time, self.rtt, self.stats.packet_loss());
 
         for unreliable_channel in self.receive_channels.iter_mut() {
             let ReceiveChannel::Unreliable(unreliable_channel) = unreliable_channel else {
@@ -467,6 +906,17 @@ impl RenetClient {
         }
     }
 
+    fn num_reassemblies(&self) -> usize {
+        self.receive_channels
+            .iter()
+            .map(|channel| match channel {
+                ReceiveChannel::Empty => 0,
+                ReceiveChannel::Unreliable(channel) => channel.num_reassemblies(),
+                ReceiveChannel::Reliable(channel) => channel.num_reassemblies(),
+            })
+            .sum()
+    }
+
     /// Process a packet received from the server.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
     /// <strong>Note:</strong> This should only be called by the transport layer.
@@ -476,9 +926,20 @@ impl RenetClient {
             return;
         }
 
-        self.stats.received_packet(packet.len() as u64);
+        let received_bytes = packet.len() as u64;
+        self.stats.received_packet(received_bytes);
+        let packet = match self.packet_checksum {
+            Some(protocol_id) => match verify_checksum(packet, protocol_id) {
+                Ok(packet) => packet,
+                Err(err) => {
+                    log::debug!("Dropped received packet: {err}");
+                    return;
+                }
+            },
+            None => packet,
+        };
         let mut octets = octets::Octets::with_slice(packet);
-        let packet = match Packet::from_bytes(&mut octets) {
+        let packet = match Packet::from_bytes(&mut octets, &*self.buffer_pool) {
             Err(err) => {
                 self.disconnect_with_reason(DisconnectReason::PacketDeserialization(err));
                 return;
@@ -487,6 +948,12 @@ impl RenetClient {
         };
 
         self.add_pending_ack(packet.sequence());
+        if let Some(stats) = packet
+            .channel_id()
+            .and_then(|channel_id| self.channel_stats.get_mut(channel_id as usize))
+        {
+            stats.received_bytes(received_bytes);
+        }
 
         match packet {
             Packet::SmallReliable { channel_id, messages, .. } => {
@@ -513,22 +980,38 @@ impl RenetClient {
                 }
             }
             Packet::ReliableSlice { channel_id, slice, .. } => {
+                let num_reassemblies = self.num_reassemblies();
                 let Some(ReceiveChannel::Reliable(channel)) = self.receive_channels.get_mut(channel_id as usize) else {
                     self.disconnect_with_reason(DisconnectReason::ReceivedInvalidChannelId(channel_id));
                     return;
                 };
 
-                if let Err(error) = channel.process_slice(slice) {
+                if channel.starts_reassembly(slice.message_id) && num_reassemblies >= self.max_concurrent_reassemblies {
+                    let error = ChannelError::TooManyReassemblies;
+                    self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
+                    return;
+                }
+
+                if let Err(error) = channel.process_slice(slice, &*self.buffer_pool) {
                     self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
                 }
             }
             Packet::UnreliableSlice { channel_id, slice, .. } => {
+                let num_reassemblies = self.num_reassemblies();
                 let Some(ReceiveChannel::Unreliable(channel)) = self.receive_channels.get_mut(channel_id as usize) else {
                     self.disconnect_with_reason(DisconnectReason::ReceivedInvalidChannelId(channel_id));
                     return;
                 };
 
-                if let Err(error) = channel.process_slice(slice, self.current_time) {
+                if channel.starts_reassembly(slice.message_id)
+                    && num_reassemblies >= self.max_concurrent_reassemblies
+                    && !channel.discard_oldest_reassembly()
+                {
+                    log::warn!("dropped unreliable slice received because too many sliced messages are being reassembled");
+                    return;
+                }
+
+                if let Err(error) = channel.process_slice(slice, self.current_time, &*self.buffer_pool) {
                     self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
                 }
             }
@@ -560,7 +1043,7 @@ impl RenetClient {
                                 panic!("Acked packet has invalid channel {channel_id}");
                             };
                             for message_id in message_ids {
-                                channel.process_message_ack(message_id);
+                                channel.process_message_ack(message_id, self.current_time);
                             }
                         }
                         PacketSentInfo::ReliableSliceMessage {
@@ -571,7 +1054,7 @@ impl RenetClient {
                             let SendChannel::Reliable(channel) = self.send_channels.get_mut(channel_id as usize).unwrap() else {
                                 panic!("Acked packet has invalid channel {channel_id}");
                             };
-                            channel.process_slice_message_ack(message_id, slice_index);
+                            channel.process_slice_message_ack(message_id, slice_index, self.current_time);
                         }
                         PacketSentInfo::Ack { largest_acked_packet } => {
                             self.acked_largest(largest_acked_packet);
@@ -593,24 +1076,66 @@ impl RenetClient {
             return vec![];
         }
 
-        let mut available_bytes = self.available_bytes_per_tick;
-        for order in self.channel_send_order.iter() {
-            match order {
-                ChannelOrder::Reliable(channel_id) => {
-                    let SendChannel::Reliable(channel) = self.send_channels.get_mut(*channel_id as usize).unwrap() else {
-                        panic!("Packet to send has invalid channel {channel_id}");
-                    };
-                    packets.append(&mut channel.get_packets_to_send(&mut self.packet_sequence, &mut available_bytes, self.current_time));
+        // Acks are still sent while messages are held back for coalescing.
+        let send_budget = self.send_budget.take();
+        let mut unused_budget = send_budget;
+        if !self.should_coalesce() {
+            let mut available_bytes = match send_budget {
+                Some(send_budget) => send_budget.min(self.available_bytes_per_tick),
+                None => self.available_bytes_per_tick,
+            };
+            for order in self.channel_send_order.iter() {
+                let (ChannelOrder::Reliable(channel_id) | ChannelOrder::Unreliable(channel_id)) = order;
+                let mut rate_limit = self.send_rate_limits[*channel_id as usize].as_mut();
+                let mut channel_bytes = match rate_limit.as_mut() {
+                    Some(rate_limit) => {
+                        let next_send_bytes = self.send_channels[*channel_id as usize].next_send_bytes();
+                        available_bytes.min(rate_limit.available_bytes(self.current_time, next_send_bytes))
+                    }
+                    None => available_bytes,
+                };
+                let start_bytes = channel_bytes;
+
+                match order {
+                    ChannelOrder::Reliable(channel_id) => {
+                        let SendChannel::Reliable(channel) = self.send_channels.get_mut(*channel_id as usize).unwrap() else {
+                            panic!("Packet to send has invalid channel {channel_id}");
+                        };
+                        packets.append(&mut channel.get_packets_to_send(&mut self.packet_sequence, &mut channel_bytes, self.current_time));
+                    }
+                    ChannelOrder::Unreliable(channel_id) => {
+                        let SendChannel::Unreliable(channel) = self.send_channels.get_mut(*channel_id as usize).unwrap() else {
+                            panic!("Packet to send has invalid channel {channel_id}");
+                        };
+                        packets.append(&mut channel.get_packets_to_send(&mut self.packet_sequence, &mut channel_bytes));
+                    }
                 }
-                ChannelOrder::Unreliable(channel_id) => {
-                    let SendChannel::Unreliable(channel) = self.send_channels.get_mut(*channel_id as usize).unwrap() else {
-                        panic!("Packet to send has invalid channel {channel_id}");
-                    };
-                    packets.append(&mut channel.get_packets_to_send(&mut self.packet_sequence, &mut available_bytes));
+
+                let used_bytes = start_bytes - channel_bytes;
+                available_bytes -= used_bytes;
+                if let Some(rate_limit) = rate_limit {
+                    rate_limit.consume(used_bytes);
                 }
             }
+
+            // The connection is starving if its share of the server's budget kept it from sending everything.
+            let held_back = send_budget.is_some_and(|send_budget| send_budget < self.available_bytes_per_tick);
+            if held_back && self.has_unsent_messages() {
+                self.send_starvation += 1.0;
+            } else {
+                self.send_starvation = 0.0;
+            }
+            unused_budget = send_budget.map(|_| available_bytes);
+            self.send_backlog = self.has_unsent_messages();
         }
 
+        // Carrying the unused budget lets messages bigger than the connection's share of a tick (e.g. the slices of
+        // reliable messages) be sent once enough budget has accumulated.
+        self.send_budget_carry = match unused_budget {
+            Some(unused_budget) if self.has_unsent_messages() => unused_budget.min(self.available_bytes_per_tick),
+            _ => 0,
+        };
+
         if !self.pending_acks.is_empty() {
             let ack_packet = Packet::Ack {
                 sequence: self.packet_sequence,
@@ -692,6 +1217,7 @@ impl RenetClient {
         let mut serialized_packets = Vec::with_capacity(packets.len());
         let mut bytes_sent: u64 = 0;
         for packet in packets {
+            let channel_id = packet.channel_id();
             let mut oct = OctetsMut::with_slice(&mut buffer);
             let len = match packet.to_bytes(&mut oct) {
                 Err(err) => {
@@ -700,8 +1226,21 @@ impl RenetClient {
                 }
                 Ok(len) => len,
             };
+            let len = match self.packet_checksum {
+                Some(protocol_id) => match write_checksum(&mut buffer, len, protocol_id) {
+                    Err(err) => {
+                        self.disconnect_with_reason(DisconnectReason::PacketSerialization(err));
+                        return vec![];
+                    }
+                    Ok(len) => len,
+                },
+                None => len,
+            };
 
             bytes_sent += len as u64;
+            if let Some(stats) = channel_id.and_then(|channel_id| self.channel_stats.get_mut(channel_id as usize)) {
+                stats.sent_bytes(len as u64);
+            }
             serialized_packets.push(buffer[..len].to_vec());
         }
 
@@ -784,9 +1323,59 @@ impl RenetClient {
         }
     }
 
+    /// Returns true if any send channel has messages that weren't sent yet.
+    pub(crate) fn has_unsent_messages(&self) -> bool {
+        self.send_channels.iter().any(|channel| match channel {
+            SendChannel::Reliable(reliable_channel) => reliable_channel.has_unsent_messages(),
+            SendChannel::Unreliable(unreliable_channel) => unreliable_channel.pending_message_count() > 0,
+            SendChannel::Empty => false,
+        })
+    }
+
+    /// Limits the bytes sent by the next call to `get_packets_to_send`, see `RenetServer::set_max_bytes_per_tick`.
+    ///
+    /// Budget left unused by the previous send is added while the connection has messages waiting.
+    pub(crate) fn set_send_budget(&mut self, send_budget: u64) {
+        self.send_budget = Some(send_budget + self.send_budget_carry);
+    }
+
+    pub(crate) fn send_starvation(&self) -> f32 {
+        self.send_starvation
+    }
+
+    /// Returns true if unsent messages should be held back to wait for more messages, see
+    /// [`ConnectionConfig::coalesce_delay`].
+    fn should_coalesce(&mut self) -> bool {
+        let Some(coalesce_delay) = self.coalesce_delay else {
+            return false;
+        };
+        if !self.has_unsent_messages() {
+            self.coalesce_started_at = None;
+            return false;
+        }
+
+        let unsent_bytes: usize = self.send_channels.iter().map(SendChannel::unsent_bytes).sum();
+        let resends_due = self.send_channels.iter().any(|channel| channel.has_resends_due(self.current_time));
+        if self.send_backlog || resends_due || unsent_bytes >= self.slice_size {
+            self.coalesce_started_at = None;
+            return false;
+        }
+
+        let started_at = *self.coalesce_started_at.get_or_insert(self.current_time);
+        if self.current_time - started_at < coalesce_delay {
+            return true;
+        }
+
+        // Start a new window for messages that are sent after this batch.
+        self.coalesce_started_at = None;
+        false
+    }
+
     pub(crate) fn disconnect_with_reason(&mut self, reason: DisconnectReason) {
         if !self.is_disconnected() {
             self.connection_status = RenetConnectionStatus::Disconnected { reason };
+            #[cfg(feature = "debug_tools")]
+            self.drop_tracked_messages();
         }
     }
 }
@@ -794,6 +1383,7 @@ impl RenetClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::packet::Slice;
 
     #[test]
     fn pending_acks() {
@@ -823,6 +1413,18 @@ mod tests {
         assert_eq!(connection.pending_acks, vec![0..8]);
     }
 
+    #[test]
+    fn receive_channels() {
+        let mut channels = DefaultChannel::config();
+        channels.remove(1);
+        let connection = RenetClient::new(ConnectionConfig::from_shared_channels(channels), false);
+        assert_eq!(connection.receive_channel_count(), 2);
+        assert!(connection.has_receive_channel(0));
+        assert!(!connection.has_receive_channel(1));
+        assert!(connection.has_receive_channel(2));
+        assert!(!connection.has_receive_channel(3));
+    }
+
     #[test]
     fn ack_pending_acks() {
         let mut connection = RenetClient::new(ConnectionConfig::test(), false);
@@ -849,6 +1451,308 @@ mod tests {
         assert_eq!(connection.pending_acks, vec![]);
     }
 
+    #[test]
+    fn coalesce_delay() {
+        fn count_packets(coalesce_delay: Option<Duration>) -> usize {
+            let mut config = ConnectionConfig::test();
+            config.coalesce_delay = coalesce_delay;
+            let mut connection = RenetClient::new(config, false);
+
+            let mut packet_count = 0;
+            for _ in 0..10 {
+                connection.send_message(DefaultChannel::Unreliable, vec![5; 5]);
+                packet_count += connection.get_packets_to_send().len();
+                connection.update(Duration::from_millis(1));
+            }
+            connection.update(Duration::from_millis(3));
+            packet_count += connection.get_packets_to_send().len();
+
+            assert_eq!(connection.pending_message_count(DefaultChannel::Unreliable), 0);
+            packet_count
+        }
+
+        assert_eq!(count_packets(None), 10);
+        // Messages are held for 3ms, so every 4 messages share a packet.
+        assert_eq!(count_packets(Some(Duration::from_millis(3))), 3);
+    }
+
+    #[test]
+    fn coalesce_delay_backlog() {
+        // Queues more messages than fit in a tick, then returns the bytes sent after some ticks.
+        fn bytes_sent(coalesce_delay: Option<Duration>, message_len: usize, available_bytes_per_tick: u64, ticks: usize) -> usize {
+            let mut config = ConnectionConfig::test();
+            config.available_bytes_per_tick = available_bytes_per_tick;
+            config.coalesce_delay = coalesce_delay;
+            let mut connection = RenetClient::new(config, false);
+
+            for _ in 0..10 {
+                connection.send_message(DefaultChannel::ReliableOrdered, vec![5; message_len]);
+            }
+            let mut bytes_sent = 0;
+            for _ in 0..ticks {
+                bytes_sent += connection.get_packets_to_send().iter().map(|packet| packet.len()).sum::<usize>();
+                connection.update(Duration::from_millis(1));
+            }
+            bytes_sent
+        }
+
+        let delay = Some(Duration::from_millis(50));
+        // Messages that fill packets are sent right away.
+        assert_eq!(bytes_sent(delay, 2_000, 2_000, 5), bytes_sent(None, 2_000, 2_000, 5));
+        // Small messages are held once, then the backlog is sent as fast as without the delay.
+        assert_eq!(bytes_sent(delay, 100, 300, 50), 0);
+        assert_eq!(bytes_sent(delay, 100, 300, 60), bytes_sent(None, 100, 300, 10));
+    }
+
+    #[test]
+    fn slice_size() {
+        let mut config = ConnectionConfig::test();
+        config.slice_size = 500;
+        let mut sender = RenetClient::new(config.clone(), false);
+        let mut receiver = RenetClient::new_from_server(config, false);
+
+        let message = vec![5; 1600];
+        sender.send_message(DefaultChannel::ReliableOrdered, message.clone());
+        let packets = sender.get_packets_to_send();
+        // 1600 bytes are split into 3 slices of 500 bytes and one of 100 bytes.
+        assert_eq!(packets.len(), 4);
+        assert!(packets.iter().all(|packet| packet.len() < 500 + 20));
+        for packet in packets {
+            receiver.process_packet(&packet);
+        }
+        assert_eq!(
+            receiver.receive_message(DefaultChannel::ReliableOrdered).as_deref(),
+            Some(&message[..])
+        );
+    }
+
+    #[test]
+    fn channel_bytes() {
+        let mut sender = RenetClient::new(ConnectionConfig::test(), false);
+        let mut receiver = RenetClient::new_from_server(ConnectionConfig::test(), false);
+
+        sender.send_message(DefaultChannel::ReliableOrdered, vec![5; 100]);
+        sender.send_message(DefaultChannel::Unreliable, vec![5; 10]);
+        let packets = sender.get_packets_to_send();
+        let total_bytes: usize = packets.iter().map(|packet| packet.len()).sum();
+        for packet in packets {
+            receiver.process_packet(&packet);
+        }
+
+        let reliable_bytes = sender.channel_bytes_sent(DefaultChannel::ReliableOrdered);
+        let unreliable_bytes = sender.channel_bytes_sent(DefaultChannel::Unreliable);
+        assert!(reliable_bytes > 100);
+        assert!(unreliable_bytes > 10 && unreliable_bytes < reliable_bytes);
+        assert_eq!(reliable_bytes + unreliable_bytes, total_bytes as u64);
+        assert_eq!(sender.channel_bytes_sent(DefaultChannel::ReliableUnordered), 0);
+        assert_eq!(sender.channel_bytes_sent(100), 0);
+
+        assert_eq!(receiver.channel_bytes_received(DefaultChannel::ReliableOrdered), reliable_bytes);
+        assert_eq!(receiver.channel_bytes_received(DefaultChannel::Unreliable), unreliable_bytes);
+
+        // Acks aren't counted on any channel.
+        receiver.update(Duration::from_secs(1));
+        let ack_packets = receiver.get_packets_to_send();
+        assert_eq!(ack_packets.len(), 1);
+        assert_eq!(receiver.channel_bytes_sent(DefaultChannel::ReliableOrdered), 0);
+
+        sender.update(Duration::from_secs(1));
+        assert_eq!(
+            sender.channel_bytes_sent_per_sec(DefaultChannel::ReliableOrdered),
+            reliable_bytes as f64
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_size_above_limit() {
+        let mut config = ConnectionConfig::test();
+        config.slice_size = SLICE_SIZE + 1;
+        RenetClient::new(config, false);
+    }
+
+    #[test]
+    fn validate_slice_size() {
+        let mut config = ConnectionConfig::test();
+        assert_eq!(config.validate(), Ok(()));
+        for slice_size in [MIN_SLICE_SIZE - 1, SLICE_SIZE + 1] {
+            config.slice_size = slice_size;
+            assert_eq!(config.validate(), Err(ConnectionConfigError::InvalidSliceSize(slice_size)));
+        }
+        config.slice_size = MIN_SLICE_SIZE;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_max_concurrent_reassemblies() {
+        let mut config = ConnectionConfig::test();
+        config.max_concurrent_reassemblies = 0;
+        assert_eq!(config.validate(), Err(ConnectionConfigError::ZeroMaxConcurrentReassemblies));
+        config.max_concurrent_reassemblies = 1;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn packet_checksum() {
+        fn exchange(sender_checksum: Option<u64>, receiver_checksum: Option<u64>) -> Option<Bytes> {
+            let mut config = ConnectionConfig::test();
+            config.packet_checksum = sender_checksum;
+            let mut sender = RenetClient::new(config, false);
+            let mut config = ConnectionConfig::test();
+            config.packet_checksum = receiver_checksum;
+            let mut receiver = RenetClient::new_from_server(config, false);
+
+            sender.send_message(DefaultChannel::ReliableOrdered, vec![5; 5]);
+            for packet in sender.get_packets_to_send() {
+                receiver.process_packet(&packet);
+            }
+            assert!(receiver.is_connected() || receiver.is_connecting());
+            receiver.receive_message(DefaultChannel::ReliableOrdered)
+        }
+
+        assert_eq!(exchange(Some(7), Some(7)).as_deref(), Some(&[5u8; 5][..]));
+        // Packets with a different protocol id are dropped without disconnecting.
+        assert_eq!(exchange(Some(7), Some(8)), None);
+        assert_eq!(exchange(None, Some(7)), None);
+    }
+
+    #[test]
+    fn channel_rate_limit() {
+        let mut config = ConnectionConfig::test();
+        for channel_config in config.client_channels_config.iter_mut() {
+            channel_config.max_bytes_per_sec = Some(10_000);
+        }
+        let mut connection = RenetClient::new(config, false);
+
+        // Send 60_000 bytes per second for 10 seconds at 60hz.
+        let mut bytes_sent = 0;
+        let mut bytes_sent_after_first_second = 0;
+        for tick in 0..600 {
+            connection.send_message(DefaultChannel::Unreliable, vec![5; 1000]);
+            let sent: usize = connection.get_packets_to_send().iter().map(|packet| packet.len()).sum();
+            bytes_sent += sent;
+            if tick >= 60 {
+                bytes_sent_after_first_second += sent;
+            }
+            connection.update(Duration::from_secs_f64(1.0 / 60.0));
+        }
+
+        // The first second includes the initial burst.
+        assert!(bytes_sent <= 11 * 10_000 + 11 * 100, "sent {bytes_sent} bytes");
+        // After the burst, the channel sends at its rate (plus packet overhead).
+        let rate = bytes_sent_after_first_second as f64 / 9.0;
+        assert!((9_500.0..10_500.0).contains(&rate), "rate {rate}");
+
+        // Excess messages are deferred instead of dropped.
+        assert!(connection.pending_message_count(DefaultChannel::Unreliable) > 0);
+    }
+
+    #[test]
+    fn channel_rate_limit_large_messages() {
+        let mut config = ConnectionConfig::test();
+        for channel_config in config.client_channels_config.iter_mut() {
+            channel_config.max_bytes_per_sec = Some(500);
+        }
+        let mut sender = RenetClient::new(config.clone(), false);
+        let mut receiver = RenetClient::new_from_server(config, false);
+
+        // Messages larger than the limit are sent once the allowance is full, the channel then waits for it to be
+        // paid back before sending the next one.
+        let tick = Duration::from_millis(100);
+        for channel in [DefaultChannel::Unreliable, DefaultChannel::ReliableOrdered] {
+            let channel_id: u8 = channel.into();
+            sender.send_message(channel_id, vec![1; 2000]);
+            sender.send_message(channel_id, vec![2; 2000]);
+            let mut received = vec![];
+            for _ in 0..100 {
+                for packet in sender.get_packets_to_send() {
+                    receiver.process_packet(&packet);
+                }
+                for packet in receiver.get_packets_to_send() {
+                    sender.process_packet(&packet);
+                }
+                if let Some(message) = receiver.receive_message(channel_id) {
+                    received.push(message[0]);
+                    if received.len() == 1 {
+                        assert!(sender.pending_message_count(channel_id) > 0);
+                    }
+                }
+                sender.update(tick);
+                receiver.update(tick);
+            }
+            assert_eq!(received, vec![1, 2]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid connection config")]
+    fn channel_rate_limit_zero() {
+        let mut config = ConnectionConfig::test();
+        config.client_channels_config[0].max_bytes_per_sec = Some(0);
+        assert_eq!(config.validate(), Err(ConnectionConfigError::ZeroChannelRateLimit(0)));
+        RenetClient::new(config, false);
+    }
+
+    #[test]
+    fn max_concurrent_reassemblies() {
+        let mut config = ConnectionConfig::test();
+        config.max_concurrent_reassemblies = 4;
+        let mut connection = RenetClient::new(config, false);
+
+        // Sends the first of two slices of a message, so the message is never completed.
+        let mut sequence = 0;
+        let mut send_first_slice = |connection: &mut RenetClient, reliable: bool, message_id: u64| {
+            let slice = Slice {
+                message_id,
+                slice_index: 0,
+                num_slices: 2,
+                payload: vec![0; SLICE_SIZE].into(),
+            };
+            let packet = match reliable {
+                false => Packet::UnreliableSlice {
+                    sequence,
+                    channel_id: DefaultChannel::Unreliable.into(),
+                    slice,
+                },
+                true => Packet::ReliableSlice {
+                    sequence,
+                    channel_id: DefaultChannel::ReliableOrdered.into(),
+                    slice,
+                },
+            };
+            sequence += 1;
+
+            let mut buffer = [0u8; 1400];
+            let mut oct = OctetsMut::with_slice(&mut buffer);
+            let len = packet.to_bytes(&mut oct).unwrap();
+            connection.process_packet(&buffer[..len]);
+        };
+
+        for message_id in 0..2 {
+            send_first_slice(&mut connection, false, message_id);
+            send_first_slice(&mut connection, true, message_id);
+        }
+        // More slices of messages that are already being reassembled are accepted.
+        send_first_slice(&mut connection, true, 1);
+        assert!(!connection.is_disconnected());
+        assert_eq!(connection.num_reassemblies(), 4);
+
+        // Unreliable channels discard their oldest incomplete message instead of disconnecting.
+        send_first_slice(&mut connection, false, 2);
+        assert!(!connection.is_disconnected());
+        assert_eq!(connection.num_reassemblies(), 4);
+
+        // Reliable channels can't drop a message, so they disconnect.
+        send_first_slice(&mut connection, true, 2);
+        assert_eq!(
+            connection.disconnect_reason(),
+            Some(DisconnectReason::ReceiveChannelError {
+                channel_id: DefaultChannel::ReliableOrdered.into(),
+                error: ChannelError::TooManyReassemblies
+            })
+        );
+    }
+
     #[test]
     fn discard_old_packets() {
         let mut connection = RenetClient::new(ConnectionConfig::test(), false);
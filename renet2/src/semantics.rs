@@ -0,0 +1,381 @@
+use crate::channel::{ChannelConfig, SendType};
+use crate::error::DisconnectReason;
+use crate::packet::Payload;
+use crate::remote_connection::{ConnectionConfig, RenetClient};
+use crate::server::RenetServer;
+use crate::ClientId;
+
+use std::time::Duration;
+
+const CLIENT_ID: ClientId = 0;
+
+/// Network conditions and message load used by [`verify_channel_semantics`].
+#[derive(Debug, Clone)]
+pub struct SemanticsScenario {
+    /// Number of messages sent on each channel, in both directions.
+    pub messages_per_channel: usize,
+    /// Size in bytes of each message (at least 8). Large messages will be sliced.
+    pub message_size: usize,
+    /// Probability in `[0, 1]` that a packet is lost, in either direction.
+    pub packet_loss: f64,
+    /// Probability in `[0, 1]` that a packet is delayed by one tick, so it arrives after packets sent later.
+    pub packet_reorder: f64,
+    /// Duration of a simulated tick.
+    pub tick: Duration,
+    /// Maximum number of ticks to simulate before giving up on reliable delivery.
+    pub max_ticks: usize,
+    /// Seed for packet loss and reordering, so scenarios are reproducible.
+    pub seed: u64,
+}
+
+impl Default for SemanticsScenario {
+    fn default() -> Self {
+        Self {
+            messages_per_channel: 100,
+            message_size: 16,
+            packet_loss: 0.1,
+            packet_reorder: 0.1,
+            tick: Duration::from_millis(16),
+            max_ticks: 10_000,
+            seed: 0,
+        }
+    }
+}
+
+/// Delivery results of one channel in [`SemanticsReport`].
+#[derive(Debug, Clone)]
+pub struct ChannelSemanticsReport {
+    pub channel_id: u8,
+    pub send_type: SendType,
+    /// Number of messages sent on the channel.
+    pub sent: usize,
+    /// Number of messages received from the channel.
+    pub received: usize,
+    /// Descriptions of every way the channel broke its [`SendType`] contract.
+    pub violations: Vec<String>,
+}
+
+impl ChannelSemanticsReport {
+    /// Returns `true` if the channel honored its [`SendType`] contract.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Result of [`verify_channel_semantics`].
+#[derive(Debug, Clone)]
+pub struct SemanticsReport {
+    /// Number of ticks that were simulated.
+    pub ticks: usize,
+    /// Set if the client or server disconnected during the scenario.
+    pub disconnect_reason: Option<DisconnectReason>,
+    /// Channels that the server sends to the client.
+    pub server_channels: Vec<ChannelSemanticsReport>,
+    /// Channels that the client sends to the server.
+    pub client_channels: Vec<ChannelSemanticsReport>,
+}
+
+impl SemanticsReport {
+    /// Returns `true` if no connection was lost and every channel honored its [`SendType`] contract.
+    pub fn is_ok(&self) -> bool {
+        self.disconnect_reason.is_none() && self.channels().all(|c| c.is_ok())
+    }
+
+    /// Iterates over all server and client channel reports.
+    pub fn channels(&self) -> impl Iterator<Item = &ChannelSemanticsReport> + '_ {
+        self.server_channels.iter().chain(self.client_channels.iter())
+    }
+}
+
+/// Runs a message sequence through a [`RenetServer`] and [`RenetClient`] connected by a lossy loopback, and checks
+/// that every channel in `config` delivers messages as promised by its [`SendType`].
+///
+/// - [`SendType::ReliableOrdered`] channels must deliver every message exactly once and in order.
+/// - [`SendType::ReliableUnordered`] channels must deliver every message exactly once.
+/// - [`SendType::Unreliable`] channels may drop or reorder messages, but must not duplicate or corrupt them. If
+///   `ordered_reliable_substrate` is set and the scenario has no loss or reordering, they must deliver every message
+///   in order.
+///
+/// The scenario stops early if the connection is lost, see [`SemanticsReport::disconnect_reason`].
+pub fn verify_channel_semantics(config: ConnectionConfig, scenario: &SemanticsScenario) -> SemanticsReport {
+    let reliable_substrate = scenario.packet_loss <= 0.0 && scenario.packet_reorder <= 0.0;
+    let mut server = RenetServer::new(config.clone());
+    let mut client = RenetClient::new(config.clone(), false);
+    server.add_connection(CLIENT_ID, false);
+    client.set_connected();
+
+    let mut server_channels: Vec<ChannelTracker> = config.server_channels_config.iter().map(ChannelTracker::new).collect();
+    let mut client_channels: Vec<ChannelTracker> = config.client_channels_config.iter().map(ChannelTracker::new).collect();
+    let mut rng = Rng(scenario.seed);
+    let mut to_client = LossyLink::default();
+    let mut to_server = LossyLink::default();
+
+    let mut ticks = 0;
+    let mut disconnect_reason = None;
+    while ticks < scenario.max_ticks {
+        ticks += 1;
+
+        for channel in server_channels.iter_mut() {
+            while channel.next_to_send < scenario.messages_per_channel {
+                let message = make_message(channel.next_to_send, scenario.message_size);
+                if !server.can_send_message(CLIENT_ID, channel.channel_id, message.len()) {
+                    break;
+                }
+                server.send_message(CLIENT_ID, channel.channel_id, message);
+                channel.next_to_send += 1;
+            }
+        }
+        for channel in client_channels.iter_mut() {
+            while channel.next_to_send < scenario.messages_per_channel {
+                let message = make_message(channel.next_to_send, scenario.message_size);
+                if !client.can_send_message(channel.channel_id, message.len()) {
+                    break;
+                }
+                client.send_message(channel.channel_id, message);
+                channel.next_to_send += 1;
+            }
+        }
+
+        server.update(scenario.tick);
+        client.update(scenario.tick);
+
+        let packets = server.get_packets_to_send(CLIENT_ID).unwrap_or_default();
+        for packet in to_client.transmit(packets, scenario, &mut rng) {
+            client.process_packet(&packet);
+        }
+        let packets = client.get_packets_to_send();
+        for packet in to_server.transmit(packets, scenario, &mut rng) {
+            let _ = server.process_packet_from(&packet, CLIENT_ID);
+        }
+
+        for channel in server_channels.iter_mut() {
+            while let Some(message) = client.receive_message(channel.channel_id) {
+                channel.receive(&message, scenario.message_size);
+            }
+        }
+        for channel in client_channels.iter_mut() {
+            while let Some(message) = server.receive_message(CLIENT_ID, channel.channel_id) {
+                channel.receive(&message, scenario.message_size);
+            }
+        }
+
+        if let Some(reason) = client.disconnect_reason().or_else(|| server.disconnect_reason(CLIENT_ID)) {
+            disconnect_reason = Some(reason);
+            break;
+        }
+
+        let done = server_channels
+            .iter()
+            .chain(client_channels.iter())
+            .all(|c| c.is_done(scenario.messages_per_channel, reliable_substrate));
+        if done && to_client.is_empty() && to_server.is_empty() {
+            break;
+        }
+    }
+
+    SemanticsReport {
+        ticks,
+        disconnect_reason,
+        server_channels: server_channels.into_iter().map(|c| c.finish(reliable_substrate)).collect(),
+        client_channels: client_channels.into_iter().map(|c| c.finish(reliable_substrate)).collect(),
+    }
+}
+
+fn make_message(index: usize, size: usize) -> Vec<u8> {
+    let mut message = vec![index as u8; size.max(8)];
+    message[..8].copy_from_slice(&(index as u64).to_le_bytes());
+    message
+}
+
+struct ChannelTracker {
+    channel_id: u8,
+    send_type: SendType,
+    next_to_send: usize,
+    received: Vec<u64>,
+    violations: Vec<String>,
+}
+
+impl ChannelTracker {
+    fn new(config: &ChannelConfig) -> Self {
+        Self {
+            channel_id: config.channel_id,
+            send_type: config.send_type.clone(),
+            next_to_send: 0,
+            received: Vec::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    fn receive(&mut self, message: &[u8], message_size: usize) {
+        let index = message.get(..8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+        match index {
+            Some(index) if message == make_message(index as usize, message_size) => self.received.push(index),
+            _ => self
+                .violations
+                .push(format!("received a corrupted message of {} bytes", message.len())),
+        }
+    }
+
+    fn expects_all(&self, reliable_substrate: bool) -> bool {
+        match self.send_type {
+            SendType::Unreliable {
+                ordered_reliable_substrate,
+            } => ordered_reliable_substrate && reliable_substrate,
+            SendType::ReliableOrdered { .. } | SendType::ReliableUnordered { .. } => true,
+        }
+    }
+
+    fn expects_order(&self, reliable_substrate: bool) -> bool {
+        match self.send_type {
+            SendType::Unreliable {
+                ordered_reliable_substrate,
+            } => ordered_reliable_substrate && reliable_substrate,
+            SendType::ReliableOrdered { .. } => true,
+            SendType::ReliableUnordered { .. } => false,
+        }
+    }
+
+    fn is_done(&self, num_messages: usize, reliable_substrate: bool) -> bool {
+        self.next_to_send >= num_messages && (!self.expects_all(reliable_substrate) || self.received.len() >= num_messages)
+    }
+
+    fn finish(mut self, reliable_substrate: bool) -> ChannelSemanticsReport {
+        let mut seen = vec![false; self.next_to_send];
+        for &index in self.received.iter() {
+            match seen.get_mut(index as usize) {
+                Some(true) => self.violations.push(format!("message {index} was received more than once")),
+                Some(seen) => *seen = true,
+                None => self.violations.push(format!("message {index} was received but never sent")),
+            }
+        }
+
+        if self.expects_all(reliable_substrate) {
+            let missing = seen.iter().filter(|s| !**s).count();
+            if missing > 0 {
+                self.violations
+                    .push(format!("{missing} of {} messages were not received", self.next_to_send));
+            }
+        }
+
+        if self.expects_order(reliable_substrate) {
+            if let Some(pair) = self.received.windows(2).find(|pair| pair[0] >= pair[1]) {
+                self.violations
+                    .push(format!("message {} was received after message {}", pair[1], pair[0]));
+            }
+        }
+
+        ChannelSemanticsReport {
+            channel_id: self.channel_id,
+            send_type: self.send_type,
+            sent: self.next_to_send,
+            received: self.received.len(),
+            violations: self.violations,
+        }
+    }
+}
+
+/// One direction of the simulated loopback.
+#[derive(Default)]
+struct LossyLink {
+    delayed: Vec<Payload>,
+}
+
+impl LossyLink {
+    /// Returns the packets that arrive this tick: surviving new packets followed by packets delayed last tick.
+    fn transmit(&mut self, packets: Vec<Payload>, scenario: &SemanticsScenario, rng: &mut Rng) -> Vec<Payload> {
+        let mut arrived = Vec::with_capacity(packets.len() + self.delayed.len());
+        let mut delayed = Vec::new();
+        for packet in packets {
+            if rng.chance(scenario.packet_loss) {
+                continue;
+            }
+            if rng.chance(scenario.packet_reorder) {
+                delayed.push(packet);
+            } else {
+                arrived.push(packet);
+            }
+        }
+        arrived.append(&mut self.delayed);
+        self.delayed = delayed;
+        arrived
+    }
+
+    fn is_empty(&self) -> bool {
+        self.delayed.is_empty()
+    }
+}
+
+/// Small deterministic PRNG (splitmix64) so scenarios don't need an external rng.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::DefaultChannel;
+    use crate::packet::SLICE_SIZE;
+
+    #[test]
+    fn default_channels_under_loss() {
+        let report = verify_channel_semantics(ConnectionConfig::test(), &SemanticsScenario::default());
+        assert!(report.is_ok(), "{report:#?}");
+        for channel in report.channels() {
+            assert_eq!(channel.sent, 100);
+            if !matches!(channel.send_type, SendType::Unreliable { .. }) {
+                assert_eq!(channel.received, 100);
+            }
+        }
+    }
+
+    #[test]
+    fn sliced_messages_under_loss() {
+        let scenario = SemanticsScenario {
+            messages_per_channel: 20,
+            message_size: SLICE_SIZE * 3 + 5,
+            packet_loss: 0.2,
+            packet_reorder: 0.3,
+            seed: 7,
+            ..Default::default()
+        };
+        let report = verify_channel_semantics(ConnectionConfig::test(), &scenario);
+        assert!(report.is_ok(), "{report:#?}");
+    }
+
+    #[test]
+    fn reliable_substrate() {
+        let mut config = ConnectionConfig::test();
+        config.downgrade_to_unreliable();
+        let scenario = SemanticsScenario {
+            packet_loss: 0.0,
+            packet_reorder: 0.0,
+            ..Default::default()
+        };
+        let report = verify_channel_semantics(config.clone(), &scenario);
+        assert!(report.is_ok(), "{report:#?}");
+
+        // Downgraded channels lose their guarantees on a lossy substrate.
+        let scenario = SemanticsScenario {
+            message_size: 200,
+            packet_loss: 0.5,
+            ..Default::default()
+        };
+        let report = verify_channel_semantics(config, &scenario);
+        assert!(report.is_ok(), "{report:#?}");
+        let ordered: u8 = DefaultChannel::ReliableOrdered.into();
+        let channel = report.server_channels.iter().find(|c| c.channel_id == ordered).unwrap();
+        assert!(channel.received < channel.sent);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::error::{ClientNotFound, DisconnectReason};
+#[cfg(feature = "debug_tools")]
+use crate::message_tracker::{MessageId, MessageStatus};
 use crate::packet::Payload;
 use crate::remote_connection::{ConnectionConfig, NetworkInfo, RenetClient};
 use crate::ClientId;
@@ -15,20 +17,52 @@ pub enum ServerEvent {
     ClientDisconnected { client_id: ClientId, reason: DisconnectReason },
 }
 
+/// Network stats summed or averaged over all connected clients, see [`RenetServer::aggregate_network_info`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AggregateNetworkInfo {
+    /// Bytes sent per second to all clients.
+    pub total_sent_bps: f64,
+    /// Bytes received per second from all clients.
+    pub total_received_bps: f64,
+    /// Average round-trip time.
+    pub avg_rtt: f64,
+    /// Average packet loss.
+    pub avg_packet_loss: f64,
+    /// Number of connected clients included in the stats.
+    pub client_count: usize,
+}
+
+/// Implements `Resource` and `Component` when the `bevy` feature is enabled.
 #[derive(Debug)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource, bevy_ecs::component::Component))]
 pub struct RenetServer {
     connections: HashMap<ClientId, RenetClient>,
     connection_config: ConnectionConfig,
     events: VecDeque<ServerEvent>,
+    max_bytes_per_tick: Option<u64>,
+    total_bandwidth_limit: Option<usize>,
+    /// Duration of the last [`Self::update`], used to convert the bandwidth limit to a per-tick budget.
+    tick_duration: Duration,
+    /// Whether the send budget must be split between the clients before the next packets are sent.
+    needs_send_budgets: bool,
 }
 
 impl RenetServer {
+    /// Makes a new server.
+    ///
+    /// Panics if the config is invalid, see [`ConnectionConfig::validate`].
     pub fn new(connection_config: ConnectionConfig) -> Self {
+        if let Err(err) = connection_config.validate() {
+            panic!("invalid connection config: {err}");
+        }
         Self {
             connections: HashMap::new(),
             connection_config,
             events: VecDeque::new(),
+            max_bytes_per_tick: None,
+            total_bandwidth_limit: None,
+            tick_duration: Duration::ZERO,
+            needs_send_budgets: false,
         }
     }
 
@@ -51,6 +85,7 @@ impl RenetServer {
         let connection_config = self.connection_config.clone();
         let mut client = RenetClient::new_from_server(connection_config, socket_is_reliable);
         // Consider newly added connections as connected
+        client.set_client_id(client_id);
         client.set_connected();
         self.connections.insert(client_id, client);
         self.events.push_back(ServerEvent::ClientConnected { client_id })
@@ -107,6 +142,30 @@ impl RenetServer {
         }
     }
 
+    /// Returns the effective ack latency of a reliable channel for the client, or `None` if the client is not found.
+    ///
+    /// See [`RenetClient::channel_effective_latency`].
+    pub fn channel_effective_latency<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> Option<Duration> {
+        self.connections.get(&client_id)?.channel_effective_latency(channel_id)
+    }
+
+    /// Returns the number of channels configured to receive messages from clients.
+    pub fn receive_channel_count(&self) -> usize {
+        self.connection_config.client_channels_config.len()
+    }
+
+    /// Returns `true` if a channel is configured to receive messages from clients.
+    ///
+    /// [`Self::receive_message`] panics for channels that aren't configured, e.g. if the client and server were
+    /// set up with different channels.
+    pub fn has_receive_channel<I: Into<u8>>(&self, channel_id: I) -> bool {
+        let channel_id = channel_id.into();
+        self.connection_config
+            .client_channels_config
+            .iter()
+            .any(|config| config.channel_id == channel_id)
+    }
+
     /// Returns the bytes sent per seconds for the client or 0.0 if the client is not found
     pub fn bytes_sent_per_sec(&self, client_id: ClientId) -> f64 {
         match self.connections.get(&client_id) {
@@ -123,6 +182,42 @@ impl RenetServer {
         }
     }
 
+    /// Returns the total bytes sent to the client on a channel, or 0 if the client is not found.
+    ///
+    /// See [`RenetClient::channel_bytes_sent`].
+    pub fn channel_bytes_sent<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.channel_bytes_sent(channel_id),
+            None => 0,
+        }
+    }
+
+    /// Returns the total bytes received from the client on a channel, or 0 if the client is not found.
+    ///
+    /// See [`RenetClient::channel_bytes_received`].
+    pub fn channel_bytes_received<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> u64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.channel_bytes_received(channel_id),
+            None => 0,
+        }
+    }
+
+    /// Returns the bytes sent per second to the client on a channel, or 0.0 if the client is not found.
+    pub fn channel_bytes_sent_per_sec<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.channel_bytes_sent_per_sec(channel_id),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the bytes received per second from the client on a channel, or 0.0 if the client is not found.
+    pub fn channel_bytes_received_per_sec<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.channel_bytes_received_per_sec(channel_id),
+            None => 0.0,
+        }
+    }
+
     /// Returns all network information for the client
     pub fn network_info(&self, client_id: ClientId) -> Result<NetworkInfo, ClientNotFound> {
         match self.connections.get(&client_id) {
@@ -131,6 +226,42 @@ impl RenetServer {
         }
     }
 
+    /// Returns network stats aggregated over all connected clients.
+    ///
+    /// Averages are 0.0 if there are no connected clients.
+    pub fn aggregate_network_info(&self) -> AggregateNetworkInfo {
+        let mut info = AggregateNetworkInfo::default();
+        for connection in self.connections.values().filter(|c| c.is_connected()) {
+            let client_info = connection.network_info();
+            info.total_sent_bps += client_info.bytes_sent_per_second;
+            info.total_received_bps += client_info.bytes_received_per_second;
+            info.avg_rtt += client_info.rtt;
+            info.avg_packet_loss += client_info.packet_loss;
+            info.client_count += 1;
+        }
+        if info.client_count > 0 {
+            info.avg_rtt /= info.client_count as f64;
+            info.avg_packet_loss /= info.client_count as f64;
+        }
+
+        info
+    }
+
+    /// Returns the network stats of every connected client, sorted by client id.
+    ///
+    /// All stats are read in the same call, so they describe the server at one point in time. This is preferable to
+    /// calling [`Self::network_info`] for each of [`Self::clients_id`] when building a dashboard.
+    pub fn network_info_snapshot(&self) -> Vec<(ClientId, NetworkInfo)> {
+        let mut snapshot: Vec<(ClientId, NetworkInfo)> = self
+            .connections
+            .iter()
+            .filter(|(_, c)| c.is_connected())
+            .map(|(client_id, connection)| (*client_id, connection.network_info()))
+            .collect();
+        snapshot.sort_unstable_by_key(|(client_id, _)| *client_id);
+        snapshot
+    }
+
     /// Removes a connection from the server, emits an disconnect server event.
     /// It does nothing if the client does not exits.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
@@ -188,6 +319,20 @@ impl RenetServer {
         }
     }
 
+    /// Overrides the maximum memory used by messages queued on a channel for the given client.
+    ///
+    /// This can be used to temporarily let more data be queued for a client, e.g. while it downloads a large level.
+    /// Lowering the limit below the current usage does not drop queued messages. Until enough memory is freed, new
+    /// messages on unreliable channels are dropped, while new messages on reliable channels disconnect the client
+    /// with [`ChannelError::ReliableChannelMaxMemoryReached`](crate::ChannelError::ReliableChannelMaxMemoryReached).
+    /// Use [`Self::can_send_message`] to check if a message fits. See [`RenetClient::set_channel_memory`].
+    pub fn set_client_channel_memory<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I, max_memory_usage_bytes: usize) {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.set_channel_memory(channel_id, max_memory_usage_bytes),
+            None => log::error!("Tried to set channel memory for invalid client {:?}", client_id),
+        }
+    }
+
     /// Checks if can send a message with the given size in bytes over a channel for the given client.
     /// Returns false if the client is not found.
     pub fn can_send_message<I: Into<u8>>(&self, client_id: ClientId, channel_id: I, size_bytes: usize) -> bool {
@@ -197,6 +342,29 @@ impl RenetServer {
         }
     }
 
+    /// Returns the number of messages on a channel that are waiting to be sent to the given client.
+    /// Returns 0 if the client is not found.
+    ///
+    /// See [`RenetClient::pending_message_count`].
+    pub fn pending_message_count<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> usize {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.pending_message_count(channel_id),
+            None => 0,
+        }
+    }
+
+    /// Drops all messages on a channel that are waiting to be sent to the given client, returning the number of
+    /// messages dropped. Returns 0 if the client is not found.
+    ///
+    /// Intended for unreliable channels. Clearing a reliable channel may desync the client, see
+    /// [`RenetClient::clear_channel`].
+    pub fn clear_client_channel<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> usize {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.clear_channel(channel_id),
+            None => 0,
+        }
+    }
+
     /// Send a message to a client over a channel.
     pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: ClientId, channel_id: I, message: B) {
         match self.connections.get_mut(&client_id) {
@@ -205,7 +373,60 @@ impl RenetServer {
         }
     }
 
+    /// Send a message to a client over a channel, with a priority.
+    ///
+    /// See [`RenetClient::send_message_prioritized`].
+    pub fn send_message_prioritized<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: ClientId, channel_id: I, priority: u8, message: B) {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.send_message_prioritized(channel_id, priority, message),
+            None => log::error!("Tried to send a message to invalid client {:?}", client_id),
+        }
+    }
+
+    /// Send a message to a client over a channel, with a key used to drop duplicates.
+    ///
+    /// See [`RenetClient::send_message_keyed`].
+    pub fn send_message_keyed<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: ClientId, channel_id: I, key: u64, message: B) {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.send_message_keyed(channel_id, key, message),
+            None => log::error!("Tried to send a message to invalid client {:?}", client_id),
+        }
+    }
+
+    /// Send a message to a client over a channel, and start tracking its status.
+    ///
+    /// Returns `None` if the client is not connected. See [`RenetClient::send_message_tracked`].
+    #[cfg(feature = "debug_tools")]
+    pub fn send_message_tracked<I: Into<u8>, B: Into<Bytes>>(
+        &mut self,
+        client_id: ClientId,
+        channel_id: I,
+        message: B,
+    ) -> Option<MessageId> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => Some(connection.send_message_tracked(channel_id, message)),
+            None => {
+                log::error!("Tried to send a message to invalid client {:?}", client_id);
+                None
+            }
+        }
+    }
+
+    /// Returns the status of a message sent to a client with [`Self::send_message_tracked`].
+    ///
+    /// Returns [`MessageStatus::Unknown`] if the client is not connected.
+    #[cfg(feature = "debug_tools")]
+    pub fn message_status(&self, client_id: ClientId, message_id: MessageId) -> MessageStatus {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.message_status(message_id),
+            None => MessageStatus::Unknown,
+        }
+    }
+
     /// Receive a message from a client over a channel.
+    ///
+    /// Returns `None` if the client is disconnected or does not exist, so it is safe to call with the id of a client
+    /// that was just disconnected (e.g. while reading [`ServerEvent`]s in parallel).
     pub fn receive_message<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> Option<Bytes> {
         if let Some(connection) = self.connections.get_mut(&client_id) {
             return connection.receive_message(channel_id);
@@ -213,6 +434,17 @@ impl RenetServer {
         None
     }
 
+    /// Drops all messages that were received from a client but not consumed yet, returning the number of messages
+    /// dropped. Returns 0 if the client is not found.
+    ///
+    /// In-flight messages are not affected, see [`RenetClient::clear_received`].
+    pub fn clear_received(&mut self, client_id: ClientId) -> usize {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.clear_received(),
+            None => 0,
+        }
+    }
+
     /// Return ids for all connected clients (iterator)
     pub fn clients_id_iter(&self) -> impl Iterator<Item = ClientId> + '_ {
         self.connections.iter().filter(|(_, c)| c.is_connected()).map(|(id, _)| *id)
@@ -238,6 +470,7 @@ impl RenetServer {
         self.connections.iter().filter(|(_, c)| c.is_connected()).count()
     }
 
+    /// Returns `true` if the client exists and is connected.
     pub fn is_connected(&self, client_id: ClientId) -> bool {
         if let Some(connection) = self.connections.get(&client_id) {
             return connection.is_connected();
@@ -252,6 +485,89 @@ impl RenetServer {
         for connection in self.connections.values_mut() {
             connection.update(duration);
         }
+        self.tick_duration = duration;
+        self.needs_send_budgets = self.max_bytes_per_tick.is_some() || self.total_bandwidth_limit.is_some();
+    }
+
+    /// Sets the number of bytes that can be sent to all clients combined per tick.
+    ///
+    /// Each tick, the first call to [`Self::get_packets_to_send`] after [`Self::update`] splits the budget between
+    /// the clients with messages waiting to be sent. A client's share grows with its starvation, which counts how
+    /// many ticks in a row it could not send all its messages because of its share (see [`Self::client_starvation`]),
+    /// so clients that are repeatedly under-served catch up over time. A client that can't use its whole share
+    /// because its next message or slice doesn't fit keeps the rest for the next tick, so the budget holds on
+    /// average and single ticks may go over it. A client never sends more than
+    /// [`ConnectionConfig::available_bytes_per_tick`] per tick, and acks are not limited.
+    ///
+    /// Default: `None`, clients are only limited by [`ConnectionConfig::available_bytes_per_tick`].
+    pub fn set_max_bytes_per_tick(&mut self, max_bytes_per_tick: Option<u64>) {
+        self.max_bytes_per_tick = max_bytes_per_tick;
+    }
+
+    /// Returns the size of the slices that large messages are split into, see [`ConnectionConfig::slice_size`].
+    pub fn slice_size(&self) -> usize {
+        self.connection_config.slice_size
+    }
+
+    /// Returns the number of bytes that can be sent to all clients combined per tick, see
+    /// [`Self::set_max_bytes_per_tick`].
+    pub fn max_bytes_per_tick(&self) -> Option<u64> {
+        self.max_bytes_per_tick
+    }
+
+    /// Sets the number of bytes per second that can be sent to all clients combined.
+    ///
+    /// Each tick the limit is converted to a budget for the duration passed to [`Self::update`], which is split
+    /// between the clients like [`Self::set_max_bytes_per_tick`] (the smaller of the two budgets is used when both
+    /// are set). Within a client's share, channels are served in the order of [`ConnectionConfig::server_channels_config`],
+    /// so traffic on the last channels is shed first: unreliable messages that don't fit are dropped (unless the
+    /// channel has an ordered-reliable substrate), and reliable messages wait for a later tick.
+    ///
+    /// Default: `None`.
+    pub fn set_total_bandwidth_limit(&mut self, bytes_per_sec: Option<usize>) {
+        self.total_bandwidth_limit = bytes_per_sec;
+    }
+
+    /// Returns the number of bytes per second that can be sent to all clients combined, see
+    /// [`Self::set_total_bandwidth_limit`].
+    pub fn total_bandwidth_limit(&self) -> Option<usize> {
+        self.total_bandwidth_limit
+    }
+
+    /// Returns the combined send budget of all clients for the current tick.
+    fn tick_send_budget(&self) -> Option<u64> {
+        let bandwidth_budget = self
+            .total_bandwidth_limit
+            .map(|bytes_per_sec| (bytes_per_sec as f64 * self.tick_duration.as_secs_f64()) as u64);
+        match (self.max_bytes_per_tick, bandwidth_budget) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Returns how many ticks in a row the client could not send all its messages because of its share of
+    /// [`Self::max_bytes_per_tick`].
+    ///
+    /// This is 0.0 if the client is not starving or does not exist.
+    pub fn client_starvation(&self, client_id: ClientId) -> f32 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.send_starvation(),
+            None => 0.0,
+        }
+    }
+
+    fn assign_send_budgets(&mut self, max_bytes_per_tick: u64) {
+        let mut total_weight = 0.0;
+        for connection in self.connections.values().filter(|c| c.is_connected() && c.has_unsent_messages()) {
+            total_weight += 1.0 + connection.send_starvation() as f64;
+        }
+
+        for connection in self.connections.values_mut() {
+            if connection.is_connected() && connection.has_unsent_messages() {
+                let weight = 1.0 + connection.send_starvation() as f64;
+                connection.set_send_budget((max_bytes_per_tick as f64 * weight / total_weight) as u64);
+            }
+        }
     }
 
     /// Returns a list of packets to be sent to the client.
@@ -259,6 +575,11 @@ impl RenetServer {
     /// <strong>Note:</strong> This should only be called by the transport layer.
     /// </p>
     pub fn get_packets_to_send(&mut self, client_id: ClientId) -> Result<Vec<Payload>, ClientNotFound> {
+        if let Some(tick_send_budget) = self.tick_send_budget().filter(|_| self.needs_send_budgets) {
+            self.needs_send_budgets = false;
+            self.assign_send_budgets(tick_send_budget);
+        }
+
         match self.connections.get_mut(&client_id) {
             Some(connection) => Ok(connection.get_packets_to_send()),
             None => Err(ClientNotFound),
@@ -283,6 +604,7 @@ impl RenetServer {
     /// Use [`Self::process_local_client`] to update the local connection.
     pub fn new_local_client(&mut self, client_id: ClientId) -> RenetClient {
         let mut client = RenetClient::new_from_server(self.connection_config.clone(), false);
+        client.set_client_id(client_id);
         client.set_connected();
         self.add_connection(client_id, false);
         client
@@ -1,5 +1,6 @@
 use bytes::Bytes;
-use renet2::{ConnectionConfig, DefaultChannel, DisconnectReason, RenetClient, RenetServer, ServerEvent};
+use renet2::{AggregateNetworkInfo, ConnectionConfig, DefaultChannel, DisconnectReason, RenetClient, RenetServer, ServerEvent};
+use std::time::Duration;
 
 pub fn init_log() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -76,6 +77,7 @@ fn test_local_client() {
     let mut server = RenetServer::new(ConnectionConfig::test());
     let client_id = 0;
     let mut client = server.new_local_client(client_id);
+    assert_eq!(client.client_id(), Some(client_id));
 
     let connect_event = server.get_event().unwrap();
     assert!(connect_event == ServerEvent::ClientConnected { client_id });
@@ -100,3 +102,447 @@ fn test_local_client() {
             }
     );
 }
+
+#[test]
+fn test_receive_after_disconnect() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut client = RenetClient::new(ConnectionConfig::test(), false);
+
+    let client_id = 0;
+    server.add_connection(client_id, false);
+    client.set_connected();
+    assert!(server.is_connected(client_id));
+
+    client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("test"));
+    for packet in client.get_packets_to_send() {
+        server.process_packet_from(&packet, client_id).unwrap();
+    }
+
+    // Disconnected clients don't return messages, even if some are buffered.
+    server.disconnect(client_id);
+    assert!(!server.is_connected(client_id));
+    assert_eq!(server.receive_message(client_id, DefaultChannel::ReliableOrdered), None);
+
+    server.remove_connection(client_id);
+    assert!(!server.is_connected(client_id));
+    assert_eq!(server.receive_message(client_id, DefaultChannel::ReliableOrdered), None);
+    assert_eq!(server.receive_message(client_id, DefaultChannel::Unreliable), None);
+}
+
+#[test]
+fn test_aggregate_network_info() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    assert_eq!(server.aggregate_network_info(), AggregateNetworkInfo::default());
+
+    let mut clients: Vec<RenetClient> = (0..3).map(|client_id| server.new_local_client(client_id)).collect();
+    server.disconnect(2);
+
+    for (client_id, client) in clients.iter_mut().enumerate() {
+        server.send_message(client_id as u64, DefaultChannel::ReliableOrdered, Bytes::from("test"));
+        client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("test"));
+    }
+    server.update(Duration::from_millis(100));
+    for (client_id, client) in clients.iter_mut().enumerate() {
+        client.update(Duration::from_millis(100));
+        let _ = server.process_local_client(client_id as u64, client);
+    }
+
+    let info = server.aggregate_network_info();
+    assert_eq!(info.client_count, 2);
+    let expected_sent: f64 = (0..2).map(|client_id| server.bytes_sent_per_sec(client_id)).sum();
+    let expected_received: f64 = (0..2).map(|client_id| server.bytes_received_per_sec(client_id)).sum();
+    assert!(expected_sent > 0.0);
+    assert!(expected_received > 0.0);
+    assert_eq!(info.total_sent_bps, expected_sent);
+    assert_eq!(info.total_received_bps, expected_received);
+    assert_eq!(info.avg_rtt, (server.rtt(0) + server.rtt(1)) / 2.0);
+}
+
+#[test]
+fn test_network_info_snapshot() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    assert!(server.network_info_snapshot().is_empty());
+
+    let mut clients: Vec<RenetClient> = (0..3).map(|client_id| server.new_local_client(client_id)).collect();
+    server.disconnect(1);
+
+    for (client_id, client) in clients.iter_mut().enumerate() {
+        server.send_message(client_id as u64, DefaultChannel::ReliableOrdered, Bytes::from("test"));
+        client.update(Duration::from_millis(100));
+        let _ = server.process_local_client(client_id as u64, client);
+    }
+    server.update(Duration::from_millis(100));
+
+    let snapshot = server.network_info_snapshot();
+    let client_ids: Vec<u64> = snapshot.iter().map(|(client_id, _)| *client_id).collect();
+    assert_eq!(client_ids, vec![0, 2]);
+    for (client_id, info) in snapshot {
+        assert_eq!(info, server.network_info(client_id).unwrap());
+    }
+}
+
+#[test]
+fn test_clear_client_channel() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut client = RenetClient::new(ConnectionConfig::test(), false);
+
+    let client_id = 0;
+    server.add_connection(client_id, false);
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::Unreliable), 0);
+    assert_eq!(server.pending_message_count(client_id + 1, DefaultChannel::Unreliable), 0);
+
+    // Queued unreliable messages are dropped.
+    for _ in 0..5 {
+        server.send_message(client_id, DefaultChannel::Unreliable, Bytes::from("stale"));
+    }
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::Unreliable), 5);
+    assert_eq!(server.clear_client_channel(client_id, DefaultChannel::Unreliable), 5);
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::Unreliable), 0);
+    assert_eq!(
+        server.channel_available_memory(client_id, DefaultChannel::Unreliable),
+        5 * 1024 * 1024
+    );
+
+    server.send_message(client_id, DefaultChannel::Unreliable, Bytes::from("fresh"));
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    assert_eq!(client.receive_message(DefaultChannel::Unreliable).unwrap(), "fresh");
+    assert!(client.receive_message(DefaultChannel::Unreliable).is_none());
+
+    // Reliable messages stay pending until acked.
+    for _ in 0..3 {
+        server.send_message(client_id, DefaultChannel::ReliableOrdered, Bytes::from("test"));
+    }
+    server.get_packets_to_send(client_id).unwrap();
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::ReliableOrdered), 3);
+    assert_eq!(server.clear_client_channel(client_id, DefaultChannel::ReliableOrdered), 3);
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::ReliableOrdered), 0);
+    assert_eq!(server.clear_client_channel(client_id + 1, DefaultChannel::ReliableOrdered), 0);
+}
+
+#[test]
+fn test_clear_received() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut client = RenetClient::new(ConnectionConfig::test(), false);
+
+    let client_id = 0;
+    server.add_connection(client_id, false);
+
+    for channel in [
+        DefaultChannel::ReliableOrdered,
+        DefaultChannel::ReliableUnordered,
+        DefaultChannel::Unreliable,
+    ]
+    .map(u8::from)
+    {
+        server.send_message(client_id, channel, Bytes::from("stale"));
+        client.send_message(channel, Bytes::from("stale"));
+    }
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    for packet in client.get_packets_to_send() {
+        server.process_packet_from(&packet, client_id).unwrap();
+    }
+
+    assert_eq!(client.clear_received(), 3);
+    assert_eq!(server.clear_received(client_id), 3);
+    assert_eq!(server.clear_received(client_id + 1), 0);
+    for channel in [
+        DefaultChannel::ReliableOrdered,
+        DefaultChannel::ReliableUnordered,
+        DefaultChannel::Unreliable,
+    ]
+    .map(u8::from)
+    {
+        assert!(client.receive_message(channel).is_none());
+        assert!(server.receive_message(client_id, channel).is_none());
+    }
+
+    // The connection is unaffected.
+    server.send_message(client_id, DefaultChannel::ReliableOrdered, Bytes::from("fresh"));
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    assert_eq!(client.disconnect_reason(), None);
+    assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "fresh");
+}
+
+#[test]
+fn test_set_client_channel_memory() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut client = RenetClient::new(ConnectionConfig::test(), false);
+
+    let client_id = 0;
+    server.add_connection(client_id, false);
+    let default_memory = server.channel_available_memory(client_id, DefaultChannel::ReliableOrdered);
+    let message = Bytes::from(vec![0; 1024]);
+
+    // Raising the limit lets more messages be queued.
+    server.set_client_channel_memory(client_id, DefaultChannel::ReliableOrdered, default_memory * 2);
+    for _ in 0..(default_memory / 1024 + 1) {
+        server.send_message(client_id, DefaultChannel::ReliableOrdered, message.clone());
+    }
+    assert!(server.is_connected(client_id));
+    assert_eq!(
+        server.pending_message_count(client_id, DefaultChannel::ReliableOrdered),
+        default_memory / 1024 + 1
+    );
+
+    // Lowering the limit below current usage keeps queued messages but blocks new ones.
+    server.set_client_channel_memory(client_id, DefaultChannel::ReliableOrdered, 1024);
+    assert_eq!(server.channel_available_memory(client_id, DefaultChannel::ReliableOrdered), 0);
+    assert!(!server.can_send_message(client_id, DefaultChannel::ReliableOrdered, 1));
+    assert_eq!(
+        server.pending_message_count(client_id, DefaultChannel::ReliableOrdered),
+        default_memory / 1024 + 1
+    );
+
+    server.set_client_channel_memory(client_id, DefaultChannel::Unreliable, 1024);
+    server.send_message(client_id, DefaultChannel::Unreliable, message.clone());
+    server.send_message(client_id, DefaultChannel::Unreliable, message.clone());
+    assert_eq!(server.pending_message_count(client_id, DefaultChannel::Unreliable), 1);
+
+    // Messages still get delivered after the limit is lowered.
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), message);
+    assert_eq!(client.receive_message(DefaultChannel::Unreliable).unwrap(), message);
+}
+
+#[cfg(feature = "debug_tools")]
+#[test]
+fn test_tracked_messages() {
+    use renet2::MessageStatus;
+
+    init_log();
+    let mut config = ConnectionConfig::test();
+    config.available_bytes_per_tick = 1200;
+    let mut server = RenetServer::new(config.clone());
+    let mut client = RenetClient::new(config, false);
+
+    let client_id = 0;
+    server.add_connection(client_id, false);
+
+    // Reliable messages are acked once the client acknowledges them.
+    let reliable = server
+        .send_message_tracked(client_id, DefaultChannel::ReliableOrdered, "test")
+        .unwrap();
+    assert_eq!(server.message_status(client_id, reliable), MessageStatus::Enqueued);
+    for packet in server.get_packets_to_send(client_id).unwrap() {
+        client.process_packet(&packet);
+    }
+    assert_eq!(server.message_status(client_id, reliable), MessageStatus::Sent);
+    for packet in client.get_packets_to_send() {
+        server.process_packet_from(&packet, client_id).unwrap();
+    }
+    assert_eq!(server.message_status(client_id, reliable), MessageStatus::Acked);
+
+    // Sliced messages are packetized until every slice was sent.
+    let sliced = server
+        .send_message_tracked(client_id, DefaultChannel::ReliableOrdered, vec![0; 2000])
+        .unwrap();
+    server.get_packets_to_send(client_id).unwrap();
+    assert_eq!(server.message_status(client_id, sliced), MessageStatus::Packetized);
+    server.get_packets_to_send(client_id).unwrap();
+    assert_eq!(server.message_status(client_id, sliced), MessageStatus::Sent);
+
+    // Unreliable messages are finished once sent, or dropped when there is no bandwidth for them.
+    let unreliable = server.send_message_tracked(client_id, DefaultChannel::Unreliable, "test").unwrap();
+    let too_large = server
+        .send_message_tracked(client_id, DefaultChannel::Unreliable, vec![0; 2000])
+        .unwrap();
+    server.get_packets_to_send(client_id).unwrap();
+    assert_eq!(server.message_status(client_id, unreliable), MessageStatus::Sent);
+    assert_eq!(server.message_status(client_id, too_large), MessageStatus::Dropped);
+
+    let cleared = server.send_message_tracked(client_id, DefaultChannel::Unreliable, "test").unwrap();
+    server.clear_client_channel(client_id, DefaultChannel::Unreliable);
+    assert_eq!(server.message_status(client_id, cleared), MessageStatus::Dropped);
+
+    // Pending messages are dropped when the connection closes.
+    let pending = server
+        .send_message_tracked(client_id, DefaultChannel::ReliableUnordered, "test")
+        .unwrap();
+    server.disconnect(client_id);
+    assert_eq!(server.message_status(client_id, pending), MessageStatus::Dropped);
+    assert_eq!(server.message_status(client_id + 1, pending), MessageStatus::Unknown);
+    assert!(server
+        .send_message_tracked(client_id + 1, DefaultChannel::Unreliable, "test")
+        .is_none());
+
+    let pending = client.send_message_tracked(DefaultChannel::ReliableOrdered, "test");
+    client.disconnect();
+    assert_eq!(client.message_status(pending), MessageStatus::Dropped);
+    let after_disconnect = client.send_message_tracked(DefaultChannel::ReliableOrdered, "test");
+    assert_eq!(client.message_status(after_disconnect), MessageStatus::Dropped);
+}
+
+#[test]
+fn test_client_starvation() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    server.set_max_bytes_per_tick(Some(6_000));
+    let mut clients: Vec<RenetClient> = (0..2).map(|client_id| server.new_local_client(client_id)).collect();
+
+    // Queues more messages than the server can send, then returns the bytes sent to each client.
+    let mut tick = |server: &mut RenetServer, congested: &[u64]| -> Vec<usize> {
+        for client_id in congested {
+            for _ in 0..10 {
+                server.send_message(*client_id, DefaultChannel::ReliableOrdered, vec![0; 1_000]);
+            }
+        }
+        server.update(Duration::from_millis(16));
+        let mut sent = Vec::new();
+        for (client_id, client) in clients.iter_mut().enumerate() {
+            let packets = server.get_packets_to_send(client_id as u64).unwrap();
+            sent.push(packets.iter().map(|packet| packet.len()).sum());
+            for packet in packets {
+                client.process_packet(&packet);
+            }
+            client.update(Duration::from_millis(16));
+            for packet in client.get_packets_to_send() {
+                server.process_packet_from(&packet, client_id as u64).unwrap();
+            }
+        }
+        sent
+    };
+
+    // Persistent congestion: both clients starve equally and share the budget.
+    for _ in 0..10 {
+        let sent = tick(&mut server, &[0, 1]);
+        assert!(sent[0] + sent[1] <= 6_000 + 200, "sent {sent:?}");
+        assert!(sent[0].abs_diff(sent[1]) <= 1_100, "sent {sent:?}");
+    }
+    assert_eq!(server.client_starvation(0), 10.0);
+    assert_eq!(server.client_starvation(1), 10.0);
+
+    // Client 0 is fully served once it has nothing left to send, while client 1 keeps starving.
+    server.clear_client_channel(0, DefaultChannel::ReliableOrdered);
+    tick(&mut server, &[1]);
+    assert_eq!(server.client_starvation(0), 0.0);
+    assert_eq!(server.client_starvation(1), 11.0);
+
+    // The starved client gets a bigger share of the budget.
+    let sent = tick(&mut server, &[0, 1]);
+    assert!(sent[1] > 3 * sent[0], "sent {sent:?}");
+    assert_eq!(server.client_starvation(2), 0.0);
+
+    // Without a budget, clients are not held back.
+    server.set_max_bytes_per_tick(None);
+    let sent = tick(&mut server, &[0, 1]);
+    assert!(sent[0] + sent[1] > 12_000, "sent {sent:?}");
+    assert_eq!(server.client_starvation(0), 0.0);
+}
+
+#[test]
+fn test_total_bandwidth_limit() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    server.set_total_bandwidth_limit(Some(100_000));
+    let mut clients: Vec<RenetClient> = (0..3).map(|client_id| server.new_local_client(client_id)).collect();
+
+    // Floods every client for one second, at 1_600 bytes per tick.
+    let mut total_sent = 0;
+    for _ in 0..62 {
+        for client_id in 0..3 {
+            for _ in 0..20 {
+                server.send_message(client_id, DefaultChannel::Unreliable, vec![0; 200]);
+            }
+        }
+        server.update(Duration::from_millis(16));
+        let mut tick_sent = 0;
+        for (client_id, client) in clients.iter_mut().enumerate() {
+            let packets = server.get_packets_to_send(client_id as u64).unwrap();
+            tick_sent += packets.iter().map(|packet| packet.len()).sum::<usize>();
+            for packet in packets {
+                client.process_packet(&packet);
+            }
+            client.update(Duration::from_millis(16));
+        }
+        assert!(tick_sent <= 1_600 + 100, "sent {tick_sent}");
+        total_sent += tick_sent;
+    }
+    assert!(total_sent <= 100_000 + 62 * 100, "sent {total_sent}");
+    assert!(total_sent > 60_000, "sent {total_sent}");
+
+    // Without a limit, clients are not held back.
+    server.set_total_bandwidth_limit(None);
+    for client_id in 0..3 {
+        for _ in 0..20 {
+            server.send_message(client_id, DefaultChannel::Unreliable, vec![0; 200]);
+        }
+    }
+    server.update(Duration::from_millis(16));
+    let sent: usize = (0..3)
+        .flat_map(|client_id| server.get_packets_to_send(client_id).unwrap())
+        .map(|packet| packet.len())
+        .sum();
+    assert!(sent > 3 * 20 * 200, "sent {sent}");
+}
+
+#[test]
+fn test_total_bandwidth_limit_large_messages() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    server.set_total_bandwidth_limit(Some(100_000));
+    let mut clients: Vec<RenetClient> = (0..2).map(|client_id| server.new_local_client(client_id)).collect();
+
+    // Each client's share of a tick (800 bytes) is smaller than a slice, so slices can only be sent with the budget
+    // left over from previous ticks.
+    for client_id in 0..2 {
+        for _ in 0..5 {
+            server.send_message(client_id, DefaultChannel::ReliableOrdered, vec![7; 5_000]);
+        }
+    }
+
+    let mut received = [0; 2];
+    let mut total_sent = 0;
+    let mut ticks = 0;
+    while received != [5, 5] && ticks < 300 {
+        ticks += 1;
+        server.update(Duration::from_millis(16));
+        for (client_id, client) in clients.iter_mut().enumerate() {
+            let packets = server.get_packets_to_send(client_id as u64).unwrap();
+            total_sent += packets.iter().map(|packet| packet.len()).sum::<usize>();
+            for packet in packets {
+                client.process_packet(&packet);
+            }
+            client.update(Duration::from_millis(16));
+            while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
+                assert_eq!(message, vec![7; 5_000]);
+                received[client_id] += 1;
+            }
+            for packet in client.get_packets_to_send() {
+                server.process_packet_from(&packet, client_id as u64).unwrap();
+            }
+        }
+    }
+
+    assert_eq!(received, [5, 5], "sent {total_sent} bytes in {ticks} ticks");
+    assert!(total_sent <= ticks * 1_600 + 1_600, "sent {total_sent} bytes in {ticks} ticks");
+}
+
+#[test]
+#[should_panic(expected = "invalid connection config")]
+fn server_invalid_slice_size() {
+    let mut config = ConnectionConfig::test();
+    config.slice_size = 0;
+    RenetServer::new(config);
+}
+
+#[test]
+fn server_slice_size() {
+    let mut config = ConnectionConfig::test();
+    config.slice_size = 500;
+    let server = RenetServer::new(config);
+    assert_eq!(server.slice_size(), 500);
+}
@@ -0,0 +1,177 @@
+use std::net::SocketAddr;
+
+use renetcode2::NETCODE_MAX_PACKET_BYTES;
+
+use crate::{ClientSocket, NetcodeTransportError, ServerSocket};
+
+type SendCallback = Box<dyn FnMut(SocketAddr, &[u8]) -> std::io::Result<()> + Send + Sync>;
+type RecvCallback = Box<dyn FnMut(&mut [u8]) -> std::io::Result<(usize, SocketAddr)> + Send + Sync>;
+
+/// Implementation of [`ClientSocket`] and [`ServerSocket`] that delegates sending and receiving packets to
+/// user-provided closures.
+///
+/// This is the simplest way to plug a custom transport into `renet2` (e.g. websocket bindings provided by a game
+/// engine) without implementing the socket traits:
+/// - `send` is called with the destination address and an encoded packet. Return
+///   [`std::io::ErrorKind::ConnectionAborted`] if the destination's connection was closed.
+/// - `recv` should write the next received packet into the buffer and return its length and source address, or
+///   return [`std::io::ErrorKind::WouldBlock`] when no packets are available. The buffer is at least
+///   [`NETCODE_MAX_PACKET_BYTES`] long, see [`Self::with_max_packet_bytes`] for transports that can carry larger
+///   packets.
+///
+/// Connection events of [`ServerSocket`] (accepted, denied, and disconnected addresses) are ignored. Implement the
+/// socket traits directly if your transport needs them.
+///
+/// Callback sockets are treated as unencrypted and unreliable by default. Use [`Self::new_with`] to use a different
+/// policy, which must match the socket on the other end.
+pub struct CallbackSocket {
+    addr: SocketAddr,
+    encrypted: bool,
+    reliable: bool,
+    max_packet_bytes: usize,
+    closed: bool,
+    send: SendCallback,
+    recv: RecvCallback,
+}
+
+impl CallbackSocket {
+    /// Makes a new callback socket.
+    ///
+    /// The `addr` is the address of this socket. Server sockets should use one of their public addresses.
+    pub fn new(
+        addr: SocketAddr,
+        send: impl FnMut(SocketAddr, &[u8]) -> std::io::Result<()> + Send + Sync + 'static,
+        recv: impl FnMut(&mut [u8]) -> std::io::Result<(usize, SocketAddr)> + Send + Sync + 'static,
+    ) -> Self {
+        Self::new_with(addr, false, false, send, recv)
+    }
+
+    /// Makes a new callback socket with a specific encryption and reliability policy.
+    pub fn new_with(
+        addr: SocketAddr,
+        encrypted: bool,
+        reliable: bool,
+        send: impl FnMut(SocketAddr, &[u8]) -> std::io::Result<()> + Send + Sync + 'static,
+        recv: impl FnMut(&mut [u8]) -> std::io::Result<(usize, SocketAddr)> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            addr,
+            encrypted,
+            reliable,
+            max_packet_bytes: NETCODE_MAX_PACKET_BYTES,
+            closed: false,
+            send: Box::new(send),
+            recv: Box::new(recv),
+        }
+    }
+
+    /// Sets the size of the largest packet the transport can carry, see [`ServerSocket::max_packet_bytes`] and
+    /// [`ClientSocket::max_packet_bytes`].
+    ///
+    /// Defaults to [`NETCODE_MAX_PACKET_BYTES`].
+    pub fn with_max_packet_bytes(mut self, max_packet_bytes: usize) -> Self {
+        self.max_packet_bytes = max_packet_bytes;
+        self
+    }
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        if self.closed {
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+        (self.recv)(buffer)
+    }
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        if self.closed {
+            return Err(std::io::Error::from(std::io::ErrorKind::ConnectionAborted).into());
+        }
+        (self.send)(addr, packet).map_err(NetcodeTransportError::IO)
+    }
+}
+
+impl std::fmt::Debug for CallbackSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackSocket")
+            .field("addr", &self.addr)
+            .field("encrypted", &self.encrypted)
+            .field("reliable", &self.reliable)
+            .field("max_packet_bytes", &self.max_packet_bytes)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientSocket for CallbackSocket {
+    fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+    fn is_reliable(&self) -> bool {
+        self.reliable
+    }
+    fn max_packet_bytes(&self) -> usize {
+        self.max_packet_bytes
+    }
+
+    fn addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.closed
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+    }
+
+    fn preupdate(&mut self) {}
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        CallbackSocket::try_recv(self, buffer)
+    }
+
+    fn postupdate(&mut self) {}
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        CallbackSocket::send(self, addr, packet)
+    }
+}
+
+impl ServerSocket for CallbackSocket {
+    fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+    fn is_reliable(&self) -> bool {
+        self.reliable
+    }
+    fn max_packet_bytes(&self) -> usize {
+        self.max_packet_bytes
+    }
+
+    fn addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.closed
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+    }
+
+    fn connection_denied(&mut self, _: SocketAddr) {}
+    fn connection_accepted(&mut self, _: u64, _: SocketAddr) {}
+    fn disconnect(&mut self, _: SocketAddr) {}
+    fn preupdate(&mut self) {}
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        CallbackSocket::try_recv(self, buffer)
+    }
+
+    fn postupdate(&mut self) {}
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        CallbackSocket::send(self, addr, packet)
+    }
+}
@@ -1,28 +1,47 @@
 use std::{io, net::SocketAddr, time::Duration};
 
-use renetcode2::{ClientAuthentication, DisconnectReason, NetcodeClient, NetcodeError, NETCODE_MAX_PACKET_BYTES};
+use renetcode2::{
+    ClientAuthentication, DisconnectReason, HandshakeTimings, NetcodeClient, NetcodeError, NETCODE_MAX_PACKET_BYTES,
+    NETCODE_USER_DATA_BYTES,
+};
 
 use renet2::{ClientId, RenetClient};
 
-use super::{ClientSocket, NetcodeTransportError};
+use super::{ClientSocket, NetcodeTransportError, SocketKind, TransportErrorPolicy};
+#[cfg(feature = "disconnect_notices")]
+use super::{DisconnectNotices, DISCONNECT_NOTICE_ACK_PACKET_TYPE, DISCONNECT_NOTICE_PACKET_TYPE};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct NetcodeClientTransport {
     socket: Box<dyn ClientSocket>,
     netcode_client: NetcodeClient,
-    buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+    buffer: Vec<u8>,
+    error_policy: TransportErrorPolicy,
+    #[cfg(feature = "disconnect_notices")]
+    disconnect_notices: DisconnectNotices,
 }
 
 impl NetcodeClientTransport {
     /// Makes a new client transport with the given [`ClientSocket`].
     pub fn new(current_time: Duration, authentication: ClientAuthentication, socket: impl ClientSocket) -> Result<Self, NetcodeError> {
-        let netcode_client = NetcodeClient::new(current_time, authentication)?.set_encryption_policy(!socket.is_encrypted());
+        #[allow(unused_mut)]
+        let mut netcode_client = NetcodeClient::new(current_time, authentication)?.set_encryption_policy(!socket.is_encrypted());
+        #[cfg(feature = "disconnect_notices")]
+        let disconnect_notices = DisconnectNotices::default();
+        #[cfg(feature = "disconnect_notices")]
+        netcode_client.register_extension(DISCONNECT_NOTICE_PACKET_TYPE, disconnect_notices.clone())?;
+
+        // Sockets may receive packets larger than NETCODE_MAX_PACKET_BYTES if the server raised its payload limit.
+        let buffer = vec![0u8; socket.max_packet_bytes().max(NETCODE_MAX_PACKET_BYTES)];
 
         Ok(Self {
             socket: Box::new(socket),
             netcode_client,
-            buffer: [0u8; NETCODE_MAX_PACKET_BYTES],
+            buffer,
+            error_policy: TransportErrorPolicy::default(),
+            #[cfg(feature = "disconnect_notices")]
+            disconnect_notices,
         })
     }
 
@@ -31,6 +50,13 @@ impl NetcodeClientTransport {
         self.socket.is_reliable()
     }
 
+    /// Gets the kind of transport the client is connected through.
+    ///
+    /// See [`ClientSocket::kind`].
+    pub fn active_transport(&self) -> SocketKind {
+        self.socket.kind()
+    }
+
     /// Gets the client's [`SocketAddr`].
     ///
     /// Will return an error if the client doesn't have an address (e.g. a WebTransport client).
@@ -43,6 +69,20 @@ impl NetcodeClientTransport {
         self.netcode_client.client_id()
     }
 
+    /// Returns the user data the server echoed back when the connection was established.
+    ///
+    /// See [`NetcodeClient::confirmed_user_data`].
+    pub fn confirmed_user_data(&self) -> Option<[u8; NETCODE_USER_DATA_BYTES]> {
+        self.netcode_client.confirmed_user_data()
+    }
+
+    /// Returns the times at which each phase of the connection handshake completed, once connected.
+    ///
+    /// See [`NetcodeClient::handshake_timings`].
+    pub fn handshake_timings(&self) -> Option<HandshakeTimings> {
+        self.netcode_client.handshake_timings()
+    }
+
     /// Returns `true` if the netcode client is connected.
     pub fn is_connected(&self) -> bool {
         self.netcode_client.is_connected()
@@ -65,6 +105,18 @@ impl NetcodeClientTransport {
         self.netcode_client.time_since_last_received_packet()
     }
 
+    /// Returns the policy for handling recoverable errors when sending packets to the server.
+    pub fn error_policy(&self) -> TransportErrorPolicy {
+        self.error_policy
+    }
+
+    /// Sets the policy for handling recoverable errors when sending packets to the server.
+    ///
+    /// Defaults to [`TransportErrorPolicy::Ignore`]. Send errors are returned regardless of the policy.
+    pub fn set_error_policy(&mut self, error_policy: TransportErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+
     /// Disconnects the client from the transport layer.
     ///
     /// This sends the disconnect packet instantly, use this when closing/exiting games,
@@ -84,6 +136,16 @@ impl NetcodeClientTransport {
         }
     }
 
+    /// Returns the id of another client that disconnected from the server, in the order they were received.
+    ///
+    /// Notices are only sent by servers with
+    /// [`NetcodeServerTransport::set_broadcast_disconnects`](crate::NetcodeServerTransport::set_broadcast_disconnects)
+    /// enabled.
+    #[cfg(feature = "disconnect_notices")]
+    pub fn get_peer_disconnected(&mut self) -> Option<ClientId> {
+        self.disconnect_notices.pop()
+    }
+
     /// If the client is disconnected, returns the reason.
     pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
         self.netcode_client.disconnect_reason()
@@ -100,7 +162,7 @@ impl NetcodeClientTransport {
         let packets = connection.get_packets_to_send();
         for packet in packets {
             let (addr, payload) = self.netcode_client.generate_payload_packet(&packet)?;
-            self.socket.send(addr, payload)?;
+            send_with_policy(self.error_policy, &mut self.socket, connection, addr, payload)?;
         }
 
         Ok(())
@@ -130,11 +192,13 @@ impl NetcodeClientTransport {
         }
 
         if self.netcode_client.is_connected() {
+            client.set_client_id(self.netcode_client.client_id());
             client.set_connected();
         } else if self.netcode_client.is_connecting() {
             client.set_connecting();
         }
 
+        self.socket.advance_time(duration);
         self.socket.preupdate();
 
         loop {
@@ -157,8 +221,16 @@ impl NetcodeClientTransport {
             }
         }
 
+        #[cfg(feature = "disconnect_notices")]
+        if let Some(ack) = self.disconnect_notices.take_ack() {
+            let (addr, packet) = self
+                .netcode_client
+                .generate_extension_packet(DISCONNECT_NOTICE_ACK_PACKET_TYPE, &ack)?;
+            send_with_policy(self.error_policy, &mut self.socket, client, addr, packet)?;
+        }
+
         if let Some((packet, addr)) = self.netcode_client.update(duration) {
-            self.socket.send(addr, packet)?;
+            send_with_policy(self.error_policy, &mut self.socket, client, addr, packet)?;
         }
 
         self.socket.postupdate();
@@ -166,3 +238,20 @@ impl NetcodeClientTransport {
         Ok(())
     }
 }
+
+/// Sends a packet to the server, disconnecting the client if the error policy requires it.
+fn send_with_policy(
+    error_policy: TransportErrorPolicy,
+    socket: &mut Box<dyn ClientSocket>,
+    client: &mut RenetClient,
+    addr: SocketAddr,
+    packet: &[u8],
+) -> Result<(), NetcodeTransportError> {
+    error_policy.send(|| socket.send(addr, packet)).map_err(|(error, disconnect)| {
+        if disconnect {
+            log::debug!("Disconnecting client after failing to send packet: {error}");
+            client.disconnect_due_to_transport();
+        }
+        error
+    })
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use renet2::ClientId;
+use renetcode2::PacketExtension;
+
+/// The netcode extension packet type of the notices sent to the remaining clients when a client disconnects.
+///
+/// The payload of a notice is its sequence number followed by the id of the disconnected client, both as 8
+/// little-endian bytes. Notices are resent until the client acknowledges them with a
+/// [`DISCONNECT_NOTICE_ACK_PACKET_TYPE`] packet.
+///
+/// See [`NetcodeServerTransport::set_broadcast_disconnects`](crate::NetcodeServerTransport::set_broadcast_disconnects)
+/// and [`NetcodeClientTransport::get_peer_disconnected`](crate::NetcodeClientTransport::get_peer_disconnected).
+pub const DISCONNECT_NOTICE_PACKET_TYPE: u8 = 15;
+
+/// The netcode extension packet type of the acknowledgements clients send for disconnect notices.
+///
+/// The payload of an acknowledgement is the sequence number of the next notice the client expects as 8
+/// little-endian bytes, acknowledging all notices before it.
+pub const DISCONNECT_NOTICE_ACK_PACKET_TYPE: u8 = 14;
+
+/// Interval at which unacknowledged disconnect notices are resent.
+pub(crate) const DISCONNECT_NOTICE_RESEND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of times the oldest unacknowledged notice is resent before the server stops sending notices to a client.
+pub(crate) const DISCONNECT_NOTICE_MAX_RESENDS: u32 = 50;
+
+/// Number of unacknowledged notices a client can have before the server stops sending notices to it.
+pub(crate) const DISCONNECT_NOTICE_MAX_PENDING: usize = 256;
+
+#[derive(Debug, Default)]
+struct ReceivedNotices {
+    disconnected: VecDeque<ClientId>,
+    next_sequence: u64,
+    ack_pending: bool,
+}
+
+/// Collects the disconnect notices received by a client.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DisconnectNotices(Arc<Mutex<ReceivedNotices>>);
+
+impl DisconnectNotices {
+    pub(crate) fn pop(&self) -> Option<ClientId> {
+        self.0.lock().unwrap().disconnected.pop_front()
+    }
+
+    /// Returns the payload of the acknowledgement to send, if notices were received since the last one.
+    pub(crate) fn take_ack(&self) -> Option<[u8; 8]> {
+        let mut notices = self.0.lock().unwrap();
+        if !std::mem::take(&mut notices.ack_pending) {
+            return None;
+        }
+        Some(notices.next_sequence.to_le_bytes())
+    }
+}
+
+impl PacketExtension for DisconnectNotices {
+    fn receive(&mut self, _: u64, payload: &[u8]) {
+        let Ok(payload) = <[u8; 16]>::try_from(payload) else {
+            log::debug!("Discarded disconnect notice with invalid length {}", payload.len());
+            return;
+        };
+        let sequence = u64::from_le_bytes(payload[..8].try_into().unwrap());
+        let client_id = ClientId::from_le_bytes(payload[8..].try_into().unwrap());
+
+        // Notices are accepted in order, later ones are resent by the server until acknowledged.
+        let mut notices = self.0.lock().unwrap();
+        if sequence == notices.next_sequence {
+            notices.disconnected.push_back(client_id);
+            notices.next_sequence += 1;
+        }
+        notices.ack_pending = true;
+    }
+}
+
+/// Disconnect notices waiting to be acknowledged by a client.
+///
+/// Clients that don't acknowledge notices (e.g. clients built without the `disconnect_notices` feature) are given
+/// up on after [`DISCONNECT_NOTICE_MAX_RESENDS`] resends or [`DISCONNECT_NOTICE_MAX_PENDING`] notices, so their
+/// notices don't accumulate and aren't resent forever.
+#[derive(Debug, Default)]
+pub(crate) struct PendingNotices {
+    notices: VecDeque<(u64, ClientId)>,
+    next_sequence: u64,
+    next_unsent: u64,
+    /// Number of times the oldest notice was resent.
+    resends: u32,
+    /// Whether the client stopped acknowledging notices, in which case no more notices are sent to it.
+    abandoned: bool,
+}
+
+impl PendingNotices {
+    pub(crate) fn push(&mut self, client_id: ClientId) {
+        if self.abandoned {
+            return;
+        }
+        if self.notices.len() >= DISCONNECT_NOTICE_MAX_PENDING {
+            self.abandon();
+            return;
+        }
+        self.notices.push_back((self.next_sequence, client_id));
+        self.next_sequence += 1;
+    }
+
+    fn abandon(&mut self) {
+        log::debug!(
+            "Client stopped acknowledging disconnect notices, dropping {} notices",
+            self.notices.len()
+        );
+        self.notices.clear();
+        self.abandoned = true;
+    }
+
+    fn ack(&mut self, acked: u64) {
+        while self.notices.front().is_some_and(|(sequence, _)| *sequence < acked) {
+            self.notices.pop_front();
+            self.resends = 0;
+        }
+    }
+
+    /// Returns the payloads of the notices to send, only the ones that were never sent unless `resend` is true.
+    pub(crate) fn notices_to_send(&mut self, resend: bool) -> Vec<[u8; 16]> {
+        if resend && self.notices.front().is_some_and(|(sequence, _)| *sequence < self.next_unsent) {
+            self.resends += 1;
+            if self.resends > DISCONNECT_NOTICE_MAX_RESENDS {
+                self.abandon();
+            }
+        }
+        let next_unsent = std::mem::replace(&mut self.next_unsent, self.next_sequence);
+        self.notices
+            .iter()
+            .filter(|(sequence, _)| resend || *sequence >= next_unsent)
+            .map(|(sequence, client_id)| {
+                let mut payload = [0u8; 16];
+                payload[..8].copy_from_slice(&sequence.to_le_bytes());
+                payload[8..].copy_from_slice(&client_id.to_le_bytes());
+                payload
+            })
+            .collect()
+    }
+}
+
+/// Tracks the disconnect notices sent by the server to each client, and receives their acknowledgements.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DisconnectNoticeQueue(Arc<Mutex<HashMap<ClientId, PendingNotices>>>);
+
+impl DisconnectNoticeQueue {
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ClientId, PendingNotices>> {
+        self.0.lock().unwrap()
+    }
+}
+
+impl PacketExtension for DisconnectNoticeQueue {
+    fn receive(&mut self, client_id: u64, payload: &[u8]) {
+        let Ok(payload) = <[u8; 8]>::try_from(payload) else {
+            log::debug!("Discarded disconnect notice ack with invalid length {}", payload.len());
+            return;
+        };
+        let acked = u64::from_le_bytes(payload);
+        if let Some(pending) = self.0.lock().unwrap().get_mut(&client_id) {
+            pending.ack(acked);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unacknowledged_notices_expire() {
+        let mut pending = PendingNotices::default();
+        pending.push(1);
+        assert_eq!(pending.notices_to_send(false).len(), 1);
+        for _ in 0..DISCONNECT_NOTICE_MAX_RESENDS {
+            assert_eq!(pending.notices_to_send(true).len(), 1);
+        }
+
+        // Acks reset the resend count.
+        pending.push(2);
+        pending.ack(1);
+        assert_eq!(pending.notices_to_send(false).len(), 1);
+        for _ in 0..DISCONNECT_NOTICE_MAX_RESENDS {
+            assert_eq!(pending.notices_to_send(true).len(), 1);
+        }
+
+        // The client is given up on once the oldest notice was resent too many times.
+        assert!(pending.notices_to_send(true).is_empty());
+        pending.push(3);
+        assert!(pending.notices_to_send(true).is_empty());
+    }
+
+    #[test]
+    fn pending_notices_are_capped() {
+        let mut pending = PendingNotices::default();
+        for client_id in 0..DISCONNECT_NOTICE_MAX_PENDING as u64 {
+            pending.push(client_id);
+        }
+        assert_eq!(pending.notices_to_send(false).len(), DISCONNECT_NOTICE_MAX_PENDING);
+
+        pending.push(DISCONNECT_NOTICE_MAX_PENDING as u64);
+        assert!(pending.notices.is_empty());
+        assert!(pending.notices_to_send(true).is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+use std::{io, time::Duration};
+
+use super::NetcodeTransportError;
+
+/// Delay before the first retry of [`TransportErrorPolicy::RetryUpTo`], doubled for each later retry.
+const RETRY_BACKOFF: Duration = Duration::from_micros(50);
+
+/// Longest delay between two retries of [`TransportErrorPolicy::RetryUpTo`].
+const MAX_RETRY_BACKOFF: Duration = Duration::from_millis(2);
+
+/// Policy for handling recoverable errors when a transport fails to send a packet to a remote connection.
+///
+/// Recoverable errors are I/O errors with kind [`WouldBlock`](io::ErrorKind::WouldBlock),
+/// [`Interrupted`](io::ErrorKind::Interrupted), or [`TimedOut`](io::ErrorKind::TimedOut). Other errors are not
+/// affected by the policy. In particular, servers always disconnect clients when sending fails with
+/// [`ConnectionAborted`](io::ErrorKind::ConnectionAborted) (the socket reports the connection is closed).
+///
+/// Set with [`NetcodeServerTransport::set_error_policy`](crate::NetcodeServerTransport::set_error_policy) and
+/// [`NetcodeClientTransport::set_error_policy`](crate::NetcodeClientTransport::set_error_policy).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TransportErrorPolicy {
+    /// Disconnects the client as soon as a send fails.
+    DisconnectImmediately,
+    /// Retries a failed send up to `n` times, then disconnects the client if it still fails.
+    ///
+    /// The thread sleeps before each retry to give the socket a chance to drain, starting at 50µs and doubling up
+    /// to 2ms (except on WASM, where retries are immediate).
+    RetryUpTo(u32),
+    /// Drops the packet and keeps the client connected. The error is still logged or returned.
+    #[default]
+    Ignore,
+}
+
+impl TransportErrorPolicy {
+    /// Returns `true` if the error is recoverable and should be handled by the policy.
+    pub fn is_recoverable(error: &NetcodeTransportError) -> bool {
+        let NetcodeTransportError::IO(error) = error else {
+            return false;
+        };
+        matches!(
+            error.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted | io::ErrorKind::TimedOut
+        )
+    }
+
+    /// Sends a packet with `send`, applying the policy if it fails with a recoverable error.
+    ///
+    /// On failure, returns the last error and whether the client should be disconnected.
+    pub(crate) fn send(self, mut send: impl FnMut() -> Result<(), NetcodeTransportError>) -> Result<(), (NetcodeTransportError, bool)> {
+        let mut retries = match self {
+            Self::RetryUpTo(n) => n,
+            _ => 0,
+        };
+        let mut backoff = RETRY_BACKOFF;
+
+        loop {
+            let Err(error) = send() else {
+                return Ok(());
+            };
+            if !Self::is_recoverable(&error) {
+                return Err((error, false));
+            }
+            match self {
+                Self::Ignore => return Err((error, false)),
+                Self::DisconnectImmediately => return Err((error, true)),
+                Self::RetryUpTo(_) if retries == 0 => return Err((error, true)),
+                Self::RetryUpTo(_) => {
+                    retries -= 1;
+                    #[cfg(not(target_family = "wasm"))]
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a send function that fails `failures` times with the given error kind before succeeding.
+    fn failing_send(
+        failures: u32,
+        kind: io::ErrorKind,
+    ) -> (std::rc::Rc<std::cell::Cell<u32>>, impl FnMut() -> Result<(), NetcodeTransportError>) {
+        let attempts = std::rc::Rc::new(std::cell::Cell::new(0));
+        let inner_attempts = attempts.clone();
+        let send = move || {
+            inner_attempts.set(inner_attempts.get() + 1);
+            if inner_attempts.get() <= failures {
+                return Err(io::Error::from(kind).into());
+            }
+            Ok(())
+        };
+        (attempts, send)
+    }
+
+    #[test]
+    fn error_policies() {
+        let (attempts, send) = failing_send(1, io::ErrorKind::WouldBlock);
+        assert!(matches!(TransportErrorPolicy::Ignore.send(send), Err((_, false))));
+        assert_eq!(attempts.get(), 1);
+
+        let (attempts, send) = failing_send(1, io::ErrorKind::WouldBlock);
+        assert!(matches!(TransportErrorPolicy::DisconnectImmediately.send(send), Err((_, true))));
+        assert_eq!(attempts.get(), 1);
+
+        // Retries back off, waiting 50µs and then 100µs.
+        let (attempts, send) = failing_send(2, io::ErrorKind::TimedOut);
+        let start = std::time::Instant::now();
+        assert!(TransportErrorPolicy::RetryUpTo(2).send(send).is_ok());
+        assert!(start.elapsed() >= Duration::from_micros(150));
+        assert_eq!(attempts.get(), 3);
+
+        let (attempts, send) = failing_send(3, io::ErrorKind::Interrupted);
+        assert!(matches!(TransportErrorPolicy::RetryUpTo(2).send(send), Err((_, true))));
+        assert_eq!(attempts.get(), 3);
+
+        // Unrecoverable errors are not retried and don't disconnect.
+        let (attempts, send) = failing_send(1, io::ErrorKind::PermissionDenied);
+        assert!(matches!(TransportErrorPolicy::RetryUpTo(2).send(send), Err((_, false))));
+        assert_eq!(attempts.get(), 1);
+        let (_, send) = failing_send(1, io::ErrorKind::ConnectionAborted);
+        assert!(matches!(TransportErrorPolicy::DisconnectImmediately.send(send), Err((_, false))));
+    }
+}
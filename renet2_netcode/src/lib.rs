@@ -4,17 +4,33 @@
 
 use std::{error::Error, fmt};
 
+mod callback_socket;
 mod client;
+#[cfg(feature = "disconnect_notices")]
+mod disconnect_notice;
+mod error_policy;
 #[cfg(feature = "memory_transport")]
 mod memory_socket;
 #[cfg(all(feature = "native_transport", not(target_family = "wasm")))]
 mod native_socket;
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+mod packet_mirror;
 mod server;
+#[cfg(all(feature = "shared_memory_transport", not(target_family = "wasm")))]
+mod shared_memory_socket;
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+mod simulated_socket;
 mod sockets;
 mod websocket_socket;
 mod webtransport_socket;
 
+pub use callback_socket::*;
 pub use client::*;
+#[cfg(feature = "disconnect_notices")]
+pub(crate) use disconnect_notice::{DisconnectNoticeQueue, DisconnectNotices, DISCONNECT_NOTICE_RESEND_INTERVAL};
+#[cfg(feature = "disconnect_notices")]
+pub use disconnect_notice::{DISCONNECT_NOTICE_ACK_PACKET_TYPE, DISCONNECT_NOTICE_PACKET_TYPE};
+pub use error_policy::*;
 pub use server::*;
 pub use sockets::*;
 
@@ -22,14 +38,24 @@ pub use sockets::*;
 pub use memory_socket::*;
 #[cfg(all(feature = "native_transport", not(target_family = "wasm")))]
 pub use native_socket::*;
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+pub use packet_mirror::{PacketMirrorConfig, PacketMirrorMode};
+#[cfg(all(feature = "shared_memory_transport", not(target_family = "wasm")))]
+pub use shared_memory_socket::*;
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+pub use simulated_socket::*;
 #[allow(unused_imports)]
 pub use websocket_socket::*;
 pub use webtransport_socket::*;
 
 pub use renetcode2::{
-    generate_random_bytes, ClientAuthentication, ConnectToken, DisconnectReason as NetcodeDisconnectReason, NetcodeError,
-    ServerAuthentication, ServerConfig, ServerSocketConfig, TokenGenerationError, NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES,
+    generate_random_bytes, is_netcode_compatible, netcode_version, CapacityEvent, CapacityThreshold, ClientAuthentication, ClientInfo,
+    ConnectToken, DenialReason, DisconnectReason as NetcodeDisconnectReason, HandshakeTimings, NetcodeError, PendingClientInfo,
+    PendingClientState, ServerAuthentication, ServerConfig, ServerSocketConfig, TokenGenerationError, NETCODE_KEY_BYTES,
+    NETCODE_MAX_PENDING_CLIENTS, NETCODE_USER_DATA_BYTES,
 };
+#[cfg(feature = "serde")]
+pub use renetcode2::{UserData, UserDataError};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Message))]
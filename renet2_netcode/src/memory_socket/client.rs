@@ -1,6 +1,8 @@
 use std::{io::ErrorKind, net::SocketAddr};
 
-use crate::{ClientSocket, NetcodeTransportError};
+use crossbeam::channel::TrySendError;
+
+use crate::{ClientSocket, NetcodeTransportError, SocketKind};
 use renetcode2::NETCODE_MAX_PACKET_BYTES;
 
 use super::*;
@@ -19,13 +21,21 @@ pub struct MemorySocketClient {
 
 impl MemorySocketClient {
     /// Makes a new in-memory client socket.
+    ///
+    /// Panics if the channels are bounded, since the socket is reliable (see [`Self::new_with`]).
     pub fn new(client_id: u16, channels: MemorySocketChannels) -> Self {
         Self::new_with(client_id, channels, true, true)
     }
 
     /// Makes a new in-memory client socket with a specific encryption and reliability policy.
+    ///
+    /// Panics if `reliable` is `true` and the channels are bounded, since full buffers drop packets.
     pub fn new_with(client_id: u16, channels: MemorySocketChannels, encrypted: bool, reliable: bool) -> Self {
         assert!(client_id != IN_MEMORY_SERVER_ID);
+        assert!(
+            !(reliable && channels.is_bounded()),
+            "reliable memory sockets can't use bounded channels"
+        );
         Self {
             client_id,
             channels,
@@ -40,6 +50,14 @@ impl MemorySocketClient {
     pub fn id(&self) -> u64 {
         self.client_id as u64
     }
+
+    /// Returns the number of packets sent by this socket that the server has not received yet.
+    ///
+    /// A growing count means the server is not keeping up. If the socket's channels are bounded (see
+    /// [`new_memory_sockets`]), sends fail once the count reaches the capacity.
+    pub fn pending_count(&self) -> usize {
+        self.channels.sender.len()
+    }
 }
 
 impl ClientSocket for MemorySocketClient {
@@ -49,6 +67,9 @@ impl ClientSocket for MemorySocketClient {
     fn is_reliable(&self) -> bool {
         self.reliable
     }
+    fn kind(&self) -> SocketKind {
+        SocketKind::Memory
+    }
 
     fn addr(&self) -> std::io::Result<SocketAddr> {
         Ok(in_memory_client_addr(self.client_id))
@@ -85,10 +106,10 @@ impl ClientSocket for MemorySocketClient {
             ..Default::default()
         };
         mem_packet.bytes[..packet.len()].copy_from_slice(packet);
-        self.channels
-            .sender
-            .send(mem_packet)
-            .map_err(|_| std::io::Error::from(ErrorKind::ConnectionAborted))?;
+        self.channels.sender.try_send(mem_packet).map_err(|err| match err {
+            TrySendError::Full(_) => std::io::Error::from(ErrorKind::WouldBlock),
+            TrySendError::Disconnected(_) => std::io::Error::from(ErrorKind::ConnectionAborted),
+        })?;
 
         Ok(())
     }
@@ -55,6 +55,36 @@ impl MemorySocketChannels {
         let (sender_a, receiver_a) = crossbeam::channel::unbounded();
         let (sender_b, receiver_b) = crossbeam::channel::unbounded();
 
+        Self::from_channels(sender_a, receiver_a, sender_b, receiver_b)
+    }
+
+    /// Generates a pair of connected [`MemorySocketChannels`] that can each buffer up to `capacity` packets.
+    ///
+    /// Sockets fail to send packets with [`ErrorKind::WouldBlock`](std::io::ErrorKind::WouldBlock) when their
+    /// partner's buffer is full, so sockets made with bounded channels must not be reliable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn bounded_channel_pair(capacity: usize) -> (MemorySocketChannels, MemorySocketChannels) {
+        assert!(capacity > 0, "memory socket channels need a non-zero capacity");
+        let (sender_a, receiver_a) = crossbeam::channel::bounded(capacity);
+        let (sender_b, receiver_b) = crossbeam::channel::bounded(capacity);
+
+        Self::from_channels(sender_a, receiver_a, sender_b, receiver_b)
+    }
+
+    /// Returns `true` if the channels were made with [`Self::bounded_channel_pair`].
+    pub fn is_bounded(&self) -> bool {
+        self.sender.capacity().is_some()
+    }
+
+    fn from_channels(
+        sender_a: Sender<InMemoryPacket>,
+        receiver_a: Receiver<InMemoryPacket>,
+        sender_b: Sender<InMemoryPacket>,
+        receiver_b: Receiver<InMemoryPacket>,
+    ) -> (MemorySocketChannels, MemorySocketChannels) {
         (
             MemorySocketChannels {
                 sender: sender_a,
@@ -76,14 +106,30 @@ impl MemorySocketChannels {
 /// Set the `reliable` to `true` if you want to downgrade all channels to unreliable.
 /// If you don't want to downgrade channels (e.g. for performance testing), set it to false.
 ///
+/// Set `capacity` to limit the number of packets buffered in each direction between the server and each client,
+/// or `None` for unbounded buffers. When a buffer is full, sending fails with
+/// [`ErrorKind::WouldBlock`](std::io::ErrorKind::WouldBlock) and the packet is dropped. Bounded buffers keep a slow
+/// consumer from growing memory without limit, see [`MemorySocketClient::pending_count`] to detect backpressure.
+/// Since dropped packets would break the guarantees of reliable sockets, `reliable` must be `false` when `capacity`
+/// is set.
+///
 /// Returns `(server socket, client sockets)`. Client addresses are derived from client ids.
 ///
 /// Note that duplicate client ids will be removed.
 ///
 /// # Panics
 ///
-/// Panics if any client id equals `u16::MAX`.
-pub fn new_memory_sockets(mut client_ids: Vec<u16>, encrypted: bool, reliable: bool) -> (MemorySocketServer, Vec<MemorySocketClient>) {
+/// Panics if any client id equals `u16::MAX`, if `capacity` is zero, or if `capacity` is set and `reliable` is `true`.
+pub fn new_memory_sockets(
+    mut client_ids: Vec<u16>,
+    encrypted: bool,
+    reliable: bool,
+    capacity: Option<usize>,
+) -> (MemorySocketServer, Vec<MemorySocketClient>) {
+    assert!(
+        !(reliable && capacity.is_some()),
+        "reliable memory sockets can't have a capacity, since full buffers drop packets"
+    );
     client_ids.sort_unstable();
     client_ids.dedup();
 
@@ -93,7 +139,10 @@ pub fn new_memory_sockets(mut client_ids: Vec<u16>, encrypted: bool, reliable: b
     client_sockets.reserve(client_ids.len());
 
     for client_id in client_ids {
-        let (server_chans, client_chans) = MemorySocketChannels::channel_pair();
+        let (server_chans, client_chans) = match capacity {
+            Some(capacity) => MemorySocketChannels::bounded_channel_pair(capacity),
+            None => MemorySocketChannels::channel_pair(),
+        };
         server_channels.push((client_id, server_chans));
         client_sockets.push(MemorySocketClient::new_with(client_id, client_chans, encrypted, reliable));
     }
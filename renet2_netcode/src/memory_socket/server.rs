@@ -1,5 +1,7 @@
 use std::{io::ErrorKind, net::SocketAddr};
 
+use crossbeam::channel::TrySendError;
+
 use crate::{NetcodeTransportError, ServerSocket};
 
 use super::*;
@@ -20,6 +22,8 @@ impl MemorySocketServer {
     /// Makes a new in-memory socket for a server.
     ///
     /// Takes a vector of `(client id, socket channels)`.
+    ///
+    /// Panics if any channels are bounded, since the socket is reliable (see [`Self::new_with`]).
     pub fn new(clients: Vec<(u16, MemorySocketChannels)>) -> Self {
         Self::new_with(clients, true, true)
     }
@@ -33,7 +37,13 @@ impl MemorySocketServer {
     ///
     /// If `reliable` is set to `true` then the memory transport will downgrade all channels to unreliable.
     /// If you don't want to downgrade channels (e.g. for performance testing), set it to false.
+    ///
+    /// Panics if `reliable` is `true` and any channels are bounded, since full buffers drop packets.
     pub fn new_with(clients: Vec<(u16, MemorySocketChannels)>, encrypted: bool, reliable: bool) -> Self {
+        assert!(
+            !(reliable && clients.iter().any(|(_, channels)| channels.is_bounded())),
+            "reliable memory sockets can't use bounded channels"
+        );
         Self {
             clients,
             encrypted,
@@ -100,11 +110,10 @@ impl ServerSocket for MemorySocketServer {
             .iter()
             .position(|(id, _)| *id == client_id)
             .ok_or_else(|| std::io::Error::from(ErrorKind::AddrNotAvailable))?;
-        self.clients[idx]
-            .1
-            .sender
-            .send(mem_packet)
-            .map_err(|_| std::io::Error::from(ErrorKind::ConnectionAborted))?;
+        self.clients[idx].1.sender.try_send(mem_packet).map_err(|err| match err {
+            TrySendError::Full(_) => std::io::Error::from(ErrorKind::WouldBlock),
+            TrySendError::Disconnected(_) => std::io::Error::from(ErrorKind::ConnectionAborted),
+        })?;
 
         Ok(())
     }
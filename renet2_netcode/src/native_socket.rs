@@ -1,6 +1,6 @@
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6, UdpSocket};
 
-use super::{ClientSocket, NetcodeError, NetcodeTransportError, ServerSocket};
+use super::{ClientSocket, NetcodeError, NetcodeTransportError, ServerSocket, SocketKind};
 
 /// Implementation of [`ServerSocket`] for `UdpSockets`.
 #[derive(Debug)]
@@ -14,6 +14,115 @@ impl NativeSocket {
         socket.set_nonblocking(true)?;
         Ok(Self { socket })
     }
+
+    /// Makes a new native socket with specific OS buffer sizes (`SO_RCVBUF` and `SO_SNDBUF`).
+    ///
+    /// Larger receive buffers keep the OS from dropping inbound packets when the server receives bursts of traffic
+    /// between updates.
+    ///
+    /// The OS may adjust the requested sizes, e.g. Linux doubles them for bookkeeping overhead and clamps them to
+    /// `net.core.rmem_max`/`net.core.wmem_max`. Returns an error if either buffer ends up smaller than `min_bytes`.
+    pub fn new_with_buffers(socket: UdpSocket, recv_bytes: usize, send_bytes: usize, min_bytes: usize) -> Result<Self, NetcodeError> {
+        let socket = socket2::Socket::from(socket);
+        socket.set_recv_buffer_size(recv_bytes)?;
+        socket.set_send_buffer_size(send_bytes)?;
+
+        let actual_recv_bytes = socket.recv_buffer_size()?;
+        let actual_send_bytes = socket.send_buffer_size()?;
+        if actual_recv_bytes < min_bytes || actual_send_bytes < min_bytes {
+            return Err(std::io::Error::other(format!(
+                "socket buffers are smaller than {min_bytes} bytes (receive: {actual_recv_bytes}, send: {actual_send_bytes})"
+            ))
+            .into());
+        }
+
+        Self::new(socket.into())
+    }
+
+    /// Returns the raw file descriptor of the underlying `UdpSocket`, e.g. to register it in an epoll loop.
+    ///
+    /// See the caveats on the [`AsRawFd`](std::os::fd::AsRawFd) impl.
+    #[cfg(unix)]
+    pub fn raw_fd(&self) -> std::os::fd::RawFd {
+        std::os::fd::AsRawFd::as_raw_fd(&self.socket)
+    }
+
+    /// Returns the raw socket handle of the underlying `UdpSocket`, e.g. to register it in an IOCP loop.
+    ///
+    /// See the caveats on the [`AsRawSocket`](std::os::windows::io::AsRawSocket) impl.
+    #[cfg(windows)]
+    pub fn raw_socket(&self) -> std::os::windows::io::RawSocket {
+        std::os::windows::io::AsRawSocket::as_raw_socket(&self.socket)
+    }
+}
+
+/// Binds an IPv4 and an IPv6 native socket on all interfaces, to serve clients on both network stacks.
+///
+/// See [`bind_dual_stack_on`].
+pub fn bind_dual_stack(port: u16) -> Result<(NativeSocket, NativeSocket), NetcodeError> {
+    bind_dual_stack_on(Ipv4Addr::UNSPECIFIED, Ipv6Addr::UNSPECIFIED, port)
+}
+
+/// Binds an IPv4 and an IPv6 native socket, to serve clients on both network stacks.
+///
+/// The IPv6 socket only accepts IPv6 traffic and is bound to the same port as the IPv4 socket. If `port` is `0`, the
+/// OS assigns the port of the IPv4 socket.
+///
+/// Pass both sockets to [`NetcodeServerTransport::new_with_sockets`](crate::NetcodeServerTransport::new_with_sockets)
+/// as separate sockets. To let clients connect with either stack, enable
+/// [`NetcodeServerTransport::set_shares_connect_tokens`](crate::NetcodeServerTransport::set_shares_connect_tokens)
+/// on both sockets and generate connect tokens for the IPv4 socket that also list the public address of the IPv6
+/// socket.
+pub fn bind_dual_stack_on(ipv4: Ipv4Addr, ipv6: Ipv6Addr, port: u16) -> Result<(NativeSocket, NativeSocket), NetcodeError> {
+    let ipv4_socket = UdpSocket::bind(SocketAddr::new(ipv4.into(), port))?;
+    let port = ipv4_socket.local_addr()?.port();
+
+    // The IPv6 socket would also receive IPv4 traffic on some platforms, which conflicts with the IPv4 socket.
+    let ipv6_socket = socket2::Socket::new(socket2::Domain::IPV6, socket2::Type::DGRAM, Some(socket2::Protocol::UDP))?;
+    ipv6_socket.set_only_v6(true)?;
+    ipv6_socket.bind(&SocketAddrV6::new(ipv6, port, 0, 0).into())?;
+
+    Ok((NativeSocket::new(ipv4_socket)?, NativeSocket::new(ipv6_socket.into())?))
+}
+
+/// Exposes the file descriptor of the underlying `UdpSocket`.
+///
+/// The descriptor is owned by the socket and is closed when the socket is dropped. The socket is non-blocking and
+/// renet2 reads from it in the transport's `update`, so use the descriptor for readiness notifications (e.g. to wake
+/// your event loop and then call `update`) rather than reading from it yourself. Packets read elsewhere are lost
+/// to renet2, and changing the socket's options (e.g. making it blocking) can stall the transport.
+#[cfg(unix)]
+impl std::os::fd::AsRawFd for NativeSocket {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+#[cfg(unix)]
+impl std::os::fd::AsFd for NativeSocket {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+/// Exposes the socket handle of the underlying `UdpSocket`.
+///
+/// The handle is owned by the socket and is closed when the socket is dropped. The socket is non-blocking and
+/// renet2 reads from it in the transport's `update`, so use the handle for readiness notifications rather than
+/// reading from it yourself. Packets read elsewhere are lost to renet2, and changing the socket's options (e.g.
+/// making it blocking) can stall the transport.
+#[cfg(windows)]
+impl std::os::windows::io::AsRawSocket for NativeSocket {
+    fn as_raw_socket(&self) -> std::os::windows::io::RawSocket {
+        self.socket.as_raw_socket()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsSocket for NativeSocket {
+    fn as_socket(&self) -> std::os::windows::io::BorrowedSocket<'_> {
+        self.socket.as_socket()
+    }
 }
 
 impl ServerSocket for NativeSocket {
@@ -57,6 +166,9 @@ impl ClientSocket for NativeSocket {
     fn is_reliable(&self) -> bool {
         false
     }
+    fn kind(&self) -> SocketKind {
+        SocketKind::Native
+    }
 
     fn addr(&self) -> std::io::Result<SocketAddr> {
         self.socket.local_addr()
@@ -80,3 +192,68 @@ impl ClientSocket for NativeSocket {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_socket() -> NativeSocket {
+        NativeSocket::new(UdpSocket::bind("127.0.0.1:0").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn buffers() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        assert!(NativeSocket::new_with_buffers(socket, 64 * 1024, 64 * 1024, 32 * 1024).is_ok());
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let result = NativeSocket::new_with_buffers(socket, 64 * 1024, 64 * 1024, usize::MAX);
+        assert!(matches!(result, Err(NetcodeError::IoError(_))));
+    }
+
+    #[test]
+    fn dual_stack() {
+        let (ipv4_socket, ipv6_socket) = match bind_dual_stack_on(Ipv4Addr::LOCALHOST, Ipv6Addr::LOCALHOST, 0) {
+            Ok(sockets) => sockets,
+            // IPv6 may be disabled on the test machine.
+            Err(NetcodeError::IoError(err)) if err.kind() == std::io::ErrorKind::AddrNotAvailable => return,
+            Err(err) => panic!("{err}"),
+        };
+        let ipv4_addr = ServerSocket::addr(&ipv4_socket).unwrap();
+        let ipv6_addr = ServerSocket::addr(&ipv6_socket).unwrap();
+        assert!(ipv4_addr.is_ipv4());
+        assert!(ipv6_addr.is_ipv6());
+        assert_eq!(ipv4_addr.port(), ipv6_addr.port());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn raw_fd() {
+        use std::os::fd::{AsFd, AsRawFd};
+
+        let socket = new_socket();
+        let fd = socket.raw_fd();
+        assert!(fd >= 0);
+        assert_eq!(socket.as_raw_fd(), fd);
+        assert_eq!(socket.as_fd().as_raw_fd(), fd);
+
+        // The descriptor refers to the same socket.
+        let duplicate = UdpSocket::from(socket.as_fd().try_clone_to_owned().unwrap());
+        assert_eq!(duplicate.local_addr().unwrap(), ServerSocket::addr(&socket).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn raw_socket() {
+        use std::os::windows::io::{AsRawSocket, AsSocket};
+
+        let socket = new_socket();
+        let handle = socket.raw_socket();
+        assert_eq!(socket.as_raw_socket(), handle);
+        assert_eq!(socket.as_socket().as_raw_socket(), handle);
+
+        // The handle refers to the same socket.
+        let duplicate = UdpSocket::from(socket.as_socket().try_clone_to_owned().unwrap());
+        assert_eq!(duplicate.local_addr().unwrap(), ServerSocket::addr(&socket).unwrap());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use renet2::ClientId;
+
+/// Which copy of received packets is forwarded by a packet mirror, see [`PacketMirrorConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketMirrorMode {
+    /// Forwards every packet received by the server sockets as-is, before netcode processes it.
+    ///
+    /// Mirrored datagrams are prefixed with the socket id (1 byte), the sender's IP as an IPv6 address (16 bytes),
+    /// and the sender's port (2 bytes, little endian).
+    Raw,
+    /// Forwards the decrypted payloads of connected clients.
+    ///
+    /// Mirrored datagrams are prefixed with the client id (8 bytes, little endian).
+    Decrypted,
+}
+
+/// Config for mirroring received packets to an observer, see [`NetcodeServerTransport::set_packet_mirror`].
+///
+/// This is a debugging and monitoring tool. Mirrored packets are sent over plain UDP, so in
+/// [`PacketMirrorMode::Decrypted`] mode client payloads are exposed in cleartext to the observer and anyone who can
+/// read traffic to it. Only mirror to trusted observers on a trusted network.
+///
+/// [`NetcodeServerTransport::set_packet_mirror`]: crate::NetcodeServerTransport::set_packet_mirror
+#[derive(Debug, Clone)]
+pub struct PacketMirrorConfig {
+    /// Address of the observer that receives mirrored packets.
+    pub observer_addr: SocketAddr,
+    pub mode: PacketMirrorMode,
+    /// Maximum number of mirrored bytes per second (including prefixes), packets over the limit are not mirrored.
+    ///
+    /// This keeps mirroring from saturating the server's uplink when it receives a lot of traffic.
+    pub max_bytes_per_sec: usize,
+}
+
+impl PacketMirrorConfig {
+    /// Makes a new config that mirrors raw packets to `observer_addr`, up to 1MB per second.
+    pub fn new(observer_addr: SocketAddr) -> Self {
+        Self {
+            observer_addr,
+            mode: PacketMirrorMode::Raw,
+            max_bytes_per_sec: 1_000_000,
+        }
+    }
+}
+
+/// Forwards copies of received packets to an observer.
+#[derive(Debug)]
+pub(crate) struct PacketMirror {
+    socket: UdpSocket,
+    config: PacketMirrorConfig,
+    /// Bytes mirrored in the current one-second window.
+    window_bytes: usize,
+    window_elapsed: Duration,
+    out: Vec<u8>,
+}
+
+impl PacketMirror {
+    pub(crate) fn new(config: PacketMirrorConfig) -> Result<Self, io::Error> {
+        let bind_addr: SocketAddr = match config.observer_addr {
+            SocketAddr::V4(_) => "0.0.0.0:0".parse().unwrap(),
+            SocketAddr::V6(_) => "[::]:0".parse().unwrap(),
+        };
+        let socket = UdpSocket::bind(bind_addr)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            config,
+            window_bytes: 0,
+            window_elapsed: Duration::ZERO,
+            out: Vec::new(),
+        })
+    }
+
+    pub(crate) fn update(&mut self, duration: Duration) {
+        self.window_elapsed += duration;
+        if self.window_elapsed >= Duration::from_secs(1) {
+            self.window_elapsed = Duration::ZERO;
+            self.window_bytes = 0;
+        }
+    }
+
+    /// Mirrors a packet received on a server socket, if mirroring raw packets.
+    pub(crate) fn mirror_raw(&mut self, socket_id: usize, addr: SocketAddr, packet: &[u8]) {
+        if self.config.mode != PacketMirrorMode::Raw {
+            return;
+        }
+        let ip = match addr.ip() {
+            IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+            IpAddr::V6(ip) => ip,
+        };
+        self.out.clear();
+        self.out.push(socket_id as u8);
+        self.out.extend_from_slice(&ip.octets());
+        self.out.extend_from_slice(&addr.port().to_le_bytes());
+        self.out.extend_from_slice(packet);
+        self.send();
+    }
+
+    /// Mirrors a decrypted client payload, if mirroring decrypted packets.
+    pub(crate) fn mirror_decrypted(&mut self, client_id: ClientId, payload: &[u8]) {
+        if self.config.mode != PacketMirrorMode::Decrypted {
+            return;
+        }
+        self.out.clear();
+        self.out.extend_from_slice(&client_id.to_le_bytes());
+        self.out.extend_from_slice(payload);
+        self.send();
+    }
+
+    fn send(&mut self) {
+        if self.window_bytes + self.out.len() > self.config.max_bytes_per_sec {
+            log::trace!(
+                "Packet not mirrored, reached {} mirrored bytes per second",
+                self.config.max_bytes_per_sec
+            );
+            return;
+        }
+        self.window_bytes += self.out.len();
+        if let Err(err) = self.socket.send_to(&self.out, self.config.observer_addr) {
+            log::trace!("Failed to mirror packet to {}: {err}", self.config.observer_addr);
+        }
+    }
+}
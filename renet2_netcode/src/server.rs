@@ -1,11 +1,20 @@
+#[cfg(feature = "disconnect_notices")]
+use std::collections::HashSet;
 use std::{io, net::SocketAddr, time::Duration};
 
-use renetcode2::{NetcodeServer, ServerConfig, ServerResult, NETCODE_MAX_PACKET_BYTES, NETCODE_USER_DATA_BYTES};
-use renetcode2::{ServerAuthentication, ServerSocketConfig};
+use renetcode2::{CapacityEvent, CapacityThreshold, ClientInfo, PendingClientInfo, ServerAuthentication, ServerSocketConfig};
+use renetcode2::{
+    NetcodeServer, ServerConfig, ServerResult, NETCODE_KEY_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES,
+    NETCODE_USER_DATA_BYTES,
+};
 
 use renet2::{ClientId, Payload, RenetServer};
 
-use super::{NetcodeTransportError, ServerSocket};
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+use super::{packet_mirror::PacketMirror, PacketMirrorConfig};
+#[cfg(feature = "disconnect_notices")]
+use super::{DisconnectNoticeQueue, DISCONNECT_NOTICE_ACK_PACKET_TYPE, DISCONNECT_NOTICE_PACKET_TYPE, DISCONNECT_NOTICE_RESEND_INTERVAL};
+use super::{NetcodeTransportError, ServerSocket, TransportErrorPolicy};
 
 /// Config for setting up a [`NetcodeServerTransport`].
 ///
@@ -36,12 +45,26 @@ impl BoxedSocket {
     }
 }
 
+/// Implements `Resource` and `Component` when the `bevy` feature is enabled.
 #[derive(Debug)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource, bevy_ecs::component::Component))]
 pub struct NetcodeServerTransport {
     sockets: Vec<Box<dyn ServerSocket>>,
     netcode_server: NetcodeServer,
     buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+    error_policy: TransportErrorPolicy,
+    #[cfg(feature = "disconnect_notices")]
+    broadcast_disconnects: bool,
+    /// Clients connected at the end of the last update, used to detect disconnections when broadcasting them.
+    #[cfg(feature = "disconnect_notices")]
+    broadcast_clients: HashSet<ClientId>,
+    #[cfg(feature = "disconnect_notices")]
+    disconnect_notices: DisconnectNoticeQueue,
+    /// Time since unacknowledged disconnect notices were last resent.
+    #[cfg(feature = "disconnect_notices")]
+    disconnect_notice_timer: Duration,
+    #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+    packet_mirror: Option<PacketMirror>,
 }
 
 impl NetcodeServerTransport {
@@ -75,6 +98,9 @@ impl NetcodeServerTransport {
             socket_configs.push(ServerSocketConfig {
                 needs_encryption: !socket.is_encrypted(),
                 public_addresses: addrs,
+                max_payload_bytes: NETCODE_MAX_PAYLOAD_BYTES,
+                max_concurrent_handshakes: None,
+                shares_connect_tokens: false,
             });
         }
 
@@ -82,14 +108,38 @@ impl NetcodeServerTransport {
             current_time: server_config.current_time,
             max_clients: server_config.max_clients,
             protocol_id: server_config.protocol_id,
+            accepted_protocol_ids: Vec::new(),
+            capacity_thresholds: Vec::new(),
+            randomize_initial_sequence: false,
+            connection_rate_limit: None,
             sockets: socket_configs,
             authentication: server_config.authentication,
         };
 
+        #[allow(unused_mut)]
+        let mut netcode_server = NetcodeServer::new(server_config);
+        #[cfg(feature = "disconnect_notices")]
+        let disconnect_notices = DisconnectNoticeQueue::default();
+        #[cfg(feature = "disconnect_notices")]
+        netcode_server
+            .register_extension(DISCONNECT_NOTICE_ACK_PACKET_TYPE, disconnect_notices.clone())
+            .expect("disconnect notice ack packet type should be an extension packet type");
+
         Ok(Self {
             sockets,
-            netcode_server: NetcodeServer::new(server_config),
+            netcode_server,
             buffer: [0; NETCODE_MAX_PACKET_BYTES],
+            error_policy: TransportErrorPolicy::default(),
+            #[cfg(feature = "disconnect_notices")]
+            broadcast_disconnects: false,
+            #[cfg(feature = "disconnect_notices")]
+            broadcast_clients: HashSet::new(),
+            #[cfg(feature = "disconnect_notices")]
+            disconnect_notices,
+            #[cfg(feature = "disconnect_notices")]
+            disconnect_notice_timer: Duration::ZERO,
+            #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+            packet_mirror: None,
         })
     }
 
@@ -106,6 +156,48 @@ impl NetcodeServerTransport {
         Some(self.netcode_server.addresses(socket_id))
     }
 
+    /// Adds a socket to the running transport and returns its socket id, e.g. to start accepting WebSocket clients
+    /// once a TLS certificate is provisioned.
+    ///
+    /// `addrs` are the public addresses of the socket, as in [`ServerSetupConfig::socket_addresses`].
+    /// Socket ids are stable and never reused, see [`NetcodeServer::add_socket`].
+    pub fn add_socket(&mut self, socket: impl ServerSocket, addrs: Vec<SocketAddr>) -> usize {
+        let socket_id = self.netcode_server.add_socket(ServerSocketConfig {
+            needs_encryption: !socket.is_encrypted(),
+            public_addresses: addrs,
+            max_payload_bytes: NETCODE_MAX_PAYLOAD_BYTES,
+            max_concurrent_handshakes: None,
+            shares_connect_tokens: false,
+        });
+        self.sockets.push(Box::new(socket));
+        socket_id
+    }
+
+    /// Sets the maximum payload size of the packets sent to clients on a specific socket.
+    ///
+    /// [`NETCODE_MAX_PAYLOAD_BYTES`] by default. See [`ServerSocketConfig::max_payload_bytes`].
+    ///
+    /// Errors if packets with payloads of this size would be larger than the socket's
+    /// [`ServerSocket::max_packet_bytes`]. Clients on the socket must use sockets that can receive packets of that size
+    /// (see [`ClientSocket::max_packet_bytes`](crate::ClientSocket::max_packet_bytes)).
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_max_payload_bytes(&mut self, socket_id: usize, max_payload_bytes: usize) -> Result<(), io::Error> {
+        let packet_bytes = max_packet_bytes(max_payload_bytes);
+        let socket_max_packet_bytes = self.sockets[socket_id].max_packet_bytes();
+        if packet_bytes > socket_max_packet_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "payloads of {max_payload_bytes} bytes need packets of {packet_bytes} bytes, but socket {socket_id} \
+                    only supports packets of {socket_max_packet_bytes} bytes"
+                ),
+            ));
+        }
+        self.netcode_server.set_max_payload_bytes(socket_id, max_payload_bytes);
+        Ok(())
+    }
+
     /// Returns the maximum number of clients that can be connected.
     pub fn max_clients(&self) -> usize {
         self.netcode_server.max_clients()
@@ -125,6 +217,237 @@ impl NetcodeServerTransport {
         self.netcode_server.connected_clients()
     }
 
+    /// Returns the ids of connected clients that have not been confirmed yet.
+    ///
+    /// See [`NetcodeServer::unconfirmed_clients`].
+    pub fn unconfirmed_clients(&self) -> Vec<ClientId> {
+        self.netcode_server.unconfirmed_clients()
+    }
+
+    /// Sets additional protocol ids that connecting clients may use besides [`ServerSetupConfig::protocol_id`].
+    ///
+    /// See [`NetcodeServer::set_accepted_protocol_ids`].
+    pub fn set_accepted_protocol_ids(&mut self, accepted_protocol_ids: Vec<u64>) {
+        self.netcode_server.set_accepted_protocol_ids(accepted_protocol_ids);
+    }
+
+    /// Returns the protocol id a connected client used to connect.
+    pub fn client_protocol_id(&self, client_id: ClientId) -> Option<u64> {
+        self.netcode_server.client_protocol_id(client_id)
+    }
+
+    /// Returns `true` if the server is not accepting new connections.
+    pub fn is_draining(&self) -> bool {
+        self.netcode_server.is_draining()
+    }
+
+    /// Sets how long after connecting a client's repeated connection responses are answered right away.
+    ///
+    /// See [`NetcodeServer::set_response_grace`].
+    pub fn set_response_grace(&mut self, response_grace: Duration) {
+        self.netcode_server.set_response_grace(response_grace);
+    }
+
+    /// Sets whether the keep-alives that complete a client's handshake echo back the user data the server received.
+    ///
+    /// See [`NetcodeServer::set_echo_user_data`].
+    pub fn set_echo_user_data(&mut self, echo_user_data: bool) {
+        self.netcode_server.set_echo_user_data(echo_user_data);
+    }
+
+    /// Returns `true` if disconnections are broadcast to the remaining clients.
+    #[cfg(feature = "disconnect_notices")]
+    pub fn broadcast_disconnects(&self) -> bool {
+        self.broadcast_disconnects
+    }
+
+    /// Sets whether disconnections are broadcast to the remaining clients. Defaults to `false`.
+    ///
+    /// When enabled, every client that disconnects is announced to the clients still connected at the end of the
+    /// next [`Self::update`], with a netcode extension packet of type [`DISCONNECT_NOTICE_PACKET_TYPE`]. Notices
+    /// are resent until the client acknowledges them, or until it disconnects. Clients read the notices with
+    /// [`NetcodeClientTransport::get_peer_disconnected`](crate::NetcodeClientTransport::get_peer_disconnected).
+    ///
+    /// A client that doesn't acknowledge its notices for about 5 seconds, or that has 256 unacknowledged notices, is
+    /// assumed to not support them (e.g. it was built without the `disconnect_notices` feature) and gets no more
+    /// notices until it reconnects.
+    ///
+    /// Disabling the broadcast discards the notices that weren't acknowledged yet.
+    #[cfg(feature = "disconnect_notices")]
+    pub fn set_broadcast_disconnects(&mut self, broadcast_disconnects: bool) {
+        self.broadcast_disconnects = broadcast_disconnects;
+        self.broadcast_clients = match broadcast_disconnects {
+            true => self.netcode_server.clients_id().into_iter().collect(),
+            false => HashSet::new(),
+        };
+        if !broadcast_disconnects {
+            self.disconnect_notices.lock().clear();
+        }
+    }
+
+    /// Sets whether the server should deny new connections while keeping existing clients connected.
+    ///
+    /// See [`NetcodeServer::set_draining`].
+    pub fn set_draining(&mut self, draining: bool) {
+        self.netcode_server.set_draining(draining);
+    }
+
+    /// Sets a filter that decides whether to accept connection requests, based on the client id, address and user data.
+    ///
+    /// See [`NetcodeServer::set_connection_filter`].
+    pub fn set_connection_filter(
+        &mut self,
+        filter: impl FnMut(ClientId, SocketAddr, &[u8; NETCODE_USER_DATA_BYTES]) -> bool + Send + Sync + 'static,
+    ) {
+        self.netcode_server.set_connection_filter(filter);
+    }
+
+    /// Removes the filter set with [`Self::set_connection_filter`].
+    pub fn remove_connection_filter(&mut self) {
+        self.netcode_server.remove_connection_filter();
+    }
+
+    /// Bans an address, so all packets received from it are dropped.
+    ///
+    /// See [`NetcodeServer::ban_addr`].
+    pub fn ban_addr(&mut self, addr: SocketAddr) {
+        self.netcode_server.ban_addr(addr);
+    }
+
+    /// Removes an address ban, returns `true` if the address was banned.
+    pub fn unban_addr(&mut self, addr: SocketAddr) -> bool {
+        self.netcode_server.unban_addr(addr)
+    }
+
+    /// Bans a client id, so its connection requests are denied.
+    ///
+    /// If the client is connected then it is disconnected immediately. See [`NetcodeServer::ban_client`].
+    pub fn ban_client(&mut self, client_id: ClientId, server: &mut RenetServer) {
+        let server_result = self.netcode_server.ban_client(client_id);
+        handle_server_result(server_result, &mut self.sockets, server);
+    }
+
+    /// Removes a client id ban, returns `true` if the client was banned.
+    pub fn unban_client(&mut self, client_id: ClientId) -> bool {
+        self.netcode_server.unban_client(client_id)
+    }
+
+    /// Adds a private key that connect tokens can be decrypted with, e.g. while rotating the server's private key.
+    ///
+    /// See [`NetcodeServer::add_decrypt_key`].
+    pub fn add_decrypt_key(&mut self, private_key: [u8; NETCODE_KEY_BYTES]) {
+        self.netcode_server.add_decrypt_key(private_key);
+    }
+
+    /// Removes a private key that connect tokens can be decrypted with, returns `true` if the key was found.
+    pub fn retire_decrypt_key(&mut self, private_key: &[u8; NETCODE_KEY_BYTES]) -> bool {
+        self.netcode_server.retire_decrypt_key(private_key)
+    }
+
+    /// Reserves a client slot for `client_id` until `until`.
+    ///
+    /// The deadline is measured in the same time frame as [`ServerSetupConfig::current_time`], which is advanced by
+    /// [`Self::update`]. See [`NetcodeServer::reserve_slot`].
+    pub fn reserve_slot(&mut self, client_id: ClientId, until: Duration) {
+        self.netcode_server.reserve_slot(client_id, until);
+    }
+
+    /// Removes a client slot reservation.
+    ///
+    /// Returns `true` if the client had a reservation.
+    pub fn cancel_slot_reservation(&mut self, client_id: ClientId) -> bool {
+        self.netcode_server.cancel_slot_reservation(client_id)
+    }
+
+    /// Returns the number of client slots currently reserved.
+    pub fn reserved_slots(&self) -> usize {
+        self.netcode_server.reserved_slots()
+    }
+
+    /// Limits the number of clients that can be pending (i.e. mid-handshake) on a specific socket at a time.
+    ///
+    /// `None` by default. See [`ServerSocketConfig::max_concurrent_handshakes`].
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_max_concurrent_handshakes(&mut self, socket_id: usize, max_concurrent_handshakes: Option<usize>) {
+        self.netcode_server
+            .set_max_concurrent_handshakes(socket_id, max_concurrent_handshakes);
+    }
+
+    /// Lets secure connect tokens generated for other sockets that share connect tokens be used on a specific socket,
+    /// e.g. for the IPv4 and IPv6 sockets of [`bind_dual_stack`](crate::bind_dual_stack).
+    ///
+    /// `false` by default. See [`ServerSocketConfig::shares_connect_tokens`].
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_shares_connect_tokens(&mut self, socket_id: usize, shares_connect_tokens: bool) {
+        self.netcode_server.set_shares_connect_tokens(socket_id, shares_connect_tokens);
+    }
+
+    /// Returns the number of clients that are pending (i.e. mid-handshake) on a specific socket.
+    pub fn pending_count_on_socket(&self, socket_id: usize) -> usize {
+        self.netcode_server.pending_count_on_socket(socket_id)
+    }
+
+    /// Returns the number of clients that are pending (i.e. mid-handshake) on all sockets.
+    ///
+    /// See [`NetcodeServer::pending_clients_count`].
+    pub fn pending_clients_count(&self) -> usize {
+        self.netcode_server.pending_clients_count()
+    }
+
+    /// Returns the socket ids and addresses of the pending clients (iterator).
+    pub fn pending_clients_iter(&self) -> impl Iterator<Item = (usize, SocketAddr)> + '_ {
+        self.netcode_server.pending_clients_iter()
+    }
+
+    /// Returns diagnostic info about each pending client, e.g. to investigate clients stuck connecting.
+    pub fn pending_clients_info(&self) -> Vec<PendingClientInfo> {
+        self.netcode_server.pending_clients_info()
+    }
+
+    /// Starts mirroring received packets to an observer, e.g. a monitoring process. Replaces the previous mirror.
+    ///
+    /// Packets are copied in [`Self::update`] and sent from a new UDP socket. See [`PacketMirrorConfig`] for the
+    /// privacy implications.
+    #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+    pub fn set_packet_mirror(&mut self, config: PacketMirrorConfig) -> Result<(), std::io::Error> {
+        self.packet_mirror = Some(PacketMirror::new(config)?);
+        Ok(())
+    }
+
+    /// Stops mirroring received packets, see [`Self::set_packet_mirror`].
+    #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+    pub fn remove_packet_mirror(&mut self) {
+        self.packet_mirror = None;
+    }
+
+    /// Sets watermarks on the fraction of client slots in use that emit capacity events when crossed.
+    ///
+    /// See [`NetcodeServer::set_capacity_thresholds`].
+    pub fn set_capacity_thresholds(&mut self, capacity_thresholds: Vec<CapacityThreshold>) {
+        self.netcode_server.set_capacity_thresholds(capacity_thresholds);
+    }
+
+    /// Returns the next capacity event if available.
+    ///
+    /// Capacity events are produced in [`Self::update`], so this should be called after updating the transport.
+    pub fn get_capacity_event(&mut self) -> Option<CapacityEvent> {
+        self.netcode_server.get_capacity_event()
+    }
+
+    /// Returns the policy for handling recoverable errors when sending packets to clients.
+    pub fn error_policy(&self) -> TransportErrorPolicy {
+        self.error_policy
+    }
+
+    /// Sets the policy for handling recoverable errors when sending packets to clients.
+    ///
+    /// Defaults to [`TransportErrorPolicy::Ignore`].
+    pub fn set_error_policy(&mut self, error_policy: TransportErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+
     /// Returns the user data for client if connected.
     pub fn user_data(&self, client_id: ClientId) -> Option<[u8; NETCODE_USER_DATA_BYTES]> {
         self.netcode_server.user_data(client_id)
@@ -135,6 +458,22 @@ impl NetcodeServerTransport {
         self.netcode_server.client_addr(client_id)
     }
 
+    /// Returns diagnostic info about a connected client, e.g. for server dashboards.
+    ///
+    /// See [`NetcodeServer::client_info`].
+    pub fn client_info(&self, client_id: ClientId) -> Option<ClientInfo> {
+        self.netcode_server.client_info(client_id)
+    }
+
+    /// Disconnects a client with an application-defined reason code that the client can read from its disconnect
+    /// reason.
+    ///
+    /// This sends the disconnect packet instantly. See [`NetcodeServer::disconnect_with_reason`].
+    pub fn disconnect_with_reason(&mut self, client_id: ClientId, reason: u8, server: &mut RenetServer) {
+        let server_result = self.netcode_server.disconnect_with_reason(client_id, reason);
+        handle_server_result(server_result, &mut self.sockets, server);
+    }
+
     /// Disconnects all connected clients.
     ///
     /// This sends the disconnect packet instantly, use this when closing/exiting games,
@@ -153,18 +492,50 @@ impl NetcodeServerTransport {
         self.netcode_server.time_since_last_received_packet(client_id)
     }
 
+    /// Returns the duration until the connected client is disconnected for inactivity.
+    ///
+    /// Returns `None` if the client is not connected or its connect token disables timeouts.
+    pub fn time_until_timeout(&self, client_id: ClientId) -> Option<Duration> {
+        self.netcode_server.time_until_timeout(client_id)
+    }
+
+    /// Immediately sends a keep alive packet to the client, regardless of the netcode send rate.
+    ///
+    /// Does nothing if the client is not connected.
+    pub fn send_keepalive_now(&mut self, client_id: ClientId) {
+        if let ServerResult::PacketToSend { socket_id, addr, payload } = self.netcode_server.send_keepalive_now(client_id) {
+            if let Err(err) = self.sockets[socket_id].send(addr, payload) {
+                log::trace!("Failed to send keep alive packet to {socket_id}/{addr}: {err}");
+            }
+        }
+    }
+
     /// Advances the transport by the duration, and receive packets from the network.
     pub fn update(&mut self, duration: Duration, server: &mut RenetServer) -> Result<(), Vec<NetcodeTransportError>> {
         self.netcode_server.update(duration);
+        #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+        if let Some(mirror) = self.packet_mirror.as_mut() {
+            mirror.update(duration);
+        }
 
         let mut transport_errors = Vec::default();
         for socket_id in 0..self.sockets.len() {
+            self.sockets[socket_id].advance_time(duration);
             self.sockets[socket_id].preupdate();
 
             loop {
                 match self.sockets[socket_id].try_recv(&mut self.buffer) {
                     Ok((len, addr)) => {
+                        #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+                        if let Some(mirror) = self.packet_mirror.as_mut() {
+                            mirror.mirror_raw(socket_id, addr, &self.buffer[..len]);
+                        }
                         let server_result = self.netcode_server.process_packet(socket_id, addr, &mut self.buffer[..len]);
+                        #[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+                        if let (Some(mirror), ServerResult::Payload { client_id, payload }) = (self.packet_mirror.as_mut(), &server_result)
+                        {
+                            mirror.mirror_decrypted(*client_id, payload);
+                        }
                         handle_server_result(server_result, &mut self.sockets, server);
                     }
                     Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
@@ -191,6 +562,28 @@ impl NetcodeServerTransport {
             socket.postupdate();
         }
 
+        // Disconnect clients whose connections were closed by their sockets, without waiting for a netcode timeout.
+        for socket_id in 0..self.sockets.len() {
+            while let Some(addr) = self.sockets[socket_id].take_closed_connection() {
+                let Some(client_id) = self.netcode_server.client_id_from_addr(socket_id, addr) else {
+                    continue;
+                };
+                log::trace!("Connection closed for client {client_id} ({socket_id}/{addr}), disconnecting");
+                let server_result = self.netcode_server.disconnect(client_id);
+                handle_server_result(server_result, &mut self.sockets, server);
+            }
+        }
+
+        #[cfg(feature = "disconnect_notices")]
+        if self.broadcast_disconnects {
+            self.disconnect_notice_timer += duration;
+            let resend = self.disconnect_notice_timer >= DISCONNECT_NOTICE_RESEND_INTERVAL;
+            if resend {
+                self.disconnect_notice_timer = Duration::ZERO;
+            }
+            self.send_disconnect_notices(resend);
+        }
+
         if !transport_errors.is_empty() {
             return Err(transport_errors);
         }
@@ -198,6 +591,38 @@ impl NetcodeServerTransport {
         Ok(())
     }
 
+    /// Notifies the connected clients of the clients that disconnected since the last update, and resends the
+    /// unacknowledged notices if `resend` is true.
+    #[cfg(feature = "disconnect_notices")]
+    fn send_disconnect_notices(&mut self, resend: bool) {
+        let connected: HashSet<ClientId> = self.netcode_server.clients_id().into_iter().collect();
+        let mut pending = self.disconnect_notices.lock();
+        pending.retain(|client_id, _| connected.contains(client_id));
+        for &disconnected_id in self.broadcast_clients.difference(&connected) {
+            for &client_id in connected.iter() {
+                pending.entry(client_id).or_default().push(disconnected_id);
+            }
+        }
+
+        for (&client_id, notices) in pending.iter_mut() {
+            for notice in notices.notices_to_send(resend) {
+                match self
+                    .netcode_server
+                    .generate_extension_packet(client_id, DISCONNECT_NOTICE_PACKET_TYPE, &notice)
+                {
+                    Ok((socket_id, addr, payload)) => {
+                        if let Err(err) = self.sockets[socket_id].send(addr, payload) {
+                            log::trace!("Failed to send disconnect notice to {socket_id}/{addr}: {err}");
+                        }
+                    }
+                    Err(e) => log::error!("Failed to encrypt disconnect notice for client {client_id}: {e}"),
+                }
+            }
+        }
+        drop(pending);
+        self.broadcast_clients = connected;
+    }
+
     /// Sends packets to connected clients.
     pub fn send_packets(&mut self, server: &mut RenetServer) {
         //TODO: it isn't necessary to allocate client ids here, just use one big vec of packets for all clients
@@ -205,7 +630,14 @@ impl NetcodeServerTransport {
         for client_id in server.clients_id() {
             let packets = server.get_packets_to_send(client_id).unwrap();
             for packet in packets {
-                if !send_packet_to_client(&mut self.sockets, &mut self.netcode_server, server, &packet, client_id) {
+                if !send_packet_to_client(
+                    &mut self.sockets,
+                    &mut self.netcode_server,
+                    server,
+                    self.error_policy,
+                    &packet,
+                    client_id,
+                ) {
                     break;
                 }
             }
@@ -215,16 +647,17 @@ impl NetcodeServerTransport {
 
 /// Sends a packet to a client.
 ///
-/// Disconnects the client if its address connection is broken.
+/// Disconnects the client if its address connection is broken, or if the error policy requires it.
 fn send_packet_to_client(
     sockets: &mut [Box<dyn ServerSocket>],
     netcode_server: &mut NetcodeServer,
     reliable_server: &mut RenetServer,
+    error_policy: TransportErrorPolicy,
     packet: &Payload,
     client_id: ClientId,
 ) -> bool {
     let (send_result, socket_id, addr) = match netcode_server.generate_payload_packet(client_id, packet) {
-        Ok((socket_id, addr, payload)) => (sockets[socket_id].send(addr, payload), socket_id, addr),
+        Ok((socket_id, addr, payload)) => (error_policy.send(|| sockets[socket_id].send(addr, payload)), socket_id, addr),
         Err(e) => {
             log::error!("Failed to encrypt payload packet for client {client_id}: {e}");
             return false;
@@ -233,7 +666,7 @@ fn send_packet_to_client(
 
     match send_result {
         Ok(()) => true,
-        Err(NetcodeTransportError::IO(ref e)) if e.kind() == io::ErrorKind::ConnectionAborted => {
+        Err((NetcodeTransportError::IO(ref e), _)) if e.kind() == io::ErrorKind::ConnectionAborted => {
             // Manually disconnect the client if the client's address is disconnected.
             reliable_server.remove_connection(client_id);
             // Ignore the server result since this client is not connected.
@@ -241,13 +674,25 @@ fn send_packet_to_client(
 
             false
         }
-        Err(e) => {
+        Err((e, true)) => {
+            log::debug!("Disconnecting client {client_id} after failing to send packet ({socket_id}/{addr}): {e}");
+            let server_result = netcode_server.disconnect(client_id);
+            handle_server_result(server_result, sockets, reliable_server);
+
+            false
+        }
+        Err((e, false)) => {
             log::error!("Failed to send packet to client {client_id} ({socket_id}/{addr}): {e}");
             false
         }
     }
 }
 
+/// Returns the size of the largest packet with a payload of `max_payload_bytes`, at least [`NETCODE_MAX_PACKET_BYTES`].
+fn max_packet_bytes(max_payload_bytes: usize) -> usize {
+    NETCODE_MAX_PACKET_BYTES + max_payload_bytes.saturating_sub(NETCODE_MAX_PAYLOAD_BYTES)
+}
+
 fn handle_server_result(server_result: ServerResult, sockets: &mut [Box<dyn ServerSocket>], reliable_server: &mut RenetServer) {
     let send_packet = |sockets: &mut [Box<dyn ServerSocket>], packet: &[u8], socket_id: usize, addr: SocketAddr| {
         if let Err(err) = sockets[socket_id].send(addr, packet) {
@@ -260,7 +705,13 @@ fn handle_server_result(server_result: ServerResult, sockets: &mut [Box<dyn Serv
         ServerResult::Error { addr, socket_id } => {
             sockets[socket_id].disconnect(addr);
         }
-        ServerResult::ConnectionDenied { addr, socket_id, payload } => {
+        ServerResult::ConnectionDenied {
+            addr,
+            socket_id,
+            payload,
+            reason,
+        } => {
+            log::trace!("Connection denied for {socket_id}/{addr}: {reason:?}");
             if let Some(payload) = payload {
                 send_packet(sockets, payload, socket_id, addr);
             }
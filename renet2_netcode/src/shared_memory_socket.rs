@@ -0,0 +1,330 @@
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use memmap2::MmapMut;
+use renetcode2::NETCODE_MAX_PACKET_BYTES;
+
+use super::{ClientSocket, NetcodeTransportError, ServerSocket, SocketKind};
+
+/// Marks a shared memory file as fully initialized.
+const MAGIC: u64 = u64::from_le_bytes(*b"RENET2SM");
+
+/// Number of packets each direction of a shared memory socket can buffer.
+pub const SHARED_MEMORY_SLOTS: usize = 1024;
+
+// Layout of the shared memory file:
+// - Header: magic, slot count, server closed flag, client closed flag, skip encryption flag.
+// - Two rings (client -> server, then server -> client), each with a head and tail counter on separate cache
+//   lines followed by the slots. A slot is the packet length followed by the packet bytes.
+//
+// Each ring has exactly one writer and one reader. The writer owns `head`, the reader owns `tail`.
+const HEADER_BYTES: usize = 64;
+const MAGIC_OFFSET: usize = 0;
+const SLOTS_OFFSET: usize = 8;
+const SERVER_CLOSED_OFFSET: usize = 16;
+const CLIENT_CLOSED_OFFSET: usize = 24;
+const SKIP_ENCRYPTION_OFFSET: usize = 32;
+const RING_HEAD_OFFSET: usize = 0;
+const RING_TAIL_OFFSET: usize = 64;
+const RING_HEADER_BYTES: usize = 128;
+const SLOT_BYTES: usize = 8 + NETCODE_MAX_PACKET_BYTES.next_multiple_of(8);
+const RING_BYTES: usize = RING_HEADER_BYTES + SHARED_MEMORY_SLOTS * SLOT_BYTES;
+const FILE_BYTES: usize = HEADER_BYTES + 2 * RING_BYTES;
+
+const SHARED_MEMORY_SERVER_PORT: u16 = u16::MAX;
+const SHARED_MEMORY_CLIENT_PORT: u16 = 0;
+
+/// Produces the [`SocketAddr`] of the server end of a [`SharedMemorySocket`].
+///
+/// This should be used in the [`ConnectToken::server_addresses`](renetcode2::ConnectToken::server_addresses) field
+/// for client connection requests, and the
+/// [`ServerSocketConfig::public_addresses`](renetcode2::ServerSocketConfig::public_addresses) field for setting up servers.
+pub fn shared_memory_server_addr() -> SocketAddr {
+    SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), SHARED_MEMORY_SERVER_PORT)
+}
+
+/// Produces the [`SocketAddr`] of the client end of a [`SharedMemorySocket`].
+pub fn shared_memory_client_addr() -> SocketAddr {
+    SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), SHARED_MEMORY_CLIENT_PORT)
+}
+
+/// Implementation of [`ServerSocket`] and [`ClientSocket`] that connects one server and one client in separate
+/// processes on the same host.
+///
+/// Packets are passed through two ring buffers in a memory-mapped file, one per direction. To set up a connection:
+/// 1. The server process calls [`Self::create_server`] with a path, which creates (or truncates) the file. Use a
+///    path on a memory-backed filesystem (e.g. `/dev/shm` on Linux) to avoid disk writes.
+/// 2. The client process calls [`Self::open_client`] with the same path after the server created it.
+///
+/// A file supports one client at a time, use one file per client. Each ring buffers [`SHARED_MEMORY_SLOTS`] packets.
+/// If the reader falls that far behind, [`send`](ServerSocket::send) fails with
+/// [`WouldBlock`](std::io::ErrorKind::WouldBlock) and the packet is dropped, so the socket is not reliable.
+///
+/// Packets are encrypted by netcode unless the server passes `skip_encryption: true` to [`Self::create_server`].
+#[derive(Debug)]
+pub struct SharedMemorySocket {
+    map: MmapMut,
+    is_server: bool,
+    skip_encryption: bool,
+    closed: bool,
+}
+
+impl SharedMemorySocket {
+    /// Creates the shared memory file at `path` and returns the server end of the socket.
+    ///
+    /// Any existing file at `path` is truncated. On unix the file is only readable and writable by its owner.
+    ///
+    /// Set `skip_encryption` to `true` to skip netcode encryption, which the client end picks up from the file.
+    /// **Warning**: packets are then stored in plaintext in the file, so only do this if no other user or process on
+    /// the host can access the file.
+    pub fn create_server(path: impl AsRef<Path>, skip_encryption: bool) -> std::io::Result<Self> {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(path)?;
+        // The mode only applies to new files.
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.set_len(FILE_BYTES as u64)?;
+        let socket = Self {
+            map: Self::map(&file)?,
+            is_server: true,
+            skip_encryption,
+            closed: false,
+        };
+
+        // The file is zeroed when created, so only the header needs to be written. The magic is written last so
+        // clients can't open a partially initialized file.
+        socket.word(SLOTS_OFFSET).store(SHARED_MEMORY_SLOTS as u64, Ordering::Relaxed);
+        socket.word(SKIP_ENCRYPTION_OFFSET).store(skip_encryption as u64, Ordering::Relaxed);
+        socket.word(MAGIC_OFFSET).store(MAGIC, Ordering::Release);
+
+        Ok(socket)
+    }
+
+    /// Opens the shared memory file at `path` created by [`Self::create_server`] and returns the client end of the
+    /// socket.
+    ///
+    /// Returns an error with kind [`InvalidData`](std::io::ErrorKind::InvalidData) if the file is not a shared memory
+    /// socket, or the server hasn't finished creating it.
+    pub fn open_client(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        if file.metadata()?.len() != FILE_BYTES as u64 {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid shared memory socket file size",
+            ));
+        }
+        let mut socket = Self {
+            map: Self::map(&file)?,
+            is_server: false,
+            skip_encryption: false,
+            closed: false,
+        };
+
+        if socket.word(MAGIC_OFFSET).load(Ordering::Acquire) != MAGIC
+            || socket.word(SLOTS_OFFSET).load(Ordering::Relaxed) != SHARED_MEMORY_SLOTS as u64
+        {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "shared memory socket is not initialized",
+            ));
+        }
+        socket.skip_encryption = socket.word(SKIP_ENCRYPTION_OFFSET).load(Ordering::Relaxed) != 0;
+
+        Ok(socket)
+    }
+
+    fn map(file: &File) -> std::io::Result<MmapMut> {
+        // SAFETY: The file is only accessed through shared memory sockets, which only touch shared words atomically
+        // and only touch slots owned by them according to the ring counters.
+        unsafe { MmapMut::map_mut(file) }
+    }
+
+    /// Returns a shared word of the file.
+    fn word(&self, offset: usize) -> &AtomicU64 {
+        debug_assert!(offset.is_multiple_of(8) && offset + 8 <= self.map.len());
+        // SAFETY: The map is page-aligned and `offset` is a multiple of 8 within the map, and the returned reference
+        // can't outlive the map.
+        unsafe { &*(self.map.as_ptr().add(offset) as *const AtomicU64) }
+    }
+
+    fn own_closed_offset(&self) -> usize {
+        match self.is_server {
+            true => SERVER_CLOSED_OFFSET,
+            false => CLIENT_CLOSED_OFFSET,
+        }
+    }
+
+    fn peer_closed_offset(&self) -> usize {
+        match self.is_server {
+            true => CLIENT_CLOSED_OFFSET,
+            false => SERVER_CLOSED_OFFSET,
+        }
+    }
+
+    fn send_ring(&self) -> usize {
+        match self.is_server {
+            true => HEADER_BYTES + RING_BYTES,
+            false => HEADER_BYTES,
+        }
+    }
+
+    fn recv_ring(&self) -> usize {
+        match self.is_server {
+            true => HEADER_BYTES,
+            false => HEADER_BYTES + RING_BYTES,
+        }
+    }
+
+    fn slot_offset(ring: usize, index: u64) -> usize {
+        ring + RING_HEADER_BYTES + (index % SHARED_MEMORY_SLOTS as u64) as usize * SLOT_BYTES
+    }
+
+    fn is_connection_closed(&self) -> bool {
+        self.closed || self.word(self.peer_closed_offset()).load(Ordering::Acquire) != 0
+    }
+
+    fn close_end(&mut self) {
+        self.closed = true;
+        self.word(self.own_closed_offset()).store(1, Ordering::Release);
+    }
+
+    fn recv_packet(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        assert!(buffer.len() >= NETCODE_MAX_PACKET_BYTES);
+
+        let ring = self.recv_ring();
+        let tail = self.word(ring + RING_TAIL_OFFSET).load(Ordering::Relaxed);
+        let head = self.word(ring + RING_HEAD_OFFSET).load(Ordering::Acquire);
+        if tail == head {
+            return Err(std::io::Error::from(ErrorKind::WouldBlock));
+        }
+
+        let slot = Self::slot_offset(ring, tail);
+        let len = self.word(slot).load(Ordering::Relaxed) as usize;
+        if len > NETCODE_MAX_PACKET_BYTES {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "invalid shared memory packet length"));
+        }
+        // SAFETY: The slot is within the map, and the writer won't touch it until `tail` is advanced.
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.map.as_ptr().add(slot + 8), buffer.as_mut_ptr(), len);
+        }
+        self.word(ring + RING_TAIL_OFFSET).store(tail + 1, Ordering::Release);
+
+        Ok(len)
+    }
+
+    fn send_packet(&mut self, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        assert!(packet.len() <= NETCODE_MAX_PACKET_BYTES);
+        if self.is_connection_closed() {
+            return Err(std::io::Error::from(ErrorKind::ConnectionAborted).into());
+        }
+
+        let ring = self.send_ring();
+        let head = self.word(ring + RING_HEAD_OFFSET).load(Ordering::Relaxed);
+        let tail = self.word(ring + RING_TAIL_OFFSET).load(Ordering::Acquire);
+        if head - tail >= SHARED_MEMORY_SLOTS as u64 {
+            return Err(std::io::Error::from(ErrorKind::WouldBlock).into());
+        }
+
+        let slot = Self::slot_offset(ring, head);
+        self.word(slot).store(packet.len() as u64, Ordering::Relaxed);
+        // SAFETY: The slot is within the map, and the reader won't touch it until `head` is advanced.
+        unsafe {
+            std::ptr::copy_nonoverlapping(packet.as_ptr(), self.map.as_mut_ptr().add(slot + 8), packet.len());
+        }
+        self.word(ring + RING_HEAD_OFFSET).store(head + 1, Ordering::Release);
+
+        Ok(())
+    }
+}
+
+impl Drop for SharedMemorySocket {
+    fn drop(&mut self) {
+        self.close_end();
+    }
+}
+
+impl ServerSocket for SharedMemorySocket {
+    fn is_encrypted(&self) -> bool {
+        self.skip_encryption
+    }
+    fn is_reliable(&self) -> bool {
+        false
+    }
+
+    fn addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(shared_memory_server_addr())
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.closed
+    }
+
+    fn close(&mut self) {
+        self.close_end();
+    }
+
+    fn connection_denied(&mut self, _: SocketAddr) {}
+    fn connection_accepted(&mut self, _: u64, _: SocketAddr) {}
+    fn disconnect(&mut self, _: SocketAddr) {}
+    fn preupdate(&mut self) {}
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        let len = self.recv_packet(buffer)?;
+        Ok((len, shared_memory_client_addr()))
+    }
+
+    fn postupdate(&mut self) {}
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        if addr != shared_memory_client_addr() {
+            return Err(std::io::Error::from(ErrorKind::AddrNotAvailable).into());
+        }
+        self.send_packet(packet)
+    }
+}
+
+impl ClientSocket for SharedMemorySocket {
+    fn is_encrypted(&self) -> bool {
+        self.skip_encryption
+    }
+    fn is_reliable(&self) -> bool {
+        false
+    }
+    fn kind(&self) -> SocketKind {
+        SocketKind::SharedMemory
+    }
+
+    fn addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(shared_memory_client_addr())
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.is_connection_closed()
+    }
+
+    fn close(&mut self) {
+        self.close_end();
+    }
+
+    fn preupdate(&mut self) {}
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        let len = self.recv_packet(buffer)?;
+        Ok((len, shared_memory_server_addr()))
+    }
+
+    fn postupdate(&mut self) {}
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        if addr != shared_memory_server_addr() {
+            return Err(std::io::Error::from(ErrorKind::AddrNotAvailable).into());
+        }
+        self.send_packet(packet)
+    }
+}
@@ -0,0 +1,352 @@
+use std::{cmp::Ordering, collections::BinaryHeap, io, net::SocketAddr, time::Duration};
+
+use crate::{ClientSocket, NetcodeTransportError, ServerSocket, SocketKind};
+
+/// Network conditions simulated by a [`SimulatedSocket`].
+///
+/// Conditions are applied to both sent and received packets, so wrapping the socket on one end of a connection
+/// increases its round-trip time by twice `extra_latency`.
+#[derive(Debug, Clone)]
+pub struct SimulatedConditions {
+    /// Latency added to each packet.
+    pub extra_latency: Duration,
+    /// Maximum random latency added to each packet on top of `extra_latency`.
+    ///
+    /// Packets can be reordered when this is larger than the time between packets.
+    pub jitter: Duration,
+    /// Probability that a packet is dropped, between `0.0` and `1.0`.
+    pub loss_ratio: f32,
+    /// Seed of the random number generator used for jitter and packet loss, for reproducible simulations.
+    pub seed: u64,
+}
+
+impl Default for SimulatedConditions {
+    fn default() -> Self {
+        Self {
+            extra_latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            loss_ratio: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Wraps a [`ServerSocket`] or [`ClientSocket`] to simulate bad network conditions, for testing.
+///
+/// Sent and received packets are delayed and dropped according to [`SimulatedConditions`]. Delayed packets are
+/// buffered and released when their simulated arrival time passes, which is checked whenever the transport
+/// updates the socket, receives packets, or sends packets.
+///
+/// The socket's clock only advances by the durations passed to the transport's `update`, so simulations are
+/// deterministic and don't depend on the wall clock.
+///
+/// Delayed sends are reported as successful, errors from the wrapped socket when the packets are released are
+/// logged and discarded.
+///
+/// The socket is never reliable, even if the wrapped socket is, because simulated loss and jitter can drop and
+/// reorder packets (and the conditions can change at any time). Renet2 handles reliability on top of it instead.
+#[derive(Debug)]
+pub struct SimulatedSocket<S> {
+    inner: S,
+    conditions: SimulatedConditions,
+    rng: Rng,
+    /// Packets that were received, waiting for their arrival time.
+    received: BinaryHeap<DelayedPacket>,
+    /// Packets that were sent, waiting for their arrival time.
+    sent: BinaryHeap<DelayedPacket>,
+    /// Counter to keep packets with the same arrival time in order.
+    packet_counter: u64,
+    /// Simulated time, advanced by [`ServerSocket::advance_time`] or [`ClientSocket::advance_time`].
+    now: Duration,
+}
+
+impl<S> SimulatedSocket<S> {
+    /// Wraps a socket.
+    pub fn new(inner: S, conditions: SimulatedConditions) -> Self {
+        let rng = Rng(conditions.seed);
+        Self {
+            inner,
+            conditions,
+            rng,
+            received: BinaryHeap::new(),
+            sent: BinaryHeap::new(),
+            packet_counter: 0,
+            now: Duration::ZERO,
+        }
+    }
+
+    /// Gets the simulated network conditions.
+    pub fn conditions(&self) -> &SimulatedConditions {
+        &self.conditions
+    }
+
+    /// Sets the simulated network conditions. Packets that were already delayed keep their arrival time.
+    ///
+    /// The random number generator is not reseeded.
+    pub fn set_conditions(&mut self, conditions: SimulatedConditions) {
+        self.conditions = conditions;
+    }
+
+    /// Gets the wrapped socket.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Gets the wrapped socket mutably.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Returns the simulated arrival time of a new packet, or `None` if the packet is lost.
+    fn arrival_time(&mut self) -> Option<Duration> {
+        if self.rng.chance(self.conditions.loss_ratio as f64) {
+            return None;
+        }
+        let jitter = self.conditions.jitter.mul_f64(self.rng.unit());
+
+        Some(self.now + self.conditions.extra_latency + jitter)
+    }
+
+    fn delay_packet(&mut self, arrival: Duration, addr: SocketAddr, packet: &[u8], sent: bool) {
+        self.packet_counter += 1;
+        let packet = DelayedPacket {
+            arrival,
+            counter: self.packet_counter,
+            addr,
+            packet: packet.to_vec(),
+        };
+        match sent {
+            true => self.sent.push(packet),
+            false => self.received.push(packet),
+        }
+    }
+
+    fn try_recv_with(
+        &mut self,
+        buffer: &mut [u8],
+        mut recv: impl FnMut(&mut S, &mut [u8]) -> io::Result<(usize, SocketAddr)>,
+    ) -> io::Result<(usize, SocketAddr)> {
+        // Drain the wrapped socket so all received packets are delayed from the time they were received.
+        loop {
+            match recv(&mut self.inner, buffer) {
+                Ok((len, addr)) => {
+                    let Some(arrival) = self.arrival_time() else {
+                        continue;
+                    };
+                    self.delay_packet(arrival, addr, &buffer[..len], false);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        match self.received.peek() {
+            Some(packet) if packet.arrival <= self.now => (),
+            _ => return Err(io::ErrorKind::WouldBlock.into()),
+        }
+        let packet = self.received.pop().unwrap();
+        if packet.packet.len() > buffer.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "simulated packet is larger than the receive buffer",
+            ));
+        }
+        buffer[..packet.packet.len()].copy_from_slice(&packet.packet);
+
+        Ok((packet.packet.len(), packet.addr))
+    }
+
+    fn send_with(
+        &mut self,
+        addr: SocketAddr,
+        packet: &[u8],
+        mut send: impl FnMut(&mut S, SocketAddr, &[u8]) -> Result<(), NetcodeTransportError>,
+    ) -> Result<(), NetcodeTransportError> {
+        self.flush_with(&mut send);
+        let Some(arrival) = self.arrival_time() else {
+            return Ok(());
+        };
+        if arrival <= self.now && self.sent.is_empty() {
+            return send(&mut self.inner, addr, packet);
+        }
+        self.delay_packet(arrival, addr, packet, true);
+
+        Ok(())
+    }
+
+    /// Sends delayed packets whose arrival time passed.
+    fn flush_with(&mut self, mut send: impl FnMut(&mut S, SocketAddr, &[u8]) -> Result<(), NetcodeTransportError>) {
+        while self.sent.peek().is_some_and(|p| p.arrival <= self.now) {
+            let packet = self.sent.pop().unwrap();
+            if let Err(err) = send(&mut self.inner, packet.addr, &packet.packet) {
+                log::trace!("Failed to send simulated packet to {}: {err}", packet.addr);
+            }
+        }
+    }
+}
+
+impl<S: ServerSocket> ServerSocket for SimulatedSocket<S> {
+    fn is_encrypted(&self) -> bool {
+        ServerSocket::is_encrypted(&self.inner)
+    }
+    fn is_reliable(&self) -> bool {
+        false
+    }
+    fn max_packet_bytes(&self) -> usize {
+        ServerSocket::max_packet_bytes(&self.inner)
+    }
+
+    fn addr(&self) -> io::Result<SocketAddr> {
+        ServerSocket::addr(&self.inner)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        ServerSocket::is_closed(&mut self.inner)
+    }
+
+    fn close(&mut self) {
+        ServerSocket::close(&mut self.inner)
+    }
+
+    fn connection_denied(&mut self, addr: SocketAddr) {
+        self.inner.connection_denied(addr);
+    }
+
+    fn connection_accepted(&mut self, client_id: u64, addr: SocketAddr) {
+        self.inner.connection_accepted(client_id, addr);
+    }
+
+    fn disconnect(&mut self, addr: SocketAddr) {
+        self.inner.disconnect(addr);
+    }
+
+    fn advance_time(&mut self, duration: Duration) {
+        self.now += duration;
+        ServerSocket::advance_time(&mut self.inner, duration);
+    }
+
+    fn preupdate(&mut self) {
+        self.flush_with(ServerSocket::send);
+        ServerSocket::preupdate(&mut self.inner);
+    }
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.try_recv_with(buffer, ServerSocket::try_recv)
+    }
+
+    fn postupdate(&mut self) {
+        self.flush_with(ServerSocket::send);
+        ServerSocket::postupdate(&mut self.inner);
+    }
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        self.send_with(addr, packet, ServerSocket::send)
+    }
+
+    fn take_closed_connection(&mut self) -> Option<SocketAddr> {
+        self.inner.take_closed_connection()
+    }
+}
+
+impl<S: ClientSocket> ClientSocket for SimulatedSocket<S> {
+    fn is_encrypted(&self) -> bool {
+        ClientSocket::is_encrypted(&self.inner)
+    }
+    fn is_reliable(&self) -> bool {
+        false
+    }
+    fn kind(&self) -> SocketKind {
+        self.inner.kind()
+    }
+    fn max_packet_bytes(&self) -> usize {
+        ClientSocket::max_packet_bytes(&self.inner)
+    }
+
+    fn addr(&self) -> io::Result<SocketAddr> {
+        ClientSocket::addr(&self.inner)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        ClientSocket::is_closed(&mut self.inner)
+    }
+
+    fn close(&mut self) {
+        ClientSocket::close(&mut self.inner)
+    }
+
+    fn advance_time(&mut self, duration: Duration) {
+        self.now += duration;
+        ClientSocket::advance_time(&mut self.inner, duration);
+    }
+
+    fn preupdate(&mut self) {
+        self.flush_with(ClientSocket::send);
+        ClientSocket::preupdate(&mut self.inner);
+    }
+
+    fn try_recv(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.try_recv_with(buffer, ClientSocket::try_recv)
+    }
+
+    fn postupdate(&mut self) {
+        self.flush_with(ClientSocket::send);
+        ClientSocket::postupdate(&mut self.inner);
+    }
+
+    fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
+        self.send_with(addr, packet, ClientSocket::send)
+    }
+}
+
+/// A packet waiting for its simulated arrival time.
+#[derive(Debug)]
+struct DelayedPacket {
+    arrival: Duration,
+    counter: u64,
+    addr: SocketAddr,
+    packet: Vec<u8>,
+}
+
+// Reversed so the packet with the earliest arrival is at the top of the heap.
+impl Ord for DelayedPacket {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.arrival, other.counter).cmp(&(self.arrival, self.counter))
+    }
+}
+
+impl PartialOrd for DelayedPacket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for DelayedPacket {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DelayedPacket {}
+
+/// Small deterministic PRNG (splitmix64) so simulations don't need an external rng.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && self.unit() < probability
+    }
+}
@@ -1,5 +1,8 @@
 use std::fmt::Debug;
 use std::net::SocketAddr;
+use std::time::Duration;
+
+use renetcode2::NETCODE_MAX_PACKET_BYTES;
 
 use super::NetcodeTransportError;
 
@@ -13,6 +16,19 @@ use super::NetcodeTransportError;
 /// multiple server addresses but your URLs exceed 16 bytes (IPV6 addresses are 16 bytes), then you should pre-parse
 /// the server list from the connect token, and then map that list to the 16-byte IPV6 segments that will be produced
 /// by the client when it tries to reconnect to different servers.
+///
+/// # Implementing
+///
+/// Sockets are driven by the transport, which only uses the methods of this trait:
+/// - In [`NetcodeServerTransport::update`](super::NetcodeServerTransport::update), [`Self::advance_time`] and
+///   [`Self::preupdate`] are called and then [`Self::try_recv`] until it returns an error, then [`Self::postupdate`] and
+///   [`Self::take_closed_connection`] are called after all packets were processed.
+/// - [`Self::send`] may be called at any time, including while receiving packets (e.g. to answer connection
+///   requests) and in [`NetcodeServerTransport::send_packets`](super::NetcodeServerTransport::send_packets).
+/// - [`Self::connection_accepted`], [`Self::connection_denied`] and [`Self::disconnect`] are called as connections
+///   change state, and may be ignored if the socket doesn't track connections.
+///
+/// See [`CallbackSocket`](super::CallbackSocket) for an implementation that delegates to closures.
 pub trait ServerSocket: Debug + Send + Sync + 'static {
     /// Gets the encryption behavior of the socket.
     ///
@@ -24,6 +40,13 @@ pub trait ServerSocket: Debug + Send + Sync + 'static {
     /// If this is true, then [`RenetServer`](renet2::RenetServer) will 'downgrade' all channels to
     /// [`SendType::Unreliable`](renet2::SendType::Unreliable) so there is not a redundant reliability layer.
     fn is_reliable(&self) -> bool;
+    /// Gets the size of the largest packet the socket can send.
+    ///
+    /// Limits [`NetcodeServerTransport::set_max_payload_bytes`](super::NetcodeServerTransport::set_max_payload_bytes).
+    /// Defaults to [`NETCODE_MAX_PACKET_BYTES`], override it for transports that can carry larger packets.
+    fn max_packet_bytes(&self) -> usize {
+        NETCODE_MAX_PACKET_BYTES
+    }
 
     /// Gets the data source's `SocketAddr`.
     ///
@@ -52,6 +75,11 @@ pub trait ServerSocket: Debug + Send + Sync + 'static {
     /// Disconnects a remote connection with the given address.
     fn disconnect(&mut self, addr: SocketAddr);
 
+    /// Advances the data source's clock by the duration passed to the transport's update.
+    ///
+    /// Called before [`Self::preupdate`]. The default implementation does nothing.
+    fn advance_time(&mut self, _duration: Duration) {}
+
     /// Handles data-source-specific logic that must run before receiving packets.
     fn preupdate(&mut self);
 
@@ -69,11 +97,32 @@ pub trait ServerSocket: Debug + Send + Sync + 'static {
     ///
     /// Should return [`std::io::ErrorKind::ConnectionAborted`] if the destination's connection was closed internally.
     fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError>;
+
+    /// Gets the next remote connection that was closed by the data source since this was last called.
+    ///
+    /// Called after [`Self::postupdate`]. Connection-oriented sockets (e.g. websockets) should report connections that
+    /// close at the transport level so the client can be disconnected right away instead of waiting for a
+    /// `netcode` timeout. Connections closed via [`Self::disconnect`] may also be reported.
+    ///
+    /// The default implementation never reports closed connections.
+    fn take_closed_connection(&mut self) -> Option<SocketAddr> {
+        None
+    }
 }
 
 /// Unreliable data source for use in [`NetcodeClientTransport`](super::NetcodeClientTransport).
 ///
 /// See [`ServerSocket`] also.
+///
+/// # Implementing
+///
+/// In [`NetcodeClientTransport::update`](super::NetcodeClientTransport::update), [`Self::advance_time`] and
+/// [`Self::preupdate`] are called and then [`Self::try_recv`] until it returns an error, then [`Self::send`] may be called before
+/// [`Self::postupdate`]. [`Self::send`] is also called in
+/// [`NetcodeClientTransport::send_packets`](super::NetcodeClientTransport::send_packets). Packets received from
+/// addresses other than the server's are discarded.
+///
+/// See [`CallbackSocket`](super::CallbackSocket) for an implementation that delegates to closures.
 pub trait ClientSocket: Debug + Send + Sync + 'static {
     /// Gets the encryption behavior of the socket.
     ///
@@ -89,6 +138,20 @@ pub trait ClientSocket: Debug + Send + Sync + 'static {
     ///
     /// Should match the reliability of the server socket you will connect to.
     fn is_reliable(&self) -> bool;
+    /// Gets the kind of transport this socket implements.
+    ///
+    /// Defaults to [`SocketKind::Custom`] for sockets defined outside this crate.
+    fn kind(&self) -> SocketKind {
+        SocketKind::Custom
+    }
+    /// Gets the size of the largest packet the socket can receive.
+    ///
+    /// The transport's receive buffer has this size. Defaults to [`NETCODE_MAX_PACKET_BYTES`], override it for
+    /// transports that can carry larger packets (see
+    /// [`NetcodeServerTransport::set_max_payload_bytes`](super::NetcodeServerTransport::set_max_payload_bytes)).
+    fn max_packet_bytes(&self) -> usize {
+        NETCODE_MAX_PACKET_BYTES
+    }
 
     /// Gets the data source's `SocketAddr`.
     ///
@@ -103,6 +166,11 @@ pub trait ClientSocket: Debug + Send + Sync + 'static {
     /// This should disconnect any remote connections that are being tracked.
     fn close(&mut self);
 
+    /// Advances the data source's clock by the duration passed to the transport's update.
+    ///
+    /// Called before [`Self::preupdate`]. The default implementation does nothing.
+    fn advance_time(&mut self, _duration: Duration) {}
+
     /// Handles data-source-specific logic that must run before receiving packets.
     fn preupdate(&mut self);
 
@@ -121,3 +189,22 @@ pub trait ClientSocket: Debug + Send + Sync + 'static {
     /// Should return [`std::io::ErrorKind::ConnectionAborted`] if the destination's connection was closed internally.
     fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError>;
 }
+
+/// The kind of transport a [`ClientSocket`] implements.
+///
+/// See [`NetcodeClientTransport::active_transport`](super::NetcodeClientTransport::active_transport).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SocketKind {
+    /// Native UDP sockets.
+    Native,
+    /// In-memory sockets.
+    Memory,
+    /// Shared memory sockets.
+    SharedMemory,
+    /// WebTransport sockets.
+    WebTransport,
+    /// WebSocket sockets.
+    WebSocket,
+    /// A socket implemented outside this crate.
+    Custom,
+}
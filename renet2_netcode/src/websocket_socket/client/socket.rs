@@ -2,9 +2,10 @@ use std::{
     io::ErrorKind,
     net::SocketAddr,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 use js_sys::Uint8Array;
@@ -13,7 +14,7 @@ use wasm_bindgen::{closure::Closure, JsCast};
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{BinaryType, CloseEvent, ErrorEvent, MessageEvent, WebSocket};
 
-use crate::{ClientSocket, NetcodeTransportError, HTTP_CONNECT_REQ};
+use crate::{ws_ping_interval_from_timeout, ClientSocket, NetcodeTransportError, SocketKind, HTTP_CONNECT_REQ, WS_MAX_MISSED_PONGS};
 
 /// Configuration for setting up a [`WebSocketClient`].
 #[derive(Debug, Clone)]
@@ -21,9 +22,26 @@ use crate::{ClientSocket, NetcodeTransportError, HTTP_CONNECT_REQ};
 pub struct WebSocketClientConfig {
     /// The server's WebSocket URL that receives connections.
     pub server_url: url::Url,
+    /// Expected interval between protocol-level WebSocket pings.
+    ///
+    /// Browsers answer server pings automatically but don't let clients send their own pings, so on the client
+    /// this is used to close connections that receive nothing from the server for [`WS_MAX_MISSED_PONGS`] + 1
+    /// intervals. See [`ws_ping_interval_from_timeout`] for deriving an interval from the netcode timeout.
+    ///
+    /// Set to `None` to disable the check.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ws_ping_interval: Option<Duration>,
 }
 
 impl WebSocketClientConfig {
+    /// Makes a config with a ping interval derived from a 15 second netcode timeout.
+    pub fn new(server_url: url::Url) -> Self {
+        Self {
+            server_url,
+            ws_ping_interval: ws_ping_interval_from_timeout(15),
+        }
+    }
+
     /// Extracts the server address from the server URL if it contains a `SocketAddr`.
     pub fn server_address(&self) -> Result<SocketAddr, anyhow::Error> {
         let host = self
@@ -52,6 +70,9 @@ pub struct WebSocketClient {
     close_sender: async_channel::Sender<()>,
     outgoing_sender: async_channel::Sender<Vec<u8>>,
     closed: Arc<AtomicBool>,
+    ping_interval: Option<Duration>,
+    /// Time in milliseconds when the last message was received, or zero if the connection isn't open yet.
+    last_received: Arc<AtomicU64>,
     is_disconnected: bool,
     sent_connection_request: bool,
 }
@@ -80,9 +101,11 @@ impl WebSocketClient {
         let (connect_req_sender, connect_req_receiver) = async_channel::bounded::<Vec<u8>>(1);
         let (outgoing_sender, outgoing_receiver) = async_channel::unbounded::<Vec<u8>>();
         let closed = Arc::new(AtomicBool::new(false));
+        let last_received = Arc::new(AtomicU64::new(0));
 
         let inner_close_sender = close_sender.clone();
         let inner_closed = closed.clone();
+        let inner_last_received = last_received.clone();
         spawn_local(async move {
             // Wait for the initial connection request packet.
             let Ok(connection_req) = connect_req_receiver.recv().await else {
@@ -110,7 +133,9 @@ impl WebSocketClient {
             // Prep to receive messages.
             let message_closed = inner_closed.clone();
             let message_close_sender = inner_close_sender.clone();
+            let message_last_received = inner_last_received.clone();
             let on_message_callback = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
+                message_last_received.store(js_sys::Date::now() as u64, Ordering::Relaxed);
                 let msg = Uint8Array::new(&e.data()).to_vec();
                 if incoming_sender.try_send(msg).is_err() {
                     message_closed.store(true, Ordering::Relaxed);
@@ -153,7 +178,10 @@ impl WebSocketClient {
             ws.set_onopen(None);
             ws.set_onerror(None);
             let ws = match result {
-                futures_util::future::Either::Left((_, _)) => ws,
+                futures_util::future::Either::Left((_, _)) => {
+                    inner_last_received.store(js_sys::Date::now() as u64, Ordering::Relaxed);
+                    ws
+                }
                 futures_util::future::Either::Right((_, _)) => {
                     inner_closed.store(true, Ordering::Relaxed);
                     let _ = inner_close_sender.try_send(());
@@ -207,6 +235,8 @@ impl WebSocketClient {
             close_sender,
             outgoing_sender,
             closed,
+            ping_interval: config.ws_ping_interval,
+            last_received,
             is_disconnected: false,
             sent_connection_request: false,
         })
@@ -245,6 +275,9 @@ impl ClientSocket for WebSocketClient {
     fn is_reliable(&self) -> bool {
         true
     }
+    fn kind(&self) -> SocketKind {
+        SocketKind::WebSocket
+    }
 
     fn addr(&self) -> std::io::Result<SocketAddr> {
         // WebSocket clients don't have a meaningful address.
@@ -264,6 +297,16 @@ impl ClientSocket for WebSocketClient {
         if !self.is_disconnected && self.closed.load(Ordering::Relaxed) {
             self.disconnect();
         }
+
+        // Check for an idle connection.
+        if let Some(interval) = self.ping_interval {
+            let last_received = self.last_received.load(Ordering::Relaxed);
+            let idle_limit = interval.as_millis() as u64 * (WS_MAX_MISSED_PONGS as u64 + 1);
+            if !self.is_disconnected && last_received > 0 && (js_sys::Date::now() as u64).saturating_sub(last_received) > idle_limit {
+                warn!("WebSocket connection received nothing for {idle_limit}ms, disconnecting");
+                self.disconnect();
+            }
+        }
     }
 
     fn try_recv(&mut self, buffer: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
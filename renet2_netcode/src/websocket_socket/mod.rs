@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 #[cfg(all(feature = "ws_client_transport", target_family = "wasm"))]
 mod client;
 
@@ -9,3 +11,18 @@ pub use client::*;
 
 #[cfg(all(feature = "ws_server_transport", not(target_family = "wasm")))]
 pub use server::*;
+
+/// Number of consecutive websocket pings that may go unanswered before a connection is closed.
+pub const WS_MAX_MISSED_PONGS: u32 = 2;
+
+/// Derives a websocket ping interval from a netcode timeout (see `ConnectToken::timeout_seconds`).
+///
+/// The interval is chosen so a connection with [`WS_MAX_MISSED_PONGS`] missed pongs is closed at around the same
+/// time netcode would time it out. Returns `None` if the timeout is negative (i.e. timeouts are disabled).
+pub fn ws_ping_interval_from_timeout(timeout_seconds: i32) -> Option<Duration> {
+    if timeout_seconds < 0 {
+        return None;
+    }
+    let interval = Duration::from_secs(timeout_seconds as u64) / (WS_MAX_MISSED_PONGS + 1);
+    Some(interval.max(Duration::from_secs(1)))
+}
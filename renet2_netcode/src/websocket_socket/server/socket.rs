@@ -4,8 +4,8 @@ use std::{
     io::ErrorKind,
     net::SocketAddr,
     sync::{
-        atomic::{AtomicU64, AtomicUsize, Ordering},
-        Arc,
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock,
     },
     time::Duration,
 };
@@ -19,7 +19,10 @@ use anyhow::Error;
 use bytes::Bytes;
 use tokio::sync::mpsc;
 
-use crate::{client_idx_from_addr, client_idx_to_addr, NetcodeTransportError, ServerSocket, HTTP_CONNECT_REQ};
+use crate::{
+    client_idx_from_addr, client_idx_to_addr, ws_ping_interval_from_timeout, NetcodeTransportError, ServerSocket, HTTP_CONNECT_REQ,
+    WS_MAX_MISSED_PONGS,
+};
 
 /// Acceptor config for WebSocket connections.
 ///
@@ -40,28 +43,102 @@ pub enum WebSocketAcceptor {
     Rustls(tokio_rustls::TlsAcceptor),
 }
 
-/// Configuration for setting up a [`WebSocketServer`].
-pub struct WebSocketServerConfig {
-    /// Connection acceptor for this server.
-    pub acceptor: WebSocketAcceptor,
-    /// Socket address to listen on.
+impl WebSocketAcceptor {
+    /// Returns `true` if clients connect with TLS, either to the local server or to a TLS proxy.
+    fn has_tls(&self) -> bool {
+        !matches!(self, Self::Plain { has_tls_proxy: false })
+    }
+}
+
+/// Handle for replacing the acceptor of a [`WebSocketServer`] while it is running, e.g. to load renewed TLS
+/// certificates.
+///
+/// Obtained with [`WebSocketServer::cert_reloader`]. The handle can be kept after the server is moved into a
+/// [`NetcodeServerTransport`](crate::NetcodeServerTransport), and can be sent to other threads.
+#[derive(Clone)]
+pub struct WebSocketCertReloader {
+    acceptor: Arc<RwLock<WebSocketAcceptor>>,
+}
+
+impl WebSocketCertReloader {
+    /// Replaces the acceptor used for new incoming connections. Existing connections are not affected.
+    ///
+    /// Connections that are mid-handshake when the acceptor is replaced finish with the old acceptor.
+    ///
+    /// ## Errors
+    /// - Errors if the new acceptor changes whether clients connect with TLS, since that would change the server's
+    ///   url scheme (`ws` vs `wss`).
+    pub fn reload_certs(&self, acceptor: WebSocketAcceptor) -> Result<(), Error> {
+        let mut current = self.acceptor.write().unwrap();
+        if current.has_tls() != acceptor.has_tls() {
+            return Err(Error::msg(
+                "the new websocket acceptor must have the same TLS mode as the current acceptor",
+            ));
+        }
+        *current = acceptor;
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for WebSocketCertReloader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocketCertReloader").finish_non_exhaustive()
+    }
+}
+
+/// Where a [`WebSocketServer`] accepts connections.
+#[derive(Debug)]
+pub enum ListenSource {
+    /// Socket address to bind a new listener to.
     ///
     /// It is recommended to use a pre-defined IP and a wildcard port.
     ///
     /// Using a wildcard port will reduce your chance of competing with other sockets on your machine (e.g. other
     /// WebTransport servers running different game instances).
-    pub listen: SocketAddr,
+    Addr(SocketAddr),
+    /// A listener that is already bound, e.g. one passed in by systemd socket activation or handed off by a parent
+    /// process for a zero-downtime restart.
+    ///
+    /// The listener is driven by tokio, so it must support non-blocking mode. The server puts it in non-blocking mode
+    /// with [`std::net::TcpListener::set_nonblocking`].
+    PreBound(std::net::TcpListener),
+}
+
+impl From<SocketAddr> for ListenSource {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Addr(addr)
+    }
+}
+
+/// Configuration for setting up a [`WebSocketServer`].
+pub struct WebSocketServerConfig {
+    /// Connection acceptor for this server.
+    pub acceptor: WebSocketAcceptor,
+    /// Where to listen for connections, see [`ListenSource`].
+    pub listen: ListenSource,
     /// Maximum number of active clients allowed.
     pub max_clients: usize,
+    /// Interval between protocol-level WebSocket pings sent to each client.
+    ///
+    /// Pings keep intermediaries (e.g. reverse proxies) from closing idle connections. They are separate from
+    /// netcode keepalives. A client is disconnected if it misses [`WS_MAX_MISSED_PONGS`] pongs in a row.
+    ///
+    /// Set to `None` to disable pings. See [`ws_ping_interval_from_timeout`] for deriving an interval from the
+    /// netcode timeout.
+    pub ws_ping_interval: Option<Duration>,
 }
 
 impl WebSocketServerConfig {
     /// Makes a config without TLS or a TLS proxy.
-    pub fn new(listen: SocketAddr, max_clients: usize) -> Self {
+    ///
+    /// The ping interval is derived from a 15 second netcode timeout.
+    pub fn new(listen: impl Into<ListenSource>, max_clients: usize) -> Self {
         Self {
             acceptor: WebSocketAcceptor::Plain { has_tls_proxy: false },
-            listen,
+            listen: listen.into(),
             max_clients,
+            ws_ping_interval: ws_ping_interval_from_timeout(15),
         }
     }
 }
@@ -77,7 +154,7 @@ struct WebSocketServerClient {
 }
 
 impl WebSocketServerClient {
-    fn new<S>(socket: S, client_id: u64, client_idx: u64) -> Self
+    fn new<S>(socket: S, client_id: u64, client_idx: u64, ping_interval: Option<Duration>) -> Self
     where
         S: SinkExt<tungstenite::Message, Error = tungstenite::error::Error>
             + Unpin
@@ -88,15 +165,21 @@ impl WebSocketServerClient {
     {
         let (sink, stream) = socket.sink_err_into().err_into().split();
 
+        // Pings are sent by the writer and pongs are observed by the reader.
+        let missed_pongs = Arc::new(AtomicU32::new(0));
+
         // Setup reader.
         let (sender, incoming_receiver) = crossbeam::channel::bounded::<Bytes>(256);
         let (reader_aborter, abort_receiver) = mpsc::unbounded_channel::<()>();
-        let reader_handle = tokio::spawn(async move { WebSocketServer::reading_thread(stream, sender, abort_receiver).await });
+        let reader_missed_pongs = missed_pongs.clone();
+        let reader_handle =
+            tokio::spawn(async move { WebSocketServer::reading_thread(stream, sender, abort_receiver, reader_missed_pongs).await });
 
         // Setup writer.
         // - Writer must be in a thread because sending is async.
         let (outgoing_sender, receiver) = mpsc::channel::<Bytes>(256);
-        let writer_handle = tokio::spawn(async move { WebSocketServer::writing_thread(sink, receiver, client_idx).await });
+        let writer_handle =
+            tokio::spawn(async move { WebSocketServer::writing_thread(sink, receiver, client_idx, ping_interval, missed_pongs).await });
 
         Self {
             client_id,
@@ -161,6 +244,7 @@ impl PendingClient {
 pub struct WebSocketServer {
     addr: SocketAddr,
     has_tls: bool,
+    cert_reloader: WebSocketCertReloader,
 
     connection_abort_handle: AbortHandle,
 
@@ -173,6 +257,8 @@ pub struct WebSocketServer {
     /// Maps netcode client ids to internal client indices.
     client_id_to_idx: HashMap<u64, u64>,
     lost_clients: HashSet<u64>,
+    /// Addresses of removed clients that have not been reported by `take_closed_connection` yet.
+    closed_connections: Vec<SocketAddr>,
 
     closed: bool,
     current_clients: Arc<AtomicUsize>,
@@ -185,11 +271,23 @@ impl WebSocketServer {
     /// ## Errors
     /// - Errors if unable to bind to `addr`, which can happen if your
     ///   machine is using all ports on a pre-defined IP address.
+    /// - Errors if a [`ListenSource::PreBound`] listener can't be put in non-blocking mode.
     pub fn new(config: WebSocketServerConfig, handle: tokio::runtime::Handle) -> Result<Self, Error> {
         let max_clients = config.max_clients;
-        let has_tls = !matches!(config.acceptor, WebSocketAcceptor::Plain { has_tls_proxy: false });
+        let ping_interval = config.ws_ping_interval;
+        let has_tls = config.acceptor.has_tls();
+        let cert_reloader = WebSocketCertReloader {
+            acceptor: Arc::new(RwLock::new(config.acceptor)),
+        };
 
-        let socket = handle.block_on(async { tokio::net::TcpListener::bind(config.listen).await })?;
+        let socket = match config.listen {
+            ListenSource::Addr(listen) => handle.block_on(async { tokio::net::TcpListener::bind(listen).await })?,
+            ListenSource::PreBound(listener) => {
+                listener.set_nonblocking(true)?;
+                let _guard = handle.enter();
+                tokio::net::TcpListener::from_std(listener)?
+            }
+        };
         let addr = socket.local_addr()?;
 
         // Channels
@@ -205,17 +303,19 @@ impl WebSocketServer {
         let connection_abort_handle = handle
             .spawn(Self::accept_connections(
                 socket,
-                config.acceptor,
+                cert_reloader.acceptor.clone(),
                 connection_sender.clone(),
                 connection_req_sender.clone(),
                 inner_client_iterator,
                 inner_current_clients,
                 max_clients,
+                ping_interval,
             ))
             .abort_handle();
         Ok(Self {
             addr,
             has_tls,
+            cert_reloader,
             connection_abort_handle,
             connection_req_receiver,
             connection_receiver,
@@ -224,6 +324,7 @@ impl WebSocketServer {
             clients: BTreeMap::new(),
             client_id_to_idx: HashMap::new(),
             lost_clients: HashSet::new(),
+            closed_connections: Vec::new(),
             closed: false,
             current_clients,
             recv_index: 0,
@@ -243,23 +344,41 @@ impl WebSocketServer {
         make_websocket_url(self.has_tls, self.addr).unwrap()
     }
 
+    /// Replaces the acceptor used for new incoming connections, e.g. to load renewed TLS certificates without
+    /// restarting the server. Existing connections are not affected.
+    ///
+    /// In `renet2_setup`, acceptors can be built from certificate files with `GameServerSetupConfig::get_ws_acceptor`.
+    /// See [`WebSocketCertReloader::reload_certs`] for details, and [`Self::cert_reloader`] for reloading after the
+    /// server is moved into a transport.
+    pub fn reload_certs(&self, acceptor: WebSocketAcceptor) -> Result<(), Error> {
+        self.cert_reloader.reload_certs(acceptor)
+    }
+
+    /// Gets a handle for replacing the server's acceptor while it is running.
+    pub fn cert_reloader(&self) -> WebSocketCertReloader {
+        self.cert_reloader.clone()
+    }
+
     /// Disconnects the server.
     pub fn close(&mut self) {
         self.connection_abort_handle.abort();
         self.closed = true;
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn accept_connections(
         socket: tokio::net::TcpListener,
-        acceptor: WebSocketAcceptor,
+        acceptor: Arc<RwLock<WebSocketAcceptor>>,
         connection_sender: crossbeam::channel::Sender<WebSocketServerClient>,
         connection_req_sender: crossbeam::channel::Sender<ConnectionRequest>,
         client_iterator: Arc<AtomicU64>,
         current_clients: Arc<AtomicUsize>,
         max_clients: usize,
+        ping_interval: Option<Duration>,
     ) {
         while let Ok((mut stream, _)) = socket.accept().await {
-            let acceptor = acceptor.clone();
+            // The acceptor may be replaced at any time, new connections use the latest one.
+            let acceptor = acceptor.read().unwrap().clone();
             let connection_sender = connection_sender.clone();
             let connection_req_sender = connection_req_sender.clone();
             let current_clients = current_clients.clone();
@@ -277,7 +396,7 @@ impl WebSocketServer {
                     return;
                 }
 
-                match Self::handle_connection(acceptor, client_iterator, connection_req_sender, stream).await {
+                match Self::handle_connection(acceptor, client_iterator, connection_req_sender, stream, ping_interval).await {
                     Ok(result) => {
                         if let Some(result) = result {
                             if let Err(err) = connection_sender.try_send(result) {
@@ -298,11 +417,13 @@ impl WebSocketServer {
         client_iterator: Arc<AtomicU64>,
         connection_req_sender: crossbeam::channel::Sender<ConnectionRequest>,
         conn: tokio::net::TcpStream,
+        ping_interval: Option<Duration>,
     ) -> Result<Option<WebSocketServerClient>, Error> {
         let (uri_sender, mut uri_receiver) = mpsc::channel::<Uri>(1);
         // TODO: this is a multistep process that continues after receiving a Request. We would rather
         // pause to validate the URI before continuing, but tungstenite does not support that workflow.
         // Might need to use axum instead.
+        #[allow(clippy::result_large_err, reason = "the callback signature is defined by tungstenite")]
         let callback = move |req: &Request, res: Response| {
             let uri = req.uri().clone();
             uri_sender.try_send(uri).ok();
@@ -311,19 +432,19 @@ impl WebSocketServer {
         let make_server_client: Box<dyn FnOnce(u64, u64) -> WebSocketServerClient + Send + Sync> = match acceptor {
             WebSocketAcceptor::Plain { has_tls_proxy: _ } => {
                 let socket = tokio_tungstenite::accept_hdr_async(conn, callback).await?;
-                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx))
+                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx, ping_interval))
             }
             #[cfg(feature = "ws-native-tls")]
             WebSocketAcceptor::NativeTls(acceptor) => {
                 let tls_stream = acceptor.accept(conn).await?;
                 let socket = tokio_tungstenite::accept_hdr_async(tls_stream, callback).await?;
-                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx))
+                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx, ping_interval))
             }
             #[cfg(feature = "ws-rustls")]
             WebSocketAcceptor::Rustls(acceptor) => {
                 let tls_stream = acceptor.accept(conn).await?;
                 let socket = tokio_tungstenite::accept_hdr_async(tls_stream, callback).await?;
-                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx))
+                Box::new(move |client_id, client_idx| WebSocketServerClient::new(socket, client_id, client_idx, ping_interval))
             }
         };
 
@@ -373,6 +494,7 @@ impl WebSocketServer {
         mut ws_reader: R,
         sender: crossbeam::channel::Sender<Bytes>,
         mut abort_receiver: mpsc::UnboundedReceiver<()>,
+        missed_pongs: Arc<AtomicU32>,
     ) {
         // We must have a keep-alive timer here to ensure pending clients cannot occupy client slots after
         // their connect token has expired and they have been removed from the netcode server.
@@ -395,10 +517,15 @@ impl WebSocketServer {
                 _ = abort_receiver.recv() => {
                     break;
                 },
-                Some(result) = ws_reader.next() => match result {
-                    Ok(msg) => {
+                result = ws_reader.next() => match result {
+                    Some(Ok(msg)) => {
                         let data = match msg {
                             tungstenite::Message::Binary(data) => data,
+                            // Pongs don't reset the keep-alive timer, only netcode packets do.
+                            tungstenite::Message::Pong(_) => {
+                                missed_pongs.store(0, Ordering::Relaxed);
+                                continue;
+                            },
                             _ => {
                                 log::trace!("WS client socket reader received a non-binary message, ignoring.");
                                 continue;
@@ -415,10 +542,15 @@ impl WebSocketServer {
                             }
                         }
                     },
-                    Err(err) => {
+                    Some(Err(err)) => {
                         log::trace!("WS client socket reader encountered an error: {:?}", err);
                         break;
                     }
+                    // The connection was closed without an error (e.g. the TCP stream was shut down).
+                    None => {
+                        log::trace!("WS client socket reader stream ended, disconnecting.");
+                        break;
+                    }
                 },
                 _ = &mut sleep => {
                     log::trace!("WS client socket reader timed out, disconnecting.");
@@ -438,9 +570,29 @@ impl WebSocketServer {
         mut ws_writer: S,
         mut receiver: mpsc::Receiver<Bytes>,
         client_idx: u64,
+        ping_interval: Option<Duration>,
+        missed_pongs: Arc<AtomicU32>,
     ) {
-        while let Some(bytes) = receiver.recv().await {
-            let msg = tungstenite::Message::Binary(bytes);
+        let mut ping_timer = ping_interval.map(|interval| {
+            let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            timer
+        });
+
+        loop {
+            let msg = tokio::select! {
+                bytes = receiver.recv() => match bytes {
+                    Some(bytes) => tungstenite::Message::Binary(bytes),
+                    None => return,
+                },
+                _ = async { ping_timer.as_mut().unwrap().tick().await }, if ping_timer.is_some() => {
+                    if missed_pongs.fetch_add(1, Ordering::Relaxed) >= WS_MAX_MISSED_PONGS {
+                        log::trace!("Client {} missed {} pongs, disconnecting", client_idx, WS_MAX_MISSED_PONGS);
+                        return;
+                    }
+                    tungstenite::Message::Ping(Bytes::new())
+                },
+            };
             // TODO: this isn't optimal because it flushes after every send instead of batching
             if let Err(err) = ws_writer.send(msg).await {
                 log::trace!("Failed to send message to client {}: {:?}", client_idx, err);
@@ -636,6 +788,7 @@ impl ServerSocket for WebSocketServer {
             let removed_client_id = {
                 if let Some(client_data) = self.clients.remove(&client_idx) {
                     let _ = client_data.reader_aborter.send(());
+                    self.closed_connections.push(client_idx_to_addr(client_idx));
                     client_data.client_id
                 } else if let Some(pending_client) = self.pending_clients.remove(&client_idx) {
                     let _ = pending_client.result_sender.try_send(ConnectionRequestResult::Failure);
@@ -656,7 +809,8 @@ impl ServerSocket for WebSocketServer {
             }
         }
 
-        // Note: Lost clients will time out in NetcodeServer and be disconnected in RenetServer that way.
+        // Note: Removed clients are reported by `Self::take_closed_connection` so they can be disconnected from
+        // NetcodeServer and RenetServer immediately.
     }
 
     fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
@@ -672,17 +826,21 @@ impl ServerSocket for WebSocketServer {
             return Err(std::io::Error::from(ErrorKind::ConnectionAborted).into());
         };
 
-        // If the sender thread gets backed up because the client is unresponsive, then packets will be dropped.
+        // If the sender thread gets backed up because the client is unresponsive, then the send fails with
+        // `WouldBlock` so the transport's error policy decides whether to drop the packet or disconnect the client.
         let data = Bytes::copy_from_slice(packet);
         match client_data.outgoing_sender.try_send(data) {
-            Err(mpsc::error::TrySendError::Closed(_)) => return Err(std::io::Error::from(ErrorKind::ConnectionAborted).into()),
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(std::io::Error::from(ErrorKind::ConnectionAborted).into()),
             Err(mpsc::error::TrySendError::Full(_)) => {
-                log::trace!("dropping packet for client {client_idx}; writer thread is backed up, client may be unresponsive");
+                log::trace!("failed sending packet to client {client_idx}; writer thread is backed up, client may be unresponsive");
+                Err(std::io::Error::from(ErrorKind::WouldBlock).into())
             }
-            Ok(()) => (),
+            Ok(()) => Ok(()),
         }
+    }
 
-        Ok(())
+    fn take_closed_connection(&mut self) -> Option<SocketAddr> {
+        self.closed_connections.pop()
     }
 }
 
@@ -712,3 +870,224 @@ fn make_websocket_url(with_tls: bool, address: SocketAddr) -> Result<url::Url, (
     url.set_port(Some(address.port()))?;
     Ok(url)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use renet2::{ConnectionConfig, RenetServer, ServerEvent};
+    use renetcode2::{ClientAuthentication, NetcodeClient, ServerAuthentication, NETCODE_MAX_PACKET_BYTES};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    use super::*;
+    use crate::{NetcodeServerTransport, ServerSetupConfig, TransportErrorPolicy};
+
+    type ClientStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+    const DELTA: Duration = Duration::from_millis(10);
+
+    /// Forwards packets between a raw websocket stream and a netcode client.
+    fn update_client(runtime: &tokio::runtime::Runtime, stream: &mut ClientStream, client: &mut NetcodeClient) {
+        runtime.block_on(async {
+            while let Ok(Some(Ok(msg))) = tokio::time::timeout(Duration::from_millis(1), stream.next()).await {
+                if let tungstenite::Message::Binary(data) = msg {
+                    let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+                    buffer[..data.len()].copy_from_slice(&data[..]);
+                    client.process_packet(&mut buffer[..data.len()]);
+                }
+            }
+            if let Some((packet, _)) = client.update(DELTA) {
+                stream
+                    .send(tungstenite::Message::Binary(Bytes::copy_from_slice(packet)))
+                    .await
+                    .unwrap();
+            }
+        });
+    }
+
+    /// Connects a netcode client to the server over a raw websocket.
+    fn connect_client(
+        runtime: &tokio::runtime::Runtime,
+        socket: WebSocketServer,
+        client_id: u64,
+    ) -> (NetcodeServerTransport, RenetServer, ClientStream, NetcodeClient) {
+        let server_addr = socket.addr;
+        let mut server_url = socket.url();
+        let server_config = ServerSetupConfig {
+            current_time: Duration::ZERO,
+            max_clients: 4,
+            protocol_id: 7,
+            socket_addresses: vec![vec![server_addr]],
+            authentication: ServerAuthentication::Unsecure,
+        };
+        let mut transport = NetcodeServerTransport::new(server_config, socket).unwrap();
+        let mut server = RenetServer::new(ConnectionConfig::test());
+
+        // Open a websocket with the client's connection request.
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: 7,
+            client_id,
+            socket_id: 0,
+            server_addr,
+            user_data: None,
+        };
+        let mut client = NetcodeClient::new(Duration::ZERO, authentication)
+            .unwrap()
+            .set_encryption_policy(true);
+        let (connection_req, _) = client.update(Duration::ZERO).unwrap();
+        let connect_msg_ser = urlencoding::encode_binary(connection_req);
+        server_url.set_query(Some(format!("{}={}", HTTP_CONNECT_REQ, &connect_msg_ser).as_str()));
+        let (mut stream, _) = runtime.block_on(tokio_tungstenite::connect_async(server_url.as_str())).unwrap();
+
+        // Connect.
+        let start = Instant::now();
+        while !(client.is_connected() && server.is_connected(client_id)) {
+            assert!(start.elapsed() < Duration::from_secs(5), "client failed to connect");
+            transport.update(DELTA, &mut server).unwrap();
+            transport.send_packets(&mut server);
+            update_client(runtime, &mut stream, &mut client);
+            std::thread::sleep(DELTA);
+        }
+        assert_eq!(server.get_event(), Some(ServerEvent::ClientConnected { client_id }));
+
+        (transport, server, stream, client)
+    }
+
+    #[test]
+    fn pre_bound_listener() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+        let socket = WebSocketServer::new(
+            WebSocketServerConfig::new(ListenSource::PreBound(listener), 4),
+            runtime.handle().clone(),
+        )
+        .unwrap();
+        assert_eq!(socket.addr, listener_addr);
+
+        connect_client(&runtime, socket, 42);
+    }
+
+    #[test]
+    fn reload_certs() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let socket = WebSocketServer::new(
+            WebSocketServerConfig::new("127.0.0.1:0".parse::<SocketAddr>().unwrap(), 4),
+            runtime.handle().clone(),
+        )
+        .unwrap();
+        let reloader = socket.cert_reloader();
+
+        // The TLS mode can't change.
+        assert!(socket.reload_certs(WebSocketAcceptor::Plain { has_tls_proxy: true }).is_err());
+
+        // New connections use the replacement acceptor.
+        socket.reload_certs(WebSocketAcceptor::Plain { has_tls_proxy: false }).unwrap();
+        let (mut transport, mut server, _stream, _client) = connect_client(&runtime, socket, 42);
+        reloader.reload_certs(WebSocketAcceptor::Plain { has_tls_proxy: false }).unwrap();
+        transport.update(DELTA, &mut server).unwrap();
+        assert!(server.is_connected(42));
+    }
+
+    #[cfg(feature = "ws-rustls")]
+    #[test]
+    fn reload_tls_certs() {
+        use tokio_rustls::rustls::{
+            self,
+            pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer, ServerName},
+        };
+
+        let tls_acceptor = |cert: &rcgen::CertifiedKey| {
+            let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+            let config = rustls::ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(vec![cert.cert.der().clone()], key)
+                .unwrap();
+            WebSocketAcceptor::Rustls(Arc::new(config).into())
+        };
+        let old_cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let new_cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut config = WebSocketServerConfig::new("127.0.0.1:0".parse::<SocketAddr>().unwrap(), 4);
+        config.acceptor = tls_acceptor(&old_cert);
+        let socket = WebSocketServer::new(config, runtime.handle().clone()).unwrap();
+        let server_addr = socket.addr;
+
+        // Returns whether a TLS handshake with the server succeeds when only `cert` is trusted.
+        let handshake = |cert: &rcgen::CertifiedKey| {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add(cert.cert.der().clone()).unwrap();
+            let config = rustls::ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+            let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+            runtime.block_on(async {
+                let stream = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+                let server_name = ServerName::try_from("localhost").unwrap();
+                connector.connect(server_name, stream).await.is_ok()
+            })
+        };
+        assert!(handshake(&old_cert));
+        assert!(!handshake(&new_cert));
+
+        // TLS can't be turned off by a reload.
+        assert!(socket.reload_certs(WebSocketAcceptor::Plain { has_tls_proxy: false }).is_err());
+
+        // New connections use the reloaded certificate.
+        socket.cert_reloader().reload_certs(tls_acceptor(&new_cert)).unwrap();
+        assert!(!handshake(&old_cert));
+        assert!(handshake(&new_cert));
+    }
+
+    #[test]
+    fn closed_connection_disconnects_immediately() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let socket = WebSocketServer::new(
+            WebSocketServerConfig::new("127.0.0.1:0".parse::<SocketAddr>().unwrap(), 4),
+            runtime.handle().clone(),
+        )
+        .unwrap();
+        let client_id = 42;
+        let (mut transport, mut server, stream, _client) = connect_client(&runtime, socket, client_id);
+
+        // Drop the connection without a closing handshake.
+        drop(stream);
+
+        // The client is disconnected well before the netcode timeout (15 seconds for unsecure connections).
+        let start = Instant::now();
+        while server.is_connected(client_id) {
+            assert!(start.elapsed() < Duration::from_secs(2), "closed connection was not detected");
+            transport.update(DELTA, &mut server).unwrap();
+            std::thread::sleep(DELTA);
+        }
+        assert!(matches!(
+            server.get_event(),
+            Some(ServerEvent::ClientDisconnected { client_id: id, .. }) if id == client_id
+        ));
+        assert_eq!(transport.connected_clients(), 0);
+    }
+
+    #[test]
+    fn backed_up_client_uses_error_policy() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let socket = WebSocketServer::new(
+            WebSocketServerConfig::new("127.0.0.1:0".parse::<SocketAddr>().unwrap(), 4),
+            runtime.handle().clone(),
+        )
+        .unwrap();
+        let client_id = 42;
+        let (mut transport, mut server, _stream, _client) = connect_client(&runtime, socket, client_id);
+        transport.set_error_policy(TransportErrorPolicy::DisconnectImmediately);
+
+        // The client stops reading, so the connection's buffers fill up until sends fail.
+        let start = Instant::now();
+        while server.is_connected(client_id) {
+            assert!(start.elapsed() < Duration::from_secs(20), "backed up client was not disconnected");
+            for _ in 0..1_000 {
+                server.send_message(client_id, renet2::DefaultChannel::Unreliable, vec![0; 1_000]);
+            }
+            server.update(DELTA);
+            transport.update(DELTA, &mut server).unwrap();
+            transport.send_packets(&mut server);
+        }
+    }
+}
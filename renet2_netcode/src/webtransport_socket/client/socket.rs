@@ -16,7 +16,7 @@ use wasm_bindgen::{prelude::Closure, JsValue};
 use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::{ReadableStreamDefaultReader, WritableStreamDefaultWriter};
 
-use crate::{ClientSocket, NetcodeTransportError, ServerCertHash, WebServerDestination, HTTP_CONNECT_REQ};
+use crate::{ClientSocket, NetcodeTransportError, ServerCertHash, SocketKind, WebServerDestination, HTTP_CONNECT_REQ};
 
 use super::bindings::{
     ReadableStreamDefaultReadResult, WebTransport, WebTransportCongestionControl, WebTransportError, WebTransportHash, WebTransportOptions,
@@ -350,6 +350,9 @@ impl ClientSocket for WebTransportClient {
     fn is_reliable(&self) -> bool {
         false
     }
+    fn kind(&self) -> SocketKind {
+        SocketKind::WebTransport
+    }
 
     fn addr(&self) -> std::io::Result<SocketAddr> {
         // WebTransport clients don't have a meaningful address.
@@ -3,7 +3,7 @@ use rcgen::{CertificateParams, DistinguishedName, DnType, IsCa, KeyIdMethod, San
 use rustls_pki_types::{CertificateDer, PrivateKeyDer};
 use time::{ext::NumericalDuration, OffsetDateTime};
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::SystemTime};
 
 use crate::{ServerCertHash, WebServerDestination};
 
@@ -31,6 +31,15 @@ pub fn generate_self_signed_certificate(
 pub fn generate_self_signed_certificate_opinionated<T: Into<WebServerDestination>>(
     subject_alt_names: impl IntoIterator<Item = T>,
 ) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), rcgen::Error> {
+    let (cert, key, _) = generate_self_signed_certificate_opinionated_with_expiry(subject_alt_names)?;
+    Ok((cert, key))
+}
+
+/// Generates a self-signed certificate like [`generate_self_signed_certificate_opinionated`], and also returns the
+/// time when the certificate expires (its `not_after` timestamp).
+pub fn generate_self_signed_certificate_opinionated_with_expiry<T: Into<WebServerDestination>>(
+    subject_alt_names: impl IntoIterator<Item = T>,
+) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>, SystemTime), rcgen::Error> {
     let not_before = OffsetDateTime::now_utc().saturating_sub(1.hours()); //adjust for client system time variance
     let not_after = not_before.saturating_add(2.weeks());
     let mut distinguished_name = DistinguishedName::new();
@@ -63,7 +72,8 @@ pub fn generate_self_signed_certificate_opinionated<T: Into<WebServerDestination
     params.use_authority_key_identifier_extension = false;
     params.key_identifier_method = KeyIdMethod::Sha256;
 
-    generate_self_signed_certificate(params)
+    let (cert, key) = generate_self_signed_certificate(params)?;
+    Ok((cert, key, not_after.into()))
 }
 
 /// Loads a certificate and private key from the file system.
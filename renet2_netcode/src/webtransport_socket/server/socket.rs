@@ -1,6 +1,6 @@
 use anyhow::Error;
 use bytes::Bytes;
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use quinn::crypto::rustls::QuicServerConfig;
 use quinn::IdleTimeout;
 use rustls_pki_types::{CertificateDer, PrivateKeyDer};
@@ -13,12 +13,12 @@ use std::sync::atomic::AtomicU64;
 use std::{
     collections::{BTreeMap, HashSet},
     io::ErrorKind,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc,
+        Arc, Mutex, Weak,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
     vec,
 };
 
@@ -26,7 +26,16 @@ use crate::{
     client_idx_from_addr, client_idx_to_addr, NetcodeTransportError, ServerCertHash, ServerSocket, WebServerDestination, HTTP_CONNECT_REQ,
 };
 
-use super::{generate_self_signed_certificate_opinionated, get_server_cert_hash};
+use super::{generate_self_signed_certificate_opinionated_with_expiry, get_server_cert_hash};
+
+/// Information about a self-signed certificate, see [`WebTransportServerConfig::selfsigned`].
+#[derive(Debug, Clone)]
+pub struct SelfSignedCertInfo {
+    /// Destinations the certificate is bound to.
+    pub destinations: Vec<WebServerDestination>,
+    /// When the certificate expires.
+    pub not_after: SystemTime,
+}
 
 /// Configuration for setting up a [`WebTransportServer`].
 #[derive(Debug)]
@@ -50,6 +59,20 @@ pub struct WebTransportServerConfig {
     pub listen: SocketAddr,
     /// Maximum number of active clients allowed.
     pub max_clients: usize,
+    /// Maximum number of concurrent sessions from a single IP address.
+    ///
+    /// New sessions from an IP at the limit are refused before their handshake. Sessions count against the limit
+    /// from when they are received until they close, including while their connection requests are being
+    /// evaluated. This complements [`Self::max_clients`] by keeping one source from exhausting the server's
+    /// resources, but note that clients behind the same NAT share an IP.
+    ///
+    /// `None` by default.
+    pub max_connections_per_ip: Option<usize>,
+    /// Information about [`Self::cert`] if it is self-signed, which allows it to be regenerated with
+    /// [`WebTransportServer::regenerate_selfsigned`].
+    ///
+    /// Set by [`Self::new_selfsigned`] and [`Self::new_selfsigned_with_proxies`].
+    pub selfsigned: Option<SelfSignedCertInfo>,
     //todo: client keep-alive timeout
 }
 
@@ -79,13 +102,18 @@ impl WebTransportServerConfig {
         proxies: Vec<WebServerDestination>,
         max_clients: usize,
     ) -> Result<(Self, ServerCertHash), Error> {
-        let (cert, key) = generate_self_signed_certificate_opinionated(proxies)?;
+        let (cert, key, not_after) = generate_self_signed_certificate_opinionated_with_expiry(proxies.clone())?;
         let hash = get_server_cert_hash(&cert);
         let config = WebTransportServerConfig {
             cert,
             key,
             listen,
             max_clients,
+            max_connections_per_ip: None,
+            selfsigned: Some(SelfSignedCertInfo {
+                destinations: proxies,
+                not_after,
+            }),
         };
 
         Ok((config, hash))
@@ -140,6 +168,119 @@ impl Clone for WebTransportServerConfig {
             key: self.key.clone_key(),
             listen: self.listen,
             max_clients: self.max_clients,
+            max_connections_per_ip: self.max_connections_per_ip,
+            selfsigned: self.selfsigned.clone(),
+        }
+    }
+}
+
+/// Handle for rotating the certificate of a [`WebTransportServer`] while it is running.
+///
+/// Obtained with [`WebTransportServer::cert_reloader`]. The handle can be kept after the server is moved into a
+/// [`NetcodeServerTransport`](crate::NetcodeServerTransport), and can be sent to other threads. It does not keep
+/// the server alive.
+#[derive(Clone)]
+pub struct WebTransportCertReloader {
+    endpoint: Weak<wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>>,
+    config: Arc<Mutex<WebTransportServerConfig>>,
+}
+
+impl WebTransportCertReloader {
+    /// Gets the hash of the server's current certificate.
+    pub fn cert_hash(&self) -> ServerCertHash {
+        get_server_cert_hash(&self.config.lock().unwrap().cert)
+    }
+
+    /// Gets the time when the server's current certificate expires, if it is self-signed.
+    pub fn cert_not_after(&self) -> Option<SystemTime> {
+        self.config.lock().unwrap().selfsigned.as_ref().map(|info| info.not_after)
+    }
+
+    /// Replaces the server's self-signed certificate with a new one bound to the same destinations.
+    ///
+    /// New sessions use the new certificate, existing sessions stay connected. Returns the hash of the new
+    /// certificate, which clients using [`ServerCertHash`] need in order to connect.
+    ///
+    /// ## Errors
+    /// - Errors if the server's certificate is not self-signed (see [`WebTransportServerConfig::selfsigned`]).
+    /// - Errors if the server was dropped.
+    /// - Errors if the certificate can't be generated or the server can't be reconfigured.
+    pub fn regenerate_selfsigned(&self) -> Result<ServerCertHash, Error> {
+        let Some(endpoint) = self.endpoint.upgrade() else {
+            return Err(Error::msg("the WebTransport server was dropped"));
+        };
+        let mut config = self.config.lock().unwrap();
+        let Some(destinations) = config.selfsigned.as_ref().map(|info| info.destinations.clone()) else {
+            return Err(Error::msg("the WebTransport server certificate is not self-signed"));
+        };
+        let (cert, key, not_after) = generate_self_signed_certificate_opinionated_with_expiry(destinations.clone())?;
+        let hash = get_server_cert_hash(&cert);
+
+        let mut new_config = config.clone();
+        new_config.cert = cert;
+        new_config.key = key;
+        new_config.selfsigned = Some(SelfSignedCertInfo { destinations, not_after });
+        // Don't rebind, the listen address may be a wildcard.
+        endpoint.reload_config(new_config.clone().create_server_config()?, false)?;
+        *config = new_config;
+
+        Ok(hash)
+    }
+}
+
+impl std::fmt::Debug for WebTransportCertReloader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebTransportCertReloader").finish_non_exhaustive()
+    }
+}
+
+/// Number of sessions from each IP address, see [`WebTransportServerConfig::max_connections_per_ip`].
+#[derive(Clone)]
+struct IpConnectionCounts {
+    limit: Option<usize>,
+    counts: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl IpConnectionCounts {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            counts: Arc::default(),
+        }
+    }
+
+    /// Counts a new session from `ip`, or returns `None` if the IP already has `limit` sessions.
+    fn try_add(&self, ip: IpAddr) -> Option<IpConnectionGuard> {
+        // Normalize IPv4-mapped addresses so dual-stack clients are counted once.
+        let ip = ip.to_canonical();
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(ip).or_default();
+        if self.limit.is_some_and(|limit| *count >= limit) {
+            if *count == 0 {
+                counts.remove(&ip);
+            }
+            return None;
+        }
+        *count += 1;
+
+        Some(IpConnectionGuard { counts: self.clone(), ip })
+    }
+}
+
+/// Uncounts a session from [`IpConnectionCounts`] when dropped.
+struct IpConnectionGuard {
+    counts: IpConnectionCounts,
+    ip: IpAddr,
+}
+
+impl Drop for IpConnectionGuard {
+    fn drop(&mut self) {
+        let mut counts = self.counts.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.ip);
+            }
         }
     }
 }
@@ -157,6 +298,8 @@ struct WebTransportServerClient {
     reader_thread: tokio::task::JoinHandle<()>,
     /// Netcode client id.
     client_id: u64,
+    /// Keeps the session counted for its IP until the client is removed.
+    _ip_guard: IpConnectionGuard,
 }
 
 /// Wrapper struct for communicating connection requests from the internal connection handler to the server.
@@ -204,6 +347,7 @@ enum ClientConnectionResult {
         client_idx: u64,
         client_id: u64,
         session: wtransport::Connection,
+        ip_guard: IpConnectionGuard,
     },
     Failure {
         client_idx: u64,
@@ -217,6 +361,7 @@ pub struct WebTransportServer {
     handle: tokio::runtime::Handle,
 
     addr: SocketAddr,
+    cert_reloader: WebTransportCertReloader,
 
     connection_req_receiver: mpsc::Receiver<ConnectionRequest>,
     connection_receiver: mpsc::Receiver<ClientConnectionResult>,
@@ -228,6 +373,8 @@ pub struct WebTransportServer {
     /// Maps netcode client ids to internal client indices.
     client_id_to_idx: HashMap<u64, u64>,
     lost_clients: HashSet<u64>,
+    /// Addresses of removed clients that have not been reported by `take_closed_connection` yet.
+    closed_connections: Vec<SocketAddr>,
 
     closed: bool,
     current_clients: Arc<AtomicUsize>,
@@ -243,9 +390,15 @@ impl WebTransportServer {
     ///   machine is using all ports on a pre-defined IP address.
     pub fn new(config: WebTransportServerConfig, handle: tokio::runtime::Handle) -> Result<Self, Error> {
         let max_clients = config.max_clients;
+        let max_connections_per_ip = config.max_connections_per_ip;
+        let reloader_config = config.clone();
         let server_config = config.create_server_config()?;
-        let endpoint = handle.block_on(async move { wtransport::Endpoint::server(server_config) })?;
+        let endpoint = Arc::new(handle.block_on(async move { wtransport::Endpoint::server(server_config) })?);
         let addr = endpoint.local_addr()?;
+        let cert_reloader = WebTransportCertReloader {
+            endpoint: Arc::downgrade(&endpoint),
+            config: Arc::new(Mutex::new(reloader_config)),
+        };
         let (sender, receiver) = mpsc::channel::<ClientConnectionResult>(max_clients);
         let client_iterator = Arc::new(AtomicU64::new(0));
         let current_clients = Arc::new(AtomicUsize::new(0));
@@ -258,12 +411,14 @@ impl WebTransportServer {
                 Arc::clone(&current_clients),
                 connection_req_sender,
                 max_clients,
+                max_connections_per_ip,
             ))
             .abort_handle();
 
         Ok(Self {
             handle,
             addr,
+            cert_reloader,
             connection_req_receiver,
             connection_receiver: receiver,
             connection_abort_handle: abort_handle,
@@ -272,12 +427,39 @@ impl WebTransportServer {
             clients: BTreeMap::new(),
             client_id_to_idx: HashMap::default(),
             lost_clients: HashSet::new(),
+            closed_connections: Vec::new(),
             closed: false,
             current_clients,
             recv_index: 0,
         })
     }
 
+    /// Gets the hash of the server's current certificate.
+    pub fn cert_hash(&self) -> ServerCertHash {
+        self.cert_reloader.cert_hash()
+    }
+
+    /// Gets the time when the server's current certificate expires, if it is self-signed.
+    ///
+    /// Self-signed certificates are only valid for two weeks, see [`WebTransportServerConfig::cert`]. Use
+    /// [`Self::regenerate_selfsigned`] to replace the certificate before it expires.
+    pub fn cert_not_after(&self) -> Option<SystemTime> {
+        self.cert_reloader.cert_not_after()
+    }
+
+    /// Replaces the server's self-signed certificate with a new one, and returns the new certificate's hash.
+    ///
+    /// See [`WebTransportCertReloader::regenerate_selfsigned`], and [`Self::cert_reloader`] for regenerating after the
+    /// server is moved into a transport.
+    pub fn regenerate_selfsigned(&self) -> Result<ServerCertHash, Error> {
+        self.cert_reloader.regenerate_selfsigned()
+    }
+
+    /// Gets a handle for rotating the server's certificate while it is running.
+    pub fn cert_reloader(&self) -> WebTransportCertReloader {
+        self.cert_reloader.clone()
+    }
+
     /// Disconnects the server.
     // TODO: verify that aborting the endpoint's thread is enough to shut it down properly
     pub fn close(&mut self) {
@@ -287,12 +469,14 @@ impl WebTransportServer {
 
     async fn accept_connection(
         sender: mpsc::Sender<ClientConnectionResult>,
-        endpoint: wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>,
+        endpoint: Arc<wtransport::Endpoint<wtransport::endpoint::endpoint_side::Server>>,
         client_iterator: Arc<AtomicU64>,
         current_clients: Arc<AtomicUsize>,
         connection_req_sender: mpsc::Sender<ConnectionRequest>,
         max_clients: usize,
+        max_connections_per_ip: Option<usize>,
     ) {
+        let ip_counts = IpConnectionCounts::new(max_connections_per_ip);
         loop {
             let incoming_connection = endpoint.accept().await;
 
@@ -307,13 +491,22 @@ impl WebTransportServer {
                 continue;
             }
 
+            // Check for sessions from the same IP.
+            let ip = incoming_connection.remote_address().ip();
+            let Some(ip_guard) = ip_counts.try_add(ip) else {
+                let limit = max_connections_per_ip.unwrap_or_default();
+                warn!("Refusing WebTransport session from {ip}, the IP reached the limit of {limit} sessions");
+                incoming_connection.refuse();
+                continue;
+            };
+
             let sender = sender.clone();
             let client_iterator = client_iterator.clone();
             let connection_req_sender = connection_req_sender.clone();
             tokio::spawn(async move {
                 match incoming_connection.await {
                     Ok(session_request) => {
-                        match Self::handle_session_request(client_iterator, connection_req_sender, session_request).await {
+                        match Self::handle_session_request(client_iterator, connection_req_sender, session_request, ip_guard).await {
                             Ok(maybe_session) => {
                                 if let Some(session) = maybe_session {
                                     if let Err(e) = sender.try_send(session) {
@@ -338,6 +531,7 @@ impl WebTransportServer {
         client_iterator: Arc<AtomicU64>,
         connection_req_sender: mpsc::Sender<ConnectionRequest>,
         session_request: wtransport::endpoint::SessionRequest,
+        ip_guard: IpConnectionGuard,
     ) -> Result<Option<ClientConnectionResult>, wtransport::error::ConnectionError> {
         // Extract the client's first connection request from the request URL.
         //
@@ -373,6 +567,7 @@ impl WebTransportServer {
                 client_idx,
                 client_id,
                 session,
+                ip_guard,
             })),
             Err(err) => {
                 // We must return failure here because `ConnectionRequestResult::Success` means the server
@@ -509,12 +704,13 @@ impl ServerSocket for WebTransportServer {
         // Save new connections.
         while let Ok(connection) = self.connection_receiver.try_recv() {
             // Check if the connection was a success.
-            let (client_idx, client_id, session) = match connection {
+            let (client_idx, client_id, session, ip_guard) = match connection {
                 ClientConnectionResult::Success {
                     client_idx,
                     client_id,
                     session,
-                } => (client_idx, client_id, session),
+                    ip_guard,
+                } => (client_idx, client_id, session, ip_guard),
                 ClientConnectionResult::Failure { client_idx } => {
                     self.lost_clients.insert(client_idx);
                     continue;
@@ -553,6 +749,7 @@ impl ServerSocket for WebTransportServer {
                     abort_sender,
                     reader_thread: thread,
                     client_id,
+                    _ip_guard: ip_guard,
                 },
             );
 
@@ -641,6 +838,7 @@ impl ServerSocket for WebTransportServer {
             let removed_client_id = {
                 if let Some(client_data) = self.clients.remove(&client_idx) {
                     let _ = client_data.abort_sender.send(());
+                    self.closed_connections.push(client_idx_to_addr(client_idx));
                     client_data.client_id
                 } else if let Some(pending_client) = self.pending_clients.remove(&client_idx) {
                     let _ = pending_client.result_sender.try_send(ConnectionRequestResult::Failure);
@@ -661,7 +859,8 @@ impl ServerSocket for WebTransportServer {
             }
         }
 
-        // Note: Lost clients will time out in NetcodeServer and be disconnected in RenetServer that way.
+        // Note: Removed clients are reported by `Self::take_closed_connection` so they can be disconnected from
+        // NetcodeServer and RenetServer immediately.
     }
 
     fn send(&mut self, addr: SocketAddr, packet: &[u8]) -> Result<(), NetcodeTransportError> {
@@ -691,6 +890,10 @@ impl ServerSocket for WebTransportServer {
 
         Ok(())
     }
+
+    fn take_closed_connection(&mut self) -> Option<SocketAddr> {
+        self.closed_connections.pop()
+    }
 }
 
 fn extract_client_connection_req(path: &str) -> Result<Vec<u8>, wtransport::error::ConnectionError> {
@@ -706,3 +909,102 @@ fn extract_client_connection_req(path: &str) -> Result<Vec<u8>, wtransport::erro
 
     Ok(connection_req.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(counts: &IpConnectionCounts, ip: IpAddr) -> usize {
+        counts.counts.lock().unwrap().get(&ip).copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn ip_connection_limit() {
+        let counts = IpConnectionCounts::new(Some(2));
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other_ip: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let first = counts.try_add(ip).unwrap();
+        let _second = counts.try_add(ip).unwrap();
+        assert!(counts.try_add(ip).is_none());
+        assert_eq!(count(&counts, ip), 2);
+
+        // IPv4-mapped IPv6 addresses count against the same IP.
+        assert!(counts.try_add("::ffff:10.0.0.1".parse().unwrap()).is_none());
+
+        // Other IPs are limited separately.
+        let _other = counts.try_add(other_ip).unwrap();
+        assert_eq!(count(&counts, other_ip), 1);
+
+        // Dropping a guard releases its session.
+        drop(first);
+        assert_eq!(count(&counts, ip), 1);
+        let _third = counts.try_add(ip).unwrap();
+        assert!(counts.try_add(ip).is_none());
+    }
+
+    #[test]
+    fn ip_connection_release_on_drop() {
+        let counts = IpConnectionCounts::new(Some(1));
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let guards: Vec<_> = (0..3).filter_map(|_| counts.try_add(ip)).collect();
+        assert_eq!(guards.len(), 1);
+        drop(guards);
+        assert!(counts.counts.lock().unwrap().is_empty());
+
+        // Refused sessions aren't tracked.
+        let zero_limit = IpConnectionCounts::new(Some(0));
+        assert!(zero_limit.try_add(ip).is_none());
+        assert!(zero_limit.counts.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn ip_connection_unlimited() {
+        let counts = IpConnectionCounts::new(None);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let guards: Vec<_> = (0..100).map(|_| counts.try_add(ip).unwrap()).collect();
+        assert_eq!(count(&counts, ip), 100);
+        drop(guards);
+        assert_eq!(count(&counts, ip), 0);
+    }
+
+    #[test]
+    fn regenerate_selfsigned() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (config, hash) = WebTransportServerConfig::new_selfsigned("127.0.0.1:0".parse().unwrap(), 4).unwrap();
+        let server = WebTransportServer::new(config, runtime.handle().clone()).unwrap();
+        let reloader = server.cert_reloader();
+        assert_eq!(reloader.cert_hash().hash, hash.hash);
+
+        // Self-signed certificates are valid for two weeks at most.
+        let not_after = reloader.cert_not_after().unwrap();
+        assert!(not_after > SystemTime::now());
+        assert!(not_after <= SystemTime::now() + Duration::from_secs(14 * 24 * 60 * 60));
+
+        let new_hash = server.regenerate_selfsigned().unwrap();
+        assert_ne!(new_hash.hash, hash.hash);
+        assert_eq!(reloader.cert_hash().hash, new_hash.hash);
+        assert_eq!(server.cert_hash().hash, new_hash.hash);
+        assert!(reloader.cert_not_after().unwrap() >= not_after);
+
+        // The reloader doesn't keep the server alive.
+        drop(server);
+        drop(runtime);
+        assert!(reloader.regenerate_selfsigned().is_err());
+        assert_eq!(reloader.cert_hash().hash, new_hash.hash);
+    }
+
+    #[test]
+    fn regenerate_requires_selfsigned() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (mut config, hash) = WebTransportServerConfig::new_selfsigned("127.0.0.1:0".parse().unwrap(), 4).unwrap();
+        config.selfsigned = None;
+        let server = WebTransportServer::new(config, runtime.handle().clone()).unwrap();
+
+        assert!(server.regenerate_selfsigned().is_err());
+        assert!(server.cert_not_after().is_none());
+        assert_eq!(server.cert_hash().hash, hash.hash);
+    }
+}
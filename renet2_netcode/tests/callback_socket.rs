@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use renet2::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use renet2_netcode::{
+    CallbackSocket, ClientAuthentication, NetcodeClientTransport, NetcodeServerTransport, ServerAuthentication, ServerSetupConfig,
+};
+use renetcode2::NETCODE_MAX_PAYLOAD_BYTES;
+
+const PROTOCOL_ID: u64 = 7;
+const CLIENT_ID: u64 = 42;
+const TICK: Duration = Duration::from_millis(10);
+
+type Queue = Arc<Mutex<VecDeque<Vec<u8>>>>;
+
+/// Makes a socket that sends packets into `outgoing` and receives packets from `incoming`, as if they came from
+/// `peer_addr`.
+fn queue_socket(addr: SocketAddr, peer_addr: SocketAddr, outgoing: Queue, incoming: Queue) -> CallbackSocket {
+    CallbackSocket::new(
+        addr,
+        move |to, packet| {
+            assert_eq!(to, peer_addr);
+            outgoing.lock().unwrap().push_back(packet.to_vec());
+            Ok(())
+        },
+        move |buffer| {
+            let packet = incoming.lock().unwrap().pop_front().ok_or(ErrorKind::WouldBlock)?;
+            buffer[..packet.len()].copy_from_slice(&packet);
+            Ok((packet.len(), peer_addr))
+        },
+    )
+}
+
+/// Connects a client over the sockets and sends a ping to the server, returning the message the server received.
+fn ping(
+    server_addr: SocketAddr,
+    server_socket: CallbackSocket,
+    client_socket: CallbackSocket,
+    max_payload_bytes: Option<usize>,
+) -> (RenetServer, Option<Bytes>) {
+    let server_config = ServerSetupConfig {
+        current_time: Duration::ZERO,
+        max_clients: 1,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![server_addr]],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+    if let Some(max_payload_bytes) = max_payload_bytes {
+        server_transport.set_max_payload_bytes(0, max_payload_bytes).unwrap();
+    }
+
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: PROTOCOL_ID,
+        client_id: CLIENT_ID,
+        socket_id: 0,
+        server_addr,
+        user_data: None,
+    };
+    let mut client = RenetClient::new(ConnectionConfig::test(), false);
+    let mut client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+
+    let mut received = None;
+    for _ in 0..20 {
+        client.update(TICK);
+        client_transport.update(TICK, &mut client).unwrap();
+        if client.is_connected() {
+            client.send_message(DefaultChannel::ReliableOrdered, b"ping".to_vec());
+        }
+        client_transport.send_packets(&mut client).unwrap();
+
+        server.update(TICK);
+        server_transport.update(TICK, &mut server).unwrap();
+        if let Some(message) = server.receive_message(CLIENT_ID, DefaultChannel::ReliableOrdered) {
+            received = Some(message);
+            break;
+        }
+        server_transport.send_packets(&mut server);
+    }
+
+    (server, received)
+}
+
+#[test]
+fn callback_sockets() {
+    let server_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let client_addr: SocketAddr = "127.0.0.1:6000".parse().unwrap();
+    let to_server = Queue::default();
+    let to_client = Queue::default();
+
+    let server_socket = queue_socket(server_addr, client_addr, to_client.clone(), to_server.clone());
+    let client_socket = queue_socket(client_addr, server_addr, to_server, to_client);
+    let (server, received) = ping(server_addr, server_socket, client_socket, None);
+
+    assert_eq!(received.as_deref(), Some(&b"ping"[..]));
+    assert_eq!(server.connected_clients(), 1);
+}
+
+#[test]
+fn callback_sockets_max_packet_bytes() {
+    let server_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let client_addr: SocketAddr = "127.0.0.1:6000".parse().unwrap();
+    let to_server = Queue::default();
+    let to_client = Queue::default();
+
+    // Payload limits above the default are rejected unless the socket can carry the larger packets.
+    let server_socket = queue_socket(server_addr, client_addr, Queue::default(), Queue::default());
+    let server_config = ServerSetupConfig {
+        current_time: Duration::ZERO,
+        max_clients: 1,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![server_addr]],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+    assert!(server_transport.set_max_payload_bytes(0, NETCODE_MAX_PAYLOAD_BYTES + 1).is_err());
+
+    let server_socket = queue_socket(server_addr, client_addr, to_client.clone(), to_server.clone()).with_max_packet_bytes(4000);
+    let client_socket = CallbackSocket::new(
+        client_addr,
+        move |_, packet| {
+            to_server.lock().unwrap().push_back(packet.to_vec());
+            Ok(())
+        },
+        move |buffer| {
+            // The client transport sizes its receive buffer to fit the socket's packets.
+            assert!(buffer.len() >= 4000);
+            let packet = to_client.lock().unwrap().pop_front().ok_or(ErrorKind::WouldBlock)?;
+            buffer[..packet.len()].copy_from_slice(&packet);
+            Ok((packet.len(), server_addr))
+        },
+    )
+    .with_max_packet_bytes(4000);
+    let (server, received) = ping(server_addr, server_socket, client_socket, Some(3900));
+
+    assert_eq!(received.as_deref(), Some(&b"ping"[..]));
+    assert_eq!(server.connected_clients(), 1);
+}
@@ -0,0 +1,242 @@
+#![cfg(feature = "memory_transport")]
+
+use std::{io::ErrorKind, time::Duration};
+
+use renet2::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer, SLICE_SIZE};
+use renet2_netcode::{
+    in_memory_server_addr, new_memory_sockets, ClientAuthentication, ClientSocket, NetcodeClientTransport, NetcodeServerTransport,
+    NetcodeTransportError, ServerAuthentication, ServerSetupConfig, ServerSocket,
+};
+use renetcode2::{NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES};
+
+const PROTOCOL_ID: u64 = 7;
+const TICK: Duration = Duration::from_millis(10);
+
+fn update(server: &mut RenetServer, server_transport: &mut NetcodeServerTransport, clients: &mut [(RenetClient, NetcodeClientTransport)]) {
+    for (client, transport) in clients.iter_mut() {
+        client.update(TICK);
+        let _ = transport.update(TICK, client);
+        let _ = transport.send_packets(client);
+    }
+    server.update(TICK);
+    server_transport.update(TICK, server).unwrap();
+    server_transport.send_packets(server);
+}
+
+fn server_config(max_clients: usize) -> ServerSetupConfig {
+    ServerSetupConfig {
+        current_time: Duration::ZERO,
+        max_clients,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![in_memory_server_addr()]],
+        authentication: ServerAuthentication::Unsecure,
+    }
+}
+
+/// Sets up a server and clients connected over memory sockets with the given buffer capacity, without running the
+/// handshake.
+///
+/// The sockets are reliable unless they have a capacity.
+fn setup(
+    client_ids: Vec<u16>,
+    server_config: ServerSetupConfig,
+    connection_config: ConnectionConfig,
+    capacity: Option<usize>,
+) -> (RenetServer, NetcodeServerTransport, Vec<(RenetClient, NetcodeClientTransport)>) {
+    let reliable = capacity.is_none();
+    let (server_socket, client_sockets) = new_memory_sockets(client_ids, true, reliable, capacity);
+    let server = RenetServer::new(connection_config.clone());
+    let server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+    let clients = client_sockets
+        .into_iter()
+        .map(|socket| {
+            let authentication = ClientAuthentication::Unsecure {
+                protocol_id: PROTOCOL_ID,
+                client_id: socket.id(),
+                socket_id: 0,
+                server_addr: in_memory_server_addr(),
+                user_data: None,
+            };
+            let client = RenetClient::new(connection_config.clone(), reliable);
+            let transport = NetcodeClientTransport::new(Duration::ZERO, authentication, socket).unwrap();
+            (client, transport)
+        })
+        .collect();
+
+    (server, server_transport, clients)
+}
+
+#[cfg(feature = "disconnect_notices")]
+#[test]
+fn broadcast_disconnects() {
+    let (mut server, mut server_transport, mut clients) = setup(vec![1, 2, 3, 4], server_config(4), ConnectionConfig::test(), Some(16));
+    assert!(!server_transport.broadcast_disconnects());
+    server_transport.set_broadcast_disconnects(true);
+
+    for _ in 0..10 {
+        update(&mut server, &mut server_transport, &mut clients);
+    }
+    assert_eq!(server.connected_clients(), 4);
+
+    // Client 1 leaves, the remaining clients are notified.
+    let (_, mut transport) = clients.remove(0);
+    transport.disconnect();
+    for _ in 0..3 {
+        update(&mut server, &mut server_transport, &mut clients);
+    }
+    assert_eq!(server.connected_clients(), 3);
+    for (_, transport) in clients.iter_mut() {
+        assert_eq!(transport.get_peer_disconnected(), Some(1));
+        assert_eq!(transport.get_peer_disconnected(), None);
+    }
+
+    // Client 4 stops receiving until its buffer is full, so the notice of client 2 leaving is dropped.
+    for _ in 0..20 {
+        server.send_message(4, DefaultChannel::Unreliable, vec![0; 10]);
+        server.update(TICK);
+        server_transport.update(TICK, &mut server).unwrap();
+        server_transport.send_packets(&mut server);
+    }
+    let (_, mut transport) = clients.remove(0);
+    transport.disconnect();
+    update(&mut server, &mut server_transport, &mut clients[..1]);
+    server.update(TICK);
+    server_transport.update(TICK, &mut server).unwrap();
+    assert_eq!(server.connected_clients(), 2);
+
+    // The notice is resent until acknowledged, and received once.
+    for _ in 0..30 {
+        update(&mut server, &mut server_transport, &mut clients);
+    }
+    for (_, transport) in clients.iter_mut() {
+        assert_eq!(transport.get_peer_disconnected(), Some(2));
+        assert_eq!(transport.get_peer_disconnected(), None);
+    }
+
+    // Notices are only sent when enabled.
+    server_transport.set_broadcast_disconnects(false);
+    let (_, mut transport) = clients.remove(0);
+    transport.disconnect();
+    for _ in 0..3 {
+        update(&mut server, &mut server_transport, &mut clients);
+    }
+    assert_eq!(server.connected_clients(), 1);
+    assert_eq!(clients[0].1.get_peer_disconnected(), None);
+}
+
+#[test]
+fn bounded_capacity() {
+    let (mut server_socket, mut client_sockets) = new_memory_sockets(vec![1], true, false, Some(2));
+    let mut client_socket = client_sockets.remove(0);
+    let client_addr = ClientSocket::addr(&client_socket).unwrap();
+    let is_would_block = |result| matches!(result, Err(NetcodeTransportError::IO(e)) if e.kind() == ErrorKind::WouldBlock);
+
+    // Sends fail once the buffer is full.
+    client_socket.send(in_memory_server_addr(), &[1]).unwrap();
+    client_socket.send(in_memory_server_addr(), &[2]).unwrap();
+    assert_eq!(client_socket.pending_count(), 2);
+    assert!(is_would_block(client_socket.send(in_memory_server_addr(), &[3])));
+    assert_eq!(client_socket.pending_count(), 2);
+
+    // Receiving frees up space.
+    let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
+    ServerSocket::preupdate(&mut server_socket);
+    assert_eq!(ServerSocket::try_recv(&mut server_socket, &mut buffer).unwrap(), (1, client_addr));
+    assert_eq!(buffer[0], 1);
+    assert_eq!(client_socket.pending_count(), 1);
+    client_socket.send(in_memory_server_addr(), &[4]).unwrap();
+
+    // The server's buffer to the client is bounded too.
+    ServerSocket::send(&mut server_socket, client_addr, &[5]).unwrap();
+    ServerSocket::send(&mut server_socket, client_addr, &[6]).unwrap();
+    assert!(is_would_block(ServerSocket::send(&mut server_socket, client_addr, &[7])));
+}
+
+#[test]
+#[should_panic(expected = "reliable memory sockets can't have a capacity")]
+fn bounded_capacity_reliable() {
+    new_memory_sockets(vec![1], true, true, Some(2));
+}
+
+#[cfg(all(feature = "debug_tools", not(target_family = "wasm")))]
+#[test]
+fn packet_mirror() {
+    use renet2::DefaultChannel;
+    use renet2_netcode::{PacketMirrorConfig, PacketMirrorMode};
+
+    let observer = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    observer.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let mut mirror_config = PacketMirrorConfig::new(observer.local_addr().unwrap());
+    mirror_config.mode = PacketMirrorMode::Decrypted;
+
+    let (server_socket, mut client_sockets) = new_memory_sockets(vec![1], true, true, None);
+    let server_config = ServerSetupConfig {
+        current_time: Duration::ZERO,
+        max_clients: 1,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![in_memory_server_addr()]],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut server_transport = NetcodeServerTransport::new(server_config, server_socket).unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: PROTOCOL_ID,
+        client_id: 1,
+        socket_id: 0,
+        server_addr: in_memory_server_addr(),
+        user_data: None,
+    };
+    let client = RenetClient::new(ConnectionConfig::test(), true);
+    let transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_sockets.remove(0)).unwrap();
+    let mut clients = vec![(client, transport)];
+    for _ in 0..10 {
+        update(&mut server, &mut server_transport, &mut clients);
+    }
+    assert_eq!(server.connected_clients(), 1);
+
+    // Decrypted payloads are prefixed with the client id.
+    server_transport.set_packet_mirror(mirror_config).unwrap();
+    clients[0].0.send_message(DefaultChannel::ReliableOrdered, b"mirrored".to_vec());
+    update(&mut server, &mut server_transport, &mut clients);
+    let mut buffer = [0u8; 1500];
+    let (len, _) = observer.recv_from(&mut buffer).unwrap();
+    assert_eq!(buffer[..8], 1u64.to_le_bytes());
+    assert!(buffer[8..len].windows(8).any(|w| w == b"mirrored"));
+
+    // Nothing is mirrored once the mirror is removed.
+    server_transport.remove_packet_mirror();
+    observer.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    while observer.recv_from(&mut buffer).is_ok() {}
+    clients[0].0.send_message(DefaultChannel::ReliableOrdered, b"private".to_vec());
+    update(&mut server, &mut server_transport, &mut clients);
+    assert!(observer.recv_from(&mut buffer).is_err());
+}
+
+#[test]
+fn socket_max_payload_bytes() {
+    // Renet packets carry slices of up to `slice_size` bytes, so they only fit in small payloads if the slices are small.
+    let send_large_message = |slice_size| {
+        let mut connection_config = ConnectionConfig::test();
+        connection_config.slice_size = slice_size;
+        let (mut server, mut server_transport, mut clients) = setup(vec![1], server_config(1), connection_config, None);
+        server_transport.set_max_payload_bytes(0, 600).unwrap();
+        for _ in 0..10 {
+            update(&mut server, &mut server_transport, &mut clients);
+        }
+        assert_eq!(server.connected_clients(), 1);
+
+        server.send_message(1, DefaultChannel::ReliableOrdered, vec![5; 2000]);
+        for _ in 0..10 {
+            update(&mut server, &mut server_transport, &mut clients);
+        }
+        clients[0].0.receive_message(DefaultChannel::ReliableOrdered)
+    };
+
+    assert_eq!(send_large_message(SLICE_SIZE), None);
+    assert_eq!(send_large_message(500).as_deref(), Some(&[5; 2000][..]));
+
+    // Memory sockets can't carry packets larger than the default.
+    let (_, mut server_transport, _) = setup(vec![1], server_config(1), ConnectionConfig::test(), None);
+    server_transport.set_max_payload_bytes(0, NETCODE_MAX_PAYLOAD_BYTES).unwrap();
+    assert!(server_transport.set_max_payload_bytes(0, NETCODE_MAX_PAYLOAD_BYTES + 1).is_err());
+}
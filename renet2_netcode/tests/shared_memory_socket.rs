@@ -0,0 +1,131 @@
+#![cfg(all(feature = "shared_memory_transport", not(target_family = "wasm")))]
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+use renet2::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent};
+use renet2_netcode::{
+    shared_memory_server_addr, ClientAuthentication, ClientSocket, NetcodeClientTransport, NetcodeServerTransport, ServerAuthentication,
+    ServerSetupConfig, SharedMemorySocket, SocketKind,
+};
+
+const PROTOCOL_ID: u64 = 7;
+const CLIENT_ID: u64 = 42;
+const TIMEOUT: Duration = Duration::from_secs(10);
+const TICK: Duration = Duration::from_millis(5);
+
+/// Set in the child process to the shared memory file it should connect through.
+const CLIENT_PATH_VAR: &str = "RENET2_SHARED_MEMORY_CLIENT_PATH";
+
+fn current_time() -> Duration {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap()
+}
+
+/// Connects to the server, sends "ping", and waits for "pong".
+fn run_client(path: &Path) {
+    let socket = SharedMemorySocket::open_client(path).unwrap();
+    assert!(!ClientSocket::is_encrypted(&socket));
+    let mut client = RenetClient::new(ConnectionConfig::test(), ClientSocket::is_reliable(&socket));
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: PROTOCOL_ID,
+        client_id: CLIENT_ID,
+        socket_id: 0,
+        server_addr: shared_memory_server_addr(),
+        user_data: None,
+    };
+    let mut transport = NetcodeClientTransport::new(current_time(), authentication, socket).unwrap();
+    assert_eq!(transport.active_transport(), SocketKind::SharedMemory);
+
+    let start = Instant::now();
+    let mut sent = false;
+    loop {
+        assert!(start.elapsed() < TIMEOUT, "client timed out");
+
+        client.update(TICK);
+        transport.update(TICK, &mut client).unwrap();
+        if client.is_connected() && !sent {
+            client.send_message(DefaultChannel::ReliableOrdered, b"ping".to_vec());
+            sent = true;
+        }
+        if let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
+            assert_eq!(&message[..], b"pong");
+            break;
+        }
+        transport.send_packets(&mut client).unwrap();
+        std::thread::sleep(TICK);
+    }
+
+    // Flush the final acks before disconnecting.
+    transport.send_packets(&mut client).unwrap();
+    transport.disconnect();
+}
+
+#[test]
+fn cross_process() {
+    if let Ok(path) = std::env::var(CLIENT_PATH_VAR) {
+        run_client(Path::new(&path));
+        return;
+    }
+
+    let path = std::env::temp_dir().join(format!("renet2_shared_memory_{}", std::process::id()));
+    let socket = SharedMemorySocket::create_server(&path, false).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+    let server_config = ServerSetupConfig {
+        current_time: current_time(),
+        max_clients: 1,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![shared_memory_server_addr()]],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    let mut server = RenetServer::new(ConnectionConfig::test());
+    let mut transport = NetcodeServerTransport::new(server_config, socket).unwrap();
+
+    // Run this test again in a child process, which acts as the client.
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args(["cross_process", "--exact", "--nocapture"])
+        .env(CLIENT_PATH_VAR, &path)
+        .spawn()
+        .unwrap();
+
+    let start = Instant::now();
+    let mut connected = false;
+    let mut received = false;
+    loop {
+        assert!(start.elapsed() < TIMEOUT, "server timed out");
+
+        server.update(TICK);
+        transport.update(TICK, &mut server).unwrap();
+        while let Some(event) = server.get_event() {
+            match event {
+                ServerEvent::ClientConnected { client_id } => {
+                    assert_eq!(client_id, CLIENT_ID);
+                    connected = true;
+                }
+                ServerEvent::ClientDisconnected { client_id, .. } => {
+                    assert_eq!(client_id, CLIENT_ID);
+                    assert!(received);
+                }
+            }
+        }
+        if let Some(message) = server.receive_message(CLIENT_ID, DefaultChannel::ReliableOrdered) {
+            assert_eq!(&message[..], b"ping");
+            server.send_message(CLIENT_ID, DefaultChannel::ReliableOrdered, b"pong".to_vec());
+            received = true;
+        }
+        transport.send_packets(&mut server);
+
+        if let Some(status) = child.try_wait().unwrap() {
+            assert!(status.success(), "client process failed");
+            break;
+        }
+        std::thread::sleep(TICK);
+    }
+
+    assert!(connected && received);
+    std::fs::remove_file(&path).unwrap();
+}
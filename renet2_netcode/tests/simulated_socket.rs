@@ -0,0 +1,141 @@
+#![cfg(all(feature = "memory_transport", feature = "debug_tools", not(target_family = "wasm")))]
+
+use std::time::Duration;
+
+use renet2::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use renet2_netcode::{
+    in_memory_server_addr, new_memory_sockets, ClientAuthentication, ClientSocket, NetcodeClientTransport, NetcodeServerTransport,
+    ServerAuthentication, ServerSetupConfig, SimulatedConditions, SimulatedSocket,
+};
+
+const PROTOCOL_ID: u64 = 7;
+const CLIENT_ID: u64 = 1;
+const TICK: Duration = Duration::from_millis(5);
+
+fn setup(
+    server_conditions: SimulatedConditions,
+    client_conditions: SimulatedConditions,
+) -> (RenetServer, NetcodeServerTransport, RenetClient, NetcodeClientTransport) {
+    setup_with(server_conditions, client_conditions, false)
+}
+
+fn setup_with(
+    server_conditions: SimulatedConditions,
+    client_conditions: SimulatedConditions,
+    reliable: bool,
+) -> (RenetServer, NetcodeServerTransport, RenetClient, NetcodeClientTransport) {
+    let (server_socket, mut client_sockets) = new_memory_sockets(vec![CLIENT_ID as u16], true, reliable, None);
+    let server_config = ServerSetupConfig {
+        current_time: Duration::ZERO,
+        max_clients: 1,
+        protocol_id: PROTOCOL_ID,
+        socket_addresses: vec![vec![in_memory_server_addr()]],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    let server = RenetServer::new(ConnectionConfig::test());
+    let server_transport = NetcodeServerTransport::new(server_config, SimulatedSocket::new(server_socket, server_conditions)).unwrap();
+
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: PROTOCOL_ID,
+        client_id: CLIENT_ID,
+        socket_id: 0,
+        server_addr: in_memory_server_addr(),
+        user_data: None,
+    };
+    let client_socket = SimulatedSocket::new(client_sockets.remove(0), client_conditions);
+    let client = RenetClient::new(ConnectionConfig::test(), client_socket.is_reliable());
+    let client_transport = NetcodeClientTransport::new(Duration::ZERO, authentication, client_socket).unwrap();
+
+    (server, server_transport, client, client_transport)
+}
+
+fn update(
+    server: &mut RenetServer,
+    server_transport: &mut NetcodeServerTransport,
+    client: &mut RenetClient,
+    transport: &mut NetcodeClientTransport,
+) {
+    client.update(TICK);
+    transport.update(TICK, client).unwrap();
+    transport.send_packets(client).unwrap();
+    server.update(TICK);
+    server_transport.update(TICK, server).unwrap();
+    server_transport.send_packets(server);
+}
+
+#[test]
+fn latency() {
+    let conditions = SimulatedConditions {
+        extra_latency: Duration::from_millis(20),
+        ..Default::default()
+    };
+    let (mut server, mut server_transport, mut client, mut transport) = setup(SimulatedConditions::default(), conditions);
+
+    // The handshake takes two round trips, each delayed on send and receive.
+    let mut ticks = 0;
+    while !client.is_connected() {
+        assert!(ticks < 100, "client timed out");
+        update(&mut server, &mut server_transport, &mut client, &mut transport);
+        ticks += 1;
+    }
+    assert!(TICK * ticks >= Duration::from_millis(80));
+}
+
+#[test]
+fn packet_loss() {
+    lossy_reliable_messages(false);
+}
+
+#[test]
+fn packet_loss_reliable_socket() {
+    // Loss and jitter break the guarantees of the wrapped socket, so renet2 must handle reliability itself.
+    lossy_reliable_messages(true);
+}
+
+fn lossy_reliable_messages(reliable: bool) {
+    let conditions = |seed| SimulatedConditions {
+        jitter: Duration::from_millis(10),
+        loss_ratio: 0.2,
+        seed,
+        ..Default::default()
+    };
+    let (mut server, mut server_transport, mut client, mut transport) = setup_with(conditions(1), conditions(2), reliable);
+    assert!(!transport.is_reliable());
+
+    let mut ticks = 0;
+    while !client.is_connected() {
+        assert!(ticks < 1000, "client timed out");
+        update(&mut server, &mut server_transport, &mut client, &mut transport);
+        ticks += 1;
+    }
+
+    // Reliable messages are resent until they get through.
+    for i in 0..10u8 {
+        client.send_message(DefaultChannel::ReliableOrdered, vec![i]);
+    }
+    let mut received = Vec::new();
+    while received.len() < 10 {
+        assert!(ticks < 2000, "messages were not received");
+        update(&mut server, &mut server_transport, &mut client, &mut transport);
+        ticks += 1;
+        while let Some(message) = server.receive_message(CLIENT_ID, DefaultChannel::ReliableOrdered) {
+            received.push(message[0]);
+        }
+    }
+    assert_eq!(received, (0..10).collect::<Vec<u8>>());
+}
+
+#[test]
+fn total_loss() {
+    let conditions = SimulatedConditions {
+        loss_ratio: 1.0,
+        ..Default::default()
+    };
+    let (mut server, mut server_transport, mut client, mut transport) = setup(SimulatedConditions::default(), conditions);
+
+    for _ in 0..20 {
+        update(&mut server, &mut server_transport, &mut client, &mut transport);
+    }
+    assert!(client.is_connecting());
+    assert_eq!(server.connected_clients(), 0);
+}
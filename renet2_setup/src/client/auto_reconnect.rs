@@ -0,0 +1,246 @@
+use bevy_app::{App, Last, Plugin};
+use bevy_ecs::prelude::*;
+use bevy_time::{Real, Time};
+use renet2::{ConnectionConfig, DisconnectReason, RenetClient};
+
+use std::time::Duration;
+
+use crate::{setup_renet2_client_in_bevy, ClientConnectPack};
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Backoff policy used by [`RenetAutoReconnectPlugin`].
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// The maximum number of consecutive reconnect attempts.
+    ///
+    /// Set it to `None` to retry forever. Defaults to `Some(5)`.
+    pub max_attempts: Option<u32>,
+    /// Delay before the first reconnect attempt. Defaults to 1 second.
+    pub initial_delay: Duration,
+    /// Upper bound on the delay between reconnect attempts. Defaults to 30 seconds.
+    pub max_delay: Duration,
+    /// Factor applied to the delay after each failed attempt. Defaults to `2.0`.
+    pub backoff_multiplier: f32,
+}
+
+impl ReconnectPolicy {
+    /// Returns the delay before the next reconnect attempt when `attempts` attempts have already been made.
+    ///
+    /// Multipliers below `1.0` (or NaN) are treated as `1.0`, and delays that overflow are clamped to
+    /// [`Self::max_delay`].
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = (self.backoff_multiplier as f64).max(1.0).powi(attempts.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor.min(f64::MAX))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(5),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            backoff_multiplier: 2.0,
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Resource that stores the information [`RenetAutoReconnectPlugin`] needs to reconnect a client.
+///
+/// Insert this resource after setting up a client to enable auto-reconnect, and remove it to stop reconnecting
+/// (e.g. when returning to a main menu).
+///
+/// Since [`ClientConnectPack`]s are single-use, reconnecting calls a user-provided callback to get a fresh connect
+/// pack for each attempt. The callback may return a stored pack if its connect token is still valid, or request a
+/// new token from your backend.
+#[derive(Resource)]
+pub struct RenetReconnect {
+    connection_config: ConnectionConfig,
+    connect_pack_fn: Box<dyn FnMut() -> Result<ClientConnectPack, String> + Send + Sync + 'static>,
+    policy: ReconnectPolicy,
+    attempts: u32,
+    next_attempt: Option<Duration>,
+}
+
+impl RenetReconnect {
+    /// Makes a new reconnect resource.
+    pub fn new(
+        connection_config: ConnectionConfig,
+        policy: ReconnectPolicy,
+        connect_pack_fn: impl FnMut() -> Result<ClientConnectPack, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            connection_config,
+            connect_pack_fn: Box::new(connect_pack_fn),
+            policy,
+            attempts: 0,
+            next_attempt: None,
+        }
+    }
+
+    /// Returns the reconnect policy.
+    pub fn policy(&self) -> &ReconnectPolicy {
+        &self.policy
+    }
+
+    /// Returns the number of reconnect attempts made since the client was last connected.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns `true` if [`ReconnectPolicy::max_attempts`] was reached without reconnecting.
+    pub fn is_exhausted(&self) -> bool {
+        self.policy.max_attempts.is_some_and(|max| self.attempts >= max)
+    }
+
+    /// Resets the attempt counter so reconnecting can start over after being exhausted.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.next_attempt = None;
+    }
+}
+
+impl std::fmt::Debug for RenetReconnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenetReconnect")
+            .field("connection_config", &self.connection_config)
+            .field("policy", &self.policy)
+            .field("attempts", &self.attempts)
+            .field("next_attempt", &self.next_attempt)
+            .finish()
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Automatically reconnects a disconnected [`RenetClient`] when the [`RenetReconnect`] resource exists.
+///
+/// Reconnect attempts are spaced out according to [`ReconnectPolicy`] and use
+/// [`setup_renet2_client_in_bevy`] to replace the client and transport resources. Clients that disconnected
+/// themselves (see [`DisconnectReason::DisconnectedByClient`]) are not reconnected.
+///
+/// Requires `bevy_time::TimePlugin`.
+pub struct RenetAutoReconnectPlugin;
+
+impl Plugin for RenetAutoReconnectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Last,
+            auto_reconnect_system
+                .run_if(resource_exists::<RenetReconnect>)
+                .run_if(resource_exists::<RenetClient>),
+        );
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+fn auto_reconnect_system(world: &mut World) {
+    let delta = world.resource::<Time<Real>>().delta();
+    let client = world.resource::<RenetClient>();
+    let is_connected = client.is_connected();
+    let disconnect_reason = client.disconnect_reason();
+
+    world.resource_scope(|world, mut reconnect: Mut<RenetReconnect>| {
+        if is_connected {
+            reconnect.reset();
+            return;
+        }
+        let Some(reason) = disconnect_reason else {
+            // Still connecting.
+            return;
+        };
+        if matches!(reason, DisconnectReason::DisconnectedByClient) || reconnect.is_exhausted() {
+            return;
+        }
+
+        let Some(remaining) = reconnect.next_attempt else {
+            let delay = reconnect.policy.delay(reconnect.attempts);
+            log::info!("renet2 client disconnected ({reason}), reconnecting in {delay:?}");
+            reconnect.next_attempt = Some(delay);
+            return;
+        };
+        if remaining > delta {
+            reconnect.next_attempt = Some(remaining - delta);
+            return;
+        }
+
+        // Make the attempt. If it fails then the client will stay disconnected and the next attempt will be
+        // scheduled.
+        reconnect.next_attempt = None;
+        reconnect.attempts += 1;
+        let attempt = reconnect.attempts;
+        let connection_config = reconnect.connection_config.clone();
+        let result =
+            (reconnect.connect_pack_fn)().and_then(|connect_pack| setup_renet2_client_in_bevy(world, connection_config, connect_pack));
+
+        match result {
+            Ok(()) => log::info!("renet2 client reconnect attempt {attempt}"),
+            Err(err) => {
+                log::warn!("renet2 client reconnect attempt {attempt} failed: {err}");
+                if reconnect.is_exhausted() {
+                    log::warn!("renet2 client reconnect attempts exhausted");
+                }
+            }
+        }
+    });
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delay() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_secs(1));
+        assert_eq!(policy.delay(1), Duration::from_secs(2));
+        assert_eq!(policy.delay(4), Duration::from_secs(16));
+
+        // Many attempts are clamped to the max delay.
+        assert_eq!(policy.delay(5), Duration::from_secs(30));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn reconnect_delay_invalid_multiplier() {
+        for backoff_multiplier in [0.5, -2.0, 0.0, f32::NAN] {
+            let policy = ReconnectPolicy {
+                backoff_multiplier,
+                ..Default::default()
+            };
+            assert_eq!(policy.delay(0), Duration::from_secs(1));
+            assert_eq!(policy.delay(10), Duration::from_secs(1));
+        }
+
+        let policy = ReconnectPolicy {
+            backoff_multiplier: f32::INFINITY,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn reconnect_delay_overflow() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::MAX,
+            max_delay: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(0), Duration::MAX);
+        assert_eq!(policy.delay(u32::MAX), Duration::MAX);
+
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(u32::MAX), Duration::ZERO);
+    }
+}
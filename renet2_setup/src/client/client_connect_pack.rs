@@ -1,15 +1,92 @@
-use crate::{client_address_from_server_address, connect_token_from_bytes, ServerConnectToken};
+use crate::{client_address_from_server_address, connect_token_from_bytes, ConnectionType, ServerConnectToken};
 
-use renet2_netcode::ClientAuthentication;
+use renet2_netcode::{ClientAuthentication, ConnectToken, NetcodeError};
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Error returned by [`ClientConnectPack::new`] and [`ClientConnectPack::validate_freshness`].
+#[derive(Debug)]
+pub enum ClientConnectPackError {
+    /// The connect token's protocol id doesn't match the expected protocol id.
+    ///
+    /// The client and server are likely running different versions.
+    ProtocolMismatch { expected: u64, found: u64 },
+    /// The connect token doesn't contain a server address.
+    MissingServerAddress,
+    /// The token's transport can't be used on this platform or with the enabled features.
+    TransportUnsupported(ConnectionType),
+    /// The connect token could not be deserialized.
+    DeserializationFailed(NetcodeError),
+    /// The connect token expired, the server will deny connection requests that use it.
+    ///
+    /// Timestamps are in seconds since the unix epoch.
+    TokenExpired { expire_timestamp: u64, current_time: u64 },
+}
+
+impl std::fmt::Display for ClientConnectPackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ProtocolMismatch { expected, found } => {
+                write!(f, "protocol id mismatch (expected {expected}, found {found})")
+            }
+            Self::MissingServerAddress => write!(f, "server address is missing"),
+            Self::TransportUnsupported(ConnectionType::WasmWt) => write!(
+                f,
+                "ServerConnectToken::WasmWt can only be converted to ClientConnectPack in WASM with \
+                wt_client_transport feature"
+            ),
+            Self::TransportUnsupported(ConnectionType::WasmWs) => write!(
+                f,
+                "ServerConnectToken::WasmWs can only be converted to ClientConnectPack in WASM with \
+                ws_client_transport feature"
+            ),
+            Self::TransportUnsupported(connection_type) => {
+                write!(f, "{connection_type:?} connect tokens are not supported")
+            }
+            Self::DeserializationFailed(err) => write!(f, "failed deserializing connect token: {err:?}"),
+            Self::TokenExpired {
+                expire_timestamp,
+                current_time,
+            } => {
+                write!(
+                    f,
+                    "connect token expired (expired at {expire_timestamp}, current time {current_time})"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientConnectPackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DeserializationFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Extracts a renet2 [`ConnectToken`] and validates its protocol id.
+fn extract_connect_token(expected_protocol_id: u64, token: &[u8]) -> Result<ConnectToken, ClientConnectPackError> {
+    let connect_token = connect_token_from_bytes(token).map_err(ClientConnectPackError::DeserializationFailed)?;
+    if connect_token.protocol_id != expected_protocol_id {
+        return Err(ClientConnectPackError::ProtocolMismatch {
+            expected: expected_protocol_id,
+            found: connect_token.protocol_id,
+        });
+    }
+
+    Ok(connect_token)
+}
 
 //-------------------------------------------------------------------------------------------------------------------
 
 /// Information needed to connect a renet2 client to a renet2 server.
 ///
 /// Connect packs should be considered single-use. If you need to reconnect, make a new connect pack with fresh
-/// client authentication.
+/// client authentication, or see [`Self::clone_for_reconnect`] for native connections.
 ///
 /// Implements `Resource` when the `bevy` feature is enabled.
 #[derive(Debug)]
@@ -30,21 +107,62 @@ pub enum ClientConnectPack {
 }
 
 impl ClientConnectPack {
+    /// Returns the type of connection this pack will make.
+    pub fn connection_type(&self) -> ConnectionType {
+        match self {
+            Self::Native(..) => ConnectionType::Native,
+            #[cfg(all(target_family = "wasm", feature = "wt_client_transport"))]
+            Self::WasmWt(..) => ConnectionType::WasmWt,
+            #[cfg(all(target_family = "wasm", feature = "ws_client_transport"))]
+            Self::WasmWs(..) => ConnectionType::WasmWs,
+            #[cfg(feature = "memory_transport")]
+            Self::Memory(..) => ConnectionType::Memory,
+        }
+    }
+
     /// Make a new connect pack from a server connect token.
-    pub fn new(expected_protocol_id: u64, token: ServerConnectToken) -> Result<Self, String> {
+    ///
+    /// Native connections use the first server address in the token. See [`Self::new_with_address_filter`] to choose
+    /// between the IPv4 and IPv6 addresses of a dual-stack server.
+    pub fn new(expected_protocol_id: u64, token: ServerConnectToken) -> Result<Self, ClientConnectPackError> {
+        Self::new_with_address_filter(expected_protocol_id, token, |_| true)
+    }
+
+    /// Make a new connect pack from a server connect token, connecting native clients to the first server address
+    /// accepted by `filter`.
+    ///
+    /// Falls back to the first server address if `filter` rejects all of them. Only the token's addresses in the same
+    /// family (IPv4/IPv6) as the chosen address are kept, since the client socket can only send to addresses of the
+    /// family it's bound to. For example, pass [`is_server_address_reachable`](crate::is_server_address_reachable)
+    /// to use the first address family the local network stack can reach.
+    pub fn new_with_address_filter(
+        expected_protocol_id: u64,
+        token: ServerConnectToken,
+        filter: impl Fn(&SocketAddr) -> bool,
+    ) -> Result<Self, ClientConnectPackError> {
         match token {
             ServerConnectToken::Native { token } => {
                 // Extract renet2 ConnectToken.
-                let connect_token =
-                    connect_token_from_bytes(&token).map_err(|err| format!("failed deserializing connect token: {err:?}"))?;
-                if connect_token.protocol_id != expected_protocol_id {
-                    return Err(String::from("protocol id mismatch"));
-                }
+                let mut connect_token = extract_connect_token(expected_protocol_id, &token)?;
 
                 // prepare client address based on server address
-                let Some(server_addr) = connect_token.server_addresses[0] else {
-                    return Err(String::from("server address is missing"));
+                let Some(first_addr) = connect_token.server_addresses[0] else {
+                    return Err(ClientConnectPackError::MissingServerAddress);
                 };
+
+                let server_addr = connect_token
+                    .server_addresses
+                    .iter()
+                    .flatten()
+                    .find(|addr| filter(addr))
+                    .copied()
+                    .unwrap_or(first_addr);
+                let mut addresses = connect_token
+                    .server_addresses
+                    .into_iter()
+                    .flatten()
+                    .filter(|addr| addr.is_ipv4() == server_addr.is_ipv4());
+                connect_token.server_addresses = std::array::from_fn(|_| addresses.next());
                 let client_address = client_address_from_server_address(&server_addr);
 
                 Ok(Self::Native(ClientAuthentication::Secure { connect_token }, client_address))
@@ -54,15 +172,11 @@ impl ClientConnectPack {
                 #[cfg(all(target_family = "wasm", feature = "wt_client_transport"))]
                 {
                     // Extract renet2 ConnectToken.
-                    let connect_token =
-                        connect_token_from_bytes(&token).map_err(|err| format!("failed deserializing connect token: {err:?}"))?;
-                    if connect_token.protocol_id != expected_protocol_id {
-                        return Err(String::from("protocol id mismatch"));
-                    }
+                    let connect_token = extract_connect_token(expected_protocol_id, &token)?;
 
                     // prepare client config based on server address
                     let Some(server_addr) = connect_token.server_addresses[0] else {
-                        return Err(String::from("server address is missing"));
+                        return Err(ClientConnectPackError::MissingServerAddress);
                     };
                     let config = renet2_netcode::WebTransportClientConfig::new_with_certs(server_addr, cert_hashes);
 
@@ -70,52 +184,300 @@ impl ClientConnectPack {
                 }
 
                 #[cfg(not(all(target_family = "wasm", feature = "wt_client_transport")))]
-                return Err(
-                    "ServerConnectToken::WasmWt can only be converted to ClientConnectPack in WASM with \
-                    wt_client_transport feature"
-                        .to_string(),
-                );
+                return Err(ClientConnectPackError::TransportUnsupported(ConnectionType::WasmWt));
             }
             #[allow(unused_variables)]
             ServerConnectToken::WasmWs { token, url } => {
                 #[cfg(all(target_family = "wasm", feature = "ws_client_transport"))]
                 {
                     // Extract renet2 ConnectToken.
-                    let connect_token =
-                        connect_token_from_bytes(&token).map_err(|err| format!("failed deserializing connect token: {err:?}"))?;
-                    if connect_token.protocol_id != expected_protocol_id {
-                        return Err(String::from("protocol id mismatch"));
-                    }
+                    let connect_token = extract_connect_token(expected_protocol_id, &token)?;
 
                     // prepare client config based on server url
                     if connect_token.server_addresses[0].is_none() {
-                        return Err(String::from("server address is missing"));
+                        return Err(ClientConnectPackError::MissingServerAddress);
+                    };
+                    let config = renet2_netcode::WebSocketClientConfig {
+                        server_url: url,
+                        ws_ping_interval: renet2_netcode::ws_ping_interval_from_timeout(connect_token.timeout_seconds),
                     };
-                    let config = renet2_netcode::WebSocketClientConfig { server_url: url };
 
                     return Ok(Self::WasmWs(ClientAuthentication::Secure { connect_token }, config));
                 }
 
                 #[cfg(not(all(target_family = "wasm", feature = "ws_client_transport")))]
-                return Err(
-                    "ServerConnectToken::WasmWs can only be converted to ClientConnectPack in WASM with \
-                    ws_client_transport feature"
-                        .to_string(),
-                );
+                return Err(ClientConnectPackError::TransportUnsupported(ConnectionType::WasmWs));
             }
             #[cfg(feature = "memory_transport")]
             ServerConnectToken::Memory { token, client } => {
                 // Extract renet2 ConnectToken.
-                let connect_token =
-                    connect_token_from_bytes(&token).map_err(|err| format!("failed deserializing connect token: {err:?}"))?;
-                if connect_token.protocol_id != expected_protocol_id {
-                    return Err(String::from("protocol id mismatch"));
-                }
+                let connect_token = extract_connect_token(expected_protocol_id, &token)?;
 
                 Ok(Self::Memory(ClientAuthentication::Secure { connect_token }, client))
             }
         }
     }
+
+    /// Makes a copy of a native connect pack to reconnect with the same connect token, avoiding a round-trip to
+    /// the token provider.
+    ///
+    /// Returns `None` if the token expired, or for transports where reusing a token isn't supported (WASM and
+    /// in-memory). Unsecure packs are always reusable.
+    ///
+    /// The server binds a connect token to the first client address that uses it, and denies the token from
+    /// other addresses until it expires. The returned pack binds the new client to `prev_addr`, which should be the
+    /// local address of the previous connection (see `NetcodeClientTransport::addr`). Drop the previous transport
+    /// first so the address can be bound again. A fresh token is needed if the client's public address changed (e.g.
+    /// the client switched networks).
+    pub fn clone_for_reconnect(&self, prev_addr: SocketAddr) -> Option<Self> {
+        let Self::Native(authentication, _) = self else {
+            return None;
+        };
+        let current_time = wasm_timer::SystemTime::now()
+            .duration_since(wasm_timer::SystemTime::UNIX_EPOCH)
+            .ok()?;
+        self.validate_freshness(current_time).ok()?;
+
+        Some(Self::Native(authentication.clone(), prev_addr))
+    }
+
+    /// Checks that the pack's connect token has not expired.
+    ///
+    /// The server denies expired tokens, so connecting with one fails with `DisconnectReason::ConnectTokenExpired`
+    /// after a round-trip to the server, or times out if the server doesn't rate limit connection requests. Call this before setting up a client to detect stale tokens early (e.g. a
+    /// token that was cached by a lobby or that waited too long in a queue) and request a new one.
+    ///
+    /// `current_time` is the duration since the unix epoch. Always succeeds for unsecure authentication.
+    pub fn validate_freshness(&self, current_time: Duration) -> Result<(), ClientConnectPackError> {
+        let authentication = match self {
+            Self::Native(authentication, _) => authentication,
+            #[cfg(all(target_family = "wasm", feature = "wt_client_transport"))]
+            Self::WasmWt(authentication, _) => authentication,
+            #[cfg(all(target_family = "wasm", feature = "ws_client_transport"))]
+            Self::WasmWs(authentication, _) => authentication,
+            #[cfg(feature = "memory_transport")]
+            Self::Memory(authentication, _) => authentication,
+        };
+        let ClientAuthentication::Secure { connect_token } = authentication else {
+            return Ok(());
+        };
+
+        // Matches the server's expiry check.
+        if current_time.as_secs() >= connect_token.expire_timestamp {
+            return Err(ClientConnectPackError::TokenExpired {
+                expire_timestamp: connect_token.expire_timestamp,
+                current_time: current_time.as_secs(),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connect_token_to_bytes, ConnectMetaNative};
+
+    fn native_token_bytes() -> (u64, Vec<u8>) {
+        let meta = ConnectMetaNative::dummy();
+        let ServerConnectToken::Native { token } = meta.new_connect_token(Duration::from_secs(1), 0).unwrap() else {
+            panic!("expected native token");
+        };
+        (meta.server_config.protocol_id, token)
+    }
+
+    #[test]
+    fn protocol_mismatch() {
+        let (protocol_id, token) = native_token_bytes();
+        let err = ClientConnectPack::new(protocol_id + 1, ServerConnectToken::Native { token }).unwrap_err();
+        assert!(matches!(
+            err,
+            ClientConnectPackError::ProtocolMismatch { expected, found } if expected == protocol_id + 1 && found == protocol_id
+        ));
+    }
+
+    #[test]
+    fn missing_server_address() {
+        let (protocol_id, token) = native_token_bytes();
+        let mut connect_token = connect_token_from_bytes(&token).unwrap();
+        connect_token.server_addresses[0] = None;
+        let token = connect_token_to_bytes(&connect_token).unwrap();
+        let err = ClientConnectPack::new(protocol_id, ServerConnectToken::Native { token }).unwrap_err();
+        assert!(matches!(err, ClientConnectPackError::MissingServerAddress));
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn dual_stack_addresses() {
+        let mut meta = ConnectMetaNative::dummy();
+        meta.server_addresses.push("[::1]:8080".parse().unwrap());
+        let ServerConnectToken::Native { token } = meta.new_connect_token(Duration::from_secs(1), 0).unwrap() else {
+            panic!("expected native token");
+        };
+
+        let protocol_id = meta.server_config.protocol_id;
+        let native_pack = |filter: fn(&SocketAddr) -> bool| {
+            let pack = ClientConnectPack::new_with_address_filter(protocol_id, ServerConnectToken::Native { token: token.clone() }, filter);
+            let ClientConnectPack::Native(ClientAuthentication::Secure { connect_token }, client_address) = pack.unwrap() else {
+                panic!("expected native pack");
+            };
+            (connect_token.server_addresses, client_address)
+        };
+
+        // Only addresses of the chosen family are kept.
+        let (addresses, client_address) = native_pack(|addr| addr.is_ipv6());
+        assert_eq!(addresses[0], Some(meta.server_addresses[1]));
+        assert_eq!(client_address, client_address_from_server_address(&meta.server_addresses[1]));
+        assert!(addresses[1].is_none());
+
+        // The first address is used by default, or if no address is accepted.
+        for filter in [(|_| true) as fn(&SocketAddr) -> bool, |_| false] {
+            let (addresses, client_address) = native_pack(filter);
+            assert_eq!(addresses[0], Some(meta.server_addresses[0]));
+            assert_eq!(client_address, client_address_from_server_address(&meta.server_addresses[0]));
+            assert!(addresses[1].is_none());
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn transport_unsupported() {
+        let (protocol_id, token) = native_token_bytes();
+        let wt_token = ServerConnectToken::WasmWt {
+            token: token.clone(),
+            cert_hashes: Vec::default(),
+        };
+        let err = ClientConnectPack::new(protocol_id, wt_token).unwrap_err();
+        assert!(matches!(err, ClientConnectPackError::TransportUnsupported(ConnectionType::WasmWt)));
+
+        let url = url::Url::parse("ws://127.0.0.1:8080").unwrap();
+        let err = ClientConnectPack::new(protocol_id, ServerConnectToken::WasmWs { token, url }).unwrap_err();
+        assert!(matches!(err, ClientConnectPackError::TransportUnsupported(ConnectionType::WasmWs)));
+    }
+
+    #[test]
+    fn deserialization_failed() {
+        let (protocol_id, _) = native_token_bytes();
+        let err = ClientConnectPack::new(protocol_id, ServerConnectToken::Native { token: vec![0u8; 3] }).unwrap_err();
+        assert!(matches!(err, ClientConnectPackError::DeserializationFailed(_)));
+        assert!(err.to_string().starts_with("failed deserializing connect token"));
+    }
+
+    #[test]
+    fn clone_for_reconnect() {
+        let meta = ConnectMetaNative::dummy();
+        let current_time = wasm_timer::SystemTime::now()
+            .duration_since(wasm_timer::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let protocol_id = meta.server_config.protocol_id;
+        let token = meta.new_connect_token(current_time, 0).unwrap();
+        let pack = ClientConnectPack::new(protocol_id, token).unwrap();
+
+        let ClientConnectPack::Native(ClientAuthentication::Secure { connect_token }, _) = &pack else {
+            panic!("expected native pack");
+        };
+        let prev_addr: SocketAddr = "127.0.0.1:5555".parse().unwrap();
+        let Some(ClientConnectPack::Native(ClientAuthentication::Secure { connect_token: reused }, reused_address)) =
+            pack.clone_for_reconnect(prev_addr)
+        else {
+            panic!("expected native pack");
+        };
+        assert_eq!(&reused, connect_token);
+        assert_eq!(reused_address, prev_addr);
+
+        // Expired tokens can't be reused.
+        let token = meta.new_connect_token(Duration::from_secs(1), 0).unwrap();
+        let pack = ClientConnectPack::new(protocol_id, token).unwrap();
+        assert!(pack.clone_for_reconnect(prev_addr).is_none());
+    }
+
+    #[cfg(all(not(target_family = "wasm"), feature = "native_transport"))]
+    #[test]
+    fn reconnect_to_server() {
+        use renet2::{ConnectionConfig, RenetServer};
+        use renet2_netcode::{NativeSocket, NetcodeServerTransport, ServerAuthentication, ServerSetupConfig};
+        use std::net::UdpSocket;
+
+        use crate::setup_renet2_client;
+
+        let udp_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut meta = ConnectMetaNative::dummy();
+        meta.server_addresses = vec![udp_socket.local_addr().unwrap()];
+        let current_time = wasm_timer::SystemTime::now()
+            .duration_since(wasm_timer::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let server_config = ServerSetupConfig {
+            current_time,
+            max_clients: 1,
+            protocol_id: meta.server_config.protocol_id,
+            socket_addresses: vec![meta.server_addresses.clone()],
+            authentication: ServerAuthentication::Secure {
+                private_key: meta.auth_key,
+            },
+        };
+        let mut server = RenetServer::new(ConnectionConfig::test());
+        let mut server_transport = NetcodeServerTransport::new(server_config, NativeSocket::new(udp_socket).unwrap()).unwrap();
+
+        let protocol_id = meta.server_config.protocol_id;
+        let token = meta.new_connect_token(current_time, 0).unwrap();
+
+        // Runs the client and server until the client connects, returning the client's local address.
+        let mut connect = |pack: ClientConnectPack| -> SocketAddr {
+            let (mut client, mut transport) = setup_renet2_client(ConnectionConfig::test(), pack).unwrap();
+            let tick = Duration::from_millis(5);
+            for _ in 0..200 {
+                client.update(tick);
+                transport.update(tick, &mut client).unwrap();
+                transport.send_packets(&mut client).unwrap();
+                server.update(tick);
+                server_transport.update(tick, &mut server).unwrap();
+                server_transport.send_packets(&mut server);
+                if client.is_connected() && server.is_connected(0) {
+                    break;
+                }
+                std::thread::sleep(tick);
+            }
+            assert!(client.is_connected());
+            let addr = transport.addr().unwrap();
+
+            transport.disconnect();
+            for _ in 0..200 {
+                server.update(tick);
+                server_transport.update(tick, &mut server).unwrap();
+                if !server.is_connected(0) {
+                    break;
+                }
+                std::thread::sleep(tick);
+            }
+            assert!(!server.is_connected(0));
+            addr
+        };
+
+        // The token is bound to the address of the first connection, so the reconnect must use the same address.
+        let pack = ClientConnectPack::new(protocol_id, token).unwrap();
+        let prev_addr = connect(pack.clone_for_reconnect("0.0.0.0:0".parse().unwrap()).unwrap());
+        connect(pack.clone_for_reconnect(prev_addr).unwrap());
+    }
+
+    #[test]
+    fn token_expired() {
+        let (protocol_id, token) = native_token_bytes();
+        let connect_token = connect_token_from_bytes(&token).unwrap();
+        let pack = ClientConnectPack::new(protocol_id, ServerConnectToken::Native { token }).unwrap();
+
+        let fresh = Duration::from_secs(connect_token.expire_timestamp - 1);
+        assert!(pack.validate_freshness(fresh).is_ok());
+
+        let expired = Duration::from_secs(connect_token.expire_timestamp);
+        let err = pack.validate_freshness(expired).unwrap_err();
+        assert!(matches!(
+            err,
+            ClientConnectPackError::TokenExpired { expire_timestamp, current_time }
+                if expire_timestamp == connect_token.expire_timestamp && current_time == connect_token.expire_timestamp
+        ));
+        assert!(err.to_string().starts_with("connect token expired"));
+    }
+}
@@ -0,0 +1,170 @@
+use bevy_app::{App, Last, Plugin};
+use bevy_ecs::prelude::*;
+use bevy_time::{Real, Time};
+use renet2::{ConnectionConfig, RenetClient};
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{setup_renet2_client_in_bevy, ClientConnectPack, ConnectionType, ServerConnectToken};
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Resource that stores a chain of connect packs for [`RenetConnectFallbackPlugin`].
+///
+/// The first pack is used to connect as soon as the plugin sees this resource. If that connection fails or doesn't
+/// finish within the timeout, the next pack is used, and so on. For example, a wasm client can try webtransport first
+/// and fall back to websockets (see `ConnectMetas::new_connect_tokens_with_fallback`).
+///
+/// Do not call [`setup_renet2_client_in_bevy`] yourself when using this resource.
+#[derive(Resource)]
+pub struct RenetConnectFallback {
+    connection_config: ConnectionConfig,
+    timeout: Duration,
+    remaining: VecDeque<ClientConnectPack>,
+    current: Option<ConnectionType>,
+    elapsed: Duration,
+    connected: Option<ConnectionType>,
+    exhausted: bool,
+}
+
+impl RenetConnectFallback {
+    /// Makes a new fallback chain from connect packs in order of preference.
+    ///
+    /// Each connection attempt is abandoned if it isn't connected within `timeout`.
+    pub fn new(connection_config: ConnectionConfig, timeout: Duration, packs: Vec<ClientConnectPack>) -> Self {
+        Self {
+            connection_config,
+            timeout,
+            remaining: packs.into(),
+            current: None,
+            elapsed: Duration::ZERO,
+            connected: None,
+            exhausted: false,
+        }
+    }
+
+    /// Makes a new fallback chain from server connect tokens in order of preference.
+    ///
+    /// Tokens that can't be used in this environment (e.g. webtransport tokens without the `wt_client_transport`
+    /// feature) are skipped. Errors if none of the tokens can be used.
+    pub fn from_tokens(
+        connection_config: ConnectionConfig,
+        timeout: Duration,
+        expected_protocol_id: u64,
+        tokens: Vec<ServerConnectToken>,
+    ) -> Result<Self, String> {
+        let mut packs = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            match ClientConnectPack::new(expected_protocol_id, token) {
+                Ok(pack) => packs.push(pack),
+                Err(err) => log::warn!("skipping unusable connect token for renet2 client fallback: {err}"),
+            }
+        }
+        if packs.is_empty() {
+            return Err("no usable connect tokens for renet2 client fallback".to_string());
+        }
+
+        Ok(Self::new(connection_config, timeout, packs))
+    }
+
+    /// Returns the transport of the current connection attempt.
+    pub fn current_transport(&self) -> Option<ConnectionType> {
+        self.current
+    }
+
+    /// Returns the transport that successfully connected, if any.
+    pub fn connected_transport(&self) -> Option<ConnectionType> {
+        self.connected
+    }
+
+    /// Returns `true` if every transport failed to connect.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+impl std::fmt::Debug for RenetConnectFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenetConnectFallback")
+            .field("connection_config", &self.connection_config)
+            .field("timeout", &self.timeout)
+            .field("remaining", &self.remaining.iter().map(|p| p.connection_type()).collect::<Vec<_>>())
+            .field("current", &self.current)
+            .field("elapsed", &self.elapsed)
+            .field("connected", &self.connected)
+            .field("exhausted", &self.exhausted)
+            .finish()
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Connects a client using the transports in [`RenetConnectFallback`], falling back to the next transport when a
+/// connection attempt fails or times out.
+///
+/// Once a transport connects, the remaining fallbacks are discarded. Later disconnects are not handled by this
+/// plugin (see [`RenetAutoReconnectPlugin`](crate::RenetAutoReconnectPlugin)).
+///
+/// Requires `bevy_time::TimePlugin`.
+pub struct RenetConnectFallbackPlugin;
+
+impl Plugin for RenetConnectFallbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Last, connect_fallback_system.run_if(resource_exists::<RenetConnectFallback>));
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+fn connect_fallback_system(world: &mut World) {
+    let delta = world.resource::<Time<Real>>().delta();
+    let status = world
+        .get_resource::<RenetClient>()
+        .map(|client| (client.is_connected(), client.is_connecting()));
+
+    world.resource_scope(|world, mut fallback: Mut<RenetConnectFallback>| {
+        if fallback.connected.is_some() || fallback.exhausted {
+            return;
+        }
+
+        if let Some(current) = fallback.current {
+            match status {
+                Some((true, _)) => {
+                    log::info!("renet2 client connected with {current:?}");
+                    fallback.connected = Some(current);
+                    fallback.remaining.clear();
+                    return;
+                }
+                Some((false, true)) => {
+                    fallback.elapsed += delta;
+                    if fallback.elapsed < fallback.timeout {
+                        return;
+                    }
+                    log::warn!("renet2 client connection with {current:?} timed out");
+                }
+                _ => log::warn!("renet2 client connection with {current:?} failed"),
+            }
+        }
+
+        // Try the next transports until one can be set up.
+        fallback.current = None;
+        fallback.elapsed = Duration::ZERO;
+        while let Some(pack) = fallback.remaining.pop_front() {
+            let connection_type = pack.connection_type();
+            match setup_renet2_client_in_bevy(world, fallback.connection_config.clone(), pack) {
+                Ok(()) => {
+                    log::info!("renet2 client connecting with {connection_type:?}");
+                    fallback.current = Some(connection_type);
+                    return;
+                }
+                Err(err) => log::warn!("failed setting up renet2 client with {connection_type:?}: {err}"),
+            }
+        }
+
+        log::warn!("renet2 client fallback transports exhausted");
+        fallback.exhausted = true;
+    });
+}
+
+//-------------------------------------------------------------------------------------------------------------------
@@ -1,5 +1,15 @@
+#[cfg(feature = "bevy")]
+mod auto_reconnect;
 mod client_connect_pack;
+#[cfg(feature = "bevy")]
+mod connect_fallback;
+mod prewarm;
 mod renet2_setup;
 
+#[cfg(feature = "bevy")]
+pub use auto_reconnect::*;
 pub use client_connect_pack::*;
+#[cfg(feature = "bevy")]
+pub use connect_fallback::*;
+pub use prewarm::*;
 pub use renet2_setup::*;
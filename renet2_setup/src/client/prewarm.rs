@@ -0,0 +1,92 @@
+use crate::ClientConnectPack;
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Keeps the transport-level resources warmed by [`prewarm`] alive.
+///
+/// In WASM, the handle owns a `<link rel="preconnect">` hint in the document's head. The browser resolves DNS and
+/// opens a TCP + TLS connection to the server's origin, then keeps it in its connection pool. Dropping the handle
+/// removes the hint, but the browser may keep the pooled connection until it is idle for a while (typically around
+/// 10 seconds).
+///
+/// Keep the handle alive until [`setup_renet2_client`](crate::setup_renet2_client) is called with the same connect
+/// pack, then drop it. The browser reuses the warmed resources automatically.
+#[derive(Debug, Default)]
+pub struct PrewarmHandle {
+    #[cfg(all(target_family = "wasm", any(feature = "wt_client_transport", feature = "ws_client_transport")))]
+    link: Option<web_sys::Element>,
+}
+
+impl PrewarmHandle {
+    /// Returns `true` if anything is being warmed.
+    pub fn is_warming(&self) -> bool {
+        #[cfg(all(target_family = "wasm", any(feature = "wt_client_transport", feature = "ws_client_transport")))]
+        return self.link.is_some();
+
+        #[cfg(not(all(target_family = "wasm", any(feature = "wt_client_transport", feature = "ws_client_transport"))))]
+        false
+    }
+}
+
+impl Drop for PrewarmHandle {
+    fn drop(&mut self) {
+        #[cfg(all(target_family = "wasm", any(feature = "wt_client_transport", feature = "ws_client_transport")))]
+        if let Some(link) = self.link.take() {
+            link.remove();
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Adds a preconnect hint for the origin of `url` to the document.
+#[cfg(all(target_family = "wasm", any(feature = "wt_client_transport", feature = "ws_client_transport")))]
+fn preconnect(mut url: url::Url) -> Option<web_sys::Element> {
+    let scheme = match url.scheme() {
+        "wss" | "https" => "https",
+        _ => "http",
+    };
+    url.set_scheme(scheme).ok()?;
+
+    let document = web_sys::window()?.document()?;
+    let link = document.create_element("link").ok()?;
+    link.set_attribute("rel", "preconnect").ok()?;
+    link.set_attribute("href", &url.origin().ascii_serialization()).ok()?;
+    link.set_attribute("crossorigin", "").ok()?;
+    document.head()?.append_child(&link).ok()?;
+
+    Some(link)
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Warms up the transport-level resources for a connect pack ahead of [`setup_renet2_client`], to reduce the
+/// latency of the first connection (e.g. when the player clicks "play" in a browser game).
+///
+/// Only the DNS lookup and the TLS connection to the server's origin can be warmed. The transport session itself
+/// can't be opened early, because the netcode connection request is sent when the session is opened.
+///
+/// - WASM websockets: DNS and the TCP + TLS connection are warmed.
+/// - WASM webtransport: only DNS is warmed in practice, since the session runs over QUIC. Nothing is warmed if the
+///   server destination is an IP address.
+/// - Native and in-memory: nothing is warmed, the connect pack already contains the resolved addresses.
+///
+/// See [`PrewarmHandle`] for the lifetime of the warmed resources.
+///
+/// [`setup_renet2_client`]: crate::setup_renet2_client
+pub fn prewarm(connect_pack: &ClientConnectPack) -> PrewarmHandle {
+    match connect_pack {
+        #[cfg(all(target_family = "wasm", feature = "wt_client_transport"))]
+        ClientConnectPack::WasmWt(_, config) => match config.server_dest.clone() {
+            renet2_netcode::WebServerDestination::Url(url) => PrewarmHandle { link: preconnect(url) },
+            _ => PrewarmHandle::default(),
+        },
+        #[cfg(all(target_family = "wasm", feature = "ws_client_transport"))]
+        ClientConnectPack::WasmWs(_, config) => PrewarmHandle {
+            link: preconnect(config.server_url.clone()),
+        },
+        _ => PrewarmHandle::default(),
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
@@ -7,7 +7,7 @@ use renet2_netcode::{ClientAuthentication, ClientSocket, NetcodeClientTransport}
 use std::net::{SocketAddr, UdpSocket};
 use wasm_timer::SystemTime;
 
-use crate::ClientConnectPack;
+use crate::{ClientConnectPack, ClientConnectPackError, ServerConnectToken};
 
 //-------------------------------------------------------------------------------------------------------------------
 
@@ -164,3 +164,115 @@ pub fn setup_renet2_client_in_bevy(
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+/// Error returned by [`connect_from_token_bytes`].
+#[derive(Debug)]
+pub enum ClientConnectError {
+    /// The connect token could not be used.
+    Token(ClientConnectPackError),
+    /// The client or transport could not be set up.
+    Setup(String),
+}
+
+impl std::fmt::Display for ClientConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Token(err) => write!(f, "invalid connect token: {err}"),
+            Self::Setup(err) => write!(f, "failed setting up renet2 client: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientConnectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Token(err) => Some(err),
+            Self::Setup(_) => None,
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Sets up a renet2 client from the bytes of a native connect token.
+///
+/// The bytes are a serialized renet2 `ConnectToken`, i.e. the `token` of [`ServerConnectToken::Native`]. This is
+/// shorthand for [`ClientConnectPack::new`] followed by [`setup_renet2_client`], use those directly for other
+/// transports.
+pub fn connect_from_token_bytes(
+    expected_protocol_id: u64,
+    token_bytes: &[u8],
+    connection_config: ConnectionConfig,
+) -> Result<(RenetClient, NetcodeClientTransport), ClientConnectError> {
+    let token = ServerConnectToken::Native {
+        token: token_bytes.to_vec(),
+    };
+    let connect_pack = ClientConnectPack::new(expected_protocol_id, token).map_err(ClientConnectError::Token)?;
+    setup_renet2_client(connection_config, connect_pack).map_err(ClientConnectError::Setup)
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Sets up a renet2 client from the bytes of a native connect token and inserts the [`RenetClient`] and
+/// [`NetcodeClientTransport`] into `world` as resources.
+///
+/// See [`connect_from_token_bytes`].
+#[cfg(feature = "bevy")]
+pub fn connect_from_token_bytes_in_bevy(
+    world: &mut bevy_ecs::prelude::World,
+    expected_protocol_id: u64,
+    token_bytes: &[u8],
+    connection_config: ConnectionConfig,
+) -> Result<(), ClientConnectError> {
+    let token = ServerConnectToken::Native {
+        token: token_bytes.to_vec(),
+    };
+    let connect_pack = ClientConnectPack::new(expected_protocol_id, token).map_err(ClientConnectError::Token)?;
+    setup_renet2_client_in_bevy(world, connection_config, connect_pack).map_err(ClientConnectError::Setup)
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(all(test, feature = "native_transport"))]
+mod tests {
+    use std::time::Duration;
+
+    use renet2_netcode::{ConnectToken, NETCODE_KEY_BYTES};
+
+    use super::*;
+    use crate::connect_token_to_bytes;
+
+    #[test]
+    fn connect_from_native_token_bytes() {
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            7,
+            300,
+            1,
+            15,
+            0,
+            vec!["127.0.0.1:5000".parse().unwrap()],
+            None,
+            &[0u8; NETCODE_KEY_BYTES],
+        )
+        .unwrap();
+        let token_bytes = connect_token_to_bytes(&connect_token).unwrap();
+
+        let (client, transport) = connect_from_token_bytes(7, &token_bytes, ConnectionConfig::test()).unwrap();
+        assert!(client.is_connecting());
+        assert!(!client.has_reliable_socket());
+        assert!(!transport.is_connected());
+
+        assert!(matches!(
+            connect_from_token_bytes(8, &token_bytes, ConnectionConfig::test()),
+            Err(ClientConnectError::Token(ClientConnectPackError::ProtocolMismatch {
+                expected: 8,
+                found: 7
+            }))
+        ));
+        assert!(matches!(
+            connect_from_token_bytes(7, &token_bytes[..10], ConnectionConfig::test()),
+            Err(ClientConnectError::Token(ClientConnectPackError::DeserializationFailed(_)))
+        ));
+    }
+}
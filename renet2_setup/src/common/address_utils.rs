@@ -1,4 +1,4 @@
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 
 //-------------------------------------------------------------------------------------------------------------------
 
@@ -12,4 +12,14 @@ pub fn client_address_from_server_address(server_addr: &SocketAddr) -> SocketAdd
     }
 }
 
+/// Checks if the local network stack has a route to a server address, e.g. to choose between the IPv4 and IPv6
+/// addresses of a dual-stack server with [`ClientConnectPack::new_with_address_filter`](crate::ClientConnectPack::new_with_address_filter).
+///
+/// Binds a temporary local socket, but no packets are sent.
+pub fn is_server_address_reachable(server_addr: &SocketAddr) -> bool {
+    UdpSocket::bind(client_address_from_server_address(server_addr))
+        .and_then(|socket| socket.connect(server_addr))
+        .is_ok()
+}
+
 //-------------------------------------------------------------------------------------------------------------------
@@ -1,4 +1,4 @@
-use renet2_netcode::{ConnectToken, ServerCertHash};
+use renet2_netcode::{ConnectToken, ServerCertHash, NETCODE_USER_DATA_BYTES};
 use serde::{Deserialize, Serialize};
 
 use std::{
@@ -10,6 +10,37 @@ use crate::{connect_token_to_bytes, ConnectionType, GameServerSetupConfig, Serve
 
 //-------------------------------------------------------------------------------------------------------------------
 
+/// Generates a serialized connect token.
+///
+/// Random user data is used if `user_data` is `None`.
+#[allow(clippy::too_many_arguments)]
+fn generate_connect_token(
+    server_config: &GameServerSetupConfig,
+    socket_id: u8,
+    server_addresses: Vec<SocketAddr>,
+    auth_key: &[u8; 32],
+    current_time: Duration,
+    client_id: u64,
+    user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+) -> Result<Vec<u8>, String> {
+    let token = ConnectToken::generate(
+        current_time,
+        server_config.protocol_id,
+        server_config.expire_secs,
+        client_id,
+        server_config.timeout_secs,
+        socket_id,
+        server_addresses,
+        user_data,
+        auth_key,
+    )
+    .map_err(|err| format!("failed generating connect token: {err:?}"))?;
+
+    connect_token_to_bytes(&token).map_err(|err| format!("failed writing connect token to bytes: {err:?}"))
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
 /// Metadata required to generate connect tokens for in-memory clients.
 #[cfg(feature = "memory_transport")]
 #[derive(Debug, Clone)]
@@ -27,20 +58,36 @@ impl ConnectMetaMemory {
     /// Note that [`ConnectMetaMemory`] can contain sockets for multiple clients. We search available clients for
     /// the requested client id, and return `None` on failure.
     pub fn new_connect_token(&self, current_time: Duration, client_id: u64) -> Result<ServerConnectToken, String> {
-        let token = ConnectToken::generate(
-            current_time,
-            self.server_config.protocol_id,
-            self.server_config.expire_secs,
-            client_id,
-            self.server_config.timeout_secs,
+        self.connect_token(current_time, client_id, None)
+    }
+
+    /// Generates a new connect token for an in-memory client, with custom user data.
+    ///
+    /// The server receives the user data when the client connects.
+    pub fn new_connect_token_with_user_data(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: [u8; NETCODE_USER_DATA_BYTES],
+    ) -> Result<ServerConnectToken, String> {
+        self.connect_token(current_time, client_id, Some(&user_data))
+    }
+
+    fn connect_token(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Result<ServerConnectToken, String> {
+        let token = generate_connect_token(
+            &self.server_config,
             self.socket_id,
             vec![renet2_netcode::in_memory_server_addr()],
-            None,
             &self.auth_key,
-        )
-        .map_err(|err| format!("failed generating connect token: {err:?}"))?;
-
-        let token = connect_token_to_bytes(&token).map_err(|err| format!("failed writing connect token to bytes: {err:?}"))?;
+            current_time,
+            client_id,
+            user_data,
+        )?;
         let client = self
             .clients
             .iter()
@@ -82,22 +129,50 @@ impl ConnectMetaNative {
 
     /// Generates a new connect token for a native client.
     pub fn new_connect_token(&self, current_time: Duration, client_id: u64) -> Result<ServerConnectToken, String> {
-        let token = ConnectToken::generate(
-            current_time,
-            self.server_config.protocol_id,
-            self.server_config.expire_secs,
-            client_id,
-            self.server_config.timeout_secs,
+        self.connect_token(current_time, client_id, None)
+    }
+
+    /// Generates a new connect token for a native client, with custom user data.
+    ///
+    /// The server receives the user data when the client connects.
+    pub fn new_connect_token_with_user_data(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: [u8; NETCODE_USER_DATA_BYTES],
+    ) -> Result<ServerConnectToken, String> {
+        self.connect_token(current_time, client_id, Some(&user_data))
+    }
+
+    fn connect_token(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Result<ServerConnectToken, String> {
+        let token = generate_connect_token(
+            &self.server_config,
             self.socket_id,
             self.server_addresses.clone(),
-            None,
             &self.auth_key,
-        )
-        .map_err(|err| format!("failed generating connect token: {err:?}"))?;
-
-        let token = connect_token_to_bytes(&token).map_err(|err| format!("failed writing connect token to bytes: {err:?}"))?;
+            current_time,
+            client_id,
+            user_data,
+        )?;
         Ok(ServerConnectToken::Native { token })
     }
+
+    /// Generates connect tokens for many native clients at once.
+    ///
+    /// Useful for load testing with simulated clients. Use [`ServerConnectToken::write_batch`] to save the tokens
+    /// to a file.
+    pub fn generate_token_batch(&self, current_time: Duration, client_ids: &[u64]) -> Result<Vec<ServerConnectToken>, String> {
+        let mut tokens = Vec::with_capacity(client_ids.len());
+        for client_id in client_ids.iter().copied() {
+            tokens.push(self.new_connect_token(current_time, client_id)?);
+        }
+        Ok(tokens)
+    }
 }
 
 //-------------------------------------------------------------------------------------------------------------------
@@ -115,21 +190,36 @@ pub struct ConnectMetaWasmWt {
 impl ConnectMetaWasmWt {
     /// Generates a new connect token for a wasm webtransport client.
     pub fn new_connect_token(&self, current_time: Duration, client_id: u64) -> Result<ServerConnectToken, String> {
-        let token = ConnectToken::generate(
-            current_time,
-            self.server_config.protocol_id,
-            self.server_config.expire_secs,
-            client_id,
-            self.server_config.timeout_secs,
+        self.connect_token(current_time, client_id, None)
+    }
+
+    /// Generates a new connect token for a wasm webtransport client, with custom user data.
+    ///
+    /// The server receives the user data when the client connects.
+    pub fn new_connect_token_with_user_data(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: [u8; NETCODE_USER_DATA_BYTES],
+    ) -> Result<ServerConnectToken, String> {
+        self.connect_token(current_time, client_id, Some(&user_data))
+    }
+
+    fn connect_token(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Result<ServerConnectToken, String> {
+        let token = generate_connect_token(
+            &self.server_config,
             self.socket_id,
             self.server_addresses.clone(),
-            None,
             &self.auth_key,
-        )
-        .map_err(|err| format!("failed generating connect token: {err:?}"))?;
-
-        let token = connect_token_to_bytes(&token).map_err(|err| format!("failed writing connect token to bytes: {err:?}"))?;
-
+            current_time,
+            client_id,
+            user_data,
+        )?;
         Ok(ServerConnectToken::WasmWt {
             token,
             cert_hashes: self.cert_hashes.clone(),
@@ -152,21 +242,36 @@ pub struct ConnectMetaWasmWs {
 impl ConnectMetaWasmWs {
     /// Generates a new connect token for a wasm websocket client.
     pub fn new_connect_token(&self, current_time: Duration, client_id: u64) -> Result<ServerConnectToken, String> {
-        let token = ConnectToken::generate(
-            current_time,
-            self.server_config.protocol_id,
-            self.server_config.expire_secs,
-            client_id,
-            self.server_config.timeout_secs,
+        self.connect_token(current_time, client_id, None)
+    }
+
+    /// Generates a new connect token for a wasm websocket client, with custom user data.
+    ///
+    /// The server receives the user data when the client connects.
+    pub fn new_connect_token_with_user_data(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: [u8; NETCODE_USER_DATA_BYTES],
+    ) -> Result<ServerConnectToken, String> {
+        self.connect_token(current_time, client_id, Some(&user_data))
+    }
+
+    fn connect_token(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Result<ServerConnectToken, String> {
+        let token = generate_connect_token(
+            &self.server_config,
             self.socket_id,
             self.server_addresses.clone(),
-            None,
             &self.auth_key,
-        )
-        .map_err(|err| format!("failed generating connect token: {err:?}"))?;
-
-        let token = connect_token_to_bytes(&token).map_err(|err| format!("failed writing connect token to bytes: {err:?}"))?;
-
+            current_time,
+            client_id,
+            user_data,
+        )?;
         Ok(ServerConnectToken::WasmWs {
             token,
             url: self.url.clone(),
@@ -187,41 +292,258 @@ pub struct ConnectMetas {
 }
 
 impl ConnectMetas {
+    /// Returns the certificate hashes of the webtransport server, e.g. to publish them to a matchmaker.
+    ///
+    /// Returns an empty slice if there is no webtransport server, or if it uses certificates that don't need to be
+    /// pinned by clients.
+    pub fn webtransport_cert_hashes(&self) -> &[ServerCertHash] {
+        self.wasm_wt.as_ref().map(|meta| meta.cert_hashes.as_slice()).unwrap_or_default()
+    }
+
+    /// Returns the url of the websocket server, e.g. to publish it to a matchmaker.
+    pub fn websocket_url(&self) -> Option<&url::Url> {
+        self.wasm_ws.as_ref().map(|meta| &meta.url)
+    }
+
+    /// Generates a connect token for the requested connection type.
+    ///
+    /// Clients that request [`ConnectionType::WasmWt`] fall back to websockets if there is no webtransport meta.
+    /// See [`Self::new_connect_token_with_preferences`] to control the fallback order.
     pub fn new_connect_token(
         &self,
         current_time: Duration,
         client_id: u64,
         connection_type: ConnectionType,
     ) -> Result<ServerConnectToken, String> {
-        match connection_type {
-            ConnectionType::Memory | ConnectionType::Native => {
-                let Some(meta) = &self.native else {
-                    return Err("no native connect meta for native client".to_string());
-                };
-                meta.new_connect_token(current_time, client_id)
-                    .map_err(|err| format!("failed constructing native connect token: {err:?}"))
+        self.new_connect_token_with_preferences(current_time, client_id, Self::default_preferences(connection_type))
+    }
+
+    /// Generates a connect token for the requested connection type, with custom user data (e.g. a session identity
+    /// or role).
+    ///
+    /// The server receives the user data when the client connects. Uses the same transport fallbacks as
+    /// [`Self::new_connect_token`].
+    pub fn new_connect_token_with_user_data(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        connection_type: ConnectionType,
+        user_data: [u8; NETCODE_USER_DATA_BYTES],
+    ) -> Result<ServerConnectToken, String> {
+        let preferences = Self::default_preferences(connection_type);
+        for connection_type in preferences.iter().copied() {
+            if let Some(result) = self.try_new_connect_token(current_time, client_id, connection_type, Some(&user_data)) {
+                return result;
             }
-            ConnectionType::WasmWt => {
-                // Clients that request webtransport can fall back to websockets.
-                if let Some(meta) = &self.wasm_wt {
-                    meta.new_connect_token(current_time, client_id)
-                        .map_err(|err| format!("failed constructing wasm wt connect token for wasm client: {err:?}"))
-                } else if let Some(meta) = &self.wasm_ws {
-                    meta.new_connect_token(current_time, client_id)
-                        .map_err(|err| format!("failed constructing wasm ws connect token for wasm client: {err:?}"))
-                } else {
-                    Err("no wasm webtransport connect meta for wasm client".to_string())
-                }
+        }
+
+        Err(format!("no connect meta for client with connection preferences {preferences:?}"))
+    }
+
+    /// Generates a connect token for the first connection type in `preferences` that has a connect meta.
+    ///
+    /// For example, `&[ConnectionType::WasmWs, ConnectionType::WasmWt]` prefers websockets and falls back to
+    /// webtransport. Errors if none of the preferred connection types are available.
+    pub fn new_connect_token_with_preferences(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        preferences: &[ConnectionType],
+    ) -> Result<ServerConnectToken, String> {
+        for connection_type in preferences.iter().copied() {
+            if let Some(result) = self.try_new_connect_token(current_time, client_id, connection_type, None) {
+                return result;
             }
-            ConnectionType::WasmWs => {
-                let Some(meta) = &self.wasm_ws else {
-                    return Err("no wasm websocket connect meta for wasm client".to_string());
-                };
-                meta.new_connect_token(current_time, client_id)
-                    .map_err(|err| format!("failed constructing wasm ws connect token for wasm client: {err:?}"))
+        }
+
+        Err(format!("no connect meta for client with connection preferences {preferences:?}"))
+    }
+
+    /// Generates connect tokens for every transport the client may use, in order of preference.
+    ///
+    /// For [`ConnectionType::WasmWt`] clients this returns a webtransport token followed by a websocket token (if
+    /// both metas are available), so the client can fall back to websockets if webtransport fails at runtime. See
+    /// `RenetConnectFallback`. Other connection types return a single token.
+    pub fn new_connect_tokens_with_fallback(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        connection_type: ConnectionType,
+    ) -> Result<Vec<ServerConnectToken>, String> {
+        let preferences = Self::default_preferences(connection_type);
+        let mut tokens = Vec::with_capacity(preferences.len());
+        for connection_type in preferences.iter().copied() {
+            if let Some(result) = self.try_new_connect_token(current_time, client_id, connection_type, None) {
+                tokens.push(result?);
             }
         }
+        if tokens.is_empty() {
+            return Err(format!("no connect meta for client with connection preferences {preferences:?}"));
+        }
+
+        Ok(tokens)
+    }
+
+    /// Returns the default transport preference order for a connection type.
+    fn default_preferences(connection_type: ConnectionType) -> &'static [ConnectionType] {
+        match connection_type {
+            ConnectionType::Memory => &[ConnectionType::Memory],
+            ConnectionType::Native => &[ConnectionType::Native],
+            // Clients that request webtransport can fall back to websockets.
+            ConnectionType::WasmWt => &[ConnectionType::WasmWt, ConnectionType::WasmWs],
+            ConnectionType::WasmWs => &[ConnectionType::WasmWs],
+        }
+    }
+
+    /// Generates a connect token for a specific connection type, or returns `None` if there is no meta for it.
+    fn try_new_connect_token(
+        &self,
+        current_time: Duration,
+        client_id: u64,
+        connection_type: ConnectionType,
+        user_data: Option<&[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Option<Result<ServerConnectToken, String>> {
+        let result = match connection_type {
+            ConnectionType::Memory | ConnectionType::Native => self
+                .native
+                .as_ref()?
+                .connect_token(current_time, client_id, user_data)
+                .map_err(|err| format!("failed constructing native connect token: {err:?}")),
+            ConnectionType::WasmWt => self
+                .wasm_wt
+                .as_ref()?
+                .connect_token(current_time, client_id, user_data)
+                .map_err(|err| format!("failed constructing wasm wt connect token for wasm client: {err:?}")),
+            ConnectionType::WasmWs => self
+                .wasm_ws
+                .as_ref()?
+                .connect_token(current_time, client_id, user_data)
+                .map_err(|err| format!("failed constructing wasm ws connect token for wasm client: {err:?}")),
+        };
+
+        Some(result)
     }
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect_token_from_bytes;
+
+    #[test]
+    fn token_batch_round_trip() {
+        let meta = ConnectMetaNative::dummy();
+        let client_ids: Vec<u64> = (0..100).collect();
+        let tokens = meta.generate_token_batch(Duration::from_secs(1), &client_ids).unwrap();
+        assert_eq!(tokens.len(), client_ids.len());
+
+        let mut file = Vec::default();
+        ServerConnectToken::write_batch(&tokens, &mut file).unwrap();
+        let read_tokens = ServerConnectToken::read_batch(&mut &file[..]).unwrap();
+        assert_eq!(read_tokens.len(), tokens.len());
+
+        for ((token, read_token), client_id) in tokens.iter().zip(read_tokens.iter()).zip(client_ids) {
+            let (ServerConnectToken::Native { token }, ServerConnectToken::Native { token: read_token }) = (token, read_token) else {
+                panic!("expected native tokens");
+            };
+            assert_eq!(token, read_token);
+            let connect_token = connect_token_from_bytes(read_token).unwrap();
+            assert_eq!(connect_token.client_id, client_id);
+            assert_eq!(connect_token.protocol_id, meta.server_config.protocol_id);
+        }
+    }
+
+    fn dummy_metas(wasm_wt: bool, wasm_ws: bool) -> ConnectMetas {
+        let native = ConnectMetaNative::dummy();
+        ConnectMetas {
+            memory: None,
+            wasm_wt: wasm_wt.then(|| ConnectMetaWasmWt {
+                server_config: native.server_config.clone(),
+                server_addresses: native.server_addresses.clone(),
+                socket_id: 1,
+                auth_key: native.auth_key,
+                cert_hashes: Vec::default(),
+            }),
+            wasm_ws: wasm_ws.then(|| ConnectMetaWasmWs {
+                server_config: native.server_config.clone(),
+                server_addresses: native.server_addresses.clone(),
+                socket_id: 2,
+                auth_key: native.auth_key,
+                url: url::Url::parse("ws://127.0.0.1:8080").unwrap(),
+            }),
+            native: Some(native),
+        }
+    }
+
+    fn token_type(token: &ServerConnectToken) -> ConnectionType {
+        match token {
+            ServerConnectToken::Native { .. } => ConnectionType::Native,
+            ServerConnectToken::WasmWt { .. } => ConnectionType::WasmWt,
+            ServerConnectToken::WasmWs { .. } => ConnectionType::WasmWs,
+            #[cfg(feature = "memory_transport")]
+            ServerConnectToken::Memory { .. } => ConnectionType::Memory,
+        }
+    }
+
+    #[test]
+    fn connect_token_preferences() {
+        let time = Duration::from_secs(1);
+        let metas = dummy_metas(true, true);
+        let wt_first = [ConnectionType::WasmWt, ConnectionType::WasmWs];
+        let ws_first = [ConnectionType::WasmWs, ConnectionType::WasmWt];
+
+        let token = metas.new_connect_token_with_preferences(time, 0, &wt_first).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::WasmWt);
+        let token = metas.new_connect_token_with_preferences(time, 0, &ws_first).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::WasmWs);
+        let token = metas
+            .new_connect_token_with_preferences(time, 0, &[ConnectionType::Native])
+            .unwrap();
+        assert_eq!(token_type(&token), ConnectionType::Native);
+
+        // Unavailable transports are skipped.
+        let metas = dummy_metas(false, true);
+        let token = metas.new_connect_token_with_preferences(time, 0, &wt_first).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::WasmWs);
+        let metas = dummy_metas(true, false);
+        let token = metas.new_connect_token_with_preferences(time, 0, &ws_first).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::WasmWt);
+
+        // Errors if no preferred transport is available.
+        let metas = dummy_metas(false, false);
+        assert!(metas.new_connect_token_with_preferences(time, 0, &wt_first).is_err());
+        assert!(metas.new_connect_token_with_preferences(time, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn published_server_info() {
+        let metas = dummy_metas(false, false);
+        assert!(metas.webtransport_cert_hashes().is_empty());
+        assert!(metas.websocket_url().is_none());
+
+        let mut metas = dummy_metas(true, true);
+        metas.wasm_wt.as_mut().unwrap().cert_hashes.push(ServerCertHash { hash: [7u8; 32] });
+        let hashes: Vec<_> = metas.webtransport_cert_hashes().iter().map(|cert_hash| cert_hash.hash).collect();
+        assert_eq!(hashes, [[7u8; 32]]);
+        assert_eq!(metas.websocket_url().map(url::Url::as_str), Some("ws://127.0.0.1:8080/"));
+    }
+
+    #[test]
+    fn connect_token_default_preferences() {
+        let time = Duration::from_secs(1);
+        let metas = dummy_metas(false, true);
+        let token = metas.new_connect_token(time, 0, ConnectionType::WasmWt).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::WasmWs);
+        let token = metas.new_connect_token(time, 0, ConnectionType::Memory).unwrap();
+        assert_eq!(token_type(&token), ConnectionType::Native);
+        let metas = dummy_metas(true, false);
+        assert!(metas.new_connect_token(time, 0, ConnectionType::WasmWs).is_err());
+
+        let metas = dummy_metas(true, true);
+        let tokens = metas.new_connect_tokens_with_fallback(time, 0, ConnectionType::WasmWt).unwrap();
+        let types: Vec<_> = tokens.iter().map(token_type).collect();
+        assert_eq!(types, [ConnectionType::WasmWt, ConnectionType::WasmWs]);
+    }
+}
@@ -0,0 +1,475 @@
+use renet2_netcode::ServerCertHash;
+
+use std::{
+    io::{self, Read},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+};
+
+use crate::{ConnectMetaNative, ConnectMetaWasmWs, ConnectMetaWasmWt, ConnectMetas, GameServerSetupConfig};
+
+//-------------------------------------------------------------------------------------------------------------------
+
+const COMPACT_VERSION: u8 = 0;
+
+const HAS_NATIVE: u8 = 1 << 0;
+const HAS_WASM_WT: u8 = 1 << 1;
+const HAS_WASM_WS: u8 = 1 << 2;
+
+const HAS_PROXY_IP: u8 = 1 << 0;
+const HAS_WS_DOMAIN: u8 = 1 << 1;
+const HAS_WSS_CERTS: u8 = 1 << 2;
+const HAS_WSS_PROXY: u8 = 1 << 3;
+const HAS_SERVER_IPV6: u8 = 1 << 4;
+const HAS_PROXY_IPV6: u8 = 1 << 5;
+
+const CONFIG_SAME_AS_PREV: u8 = 0;
+const CONFIG_NEW: u8 = 1;
+
+const IP_V4: u8 = 4;
+const IP_V6: u8 = 6;
+
+//-------------------------------------------------------------------------------------------------------------------
+
+impl ConnectMetas {
+    /// Encodes the metas in a compact binary format, e.g. for embedding in a QR code so local players can join
+    /// by scanning it.
+    ///
+    /// The format is optimized for size: integers are varint-encoded, empty optional fields are omitted, and
+    /// server configs shared by multiple metas are only written once. Use serde for general-purpose serialization.
+    ///
+    /// The in-memory meta is not encoded. Errors if a websocket cert path in a server config is not valid UTF-8.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let mut out = Vec::with_capacity(256);
+        let mut flags = 0u8;
+        if self.native.is_some() {
+            flags |= HAS_NATIVE;
+        }
+        if self.wasm_wt.is_some() {
+            flags |= HAS_WASM_WT;
+        }
+        if self.wasm_ws.is_some() {
+            flags |= HAS_WASM_WS;
+        }
+        out.extend_from_slice(&[COMPACT_VERSION, flags]);
+
+        let mut prev_config = None;
+        if let Some(meta) = &self.native {
+            write_meta(
+                &mut out,
+                &mut prev_config,
+                &meta.server_config,
+                &meta.server_addresses,
+                meta.socket_id,
+                &meta.auth_key,
+            )?;
+        }
+        if let Some(meta) = &self.wasm_wt {
+            write_meta(
+                &mut out,
+                &mut prev_config,
+                &meta.server_config,
+                &meta.server_addresses,
+                meta.socket_id,
+                &meta.auth_key,
+            )?;
+            write_varint(&mut out, meta.cert_hashes.len() as u64);
+            for cert_hash in meta.cert_hashes.iter() {
+                out.extend_from_slice(&cert_hash.hash);
+            }
+        }
+        if let Some(meta) = &self.wasm_ws {
+            write_meta(
+                &mut out,
+                &mut prev_config,
+                &meta.server_config,
+                &meta.server_addresses,
+                meta.socket_id,
+                &meta.auth_key,
+            )?;
+            write_str(&mut out, meta.url.as_str());
+        }
+
+        Ok(out)
+    }
+
+    /// Decodes metas encoded with [`Self::to_compact_bytes`].
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, io::Error> {
+        let src = &mut &bytes[..];
+        let [version, flags] = read_array(src)?;
+        if version != COMPACT_VERSION {
+            return Err(invalid_data(format!("unsupported compact connect metas version {version}")));
+        }
+
+        let mut metas = Self::default();
+        let mut prev_config = None;
+        if flags & HAS_NATIVE != 0 {
+            let (server_config, server_addresses, socket_id, auth_key) = read_meta(src, &mut prev_config)?;
+            metas.native = Some(ConnectMetaNative {
+                server_config,
+                server_addresses,
+                socket_id,
+                auth_key,
+            });
+        }
+        if flags & HAS_WASM_WT != 0 {
+            let (server_config, server_addresses, socket_id, auth_key) = read_meta(src, &mut prev_config)?;
+            let num_hashes = read_varint(src)? as usize;
+            let mut cert_hashes = Vec::with_capacity(num_hashes.min(16));
+            for _ in 0..num_hashes {
+                cert_hashes.push(ServerCertHash { hash: read_array(src)? });
+            }
+            metas.wasm_wt = Some(ConnectMetaWasmWt {
+                server_config,
+                server_addresses,
+                socket_id,
+                auth_key,
+                cert_hashes,
+            });
+        }
+        if flags & HAS_WASM_WS != 0 {
+            let (server_config, server_addresses, socket_id, auth_key) = read_meta(src, &mut prev_config)?;
+            let url = url::Url::parse(&read_string(src)?).map_err(invalid_data)?;
+            metas.wasm_ws = Some(ConnectMetaWasmWs {
+                server_config,
+                server_addresses,
+                socket_id,
+                auth_key,
+                url,
+            });
+        }
+        if !src.is_empty() {
+            return Err(invalid_data("unexpected trailing bytes in compact connect metas"));
+        }
+
+        Ok(metas)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(src: &mut &[u8]) -> Result<u64, io::Error> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [byte] = read_array(src)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint is too long"))
+}
+
+fn read_array<const N: usize>(src: &mut &[u8]) -> Result<[u8; N], io::Error> {
+    let mut bytes = [0u8; N];
+    src.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_str(out: &mut Vec<u8>, string: &str) {
+    write_varint(out, string.len() as u64);
+    out.extend_from_slice(string.as_bytes());
+}
+
+fn read_string(src: &mut &[u8]) -> Result<String, io::Error> {
+    let len = read_varint(src)? as usize;
+    if len > src.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let (string, rest) = src.split_at(len);
+    *src = rest;
+    String::from_utf8(string.to_vec()).map_err(invalid_data)
+}
+
+fn write_u16(out: &mut Vec<u8>, value: u16) {
+    write_varint(out, value.into());
+}
+
+fn read_u16(src: &mut &[u8]) -> Result<u16, io::Error> {
+    u16::try_from(read_varint(src)?).map_err(invalid_data)
+}
+
+fn write_ip(out: &mut Vec<u8>, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(ip) => {
+            out.push(IP_V4);
+            out.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            out.push(IP_V6);
+            out.extend_from_slice(&ip.octets());
+        }
+    }
+}
+
+fn read_ip(src: &mut &[u8]) -> Result<IpAddr, io::Error> {
+    match read_array(src)? {
+        [IP_V4] => Ok(Ipv4Addr::from(read_array::<4>(src)?).into()),
+        [IP_V6] => Ok(Ipv6Addr::from(read_array::<16>(src)?).into()),
+        [kind] => Err(invalid_data(format!("unknown ip address kind {kind}"))),
+    }
+}
+
+fn read_ipv6(src: &mut &[u8]) -> Result<Ipv6Addr, io::Error> {
+    match read_ip(src)? {
+        IpAddr::V6(ip) => Ok(ip),
+        IpAddr::V4(ip) => Err(invalid_data(format!("expected an ipv6 address, found {ip}"))),
+    }
+}
+
+fn write_path(out: &mut Vec<u8>, path: &std::path::Path) -> Result<(), io::Error> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("path {path:?} is not valid UTF-8")))?;
+    write_str(out, path);
+    Ok(())
+}
+
+fn write_config(out: &mut Vec<u8>, config: &GameServerSetupConfig) -> Result<(), io::Error> {
+    write_varint(out, config.protocol_id);
+    write_varint(out, config.expire_secs);
+    // Zigzag encoding so negative timeouts stay small.
+    write_varint(out, ((config.timeout_secs << 1) ^ (config.timeout_secs >> 31)) as u32 as u64);
+    write_ip(out, config.server_ip);
+    for port in [
+        config.native_port,
+        config.wasm_wt_port,
+        config.wasm_ws_port,
+        config.native_port_proxy,
+        config.wasm_wt_port_proxy,
+        config.wasm_ws_port_proxy,
+    ] {
+        write_u16(out, port);
+    }
+
+    let mut flags = 0u8;
+    if config.proxy_ip.is_some() {
+        flags |= HAS_PROXY_IP;
+    }
+    if config.ws_domain.is_some() {
+        flags |= HAS_WS_DOMAIN;
+    }
+    if config.wss_certs.is_some() {
+        flags |= HAS_WSS_CERTS;
+    }
+    if config.has_wss_proxy {
+        flags |= HAS_WSS_PROXY;
+    }
+    if config.server_ipv6.is_some() {
+        flags |= HAS_SERVER_IPV6;
+    }
+    if config.proxy_ipv6.is_some() {
+        flags |= HAS_PROXY_IPV6;
+    }
+    out.push(flags);
+
+    if let Some(proxy_ip) = config.proxy_ip {
+        write_ip(out, proxy_ip);
+    }
+    if let Some(ws_domain) = &config.ws_domain {
+        write_str(out, ws_domain);
+    }
+    if let Some((cert_chain, privkey)) = &config.wss_certs {
+        write_path(out, cert_chain)?;
+        write_path(out, privkey)?;
+    }
+    for ip in [config.server_ipv6, config.proxy_ipv6].into_iter().flatten() {
+        write_ip(out, ip.into());
+    }
+
+    Ok(())
+}
+
+fn read_config(src: &mut &[u8]) -> Result<GameServerSetupConfig, io::Error> {
+    let protocol_id = read_varint(src)?;
+    let expire_secs = read_varint(src)?;
+    let timeout_zigzag = u32::try_from(read_varint(src)?).map_err(invalid_data)?;
+    let timeout_secs = ((timeout_zigzag >> 1) as i32) ^ -((timeout_zigzag & 1) as i32);
+    let server_ip = read_ip(src)?;
+    let native_port = read_u16(src)?;
+    let wasm_wt_port = read_u16(src)?;
+    let wasm_ws_port = read_u16(src)?;
+    let native_port_proxy = read_u16(src)?;
+    let wasm_wt_port_proxy = read_u16(src)?;
+    let wasm_ws_port_proxy = read_u16(src)?;
+
+    let [flags] = read_array(src)?;
+    let proxy_ip = match flags & HAS_PROXY_IP != 0 {
+        true => Some(read_ip(src)?),
+        false => None,
+    };
+    let ws_domain = match flags & HAS_WS_DOMAIN != 0 {
+        true => Some(read_string(src)?),
+        false => None,
+    };
+    let wss_certs = match flags & HAS_WSS_CERTS != 0 {
+        true => Some((PathBuf::from(read_string(src)?), PathBuf::from(read_string(src)?))),
+        false => None,
+    };
+    let server_ipv6 = match flags & HAS_SERVER_IPV6 != 0 {
+        true => Some(read_ipv6(src)?),
+        false => None,
+    };
+    let proxy_ipv6 = match flags & HAS_PROXY_IPV6 != 0 {
+        true => Some(read_ipv6(src)?),
+        false => None,
+    };
+
+    Ok(GameServerSetupConfig {
+        protocol_id,
+        expire_secs,
+        timeout_secs,
+        server_ip,
+        native_port,
+        wasm_wt_port,
+        wasm_ws_port,
+        native_port_proxy,
+        wasm_wt_port_proxy,
+        wasm_ws_port_proxy,
+        proxy_ip,
+        server_ipv6,
+        proxy_ipv6,
+        ws_domain,
+        wss_certs,
+        has_wss_proxy: flags & HAS_WSS_PROXY != 0,
+    })
+}
+
+/// Writes the fields shared by all connect metas.
+///
+/// The server config is only written if it differs from the previous meta's config.
+fn write_meta(
+    out: &mut Vec<u8>,
+    prev_config: &mut Option<Vec<u8>>,
+    server_config: &GameServerSetupConfig,
+    server_addresses: &[SocketAddr],
+    socket_id: u8,
+    auth_key: &[u8; 32],
+) -> Result<(), io::Error> {
+    let mut config = Vec::new();
+    write_config(&mut config, server_config)?;
+    if prev_config.as_ref() == Some(&config) {
+        out.push(CONFIG_SAME_AS_PREV);
+    } else {
+        out.push(CONFIG_NEW);
+        out.extend_from_slice(&config);
+        *prev_config = Some(config);
+    }
+
+    write_varint(out, server_addresses.len() as u64);
+    for addr in server_addresses.iter() {
+        write_ip(out, addr.ip());
+        write_u16(out, addr.port());
+    }
+    out.push(socket_id);
+    out.extend_from_slice(auth_key);
+
+    Ok(())
+}
+
+/// Reads the fields written by [`write_meta`].
+fn read_meta(
+    src: &mut &[u8],
+    prev_config: &mut Option<GameServerSetupConfig>,
+) -> Result<(GameServerSetupConfig, Vec<SocketAddr>, u8, [u8; 32]), io::Error> {
+    let server_config = match read_array(src)? {
+        [CONFIG_SAME_AS_PREV] => prev_config
+            .clone()
+            .ok_or_else(|| invalid_data("compact connect meta refers to a missing server config"))?,
+        [CONFIG_NEW] => {
+            let config = read_config(src)?;
+            *prev_config = Some(config.clone());
+            config
+        }
+        [marker] => return Err(invalid_data(format!("unknown server config marker {marker}"))),
+    };
+
+    let num_addresses = read_varint(src)? as usize;
+    let mut server_addresses = Vec::with_capacity(num_addresses.min(16));
+    for _ in 0..num_addresses {
+        let ip = read_ip(src)?;
+        server_addresses.push(SocketAddr::new(ip, read_u16(src)?));
+    }
+    let [socket_id] = read_array(src)?;
+    let auth_key = read_array(src)?;
+
+    Ok((server_config, server_addresses, socket_id, auth_key))
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_metas() -> ConnectMetas {
+        let native = ConnectMetaNative::dummy();
+        let mut ws_config = native.server_config.clone();
+        ws_config.proxy_ip = Some(Ipv6Addr::LOCALHOST.into());
+        ws_config.ws_domain = Some("example.com".into());
+        ws_config.wss_certs = Some(("certs/chain.pem".into(), "certs/key.pem".into()));
+        ws_config.timeout_secs = -1;
+        ws_config.server_ipv6 = Some(Ipv6Addr::UNSPECIFIED);
+        ws_config.proxy_ipv6 = Some(Ipv6Addr::LOCALHOST);
+
+        ConnectMetas {
+            memory: None,
+            wasm_wt: Some(ConnectMetaWasmWt {
+                server_config: native.server_config.clone(),
+                server_addresses: vec!["127.0.0.1:4433".parse().unwrap()],
+                socket_id: 1,
+                auth_key: native.auth_key,
+                cert_hashes: vec![ServerCertHash { hash: [7u8; 32] }],
+            }),
+            wasm_ws: Some(ConnectMetaWasmWs {
+                server_config: ws_config,
+                server_addresses: vec!["[::1]:8081".parse().unwrap()],
+                socket_id: 2,
+                auth_key: [3u8; 32],
+                url: url::Url::parse("wss://example.com:8081").unwrap(),
+            }),
+            native: Some(native),
+        }
+    }
+
+    #[test]
+    fn compact_round_trip() {
+        for metas in [test_metas(), ConnectMetas::default()] {
+            let bytes = metas.to_compact_bytes().unwrap();
+            let decoded = ConnectMetas::from_compact_bytes(&bytes).unwrap();
+            assert_eq!(serde_json::to_string(&decoded).unwrap(), serde_json::to_string(&metas).unwrap());
+        }
+    }
+
+    #[test]
+    fn compact_size() {
+        let metas = test_metas();
+        let bytes = metas.to_compact_bytes().unwrap();
+        let json = serde_json::to_vec(&metas).unwrap();
+        assert!(bytes.len() * 4 < json.len(), "compact: {}, json: {}", bytes.len(), json.len());
+    }
+
+    #[test]
+    fn compact_invalid() {
+        let bytes = test_metas().to_compact_bytes().unwrap();
+        assert!(ConnectMetas::from_compact_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(ConnectMetas::from_compact_bytes(&extended).is_err());
+        let mut wrong_version = bytes;
+        wrong_version[0] = COMPACT_VERSION + 1;
+        assert!(ConnectMetas::from_compact_bytes(&wrong_version).is_err());
+    }
+}
@@ -20,6 +20,15 @@ impl ConnectionType {
     ///
     /// If in WASM and the `wt_client_transport` feature is not enabled, always falls back to [`Self::WasmWs`].
     pub fn inferred() -> Self {
+        Self::inferred_with_preference(false)
+    }
+
+    /// Infers the connection type from the environment, optionally preferring websockets over webtransport.
+    ///
+    /// Set `prefer_ws` to use [`Self::WasmWs`] in WASM even if webtransport is available, e.g. for networks that
+    /// break webtransport (some corporate proxies). Has no effect outside WASM.
+    #[allow(unused_variables)]
+    pub fn inferred_with_preference(prefer_ws: bool) -> Self {
         #[cfg(not(target_family = "wasm"))]
         {
             ConnectionType::Native
@@ -27,7 +36,7 @@ impl ConnectionType {
 
         #[cfg(all(target_family = "wasm", feature = "wt_client_transport"))]
         {
-            match renet2_netcode::webtransport_is_available_with_cert_hashes() {
+            match !prefer_ws && renet2_netcode::webtransport_is_available_with_cert_hashes() {
                 true => ConnectionType::WasmWt,
                 false => ConnectionType::WasmWs,
             }
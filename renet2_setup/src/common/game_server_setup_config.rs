@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use std::{
-    net::{IpAddr, Ipv4Addr},
-    path::PathBuf,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
 };
 
 //-------------------------------------------------------------------------------------------------------------------
@@ -48,6 +48,14 @@ pub struct GameServerSetupConfig {
     ///
     /// Proxy IP addresses will be associated with the local ports assigned to each socket.
     pub proxy_ip: Option<IpAddr>,
+    /// The server's IPv6 address for native sockets, to serve native clients on both IPv4 and IPv6.
+    ///
+    /// If set, an extra IPv6 native socket is bound to the same port as the IPv4 native socket (in which case
+    /// [`Self::server_ip`] should be an IPv4 address). Native connect tokens will contain the public addresses of
+    /// both sockets.
+    pub server_ipv6: Option<Ipv6Addr>,
+    /// Proxy IP address to send to clients in connect tokens instead of [`Self::server_ipv6`].
+    pub proxy_ipv6: Option<Ipv6Addr>,
     /// Domain name to use instead of the proxy_ip for websocket servers.
     ///
     /// This is required if using [`Self::wss_certs`].
@@ -64,6 +72,73 @@ pub struct GameServerSetupConfig {
 }
 
 impl GameServerSetupConfig {
+    /// Makes a [`GameServerSetupConfigBuilder`].
+    pub fn builder(protocol_id: u64, server_ip: IpAddr) -> GameServerSetupConfigBuilder {
+        GameServerSetupConfigBuilder::new(protocol_id, server_ip)
+    }
+
+    /// Checks that the config doesn't contain contradictory settings.
+    ///
+    /// Returns an error if:
+    /// - [`Self::wss_certs`] is set together with [`Self::has_wss_proxy`].
+    /// - [`Self::wss_certs`] is set without [`Self::ws_domain`].
+    /// - A proxy port is set without a proxy address to pair it with (proxy ports are otherwise ignored).
+    pub fn validate(&self) -> Result<(), String> {
+        if self.wss_certs.is_some() && self.has_wss_proxy {
+            return Err("wss_certs can't be used with a TLS proxy (has_wss_proxy)".to_string());
+        }
+        if self.wss_certs.is_some() && self.ws_domain.is_none() {
+            return Err("ws_domain is required when using wss_certs".to_string());
+        }
+        if self.native_port_proxy > 0 && self.proxy_ip.is_none() && self.proxy_ipv6.is_none() {
+            return Err("native_port_proxy is set without proxy_ip or proxy_ipv6".to_string());
+        }
+        if self.wasm_wt_port_proxy > 0 && self.proxy_ip.is_none() {
+            return Err("wasm_wt_port_proxy is set without proxy_ip".to_string());
+        }
+        if self.wasm_ws_port_proxy > 0 && self.proxy_ip.is_none() && self.ws_domain.is_none() {
+            return Err("wasm_ws_port_proxy is set without proxy_ip or ws_domain".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Loads a config from a TOML file.
+    ///
+    /// Relative [`Self::wss_certs`] paths are resolved against the file's directory.
+    #[cfg(feature = "config_files")]
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| format!("failed reading server config at {path:?}: {err:?}"))?;
+        let config: Self = toml::from_str(&contents).map_err(|err| format!("failed parsing server config at {path:?}: {err}"))?;
+        Ok(config.with_paths_relative_to(path))
+    }
+
+    /// Loads a config from a JSON file.
+    ///
+    /// Relative [`Self::wss_certs`] paths are resolved against the file's directory.
+    #[cfg(feature = "config_files")]
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| format!("failed reading server config at {path:?}: {err:?}"))?;
+        let config: Self = serde_json::from_str(&contents).map_err(|err| format!("failed parsing server config at {path:?}: {err}"))?;
+        Ok(config.with_paths_relative_to(path))
+    }
+
+    /// Resolves relative paths against the directory of a config file.
+    #[cfg(feature = "config_files")]
+    fn with_paths_relative_to(mut self, config_path: &Path) -> Self {
+        let dir = config_path.parent().unwrap_or(Path::new(""));
+        if let Some((cert_chain, privkey)) = &mut self.wss_certs {
+            for path in [cert_chain, privkey] {
+                if path.is_relative() {
+                    *path = dir.join(&*path);
+                }
+            }
+        }
+        self
+    }
+
     /// Make a dummy config.
     ///
     /// Should not be used to connect to a real renet server.
@@ -80,6 +155,8 @@ impl GameServerSetupConfig {
             wasm_wt_port_proxy: 0,
             wasm_ws_port_proxy: 0,
             proxy_ip: None,
+            server_ipv6: None,
+            proxy_ipv6: None,
             wss_certs: None,
             ws_domain: None,
             has_wss_proxy: false,
@@ -161,3 +238,186 @@ impl GameServerSetupConfig {
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+/// Builder for [`GameServerSetupConfig`] that validates the config when built.
+#[derive(Debug, Clone)]
+pub struct GameServerSetupConfigBuilder {
+    config: GameServerSetupConfig,
+}
+
+impl GameServerSetupConfigBuilder {
+    /// Makes a new builder.
+    ///
+    /// Connect tokens expire after 10 seconds and connections time out after 5 seconds by default. All ports are `0`
+    /// and no proxies are set.
+    pub fn new(protocol_id: u64, server_ip: IpAddr) -> Self {
+        let mut config = GameServerSetupConfig::dummy();
+        config.protocol_id = protocol_id;
+        config.server_ip = server_ip;
+        Self { config }
+    }
+
+    /// See [`GameServerSetupConfig::expire_secs`].
+    pub fn expire_secs(mut self, expire_secs: u64) -> Self {
+        self.config.expire_secs = expire_secs;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::timeout_secs`].
+    pub fn timeout_secs(mut self, timeout_secs: i32) -> Self {
+        self.config.timeout_secs = timeout_secs;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::native_port`].
+    pub fn native_port(mut self, port: u16) -> Self {
+        self.config.native_port = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::wasm_wt_port`].
+    pub fn wasm_wt_port(mut self, port: u16) -> Self {
+        self.config.wasm_wt_port = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::wasm_ws_port`].
+    pub fn wasm_ws_port(mut self, port: u16) -> Self {
+        self.config.wasm_ws_port = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::native_port_proxy`].
+    pub fn native_port_proxy(mut self, port: u16) -> Self {
+        self.config.native_port_proxy = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::wasm_wt_port_proxy`].
+    pub fn wasm_wt_port_proxy(mut self, port: u16) -> Self {
+        self.config.wasm_wt_port_proxy = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::wasm_ws_port_proxy`].
+    pub fn wasm_ws_port_proxy(mut self, port: u16) -> Self {
+        self.config.wasm_ws_port_proxy = port;
+        self
+    }
+
+    /// See [`GameServerSetupConfig::proxy_ip`].
+    pub fn proxy_ip(mut self, proxy_ip: IpAddr) -> Self {
+        self.config.proxy_ip = Some(proxy_ip);
+        self
+    }
+
+    /// See [`GameServerSetupConfig::server_ipv6`].
+    pub fn server_ipv6(mut self, server_ipv6: Ipv6Addr) -> Self {
+        self.config.server_ipv6 = Some(server_ipv6);
+        self
+    }
+
+    /// See [`GameServerSetupConfig::proxy_ipv6`].
+    pub fn proxy_ipv6(mut self, proxy_ipv6: Ipv6Addr) -> Self {
+        self.config.proxy_ipv6 = Some(proxy_ipv6);
+        self
+    }
+
+    /// See [`GameServerSetupConfig::ws_domain`].
+    pub fn ws_domain(mut self, ws_domain: impl Into<String>) -> Self {
+        self.config.ws_domain = Some(ws_domain.into());
+        self
+    }
+
+    /// See [`GameServerSetupConfig::wss_certs`].
+    pub fn wss_certs(mut self, cert_chain: impl Into<PathBuf>, privkey: impl Into<PathBuf>) -> Self {
+        self.config.wss_certs = Some((cert_chain.into(), privkey.into()));
+        self
+    }
+
+    /// See [`GameServerSetupConfig::has_wss_proxy`].
+    pub fn has_wss_proxy(mut self, has_wss_proxy: bool) -> Self {
+        self.config.has_wss_proxy = has_wss_proxy;
+        self
+    }
+
+    /// Builds the config, see [`GameServerSetupConfig::validate`].
+    pub fn build(self) -> Result<GameServerSetupConfig, String> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> GameServerSetupConfigBuilder {
+        GameServerSetupConfig::builder(7, Ipv4Addr::LOCALHOST.into())
+    }
+
+    #[test]
+    fn build() {
+        let config = builder()
+            .native_port(4000)
+            .wasm_ws_port(4001)
+            .wasm_ws_port_proxy(443)
+            .ws_domain("example.com")
+            .wss_certs("certs/chain.pem", "certs/key.pem")
+            .build()
+            .unwrap();
+        assert_eq!(config.protocol_id, 7);
+        assert_eq!(config.native_port, 4000);
+        assert_eq!(config.wasm_ws_port_proxy, 443);
+        assert_eq!(config.ws_domain.as_deref(), Some("example.com"));
+    }
+
+    #[cfg(feature = "config_files")]
+    #[test]
+    fn config_files() {
+        let dir = std::env::temp_dir().join(format!("renet2_setup_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = builder()
+            .ws_domain("example.com")
+            .wss_certs("certs/chain.pem", dir.join("key.pem"))
+            .build()
+            .unwrap();
+
+        let toml_path = dir.join("server.toml");
+        std::fs::write(&toml_path, toml::to_string(&config).unwrap()).unwrap();
+        let json_path = dir.join("server.json");
+        std::fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let mut expected = config.clone();
+        expected.wss_certs = Some((dir.join("certs/chain.pem"), dir.join("key.pem")));
+        let expected = serde_json::to_string(&expected).unwrap();
+        for loaded in [
+            GameServerSetupConfig::from_toml_file(&toml_path).unwrap(),
+            GameServerSetupConfig::from_json_file(&json_path).unwrap(),
+        ] {
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), expected);
+        }
+
+        assert!(GameServerSetupConfig::from_toml_file(&json_path).is_err());
+        assert!(GameServerSetupConfig::from_json_file(dir.join("missing.json")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_invalid() {
+        let certs = builder().ws_domain("example.com").wss_certs("certs/chain.pem", "certs/key.pem");
+        assert!(certs.clone().build().is_ok());
+        assert!(certs.has_wss_proxy(true).build().is_err());
+        assert!(builder().wss_certs("certs/chain.pem", "certs/key.pem").build().is_err());
+        assert!(builder().native_port_proxy(4000).build().is_err());
+        assert!(builder().wasm_wt_port_proxy(4000).build().is_err());
+        assert!(builder().wasm_ws_port_proxy(4000).build().is_err());
+
+        let proxy_ip = Ipv4Addr::new(1, 2, 3, 4).into();
+        assert!(builder().native_port_proxy(4000).proxy_ip(proxy_ip).build().is_ok());
+        assert!(builder().native_port_proxy(4000).proxy_ipv6(Ipv6Addr::LOCALHOST).build().is_ok());
+        assert!(builder().wasm_wt_port_proxy(4000).proxy_ip(proxy_ip).build().is_ok());
+    }
+}
@@ -1,6 +1,8 @@
 mod address_utils;
 #[cfg(feature = "netcode")]
 mod connect_meta;
+#[cfg(feature = "netcode")]
+mod connect_meta_compact;
 mod connection_type;
 mod game_server_setup_config;
 #[cfg(feature = "netcode")]
@@ -2,6 +2,8 @@ use renet2_netcode::{ConnectToken, NetcodeError, ServerCertHash};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, Bytes};
 
+use std::io::{self, Read};
+
 //-------------------------------------------------------------------------------------------------------------------
 
 pub fn connect_token_to_bytes(connect_token: &ConnectToken) -> Result<Vec<u8>, std::io::Error> {
@@ -57,6 +59,84 @@ pub enum ServerConnectToken {
     },
 }
 
+impl ServerConnectToken {
+    /// Writes a batch of tokens to `writer`, e.g. to persist them in a file for a load tester.
+    ///
+    /// Read the batch back with [`Self::read_batch`]. Fails if any of the tokens are in-memory tokens.
+    pub fn write_batch(tokens: &[ServerConnectToken], writer: &mut impl io::Write) -> Result<(), io::Error> {
+        write_len(writer, tokens.len())?;
+        for token in tokens {
+            match token {
+                Self::Native { token } => {
+                    writer.write_all(&[BATCH_NATIVE])?;
+                    write_bytes(writer, token)?;
+                }
+                Self::WasmWt { token, cert_hashes } => {
+                    writer.write_all(&[BATCH_WASM_WT])?;
+                    write_bytes(writer, token)?;
+                    write_len(writer, cert_hashes.len())?;
+                    for cert_hash in cert_hashes {
+                        writer.write_all(&cert_hash.hash)?;
+                    }
+                }
+                Self::WasmWs { token, url } => {
+                    writer.write_all(&[BATCH_WASM_WS])?;
+                    write_bytes(writer, token)?;
+                    write_bytes(writer, url.as_str().as_bytes())?;
+                }
+                #[cfg(feature = "memory_transport")]
+                Self::Memory { .. } => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "in-memory connect tokens cannot be written to a batch",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a batch of tokens written by [`Self::write_batch`].
+    pub fn read_batch(src: &mut impl io::Read) -> Result<Vec<ServerConnectToken>, io::Error> {
+        let len = read_len(src)?;
+        let mut tokens = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            let mut kind = [0u8; 1];
+            src.read_exact(&mut kind)?;
+            let token = match kind[0] {
+                BATCH_NATIVE => Self::Native { token: read_bytes(src)? },
+                BATCH_WASM_WT => {
+                    let token = read_bytes(src)?;
+                    let num_hashes = read_len(src)?;
+                    let mut cert_hashes = Vec::with_capacity(num_hashes.min(16));
+                    for _ in 0..num_hashes {
+                        let mut hash = [0u8; 32];
+                        src.read_exact(&mut hash)?;
+                        cert_hashes.push(ServerCertHash { hash });
+                    }
+                    Self::WasmWt { token, cert_hashes }
+                }
+                BATCH_WASM_WS => {
+                    let token = read_bytes(src)?;
+                    let url = String::from_utf8(read_bytes(src)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    let url = url::Url::parse(&url).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    Self::WasmWs { token, url }
+                }
+                kind => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown connect token kind {kind} in batch"),
+                    ));
+                }
+            };
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+}
+
 impl Default for ServerConnectToken {
     fn default() -> Self {
         Self::Native { token: vec![] }
@@ -64,3 +144,35 @@ impl Default for ServerConnectToken {
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+const BATCH_NATIVE: u8 = 0;
+const BATCH_WASM_WT: u8 = 1;
+const BATCH_WASM_WS: u8 = 2;
+
+fn write_len(writer: &mut impl io::Write, len: usize) -> Result<(), io::Error> {
+    let len = u32::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "batch entry is too long"))?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+fn read_len(src: &mut impl io::Read) -> Result<usize, io::Error> {
+    let mut len = [0u8; 4];
+    src.read_exact(&mut len)?;
+    Ok(u32::from_le_bytes(len) as usize)
+}
+
+fn write_bytes(writer: &mut impl io::Write, bytes: &[u8]) -> Result<(), io::Error> {
+    write_len(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(src: &mut impl io::Read) -> Result<Vec<u8>, io::Error> {
+    let len = read_len(src)?;
+    let mut bytes = Vec::default();
+    src.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+//-------------------------------------------------------------------------------------------------------------------
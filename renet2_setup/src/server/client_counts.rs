@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::common::ConnectionType;
 
 //-------------------------------------------------------------------------------------------------------------------
@@ -7,13 +9,16 @@ use crate::common::ConnectionType;
 /// Used by [`setup_combo_renet2_server`] to set max-client limits and determine what server sockets are required.
 /// Note that we assume clients will not change connection type throughout a game. If you want to allow clients
 /// to change connection type, then set each count below equal to the total number of clients.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClientCounts {
     /// The ids of in-memory clients that will connect.
     ///
     /// Ids must be in the range `[0, u16::MAX)`.
     pub memory_clients: Vec<u16>,
     /// The number of native clients that will connect.
+    ///
+    /// This is shared by the IPv4 and IPv6 sockets of dual-stack servers (see
+    /// [`GameServerSetupConfig::server_ipv6`](crate::GameServerSetupConfig::server_ipv6)).
     pub native_count: usize,
     /// The number of WASM webtransport clients that will connect.
     pub wasm_wt_count: usize,
@@ -1,5 +1,7 @@
 mod client_counts;
 mod renet2_setup;
+mod server_plan;
 
 pub use client_counts::*;
 pub use renet2_setup::*;
+pub use server_plan::*;
@@ -53,7 +53,7 @@ fn add_memory_socket(
 
     #[cfg(feature = "memory_transport")]
     {
-        let (server_socket, client_sockets) = renet2_netcode::new_memory_sockets(memory_clients, true, true);
+        let (server_socket, client_sockets) = renet2_netcode::new_memory_sockets(memory_clients, true, true, None);
         let addrs = vec![renet2_netcode::in_memory_server_addr()];
 
         let meta = crate::ConnectMetaMemory {
@@ -94,20 +94,53 @@ fn add_native_socket(
     #[cfg(feature = "native_transport")]
     {
         use renet2_netcode::ServerSocket;
-        let wildcard_addr = SocketAddr::new(config.server_ip, config.native_port);
-        let server_socket = std::net::UdpSocket::bind(wildcard_addr)
-            .map_err(|err| format!("failed binding renet2 server address {wildcard_addr:?}: {err:?}"))?;
-        let socket =
-            renet2_netcode::NativeSocket::new(server_socket).map_err(|err| format!("failed constructing renet2 native socket: {err:?}"))?;
+        use std::net::IpAddr;
+        let (socket, ipv6_socket) = match (config.server_ip, config.server_ipv6) {
+            (IpAddr::V4(server_ip), Some(server_ipv6)) => {
+                let (socket, ipv6_socket) = renet2_netcode::bind_dual_stack_on(server_ip, server_ipv6, config.native_port)
+                    .map_err(|err| format!("failed binding renet2 dual-stack native sockets: {err:?}"))?;
+                (socket, Some(ipv6_socket))
+            }
+            (IpAddr::V6(_), Some(_)) => {
+                return Err("tried setting up renet2 dual-stack native sockets, but server_ip is not an IPv4 address".to_string());
+            }
+            (_, None) => {
+                let wildcard_addr = SocketAddr::new(config.server_ip, config.native_port);
+                let server_socket = std::net::UdpSocket::bind(wildcard_addr)
+                    .map_err(|err| format!("failed binding renet2 server address {wildcard_addr:?}: {err:?}"))?;
+                let socket = renet2_netcode::NativeSocket::new(server_socket)
+                    .map_err(|err| format!("failed constructing renet2 native socket: {err:?}"))?;
+                (socket, None)
+            }
+        };
         let local_addr = socket
             .addr()
             .map_err(|err| format!("failed getting local addr for renet2 native socket: {err:?}"))?;
         let public_port = if config.native_port_proxy > 0 { config.native_port_proxy } else { local_addr.port() };
         let addrs = if let Some(proxy) = config.proxy_ip { vec![SocketAddr::new(proxy, public_port)] } else { vec![local_addr] };
 
+        // Connect tokens list the public addresses of both sockets, the sockets share connect tokens once the
+        // transport is constructed.
+        let mut token_addrs = addrs.clone();
+        let ipv6_socket = match ipv6_socket {
+            Some(ipv6_socket) => {
+                let local_addr = ipv6_socket
+                    .addr()
+                    .map_err(|err| format!("failed getting local addr for renet2 native socket: {err:?}"))?;
+                let addrs = match config.proxy_ipv6 {
+                    Some(proxy) => vec![SocketAddr::new(proxy.into(), public_port)],
+                    None => vec![local_addr],
+                };
+                log::info!("native renet2 IPv6 socket; local addr = {}, public addr = {}", local_addr, addrs[0]);
+                token_addrs.extend_from_slice(&addrs);
+                Some((ipv6_socket, addrs))
+            }
+            None => None,
+        };
+
         let meta = ConnectMetaNative {
             server_config: config.clone(),
-            server_addresses: addrs.clone(),
+            server_addresses: token_addrs,
             socket_id: sockets.len() as u8, // DO THIS BEFORE PUSHING SOCKET
             auth_key: *auth_key,
         };
@@ -116,6 +149,10 @@ fn add_native_socket(
 
         socket_addresses.push(addrs);
         sockets.push(BoxedSocket::new(socket));
+        if let Some((ipv6_socket, ipv6_addrs)) = ipv6_socket {
+            socket_addresses.push(ipv6_addrs);
+            sockets.push(BoxedSocket::new(ipv6_socket));
+        }
 
         Ok(Some(meta))
     }
@@ -210,8 +247,9 @@ fn add_wasm_ws_socket(
         let listen = SocketAddr::new(config.server_ip, config.wasm_ws_port);
         let ws_config = renet2_netcode::WebSocketServerConfig {
             acceptor,
-            listen,
+            listen: listen.into(),
             max_clients: count,
+            ws_ping_interval: renet2_netcode::ws_ping_interval_from_timeout(config.timeout_secs),
         };
         let handle = enfync::builtin::native::TokioHandle::adopt_or_default(); //todo: don't depend on tokio...
         let socket = renet2_netcode::WebSocketServer::new(ws_config, handle.0)
@@ -315,6 +353,9 @@ pub fn setup_combo_renet2_server_with_key(
     log::info!("setting up renet2 server");
 
     let max_clients = counts.total();
+    if max_clients == 0 {
+        return Err("tried setting up renet2 server with no clients; at least one transport is required".to_string());
+    }
 
     // add sockets
     let mut socket_addresses = Vec::default();
@@ -324,6 +365,9 @@ pub fn setup_combo_renet2_server_with_key(
     let native_meta = add_native_socket(&config, counts.native_count, &mut socket_addresses, &mut sockets, auth_key)?;
     let wasm_wt_meta = add_wasm_wt_socket(&config, counts.wasm_wt_count, &mut socket_addresses, &mut sockets, auth_key)?;
     let wasm_ws_meta = add_wasm_ws_socket(&config, counts.wasm_ws_count, &mut socket_addresses, &mut sockets, auth_key)?;
+    if sockets.is_empty() {
+        return Err("tried setting up renet2 server, but no sockets were created".to_string());
+    }
 
     let connect_metas = ConnectMetas {
         memory: memory_meta,
@@ -343,9 +387,16 @@ pub fn setup_combo_renet2_server_with_key(
 
     // construct server
     let server = RenetServer::new(connection_config);
-    let server_transport = NetcodeServerTransport::new_with_sockets(server_config, sockets)
+    let mut server_transport = NetcodeServerTransport::new_with_sockets(server_config, sockets)
         .map_err(|err| format!("failed constructing renet2 netcode server transport: {err:?}"))?;
 
+    // Dual-stack native sockets are added right after each other.
+    if let (Some(native_meta), Some(_)) = (&connect_metas.native, config.server_ipv6) {
+        let socket_id = native_meta.socket_id as usize;
+        server_transport.set_shares_connect_tokens(socket_id, true);
+        server_transport.set_shares_connect_tokens(socket_id + 1, true);
+    }
+
     Ok((server, server_transport, connect_metas))
 }
 
@@ -435,3 +486,53 @@ pub fn setup_combo_renet2_server_in_bevy(
 }
 
 //-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_clients() {
+        let result = setup_combo_renet2_server(GameServerSetupConfig::dummy(), ClientCounts::default(), ConnectionConfig::test());
+        assert!(result.is_err());
+    }
+
+    #[cfg(all(feature = "native_transport", feature = "client"))]
+    #[test]
+    fn connect_with_user_data() {
+        use crate::{setup_renet2_client, ClientConnectPack, ConnectionType};
+        use renet2_netcode::NETCODE_USER_DATA_BYTES;
+        use std::time::Duration;
+
+        let counts = ClientCounts {
+            native_count: 1,
+            ..Default::default()
+        };
+        let (mut server, mut server_transport, metas) =
+            setup_combo_renet2_server(GameServerSetupConfig::dummy(), counts, ConnectionConfig::test()).unwrap();
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let user_data = [7u8; NETCODE_USER_DATA_BYTES];
+        let token = metas
+            .new_connect_token_with_user_data(current_time, 1, ConnectionType::Native, user_data)
+            .unwrap();
+        let connect_pack = ClientConnectPack::new(0, token).unwrap();
+        let (mut client, mut client_transport) = setup_renet2_client(ConnectionConfig::test(), connect_pack).unwrap();
+
+        let dt = Duration::from_millis(10);
+        for _ in 0..100 {
+            client.update(dt);
+            client_transport.update(dt, &mut client).unwrap();
+            client_transport.send_packets(&mut client).unwrap();
+            server.update(dt);
+            server_transport.update(dt, &mut server).unwrap();
+            server_transport.send_packets(&mut server);
+            if client.is_connected() {
+                break;
+            }
+            std::thread::sleep(dt);
+        }
+        assert!(client.is_connected());
+        assert_eq!(server_transport.user_data(1), Some(user_data));
+    }
+}
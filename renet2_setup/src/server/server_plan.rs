@@ -0,0 +1,87 @@
+use renet2::{ConnectionConfig, RenetServer};
+use renet2_netcode::NetcodeServerTransport;
+use serde::{Deserialize, Serialize};
+
+use super::{setup_combo_renet2_server, ClientCounts};
+use crate::common::{ConnectMetas, GameServerSetupConfig};
+
+//-------------------------------------------------------------------------------------------------------------------
+
+/// Serializable description of a renet2 server to set up.
+///
+/// Lets a controller store or transmit exactly which server to spin up, then reconstruct it with [`Self::build`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerPlan {
+    /// Configuration of the server's sockets.
+    pub config: GameServerSetupConfig,
+    /// The clients the server should accept.
+    pub counts: ClientCounts,
+}
+
+impl ServerPlan {
+    /// Sets up the server described by this plan with [`setup_combo_renet2_server`].
+    ///
+    /// The `connection_config` is not part of the plan because it can't be serialized.
+    pub fn build(self, connection_config: ConnectionConfig) -> Result<(RenetServer, NetcodeServerTransport, ConnectMetas), String> {
+        setup_combo_renet2_server(self.config, self.counts, connection_config)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> ServerPlan {
+        ServerPlan {
+            config: GameServerSetupConfig::dummy(),
+            counts: ClientCounts {
+                memory_clients: vec![1, 2],
+                native_count: 3,
+                wasm_wt_count: 0,
+                wasm_ws_count: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let plan = plan();
+        let json = serde_json::to_string(&plan).unwrap();
+        let restored: ServerPlan = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.counts, plan.counts);
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    }
+
+    #[cfg(all(feature = "memory_transport", feature = "native_transport"))]
+    #[test]
+    fn build() {
+        let (server, transport, metas) = plan().build(ConnectionConfig::test()).unwrap();
+        assert_eq!(server.connected_clients(), 0);
+        assert_eq!(transport.max_clients(), 5);
+        assert!(metas.memory.is_some());
+        assert!(metas.native.is_some());
+    }
+
+    #[cfg(all(feature = "memory_transport", feature = "native_transport"))]
+    #[test]
+    fn build_dual_stack() {
+        let mut plan = plan();
+        plan.config.server_ipv6 = Some(std::net::Ipv6Addr::LOCALHOST);
+        let (_, transport, metas) = plan.build(ConnectionConfig::test()).unwrap();
+
+        // The IPv6 socket doesn't add to the client budget.
+        assert_eq!(transport.max_clients(), 5);
+
+        // Memory socket + IPv4 and IPv6 native sockets.
+        let native = metas.native.unwrap();
+        assert_eq!(transport.get_addresses(2), Some(vec![native.server_addresses[1]]));
+        assert_eq!(transport.get_addresses(3), None);
+        assert_eq!(native.server_addresses.len(), 2);
+        assert!(native.server_addresses[0].is_ipv4());
+        assert!(native.server_addresses[1].is_ipv6());
+        assert_eq!(native.server_addresses[0].port(), native.server_addresses[1].port());
+    }
+}
@@ -15,6 +15,7 @@ enum ConnectionState {
 pub struct SteamClientTransport {
     networking_sockets: NetworkingSockets<ClientManager>,
     state: ConnectionState,
+    client_id: u64,
 }
 
 impl SteamClientTransport {
@@ -28,6 +29,7 @@ impl SteamClientTransport {
         Ok(Self {
             networking_sockets,
             state: ConnectionState::Connected { connection },
+            client_id: client.user().steam_id().raw(),
         })
     }
 
@@ -120,6 +122,7 @@ impl SteamClientTransport {
         };
 
         if self.is_connected() {
+            client.set_client_id(self.client_id);
             client.set_connected();
         } else if self.is_connecting() {
             client.set_connecting();
@@ -3,9 +3,11 @@
 const MAX_MESSAGE_BATCH_SIZE: usize = 512;
 
 mod client;
+mod lobby;
 mod server;
 
 pub use client::SteamClientTransport;
+pub use lobby::{create_lobby, join_lobby, LobbyError, PendingLobby, SteamLobby, STEAM_LOBBY_MAX_MEMBERS};
 pub use server::{AccessPermission, SteamServerConfig, SteamServerTransport};
 
 #[doc(hidden)]
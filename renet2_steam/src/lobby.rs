@@ -0,0 +1,151 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use steamworks::{networking_sockets::InvalidHandle, Client, ClientManager, LobbyId, LobbyType, SteamError, SteamId};
+
+use crate::{AccessPermission, SteamClientTransport, SteamServerConfig, SteamServerTransport};
+
+/// The maximum number of members a steam lobby can have.
+pub const STEAM_LOBBY_MAX_MEMBERS: u32 = 250;
+
+/// Error from [`create_lobby`] or [`join_lobby`].
+#[derive(Debug)]
+pub enum LobbyError {
+    /// Steam failed to create the lobby.
+    Create(SteamError),
+    /// The lobby was requested with more than [`STEAM_LOBBY_MAX_MEMBERS`] members.
+    TooManyMembers(u32),
+    /// Steam failed to join the lobby, e.g. because it is full or doesn't exist.
+    Join,
+    /// The steam callback was dropped without reporting a result.
+    Cancelled,
+}
+
+impl std::fmt::Display for LobbyError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LobbyError::Create(e) => write!(fmt, "failed to create lobby: {e}"),
+            LobbyError::TooManyMembers(max_members) => {
+                write!(
+                    fmt,
+                    "lobby can't have {max_members} members, the maximum is {STEAM_LOBBY_MAX_MEMBERS}"
+                )
+            }
+            LobbyError::Join => write!(fmt, "failed to join lobby"),
+            LobbyError::Cancelled => write!(fmt, "lobby request was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for LobbyError {}
+
+/// A lobby request waiting for steam's response, see [`create_lobby`] and [`join_lobby`].
+///
+/// Steam reports the result in a callback, so `SingleClient::run_callbacks` must be called until
+/// [`PendingLobby::poll`] returns a result.
+pub struct PendingLobby {
+    client: Client<ClientManager>,
+    receiver: Receiver<Result<LobbyId, LobbyError>>,
+}
+
+impl PendingLobby {
+    /// Returns the lobby once steam has responded.
+    pub fn poll(&self) -> Option<Result<SteamLobby, LobbyError>> {
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(LobbyError::Cancelled),
+        };
+
+        Some(result.map(|lobby_id| SteamLobby {
+            client: self.client.clone(),
+            lobby_id,
+        }))
+    }
+}
+
+/// Creates a steam lobby owned by the local user, with room for `max_members` users including the owner.
+///
+/// Once created, use [`SteamLobby::server_transport`] to host a server that only accepts lobby members.
+///
+/// Returns [`LobbyError::TooManyMembers`] if `max_members` is above [`STEAM_LOBBY_MAX_MEMBERS`], which steam doesn't
+/// support.
+pub fn create_lobby(client: &Client<ClientManager>, lobby_type: LobbyType, max_members: u32) -> Result<PendingLobby, LobbyError> {
+    if max_members > STEAM_LOBBY_MAX_MEMBERS {
+        return Err(LobbyError::TooManyMembers(max_members));
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    client.matchmaking().create_lobby(lobby_type, max_members, move |result| {
+        let _ = sender.send(result.map_err(LobbyError::Create));
+    });
+
+    Ok(PendingLobby {
+        client: client.clone(),
+        receiver,
+    })
+}
+
+/// Joins an existing steam lobby.
+///
+/// Once joined, use [`SteamLobby::client_transport`] to connect to the lobby owner's server.
+pub fn join_lobby(client: &Client<ClientManager>, lobby_id: LobbyId) -> PendingLobby {
+    let (sender, receiver) = mpsc::channel();
+    client.matchmaking().join_lobby(lobby_id, move |result| {
+        let _ = sender.send(result.map_err(|_| LobbyError::Join));
+    });
+
+    PendingLobby {
+        client: client.clone(),
+        receiver,
+    }
+}
+
+/// A steam lobby the local user is a member of.
+///
+/// The lobby owner hosts the renet server and the other members connect to it as clients.
+pub struct SteamLobby {
+    client: Client<ClientManager>,
+    lobby_id: LobbyId,
+}
+
+impl SteamLobby {
+    pub fn id(&self) -> LobbyId {
+        self.lobby_id
+    }
+
+    /// Gets the current owner of the lobby.
+    pub fn owner(&self) -> SteamId {
+        self.client.matchmaking().lobby_owner(self.lobby_id)
+    }
+
+    /// Returns `true` if the local user owns the lobby.
+    pub fn is_owner(&self) -> bool {
+        self.owner() == self.client.user().steam_id()
+    }
+
+    /// Gets the current members of the lobby, including the owner.
+    ///
+    /// The list is updated by steam callbacks, so it can be polled to show a pre-game lobby.
+    pub fn members(&self) -> Vec<SteamId> {
+        self.client.matchmaking().lobby_members(self.lobby_id)
+    }
+
+    /// Makes a server transport that only accepts members of the lobby, see [`AccessPermission::InLobby`].
+    pub fn server_transport(&self, max_clients: usize) -> Result<SteamServerTransport, InvalidHandle> {
+        let config = SteamServerConfig {
+            max_clients,
+            access_permission: AccessPermission::InLobby(self.lobby_id),
+        };
+        SteamServerTransport::new(&self.client, config)
+    }
+
+    /// Makes a client transport that connects to the lobby owner.
+    pub fn client_transport(&self) -> Result<SteamClientTransport, InvalidHandle> {
+        SteamClientTransport::new(&self.client, &self.owner())
+    }
+
+    /// Leaves the lobby.
+    pub fn leave(self) {
+        self.client.matchmaking().leave_lobby(self.lobby_id);
+    }
+}
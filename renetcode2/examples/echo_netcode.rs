@@ -131,6 +131,10 @@ fn server(addr: SocketAddr, private_key: [u8; NETCODE_KEY_BYTES]) {
         current_time,
         max_clients: 16,
         protocol_id: PROTOCOL_ID,
+        accepted_protocol_ids: Vec::new(),
+        capacity_thresholds: Vec::new(),
+        randomize_initial_sequence: false,
+        connection_rate_limit: None,
         sockets: vec![ServerSocketConfig::new(vec![addr])],
         authentication: ServerAuthentication::Secure { private_key },
     };
@@ -1,20 +1,36 @@
 use std::{error::Error, fmt, net::SocketAddr, time::Duration};
 
+#[cfg(feature = "extensions")]
+use crate::{
+    extension::PacketExtensions,
+    packet::{decode_extension_packet, encode_extension_packet, extension_packet_type},
+    PacketExtension,
+};
 use crate::{
     packet::Packet, replay_protection::ReplayProtection, token::ConnectToken, NetcodeError, NETCODE_CHALLENGE_TOKEN_BYTES,
-    NETCODE_KEY_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_SEND_RATE, NETCODE_USER_DATA_BYTES,
+    NETCODE_DENIED_EXPIRED, NETCODE_DENIED_PROTOCOL_MISMATCH, NETCODE_KEY_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES,
+    NETCODE_SEND_RATE, NETCODE_USER_DATA_BYTES,
 };
 
 /// The reason why a client is in error state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum DisconnectReason {
     ConnectTokenExpired,
     ConnectionTimedOut,
     ConnectionResponseTimedOut,
     ConnectionRequestTimedOut,
     ConnectionDenied,
+    /// The server denied the connection because it doesn't accept the connect token's protocol id, e.g. because
+    /// the client is outdated.
+    ProtocolMismatch,
     DisconnectedByClient,
-    DisconnectedByServer,
+    /// The server disconnected the client, with an application-defined reason code.
+    ///
+    /// The code is 0 unless the server used [`NetcodeServer::disconnect_with_reason`](crate::NetcodeServer::disconnect_with_reason).
+    DisconnectedByServer {
+        reason: u8,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,6 +41,37 @@ enum ClientState {
     Connected,
 }
 
+/// Times at which each phase of the connection handshake completed, see [`NetcodeClient::handshake_timings`].
+///
+/// All times are measured with the client's clock (see [`NetcodeClient::current_time`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandshakeTimings {
+    /// When the first connection request was sent.
+    pub request_sent: Duration,
+    /// When the challenge from the server was received.
+    pub challenge_received: Duration,
+    /// When the first challenge response was sent.
+    pub response_sent: Duration,
+    /// When the keep-alive that completed the connection was received.
+    pub keep_alive_received: Duration,
+}
+
+impl HandshakeTimings {
+    /// Returns the time between sending the first connection request and being connected.
+    pub fn total(&self) -> Duration {
+        self.keep_alive_received.saturating_sub(self.request_sent)
+    }
+}
+
+/// Handshake phases completed so far.
+#[derive(Debug, Clone, Copy, Default)]
+struct HandshakeProgress {
+    request_sent: Option<Duration>,
+    challenge_received: Option<Duration>,
+    response_sent: Option<Duration>,
+    keep_alive_received: Option<Duration>,
+}
+
 /// Configuration to establish a secure or unsecure connection with the server.
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
@@ -65,12 +112,16 @@ pub struct NetcodeClient {
     challenge_token_data: [u8; NETCODE_CHALLENGE_TOKEN_BYTES],
     max_clients: u32,
     client_index: u32,
+    confirmed_user_data: Option<[u8; NETCODE_USER_DATA_BYTES]>,
+    handshake: HandshakeProgress,
     send_rate: Duration,
     replay_protection: ReplayProtection,
     out: [u8; NETCODE_MAX_PACKET_BYTES],
     /// Extension to netcode to allow disabling netcode encryption if the underlying data stream is already
     /// encrypted.
     encryption_policy: bool,
+    #[cfg(feature = "extensions")]
+    extensions: PacketExtensions,
 }
 
 impl fmt::Display for DisconnectReason {
@@ -83,8 +134,10 @@ impl fmt::Display for DisconnectReason {
             ConnectionResponseTimedOut => write!(f, "connection timed out during response step"),
             ConnectionRequestTimedOut => write!(f, "connection timed out during request step"),
             ConnectionDenied => write!(f, "server denied connection"),
+            ProtocolMismatch => write!(f, "server denied connection, protocol id mismatch"),
             DisconnectedByClient => write!(f, "connection terminated by client"),
-            DisconnectedByServer => write!(f, "connection terminated by server"),
+            DisconnectedByServer { reason: 0 } => write!(f, "connection terminated by server"),
+            DisconnectedByServer { reason } => write!(f, "connection terminated by server (reason {reason})"),
         }
     }
 }
@@ -129,12 +182,16 @@ impl NetcodeClient {
             current_time,
             max_clients: 0,
             client_index: 0,
+            confirmed_user_data: None,
+            handshake: HandshakeProgress::default(),
             send_rate: NETCODE_SEND_RATE,
             challenge_token_data: [0u8; NETCODE_CHALLENGE_TOKEN_BYTES],
             connect_token,
             replay_protection: ReplayProtection::new(),
             out: [0u8; NETCODE_MAX_PACKET_BYTES],
             encryption_policy: true,
+            #[cfg(feature = "extensions")]
+            extensions: PacketExtensions::default(),
         })
     }
 
@@ -175,6 +232,20 @@ impl NetcodeClient {
         self.current_time - self.last_packet_received_time
     }
 
+    /// Returns the times at which each phase of the connection handshake completed.
+    ///
+    /// This is `None` until the client is connected. Useful to find out whether the transport or the netcode round
+    /// trips dominate the time it takes to connect.
+    pub fn handshake_timings(&self) -> Option<HandshakeTimings> {
+        let handshake = self.handshake;
+        Some(HandshakeTimings {
+            request_sent: handshake.request_sent?,
+            challenge_received: handshake.challenge_received?,
+            response_sent: handshake.response_sent?,
+            keep_alive_received: handshake.keep_alive_received?,
+        })
+    }
+
     /// Returns the reason that the client was disconnected for.
     pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
         if let ClientState::Disconnected(reason) = &self.state {
@@ -183,6 +254,14 @@ impl NetcodeClient {
         None
     }
 
+    /// Returns the user data the server echoed back when the connection was established.
+    ///
+    /// This is `None` until the client is connected, or if the server does not echo user data
+    /// (see [`NetcodeServer::set_echo_user_data`](crate::NetcodeServer::set_echo_user_data)).
+    pub fn confirmed_user_data(&self) -> Option<[u8; NETCODE_USER_DATA_BYTES]> {
+        self.confirmed_user_data
+    }
+
     /// Returns the current server address the client is connected or trying to connect.
     pub fn server_addr(&self) -> SocketAddr {
         self.server_addr
@@ -192,7 +271,7 @@ impl NetcodeClient {
     /// Returns a disconnect packet that should be sent to the server.
     pub fn disconnect(&mut self) -> Result<(SocketAddr, &mut [u8]), NetcodeError> {
         self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByClient);
-        let packet = Packet::Disconnect;
+        let packet = Packet::Disconnect { reason: 0 };
         let len = packet.encode(
             &mut self.out,
             self.connect_token.protocol_id,
@@ -207,6 +286,12 @@ impl NetcodeClient {
     /// server. If nothing is returned, it was a packet used for the internal protocol or an
     /// invalid packet.
     pub fn process_packet<'a>(&mut self, buffer: &'a mut [u8]) -> Option<&'a [u8]> {
+        #[cfg(feature = "extensions")]
+        if let Some(packet_type) = extension_packet_type(buffer) {
+            self.process_extension_packet(packet_type, buffer);
+            return None;
+        }
+
         let packet = match Packet::decode(
             buffer,
             self.connect_token.protocol_id,
@@ -223,8 +308,13 @@ impl NetcodeClient {
         log::trace!("Received packet from server: {:?}", packet.packet_type());
 
         match (packet, &self.state) {
-            (Packet::ConnectionDenied, ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse) => {
-                self.state = ClientState::Disconnected(DisconnectReason::ConnectionDenied);
+            (Packet::ConnectionDenied { reason }, ClientState::SendingConnectionRequest | ClientState::SendingConnectionResponse) => {
+                let reason = match reason {
+                    NETCODE_DENIED_PROTOCOL_MISMATCH => DisconnectReason::ProtocolMismatch,
+                    NETCODE_DENIED_EXPIRED => DisconnectReason::ConnectTokenExpired,
+                    _ => DisconnectReason::ConnectionDenied,
+                };
+                self.state = ClientState::Disconnected(reason);
                 self.last_packet_received_time = self.current_time;
             }
             (
@@ -238,23 +328,33 @@ impl NetcodeClient {
                 self.last_packet_received_time = self.current_time;
                 self.last_packet_send_time = None;
                 self.challenge_token_data = token_data;
+                self.handshake.challenge_received = Some(self.current_time);
                 self.state = ClientState::SendingConnectionResponse;
             }
             (Packet::KeepAlive { .. }, ClientState::Connected) => {
                 self.last_packet_received_time = self.current_time;
             }
-            (Packet::KeepAlive { client_index, max_clients }, ClientState::SendingConnectionResponse) => {
+            (
+                Packet::KeepAlive {
+                    client_index,
+                    max_clients,
+                    user_data,
+                },
+                ClientState::SendingConnectionResponse,
+            ) => {
                 self.last_packet_received_time = self.current_time;
                 self.max_clients = max_clients;
                 self.client_index = client_index;
+                self.confirmed_user_data = user_data.copied();
+                self.handshake.keep_alive_received = Some(self.current_time);
                 self.state = ClientState::Connected;
             }
             (Packet::Payload(p), ClientState::Connected) => {
                 self.last_packet_received_time = self.current_time;
                 return Some(p);
             }
-            (Packet::Disconnect, ClientState::Connected) => {
-                self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByServer);
+            (Packet::Disconnect { reason }, ClientState::Connected) => {
+                self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByServer { reason });
                 self.last_packet_received_time = self.current_time;
             }
             _ => {}
@@ -286,6 +386,67 @@ impl NetcodeClient {
         Ok((self.server_addr, &mut self.out[..len]))
     }
 
+    /// Registers the handler for extension packets of `packet_type` received from the server, replacing any
+    /// previous handler for that type.
+    ///
+    /// Returns [`NetcodeError::InvalidPacketType`] if `packet_type` is not one of the
+    /// [`NETCODE_EXTENSION_PACKET_TYPES`](crate::NETCODE_EXTENSION_PACKET_TYPES). See [`PacketExtension`].
+    #[cfg(feature = "extensions")]
+    pub fn register_extension(&mut self, packet_type: u8, extension: impl PacketExtension) -> Result<(), NetcodeError> {
+        self.extensions.register(packet_type, Box::new(extension))
+    }
+
+    /// Returns the server address and an encoded extension packet of `packet_type` to be sent to the server.
+    /// See [`PacketExtension`].
+    #[cfg(feature = "extensions")]
+    pub fn generate_extension_packet(&mut self, packet_type: u8, payload: &[u8]) -> Result<(SocketAddr, &mut [u8]), NetcodeError> {
+        if payload.len() > NETCODE_MAX_PAYLOAD_BYTES {
+            return Err(NetcodeError::PayloadAboveLimit);
+        }
+
+        if self.state != ClientState::Connected {
+            return Err(NetcodeError::ClientNotConnected);
+        }
+
+        let len = encode_extension_packet(
+            &mut self.out,
+            packet_type,
+            payload,
+            self.connect_token.protocol_id,
+            (self.sequence, &self.connect_token.client_to_server_key),
+            self.encryption_policy,
+        )?;
+        self.sequence += 1;
+        self.last_packet_send_time = Some(self.current_time);
+
+        Ok((self.server_addr, &mut self.out[..len]))
+    }
+
+    #[cfg(feature = "extensions")]
+    fn process_extension_packet(&mut self, packet_type: u8, buffer: &mut [u8]) {
+        if self.state != ClientState::Connected {
+            return;
+        }
+
+        let payload = match decode_extension_packet(
+            buffer,
+            self.connect_token.protocol_id,
+            &self.connect_token.server_to_client_key,
+            &mut self.replay_protection,
+            self.encryption_policy,
+        ) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::error!("Failed to decode extension packet: {}", e);
+                return;
+            }
+        };
+        log::trace!("Received extension packet from server: {}", packet_type);
+
+        self.last_packet_received_time = self.current_time;
+        self.extensions.receive(packet_type, self.client_id, payload);
+    }
+
     /// Update the internal state of the client, receives the duration since last updated.
     /// Might return the serve address and a protocol packet to be sent to the server.
     pub fn update(&mut self, duration: Duration) -> Option<(&mut [u8], SocketAddr)> {
@@ -332,6 +493,7 @@ impl NetcodeClient {
                             self.last_packet_send_time = None;
                             self.last_packet_received_time = self.current_time;
                             self.challenge_token_sequence = 0;
+                            self.handshake = HandshakeProgress::default();
 
                             return Ok(());
                         }
@@ -373,6 +535,7 @@ impl NetcodeClient {
             ClientState::Connected => Packet::KeepAlive {
                 client_index: 0,
                 max_clients: 0,
+                user_data: None,
             },
             _ => return None,
         };
@@ -387,6 +550,15 @@ impl NetcodeClient {
             Err(_) => None,
             Ok(encoded) => {
                 self.sequence += 1;
+                match self.state {
+                    ClientState::SendingConnectionRequest => {
+                        self.handshake.request_sent.get_or_insert(self.current_time);
+                    }
+                    ClientState::SendingConnectionResponse => {
+                        self.handshake.response_sent.get_or_insert(self.current_time);
+                    }
+                    _ => {}
+                }
                 Some((&mut self.out[..encoded], self.server_addr))
             }
         }
@@ -447,7 +619,11 @@ mod tests {
 
         let max_clients = 4;
         let client_index = 2;
-        let keep_alive_packet = Packet::KeepAlive { max_clients, client_index };
+        let keep_alive_packet = Packet::KeepAlive {
+            max_clients,
+            client_index,
+            user_data: None,
+        };
         let len = keep_alive_packet
             .encode(&mut buffer, protocol_id, Some((1, &server_key)), true)
             .unwrap();
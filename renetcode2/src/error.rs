@@ -14,7 +14,7 @@ pub enum NetcodeError {
     InvalidProtocolID,
     /// The connect token has an invalid version.
     InvalidVersion,
-    /// The connect token was transmitted by an invalid socket.
+    /// The packet or connect token was received by an invalid socket.
     InvalidSocketId,
     /// Packet size is too small to be a netcode packet.
     PacketTooSmall,
@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+use crate::NetcodeError;
+
+/// The packet types reserved for [`PacketExtension`]s.
+///
+/// Built-in netcode packets never use these types, so extension packets can't be mistaken for them.
+pub const NETCODE_EXTENSION_PACKET_TYPES: RangeInclusive<u8> = 8..=15;
+
+/// Handles custom packet types sent between a connected client and server.
+///
+/// Extension packets are sent with [`NetcodeServer::generate_extension_packet`](crate::NetcodeServer::generate_extension_packet)
+/// or [`NetcodeClient::generate_extension_packet`](crate::NetcodeClient::generate_extension_packet), and are
+/// sequenced, encrypted and replay-protected with the connection's keys just like payload packets. When a
+/// packet with one of the [`NETCODE_EXTENSION_PACKET_TYPES`] is received from a connected peer, its decrypted
+/// payload is passed to the extension registered for that type. Packets of unregistered types are dropped.
+///
+/// This is an advanced feature: both peers must agree on the packet types and their encoding, peers that
+/// don't know about an extension will reject its packets as invalid.
+pub trait PacketExtension: Debug + Send + Sync + 'static {
+    /// Called with the payload of an extension packet received from a connected peer.
+    ///
+    /// On the server `client_id` is the sender of the packet, on the client it is the client's own id.
+    fn receive(&mut self, client_id: u64, payload: &[u8]);
+}
+
+/// The extensions registered in a client or server, indexed by packet type.
+#[derive(Debug, Default)]
+pub(crate) struct PacketExtensions {
+    extensions: [Option<Box<dyn PacketExtension>>; 8],
+}
+
+impl PacketExtensions {
+    pub(crate) fn register(&mut self, packet_type: u8, extension: Box<dyn PacketExtension>) -> Result<(), NetcodeError> {
+        let index = extension_index(packet_type)?;
+        self.extensions[index] = Some(extension);
+        Ok(())
+    }
+
+    pub(crate) fn receive(&mut self, packet_type: u8, client_id: u64, payload: &[u8]) {
+        let Ok(index) = extension_index(packet_type) else {
+            return;
+        };
+        match &mut self.extensions[index] {
+            Some(extension) => extension.receive(client_id, payload),
+            None => log::debug!("Dropped extension packet with unregistered type {}", packet_type),
+        }
+    }
+}
+
+pub(crate) fn extension_index(packet_type: u8) -> Result<usize, NetcodeError> {
+    if !NETCODE_EXTENSION_PACKET_TYPES.contains(&packet_type) {
+        return Err(NetcodeError::InvalidPacketType);
+    }
+    Ok((packet_type - NETCODE_EXTENSION_PACKET_TYPES.start()) as usize)
+}
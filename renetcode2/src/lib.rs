@@ -20,29 +20,49 @@
 mod client;
 mod crypto;
 mod error;
+#[cfg(feature = "extensions")]
+mod extension;
 mod packet;
 mod replay_protection;
 mod serialize;
 mod server;
 mod token;
+#[cfg(feature = "serde")]
+mod user_data;
 
-pub use client::{ClientAuthentication, DisconnectReason, NetcodeClient};
+pub use client::{ClientAuthentication, DisconnectReason, HandshakeTimings, NetcodeClient};
 pub use crypto::generate_random_bytes;
 pub use error::NetcodeError;
+#[cfg(feature = "extensions")]
+pub use extension::{PacketExtension, NETCODE_EXTENSION_PACKET_TYPES};
 pub use packet::{Packet, PacketType};
-pub use server::{NetcodeServer, ServerAuthentication, ServerConfig, ServerResult, ServerSocketConfig};
+pub use server::{
+    CapacityEvent, CapacityThreshold, ClientInfo, DenialReason, NetcodeServer, PendingClientInfo, PendingClientState, ServerAuthentication,
+    ServerConfig, ServerResult, ServerSocketConfig,
+};
 pub use token::{ConnectToken, TokenGenerationError};
+#[cfg(feature = "serde")]
+pub use user_data::{UserData, UserDataError};
 
 use std::time::Duration;
 
 const NETCODE_VERSION_INFO: &[u8; 13] = b"NETCODE 1.a2\0"; //Netcode v1.02 with renet2 extensions (version 'a')
 const NETCODE_MAX_CLIENTS: usize = 1024;
-const NETCODE_MAX_PENDING_CLIENTS: usize = NETCODE_MAX_CLIENTS * 4;
+/// The maximum number of pending clients (i.e. mid-handshake) a server can have, see
+/// [`NetcodeServer::pending_clients_count`].
+pub const NETCODE_MAX_PENDING_CLIENTS: usize = NETCODE_MAX_CLIENTS * 4;
+/// The maximum number of source addresses tracked by a server's connection rate limit, see
+/// [`ServerConfig::connection_rate_limit`].
+pub const NETCODE_MAX_RATE_LIMITED_ADDRESSES: usize = NETCODE_MAX_PENDING_CLIENTS;
 
 const NETCODE_ADDRESS_NONE: u8 = 0;
 const NETCODE_ADDRESS_IPV4: u8 = 1;
 const NETCODE_ADDRESS_IPV6: u8 = 2;
 
+const NETCODE_DENIED_UNSPECIFIED: u8 = 0;
+const NETCODE_DENIED_PROTOCOL_MISMATCH: u8 = 1;
+const NETCODE_DENIED_EXPIRED: u8 = 2;
+
 const NETCODE_CONNECT_TOKEN_PRIVATE_BYTES: usize = 1024;
 /// The maximum number of bytes that a netcode packet can contain.
 pub const NETCODE_MAX_PACKET_BYTES: usize = 1400;
@@ -59,6 +79,36 @@ const NETCODE_CONNECT_TOKEN_XNONCE_BYTES: usize = 24;
 
 const NETCODE_ADDITIONAL_DATA_SIZE: usize = 13 + 8 + 8;
 const NETCODE_SEND_RATE: Duration = Duration::from_millis(250);
+const NETCODE_RESPONSE_GRACE: Duration = Duration::from_secs(1);
 
 /// The tag size of encoded (unencrypted) packets.
 const ENCODED_PACKET_TAG_BYTES: usize = 8;
+
+/// Returns the netcode protocol version implemented by this crate, e.g. `"NETCODE 1.a2"`.
+///
+/// Servers reject connect tokens and connection requests from other versions, see [`is_netcode_compatible`].
+pub fn netcode_version() -> &'static str {
+    // The version info is nul-terminated ASCII.
+    std::str::from_utf8(&NETCODE_VERSION_INFO[..NETCODE_VERSION_INFO.len() - 1]).expect("netcode version info is ASCII")
+}
+
+/// Returns true if a peer running `other_version` can connect with this crate's netcode version.
+///
+/// `other_version` is the [`netcode_version`] reported by the peer. A trailing nul byte is ignored.
+pub fn is_netcode_compatible(other_version: &str) -> bool {
+    other_version.strip_suffix('\0').unwrap_or(other_version) == netcode_version()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn netcode_version_compatibility() {
+        assert_eq!(netcode_version(), "NETCODE 1.a2");
+        assert!(is_netcode_compatible(netcode_version()));
+        assert!(is_netcode_compatible("NETCODE 1.a2\0"));
+        assert!(!is_netcode_compatible("NETCODE 1.02"));
+        assert!(!is_netcode_compatible(""));
+    }
+}
@@ -31,7 +31,12 @@ pub enum Packet<'a> {
         xnonce: [u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
         data: [u8; NETCODE_CONNECT_TOKEN_PRIVATE_BYTES],
     },
-    ConnectionDenied,
+    ConnectionDenied {
+        /// Why the connection was denied, for the reasons the client can act on (e.g. an outdated protocol id).
+        ///
+        /// Only written when non-zero, older peers ignore it.
+        reason: u8,
+    },
     Challenge {
         token_sequence: u64,
         token_data: [u8; NETCODE_CHALLENGE_TOKEN_BYTES], // encrypted ChallengeToken
@@ -43,9 +48,19 @@ pub enum Packet<'a> {
     KeepAlive {
         client_index: u32,
         max_clients: u32,
+        /// The user data the server received from the client, echoed back while the client is unconfirmed if
+        /// enabled on the server. Older clients ignore these trailing bytes.
+        user_data: Option<&'a [u8; NETCODE_USER_DATA_BYTES]>,
     },
     Payload(&'a [u8]),
-    Disconnect,
+    Disconnect {
+        /// Application-defined reason for the disconnect, see [`NetcodeServer::disconnect_with_reason`].
+        ///
+        /// Only written when non-zero, older peers ignore it.
+        ///
+        /// [`NetcodeServer::disconnect_with_reason`]: crate::NetcodeServer::disconnect_with_reason
+        reason: u8,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -82,12 +97,12 @@ impl<'a> Packet<'a> {
     pub fn packet_type(&self) -> PacketType {
         match self {
             Packet::ConnectionRequest { .. } => PacketType::ConnectionRequest,
-            Packet::ConnectionDenied => PacketType::ConnectionDenied,
+            Packet::ConnectionDenied { .. } => PacketType::ConnectionDenied,
             Packet::Challenge { .. } => PacketType::Challenge,
             Packet::Response { .. } => PacketType::Response,
             Packet::KeepAlive { .. } => PacketType::KeepAlive,
             Packet::Payload { .. } => PacketType::Payload,
-            Packet::Disconnect => PacketType::Disconnect,
+            Packet::Disconnect { .. } => PacketType::Disconnect,
         }
     }
 
@@ -148,25 +163,36 @@ impl<'a> Packet<'a> {
                 writer.write_all(&token_sequence.to_le_bytes())?;
                 writer.write_all(token_data)?;
             }
-            Packet::KeepAlive { max_clients, client_index } => {
+            Packet::KeepAlive {
+                max_clients,
+                client_index,
+                user_data,
+            } => {
                 writer.write_all(&client_index.to_le_bytes())?;
                 writer.write_all(&max_clients.to_le_bytes())?;
+                if let Some(user_data) = user_data {
+                    writer.write_all(&user_data[..])?;
+                }
             }
             Packet::Payload(p) => {
                 writer.write_all(p)?;
             }
-            Packet::ConnectionDenied | Packet::Disconnect => {}
+            Packet::Disconnect { reason } | Packet::ConnectionDenied { reason } => {
+                if *reason != 0 {
+                    writer.write_all(&[*reason])?;
+                }
+            }
         }
 
         Ok(())
     }
 
-    fn read(packet_type: PacketType, src: &'a [u8]) -> Result<Self, io::Error> {
+    fn read(packet_type: PacketType, bytes: &'a [u8]) -> Result<Self, io::Error> {
         if matches!(packet_type, PacketType::Payload) {
-            return Ok(Packet::Payload(src));
+            return Ok(Packet::Payload(bytes));
         }
 
-        let src = &mut Cursor::new(src);
+        let src = &mut Cursor::new(bytes);
 
         match packet_type {
             PacketType::ConnectionRequest => {
@@ -205,11 +231,20 @@ impl<'a> Packet<'a> {
             PacketType::KeepAlive => {
                 let client_index = read_u32(src)?;
                 let max_clients = read_u32(src)?;
+                let user_data = bytes[src.position() as usize..].first_chunk::<NETCODE_USER_DATA_BYTES>();
 
-                Ok(Packet::KeepAlive { client_index, max_clients })
+                Ok(Packet::KeepAlive {
+                    client_index,
+                    max_clients,
+                    user_data,
+                })
             }
-            PacketType::ConnectionDenied => Ok(Packet::ConnectionDenied),
-            PacketType::Disconnect => Ok(Packet::Disconnect),
+            PacketType::ConnectionDenied => Ok(Packet::ConnectionDenied {
+                reason: bytes.first().copied().unwrap_or(0),
+            }),
+            PacketType::Disconnect => Ok(Packet::Disconnect {
+                reason: bytes.first().copied().unwrap_or(0),
+            }),
             PacketType::Payload => unreachable!(),
         }
     }
@@ -229,42 +264,16 @@ impl<'a> Packet<'a> {
             self.write(&mut writer)?;
             Ok(writer.position() as usize)
         } else if let Some((sequence, private_key)) = crypto_info {
-            let (start, end, aad) = {
-                let mut writer = io::Cursor::new(&mut *buffer);
-                let prefix_byte = {
-                    let prefix_byte = encode_prefix(self.id(), sequence);
-                    writer.write_all(&prefix_byte.to_le_bytes())?;
-                    write_sequence(&mut writer, sequence)?;
-                    prefix_byte
-                };
-
-                let start = writer.position() as usize;
-                self.write(&mut writer)?;
-
-                let additional_data = get_additional_data(prefix_byte, protocol_id);
-                (start, writer.position() as usize, additional_data)
-            };
-
-            if buffer.len() < end + NETCODE_MAC_BYTES {
-                return Err(NetcodeError::IoError(io::Error::new(
-                    io::ErrorKind::WriteZero,
-                    "buffer too small to encode",
-                )));
-            }
-
-            match encrypted {
-                true => encrypt_in_place(&mut buffer[start..end + NETCODE_MAC_BYTES], sequence, private_key, &aad)?,
-                false => encode_in_place(&mut buffer[start..end + NETCODE_MAC_BYTES], protocol_id),
-            }
-
-            Ok(end + NETCODE_MAC_BYTES)
+            encode_secured(buffer, self.id(), protocol_id, sequence, private_key, encrypted, |writer| {
+                self.write(writer)
+            })
         } else {
             Err(NetcodeError::UnavailablePrivateKey)
         }
     }
 
     pub fn decode(
-        mut buffer: &'a mut [u8],
+        buffer: &'a mut [u8],
         protocol_id: u64,
         private_key: Option<&[u8; 32]>,
         replay_protection: Option<&mut ReplayProtection>,
@@ -274,39 +283,15 @@ impl<'a> Packet<'a> {
             return Err(NetcodeError::PacketTooSmall);
         }
 
-        let prefix_byte = buffer[0];
-        let (packet_type, sequence_len) = decode_prefix(prefix_byte);
+        let (packet_type, _) = decode_prefix(buffer[0]);
         let packet_type = PacketType::from_u8(packet_type)?;
 
         if matches!(packet_type, PacketType::ConnectionRequest) {
             Ok((0, Packet::read(PacketType::ConnectionRequest, &buffer[1..])?))
         } else if let Some(private_key) = private_key {
-            let (sequence, aad, read_pos) = {
-                let src = &mut io::Cursor::new(&mut buffer);
-                src.set_position(1);
-                let sequence = read_sequence(src, sequence_len)?;
-                let additional_data = get_additional_data(prefix_byte, protocol_id);
-                (sequence, additional_data, src.position() as usize)
-            };
-
-            if let Some(ref replay_protection) = replay_protection {
-                if packet_type.apply_replay_protection() && replay_protection.already_received(sequence) {
-                    return Err(NetcodeError::DuplicatedSequence);
-                }
-            }
-
-            match encrypted {
-                true => dencrypted_in_place(&mut buffer[read_pos..], sequence, private_key, &aad)?,
-                false => decode_and_check_buffer(&buffer[read_pos..], protocol_id).map_err(|()| NetcodeError::CryptoError)?,
-            }
-
-            if let Some(replay_protection) = replay_protection {
-                if packet_type.apply_replay_protection() {
-                    replay_protection.advance_sequence(sequence);
-                }
-            }
-
-            let packet = Packet::read(packet_type, &buffer[read_pos..buffer.len() - NETCODE_MAC_BYTES])?;
+            let replay_protection = replay_protection.filter(|_| packet_type.apply_replay_protection());
+            let (sequence, body) = decode_secured(buffer, protocol_id, private_key, replay_protection, encrypted)?;
+            let packet = Packet::read(packet_type, body)?;
             Ok((sequence, packet))
         } else {
             Err(NetcodeError::UnavailablePrivateKey)
@@ -325,6 +310,45 @@ impl<'a> Packet<'a> {
     }
 }
 
+/// Returns the packet type of `buffer` if it is an extension packet.
+#[cfg(feature = "extensions")]
+pub(crate) fn extension_packet_type(buffer: &[u8]) -> Option<u8> {
+    let (packet_type, _) = decode_prefix(*buffer.first()?);
+    crate::NETCODE_EXTENSION_PACKET_TYPES.contains(&packet_type).then_some(packet_type)
+}
+
+#[cfg(feature = "extensions")]
+pub(crate) fn encode_extension_packet(
+    buffer: &mut [u8],
+    packet_type: u8,
+    payload: &[u8],
+    protocol_id: u64,
+    crypto_info: (u64, &[u8; 32]),
+    encrypted: bool,
+) -> Result<usize, NetcodeError> {
+    crate::extension::extension_index(packet_type)?;
+    let (sequence, private_key) = crypto_info;
+    encode_secured(buffer, packet_type, protocol_id, sequence, private_key, encrypted, |writer| {
+        writer.write_all(payload)
+    })
+}
+
+#[cfg(feature = "extensions")]
+pub(crate) fn decode_extension_packet<'a>(
+    buffer: &'a mut [u8],
+    protocol_id: u64,
+    private_key: &[u8; 32],
+    replay_protection: &mut ReplayProtection,
+    encrypted: bool,
+) -> Result<&'a [u8], NetcodeError> {
+    if buffer.len() < 2 + NETCODE_MAC_BYTES {
+        return Err(NetcodeError::PacketTooSmall);
+    }
+
+    let (_, payload) = decode_secured(buffer, protocol_id, private_key, Some(replay_protection), encrypted)?;
+    Ok(payload)
+}
+
 impl ChallengeToken {
     pub fn new(client_id: u64, user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> Self {
         Self {
@@ -360,6 +384,87 @@ impl ChallengeToken {
     }
 }
 
+/// Encodes the prefix and sequence of a packet, then the body written by `write_body`, and encrypts (or signs)
+/// the body in place.
+fn encode_secured(
+    buffer: &mut [u8],
+    packet_type: u8,
+    protocol_id: u64,
+    sequence: u64,
+    private_key: &[u8; 32],
+    encrypted: bool,
+    write_body: impl FnOnce(&mut io::Cursor<&mut [u8]>) -> Result<(), io::Error>,
+) -> Result<usize, NetcodeError> {
+    let (start, end, aad) = {
+        let mut writer = io::Cursor::new(&mut *buffer);
+        let prefix_byte = {
+            let prefix_byte = encode_prefix(packet_type, sequence);
+            writer.write_all(&prefix_byte.to_le_bytes())?;
+            write_sequence(&mut writer, sequence)?;
+            prefix_byte
+        };
+
+        let start = writer.position() as usize;
+        write_body(&mut writer)?;
+
+        let additional_data = get_additional_data(prefix_byte, protocol_id);
+        (start, writer.position() as usize, additional_data)
+    };
+
+    if buffer.len() < end + NETCODE_MAC_BYTES {
+        return Err(NetcodeError::IoError(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "buffer too small to encode",
+        )));
+    }
+
+    match encrypted {
+        true => encrypt_in_place(&mut buffer[start..end + NETCODE_MAC_BYTES], sequence, private_key, &aad)?,
+        false => encode_in_place(&mut buffer[start..end + NETCODE_MAC_BYTES], protocol_id),
+    }
+
+    Ok(end + NETCODE_MAC_BYTES)
+}
+
+/// Reads the sequence of a packet and decrypts (or checks) its body in place, returning the sequence and
+/// the body.
+///
+/// The sequence is checked against and recorded in `replay_protection` if it is provided.
+fn decode_secured<'a>(
+    mut buffer: &'a mut [u8],
+    protocol_id: u64,
+    private_key: &[u8; 32],
+    replay_protection: Option<&mut ReplayProtection>,
+    encrypted: bool,
+) -> Result<(u64, &'a [u8]), NetcodeError> {
+    let prefix_byte = buffer[0];
+    let (_, sequence_len) = decode_prefix(prefix_byte);
+    let (sequence, aad, read_pos) = {
+        let src = &mut io::Cursor::new(&mut buffer);
+        src.set_position(1);
+        let sequence = read_sequence(src, sequence_len)?;
+        let additional_data = get_additional_data(prefix_byte, protocol_id);
+        (sequence, additional_data, src.position() as usize)
+    };
+
+    if let Some(ref replay_protection) = replay_protection {
+        if replay_protection.already_received(sequence) {
+            return Err(NetcodeError::DuplicatedSequence);
+        }
+    }
+
+    match encrypted {
+        true => dencrypted_in_place(&mut buffer[read_pos..], sequence, private_key, &aad)?,
+        false => decode_and_check_buffer(&buffer[read_pos..], protocol_id).map_err(|()| NetcodeError::CryptoError)?,
+    }
+
+    if let Some(replay_protection) = replay_protection {
+        replay_protection.advance_sequence(sequence);
+    }
+
+    Ok((sequence, &buffer[read_pos..buffer.len() - NETCODE_MAC_BYTES]))
+}
+
 fn get_additional_data(prefix: u8, protocol_id: u64) -> [u8; 13 + 8 + 1] {
     let mut buffer = [0; 13 + 8 + 1];
     buffer[..13].copy_from_slice(NETCODE_VERSION_INFO);
@@ -443,6 +548,20 @@ mod tests {
         let connection_keep_alive = Packet::KeepAlive {
             max_clients: 2,
             client_index: 1,
+            user_data: None,
+        };
+
+        let mut buffer = Vec::new();
+        connection_keep_alive.write(&mut buffer).unwrap();
+        let deserialized = Packet::read(PacketType::KeepAlive, buffer.as_slice()).unwrap();
+
+        assert_eq!(deserialized, connection_keep_alive);
+
+        let user_data = [3u8; NETCODE_USER_DATA_BYTES];
+        let connection_keep_alive = Packet::KeepAlive {
+            max_clients: 2,
+            client_index: 1,
+            user_data: Some(&user_data),
         };
 
         let mut buffer = Vec::new();
@@ -454,7 +573,7 @@ mod tests {
 
     #[test]
     fn prefix_sequence() {
-        let packet_type = Packet::Disconnect.id();
+        let packet_type = Packet::Disconnect { reason: 0 }.id();
         let sequence = 99999;
 
         let mut buffer = vec![];
@@ -474,20 +593,22 @@ mod tests {
     fn encode_decode_disconnect_packet() {
         let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
         let key = b"an example very very secret key."; // 32-bytes
-        let packet = Packet::Disconnect;
-        let protocol_id = 12;
-        let sequence = 1;
-        let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key)), false).unwrap();
-        let (d_sequence, d_packet) = Packet::decode(&mut buffer[..len], protocol_id, Some(key), None, false).unwrap();
-        assert_eq!(sequence, d_sequence);
-        assert_eq!(packet, d_packet);
+        for reason in [0, 7] {
+            let packet = Packet::Disconnect { reason };
+            let protocol_id = 12;
+            let sequence = 1;
+            let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key)), false).unwrap();
+            let (d_sequence, d_packet) = Packet::decode(&mut buffer[..len], protocol_id, Some(key), None, false).unwrap();
+            assert_eq!(sequence, d_sequence);
+            assert_eq!(packet, d_packet);
+        }
     }
 
     #[test]
     fn encrypt_decrypt_disconnect_packet() {
         let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
         let key = b"an example very very secret key."; // 32-bytes
-        let packet = Packet::Disconnect;
+        let packet = Packet::Disconnect { reason: 0 };
         let protocol_id = 12;
         let sequence = 1;
         let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key)), true).unwrap();
@@ -500,7 +621,7 @@ mod tests {
     fn encrypt_decrypt_denied_packet() {
         let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
         let key = b"an example very very secret key."; // 32-bytes
-        let packet = Packet::ConnectionDenied;
+        let packet = Packet::ConnectionDenied { reason: 0 };
         let protocol_id = 12;
         let sequence = 2;
         let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key)), true).unwrap();
@@ -1,13 +1,24 @@
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::SocketAddr,
+    time::Duration,
+};
 
 use crate::{
     crypto::generate_random_bytes,
     packet::{ChallengeToken, Packet},
     replay_protection::ReplayProtection,
     token::PrivateConnectToken,
-    NetcodeError, NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_KEY_BYTES, NETCODE_MAC_BYTES,
-    NETCODE_MAX_CLIENTS, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_MAX_PENDING_CLIENTS, NETCODE_SEND_RATE,
-    NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
+    NetcodeError, NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_DENIED_EXPIRED,
+    NETCODE_DENIED_PROTOCOL_MISMATCH, NETCODE_DENIED_UNSPECIFIED, NETCODE_KEY_BYTES, NETCODE_MAC_BYTES, NETCODE_MAX_CLIENTS,
+    NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_MAX_PENDING_CLIENTS, NETCODE_MAX_RATE_LIMITED_ADDRESSES,
+    NETCODE_RESPONSE_GRACE, NETCODE_SEND_RATE, NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
+};
+#[cfg(feature = "extensions")]
+use crate::{
+    extension::PacketExtensions,
+    packet::{decode_extension_packet, encode_extension_packet, extension_packet_type},
+    PacketExtension,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,8 +38,11 @@ struct Connection {
     user_data: [u8; NETCODE_USER_DATA_BYTES],
     socket_id: usize,
     addr: SocketAddr,
+    protocol_id: u64,
     last_packet_received_time: Duration,
     last_packet_send_time: Duration,
+    /// When the client was promoted from pending to connected.
+    connected_at: Duration,
     timeout_seconds: i32,
     sequence: u64,
     expire_timestamp: u64,
@@ -51,16 +65,50 @@ pub struct NetcodeServer {
     sockets: Vec<ServerSocketConfig>,
     clients: Box<[Option<Connection>]>,
     pending_clients: HashMap<(usize, SocketAddr), Connection>,
+    /// Number of pending clients on each socket, kept in sync with `pending_clients`.
+    pending_counts: Vec<usize>,
     connect_token_entries: Box<[Option<ConnectTokenEntry>; NETCODE_MAX_CLIENTS * 2]>,
     protocol_id: u64,
-    connect_key: [u8; NETCODE_KEY_BYTES],
+    accepted_protocol_ids: Vec<u64>,
+    /// Private keys used to decrypt connect tokens, see [`NetcodeServer::add_decrypt_key`].
+    connect_keys: Vec<[u8; NETCODE_KEY_BYTES]>,
     max_clients: usize,
+    /// Client slots reserved for specific client ids, mapped to the reservation deadlines.
+    reserved_slots: HashMap<u64, Duration>,
     challenge_sequence: u64,
     challenge_key: [u8; NETCODE_KEY_BYTES],
     current_time: Duration,
     global_sequence: u64,
     secure: bool,
-    out: [u8; NETCODE_MAX_PACKET_BYTES],
+    draining: bool,
+    response_grace: Duration,
+    echo_user_data: bool,
+    randomize_initial_sequence: bool,
+    capacity_thresholds: Vec<(CapacityThreshold, bool)>,
+    capacity_events: VecDeque<CapacityEvent>,
+    out: Box<[u8]>,
+    /// Scratch buffer for the packets returned by [`Self::disconnect_many`].
+    disconnect_out: Vec<u8>,
+    connection_filter: Option<ConnectionFilter>,
+    connection_rate_limit: Option<(u32, Duration)>,
+    /// Connection requests received per source address in the current rate limit window, mapped to
+    /// `(request count, window start)`.
+    connection_request_counts: HashMap<SocketAddr, (u32, Duration)>,
+    banned_addrs: HashSet<SocketAddr>,
+    banned_clients: HashSet<u64>,
+    #[cfg(feature = "extensions")]
+    extensions: PacketExtensions,
+}
+
+/// Decides whether to accept connection requests, see [`NetcodeServer::set_connection_filter`].
+struct ConnectionFilter(Box<ConnectionFilterFn>);
+
+type ConnectionFilterFn = dyn FnMut(u64, SocketAddr, &[u8; NETCODE_USER_DATA_BYTES]) -> bool + Send + Sync;
+
+impl std::fmt::Debug for ConnectionFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ConnectionFilter")
+    }
 }
 
 /// Result from processing an packet in the server
@@ -70,13 +118,14 @@ pub enum ServerResult<'a, 's> {
     None,
     /// An error occurred while processing the packet, the address should be rejected.
     Error { socket_id: usize, addr: SocketAddr },
-    /// A connection request was valid but denied because of connection limits or a token already in use.
+    /// A connection request was valid but denied, see [`DenialReason`].
     ///
     /// If there is a payload it should be sent to the address.
     ConnectionDenied {
         socket_id: usize,
         addr: SocketAddr,
         payload: Option<&'s mut [u8]>,
+        reason: DenialReason,
     },
     /// A connection request was accepted.
     ///
@@ -112,6 +161,104 @@ pub enum ServerResult<'a, 's> {
     },
 }
 
+/// Reason a valid connection request was denied by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DenialReason {
+    /// The address or client id is already used by a different connection.
+    AlreadyConnected,
+    /// The server reached the max amount of pending clients.
+    TooManyPendingClients,
+    /// The connect token is already in use by a different address.
+    ConnectTokenInUse,
+    /// The server has no free client slots.
+    ServerFull,
+    /// The server is not accepting new connections, see [`NetcodeServer::set_draining`].
+    Draining,
+    /// The connection filter rejected the request, see [`NetcodeServer::set_connection_filter`].
+    Filtered,
+    /// The client id is banned, see [`NetcodeServer::ban_client`].
+    Banned,
+    /// The connect token's protocol id is not accepted, see [`NetcodeServer::set_accepted_protocol_ids`].
+    ///
+    /// The client is only notified if [`ServerConfig::connection_rate_limit`] is set, so it can tell the user to
+    /// update.
+    ProtocolMismatch,
+    /// The connect token expired.
+    ///
+    /// The client is only notified if [`ServerConfig::connection_rate_limit`] is set.
+    Expired,
+}
+
+/// A watermark on the fraction of client slots in use, see [`ServerConfig::capacity_thresholds`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CapacityThreshold {
+    /// Fraction of the max clients that must be connected to exceed the threshold.
+    pub fraction: f32,
+    /// How far below `fraction` the load must fall before the threshold is considered recovered.
+    ///
+    /// This prevents repeated events when the client count hovers around the threshold.
+    pub hysteresis: f32,
+}
+
+impl CapacityThreshold {
+    /// Makes a new threshold with a hysteresis of `0.05`.
+    pub fn new(fraction: f32) -> Self {
+        Self {
+            fraction,
+            hysteresis: 0.05,
+        }
+    }
+}
+
+/// Emitted when the server's load crosses a [`CapacityThreshold`], see [`NetcodeServer::get_capacity_event`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapacityEvent {
+    /// The fraction of connected clients reached the threshold.
+    Exceeded { fraction: f32 },
+    /// The fraction of connected clients fell below the threshold minus its hysteresis.
+    Recovered { fraction: f32 },
+}
+
+/// Handshake state of a pending client, see [`PendingClientInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingClientState {
+    /// The server sent a connection challenge and is waiting for the client's response.
+    AwaitingResponse,
+    /// The client's connect token expired, it will be removed in the next [`NetcodeServer::update`].
+    Expired,
+}
+
+/// Diagnostic info about a pending client (i.e. mid-handshake), see [`NetcodeServer::pending_clients_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingClientInfo {
+    pub socket_id: usize,
+    pub addr: SocketAddr,
+    pub client_id: u64,
+    /// How long the client has been pending, since its first connection request.
+    pub time_in_pending: Duration,
+    /// How long since the server last received a packet from the client.
+    pub time_since_last_packet: Duration,
+    pub state: PendingClientState,
+}
+
+/// Diagnostic info about a connected client, see [`NetcodeServer::client_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub socket_id: usize,
+    pub addr: SocketAddr,
+    /// Whether the client confirmed the connection by sending a packet after being connected.
+    ///
+    /// Keep alive packets are sent with each payload packet until the client is confirmed.
+    pub confirmed: bool,
+    /// Sequence number of the next packet sent to the client.
+    pub sequence: u64,
+    /// When the server last received a packet from the client, in the server's time (see [`NetcodeServer::current_time`]).
+    pub last_packet_received_time: Duration,
+    /// When the server last sent a packet to the client, in the server's time (see [`NetcodeServer::current_time`]).
+    pub last_packet_send_time: Duration,
+}
+
 /// Configuration details for a socket associated with a netcode server.
 #[derive(Debug)]
 pub struct ServerSocketConfig {
@@ -121,6 +268,30 @@ pub struct ServerSocketConfig {
     pub needs_encryption: bool,
     /// Publicly available addresses to which clients will attempt to connect.
     pub public_addresses: Vec<SocketAddr>,
+    /// The maximum number of bytes a payload can have when generating payload packets for clients on this socket.
+    ///
+    /// Defaults to [`NETCODE_MAX_PAYLOAD_BYTES`], which is tuned for the UDP MTU. Reliable stream transports
+    /// (e.g. websockets) can carry larger frames, so this may be increased for them. The client's receive buffers
+    /// must be large enough for the resulting packets.
+    ///
+    /// Note that `renet2` fragments messages into slices before they reach netcode, so raising this limit only
+    /// reduces fragmentation if the channel layer produces packets larger than [`NETCODE_MAX_PAYLOAD_BYTES`].
+    pub max_payload_bytes: usize,
+    /// The maximum number of clients that can be pending (i.e. mid-handshake) on this socket at a time.
+    ///
+    /// Pending clients are also limited globally by `NETCODE_MAX_PENDING_CLIENTS`. Setting a per-socket limit stops
+    /// a connection flood on one socket from using up the handshake capacity of the other sockets.
+    ///
+    /// `None` by default.
+    pub max_concurrent_handshakes: Option<usize>,
+    /// If `true` then secure connect tokens generated for other sockets that share connect tokens may be used on this
+    /// socket, as long as the token lists one of this socket's public addresses.
+    ///
+    /// This lets sockets that serve the same clients over different address families (e.g. IPv4 and IPv6) share
+    /// connect tokens. Both the token's socket and the socket receiving the connection request must opt in.
+    ///
+    /// `false` by default.
+    pub shares_connect_tokens: bool,
 }
 
 impl ServerSocketConfig {
@@ -129,6 +300,9 @@ impl ServerSocketConfig {
         Self {
             needs_encryption: true,
             public_addresses,
+            max_payload_bytes: NETCODE_MAX_PAYLOAD_BYTES,
+            max_concurrent_handshakes: None,
+            shares_connect_tokens: false,
         }
     }
 }
@@ -154,6 +328,32 @@ pub struct ServerConfig {
     /// You can use a hash function with the current version of the game to generate this value
     /// so that older versions cannot connect to newer versions.
     pub protocol_id: u64,
+    /// Additional protocol ids that connecting clients may use, e.g. the previous version of the game during a
+    /// rolling update.
+    ///
+    /// If empty then only [`Self::protocol_id`] is accepted. See [`NetcodeServer::set_accepted_protocol_ids`].
+    pub accepted_protocol_ids: Vec<u64>,
+    /// Watermarks on `connected_clients / max_clients` that emit [`CapacityEvent`]s when crossed, e.g. to tell an
+    /// orchestrator to start another server before this one fills up.
+    ///
+    /// Thresholds are checked in [`NetcodeServer::update`]. See [`NetcodeServer::get_capacity_event`].
+    pub capacity_thresholds: Vec<CapacityThreshold>,
+    /// Starts the sequence of packets sent on each new connection at a random number instead of 0, making the
+    /// sequences harder to predict for an attacker.
+    ///
+    /// Sequences are random within `0..2^62`, so they can't overflow. Peers already accept arbitrary starting
+    /// sequences since replay protection only tracks a window of recent sequences. Defaults to `false`.
+    pub randomize_initial_sequence: bool,
+    /// Limits connection requests to `max requests` per `window` for each source address, as
+    /// `Some((max requests, window))`.
+    ///
+    /// Requests over the limit are dropped silently instead of denied, so a flood of requests can't be used
+    /// for amplification. At most [`NETCODE_MAX_RATE_LIMITED_ADDRESSES`] addresses are tracked, when a new address
+    /// sends a request the address whose window started first stops being tracked. Defaults to `None` (no limit).
+    ///
+    /// Requests with an expired token or an unaccepted protocol id are only answered with a denial when this is
+    /// set, since the token must be decrypted to encrypt the reply.
+    pub connection_rate_limit: Option<(u32, Duration)>,
     /// Settings for sockets associated with this server.
     pub sockets: Vec<ServerSocketConfig>,
     /// Authentication configuration for the server
@@ -171,6 +371,8 @@ impl NetcodeServer {
             panic!("The max clients allowed is {}", NETCODE_MAX_CLIENTS);
         }
         let challenge_key = generate_random_bytes();
+        let max_payload_bytes = config.sockets.iter().map(|s| s.max_payload_bytes).max().unwrap_or_default();
+        let out_len = NETCODE_MAX_PACKET_BYTES + max_payload_bytes.saturating_sub(NETCODE_MAX_PAYLOAD_BYTES);
         let clients = vec![None; config.max_clients].into_boxed_slice();
 
         let connect_key = match config.authentication {
@@ -184,28 +386,60 @@ impl NetcodeServer {
         };
 
         Self {
+            pending_counts: vec![0; config.sockets.len()],
             sockets: config.sockets,
             clients,
             connect_token_entries: Box::new([None; NETCODE_MAX_CLIENTS * 2]),
             pending_clients: HashMap::new(),
             protocol_id: config.protocol_id,
-            connect_key,
+            accepted_protocol_ids: config.accepted_protocol_ids,
+            connect_keys: vec![connect_key],
             max_clients: config.max_clients,
+            reserved_slots: HashMap::new(),
             challenge_sequence: 0,
             global_sequence: 0,
             challenge_key,
             current_time: config.current_time,
             secure,
-            out: [0u8; NETCODE_MAX_PACKET_BYTES],
+            draining: false,
+            response_grace: NETCODE_RESPONSE_GRACE,
+            echo_user_data: false,
+            randomize_initial_sequence: config.randomize_initial_sequence,
+            capacity_thresholds: config.capacity_thresholds.into_iter().map(|t| (t, false)).collect(),
+            capacity_events: VecDeque::new(),
+            out: vec![0u8; out_len].into_boxed_slice(),
+            disconnect_out: Vec::new(),
+            connection_filter: None,
+            connection_rate_limit: config.connection_rate_limit,
+            connection_request_counts: HashMap::new(),
+            banned_addrs: HashSet::new(),
+            banned_clients: HashSet::new(),
+            #[cfg(feature = "extensions")]
+            extensions: PacketExtensions::default(),
         }
     }
 
+    /// Makes a secure server with a newly generated private key, and returns the key.
+    ///
+    /// The `authentication` in `config` is replaced with [`ServerAuthentication::Secure`] using the generated key.
+    /// Use the key to mint [`ConnectToken`](crate::ConnectToken)s for this server. It must be kept secret: anyone
+    /// who has it can generate connect tokens that the server will accept.
+    pub fn new_secure_generated(mut config: ServerConfig) -> (Self, [u8; NETCODE_KEY_BYTES]) {
+        let private_key = generate_random_bytes();
+        config.authentication = ServerAuthentication::Secure { private_key };
+        (Self::new(config), private_key)
+    }
+
     #[doc(hidden)]
     pub fn __test() -> Self {
         let config = ServerConfig {
             current_time: Duration::ZERO,
             max_clients: 32,
             protocol_id: 0,
+            accepted_protocol_ids: Vec::new(),
+            capacity_thresholds: Vec::new(),
+            randomize_initial_sequence: false,
+            connection_rate_limit: None,
             sockets: vec![ServerSocketConfig::new(vec!["127.0.0.1:0".parse().unwrap()])],
             authentication: ServerAuthentication::Unsecure,
         };
@@ -219,10 +453,111 @@ impl NetcodeServer {
         self.sockets[socket_id].public_addresses.clone()
     }
 
+    /// Adds a socket to the running server and returns its socket id.
+    ///
+    /// Socket ids are stable and never reused: existing connections keep their socket ids, and connect tokens that
+    /// reference the other sockets stay valid. Connect tokens for the new socket must use the returned socket id.
+    ///
+    /// Panics if the server already has 256 sockets, since connect tokens store socket ids as `u8`.
+    pub fn add_socket(&mut self, config: ServerSocketConfig) -> usize {
+        if self.sockets.len() > u8::MAX as usize {
+            panic!("The max sockets allowed is {}", u8::MAX as usize + 1);
+        }
+        self.reserve_out(config.max_payload_bytes);
+        self.sockets.push(config);
+        self.pending_counts.push(0);
+        self.sockets.len() - 1
+    }
+
+    /// Sets the [`ServerSocketConfig::max_payload_bytes`] of a specific socket.
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_max_payload_bytes(&mut self, socket_id: usize, max_payload_bytes: usize) {
+        self.reserve_out(max_payload_bytes);
+        self.sockets[socket_id].max_payload_bytes = max_payload_bytes;
+    }
+
+    /// Grows the packet buffer to fit packets with payloads of `max_payload_bytes`.
+    fn reserve_out(&mut self, max_payload_bytes: usize) {
+        let out_len = NETCODE_MAX_PACKET_BYTES + max_payload_bytes.saturating_sub(NETCODE_MAX_PAYLOAD_BYTES);
+        if out_len > self.out.len() {
+            self.out = vec![0u8; out_len].into_boxed_slice();
+        }
+    }
+
+    /// Sets the [`ServerSocketConfig::max_concurrent_handshakes`] of a specific socket.
+    ///
+    /// Lowering the limit does not drop clients that are already pending.
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_max_concurrent_handshakes(&mut self, socket_id: usize, max_concurrent_handshakes: Option<usize>) {
+        self.sockets[socket_id].max_concurrent_handshakes = max_concurrent_handshakes;
+    }
+
+    /// Sets the [`ServerSocketConfig::shares_connect_tokens`] of a specific socket.
+    ///
+    /// Panics if `socket_id` is out of range.
+    pub fn set_shares_connect_tokens(&mut self, socket_id: usize, shares_connect_tokens: bool) {
+        self.sockets[socket_id].shares_connect_tokens = shares_connect_tokens;
+    }
+
+    /// Returns the number of clients that are pending (i.e. mid-handshake) on a specific socket.
+    pub fn pending_count_on_socket(&self, socket_id: usize) -> usize {
+        self.pending_counts.get(socket_id).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of clients that are pending (i.e. mid-handshake) on all sockets.
+    ///
+    /// New clients are denied with [`DenialReason::TooManyPendingClients`] when this reaches
+    /// [`NETCODE_MAX_PENDING_CLIENTS`](crate::NETCODE_MAX_PENDING_CLIENTS).
+    pub fn pending_clients_count(&self) -> usize {
+        self.pending_clients.len()
+    }
+
+    /// Returns the socket ids and addresses of the pending clients (iterator).
+    pub fn pending_clients_iter(&self) -> impl Iterator<Item = (usize, SocketAddr)> + '_ {
+        self.pending_clients.keys().copied()
+    }
+
+    /// Returns diagnostic info about each pending client, e.g. to investigate clients stuck connecting.
+    pub fn pending_clients_info(&self) -> Vec<PendingClientInfo> {
+        self.pending_clients
+            .iter()
+            .map(|(&(socket_id, addr), pending)| PendingClientInfo {
+                socket_id,
+                addr,
+                client_id: pending.client_id,
+                time_in_pending: self.current_time.saturating_sub(pending.connected_at),
+                time_since_last_packet: self.current_time.saturating_sub(pending.last_packet_received_time),
+                state: match pending.state {
+                    ConnectionState::PendingResponse => PendingClientState::AwaitingResponse,
+                    _ => PendingClientState::Expired,
+                },
+            })
+            .collect()
+    }
+
     pub fn current_time(&self) -> Duration {
         self.current_time
     }
 
+    fn remove_pending(&mut self, socket_id: usize, addr: SocketAddr) -> Option<Connection> {
+        let pending = self.pending_clients.remove(&(socket_id, addr))?;
+        self.pending_counts[socket_id] -= 1;
+        Some(pending)
+    }
+
+    fn retain_pending(&mut self, mut f: impl FnMut(&(usize, SocketAddr), &mut Connection) -> bool) {
+        let pending_counts = &mut self.pending_counts;
+        self.pending_clients.retain(|key, pending| {
+            let keep = f(key, pending);
+            if !keep {
+                pending_counts[key.0] -= 1;
+            }
+            keep
+        });
+    }
+
     fn find_or_add_connect_token_entry(&mut self, new_entry: ConnectTokenEntry) -> bool {
         let mut min = Duration::MAX;
         let mut oldest_entry = 0;
@@ -277,6 +612,22 @@ impl NetcodeServer {
         None
     }
 
+    /// Returns the duration until the connected client is disconnected for inactivity.
+    ///
+    /// This is the connect token's `timeout_seconds` minus [`Self::time_since_last_received_packet`]. A zero
+    /// duration means the client will time out on its next update.
+    /// Returns `None` if the client is not connected or its connect token disables timeouts.
+    pub fn time_until_timeout(&self, client_id: u64) -> Option<Duration> {
+        let client = find_client_by_id(&self.clients, client_id)?;
+        if client.timeout_seconds <= 0 {
+            return None;
+        }
+        let timeout = Duration::from_secs(client.timeout_seconds as u64);
+        let elapsed = self.current_time - client.last_packet_received_time;
+
+        Some(timeout.saturating_sub(elapsed))
+    }
+
     /// Returns the client socket id and address if connected.
     pub fn client_addr(&self, client_id: u64) -> Option<(usize, SocketAddr)> {
         if let Some(client) = find_client_by_id(&self.clients, client_id) {
@@ -286,6 +637,291 @@ impl NetcodeServer {
         None
     }
 
+    /// Adds a private key that connect tokens can be decrypted with. Does nothing if the key was already added.
+    ///
+    /// Use this to rotate the server's private key without rejecting connect tokens that are in flight: add the new
+    /// key, start generating tokens with it, then [retire](Self::retire_decrypt_key) the old key once tokens
+    /// generated with it have expired.
+    ///
+    /// Connected clients are not affected by key rotation, their packets are encrypted with the per-connection keys
+    /// from their connect tokens.
+    pub fn add_decrypt_key(&mut self, private_key: [u8; NETCODE_KEY_BYTES]) {
+        if !self.connect_keys.contains(&private_key) {
+            self.connect_keys.push(private_key);
+        }
+    }
+
+    /// Removes a private key added with [`Self::add_decrypt_key`] or the server config.
+    ///
+    /// Returns `false` if the key was not found. New clients can't connect after all keys are retired.
+    pub fn retire_decrypt_key(&mut self, private_key: &[u8; NETCODE_KEY_BYTES]) -> bool {
+        let len = self.connect_keys.len();
+        self.connect_keys.retain(|key| key != private_key);
+        self.connect_keys.len() != len
+    }
+
+    /// Decrypts a connect token with the first private key that succeeds.
+    fn decode_private_connect_token(
+        &self,
+        data: &[u8; NETCODE_CONNECT_TOKEN_PRIVATE_BYTES],
+        protocol_id: u64,
+        expire_timestamp: u64,
+        xnonce: &[u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
+    ) -> Result<PrivateConnectToken, NetcodeError> {
+        let mut result = Err(NetcodeError::UnavailablePrivateKey);
+        for key in self.connect_keys.iter() {
+            result = PrivateConnectToken::decode(data, protocol_id, expire_timestamp, xnonce, key).map_err(NetcodeError::from);
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Returns diagnostic info about a connected client, e.g. for server dashboards.
+    pub fn client_info(&self, client_id: u64) -> Option<ClientInfo> {
+        let client = find_client_by_id(&self.clients, client_id)?;
+
+        Some(ClientInfo {
+            socket_id: client.socket_id,
+            addr: client.addr,
+            confirmed: client.confirmed,
+            sequence: client.sequence,
+            last_packet_received_time: client.last_packet_received_time,
+            last_packet_send_time: client.last_packet_send_time,
+        })
+    }
+
+    /// Returns the id of the connected client with the given socket id and address.
+    pub fn client_id_from_addr(&self, socket_id: usize, addr: SocketAddr) -> Option<u64> {
+        self.clients
+            .iter()
+            .flatten()
+            .find(|c| (c.socket_id == socket_id) && (c.addr == addr))
+            .map(|c| c.client_id)
+    }
+
+    /// Returns the protocol id that a connected client used to connect.
+    ///
+    /// This will differ from the server's main protocol id if the client connected with one of the
+    /// [accepted protocol ids](NetcodeServer::set_accepted_protocol_ids).
+    pub fn client_protocol_id(&self, client_id: u64) -> Option<u64> {
+        find_client_by_id(&self.clients, client_id).map(|client| client.protocol_id)
+    }
+
+    /// Returns the additional protocol ids accepted from connecting clients.
+    pub fn accepted_protocol_ids(&self) -> &[u64] {
+        &self.accepted_protocol_ids
+    }
+
+    /// Sets additional protocol ids that connecting clients may use besides the server's main protocol id.
+    ///
+    /// This allows clients on different versions of a game to connect during a rolling update. Netcode only checks
+    /// that the protocol id is accepted, so the rest of the game (e.g. renet2 channels and message formats) must stay
+    /// compatible across all accepted versions. Use [`NetcodeServer::client_protocol_id`] to see which version a
+    /// client is using.
+    ///
+    /// Changing the accepted ids does not affect clients that are already connected or pending.
+    pub fn set_accepted_protocol_ids(&mut self, accepted_protocol_ids: Vec<u64>) {
+        self.accepted_protocol_ids = accepted_protocol_ids;
+    }
+
+    /// Sets how long after connecting a client's repeated connection responses are answered right away.
+    ///
+    /// A client keeps sending connection responses until it receives the keep-alive that completes the handshake. If
+    /// that keep-alive is lost or reordered, the server would normally ignore the extra responses and the client would
+    /// wait for the next scheduled keep-alive. Within this window, and until the client is confirmed, the server
+    /// answers each extra response with a keep-alive instead.
+    ///
+    /// Defaults to 1 second. Set to [`Duration::ZERO`] to disable.
+    pub fn set_response_grace(&mut self, response_grace: Duration) {
+        self.response_grace = response_grace;
+    }
+
+    /// Returns the current response grace, see [`Self::set_response_grace`].
+    pub fn response_grace(&self) -> Duration {
+        self.response_grace
+    }
+
+    /// Sets whether the keep-alives that complete a client's handshake echo back the user data the server received.
+    ///
+    /// Clients can read the echoed data with [`NetcodeClient::confirmed_user_data`](crate::NetcodeClient::confirmed_user_data)
+    /// to check that the server decoded the intended user data. The data is appended to keep-alives sent until the
+    /// client is confirmed, and clients that don't know about it ignore it.
+    ///
+    /// Defaults to `false`.
+    pub fn set_echo_user_data(&mut self, echo_user_data: bool) {
+        self.echo_user_data = echo_user_data;
+    }
+
+    /// Returns whether user data is echoed to connecting clients, see [`Self::set_echo_user_data`].
+    pub fn echo_user_data(&self) -> bool {
+        self.echo_user_data
+    }
+
+    /// Returns `true` if the server is not accepting new connections.
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
+
+    /// Sets whether the server is draining.
+    ///
+    /// While draining, new connection requests are denied with [`DenialReason::Draining`] and pending clients are
+    /// denied when they respond to their challenge. Connected clients are unaffected. This can be used to let current
+    /// players finish before shutting down the server.
+    pub fn set_draining(&mut self, draining: bool) {
+        self.draining = draining;
+    }
+
+    /// Sets a filter that decides whether to accept connection requests, replacing any previous filter.
+    ///
+    /// The filter is called with the client id, address and user data of the decrypted connect token when a new
+    /// address requests to connect, before the client is assigned a pending slot. If it returns `false` the request is
+    /// denied with [`DenialReason::Filtered`], and the client is notified with a connection denied packet.
+    pub fn set_connection_filter(
+        &mut self,
+        filter: impl FnMut(u64, SocketAddr, &[u8; NETCODE_USER_DATA_BYTES]) -> bool + Send + Sync + 'static,
+    ) {
+        self.connection_filter = Some(ConnectionFilter(Box::new(filter)));
+    }
+
+    /// Removes the filter set with [`Self::set_connection_filter`], so all valid connection requests are considered.
+    pub fn remove_connection_filter(&mut self) {
+        self.connection_filter = None;
+    }
+
+    /// Bans an address, so all packets received from it are dropped.
+    ///
+    /// Pending clients from the address are removed. Clients already connected from the address will time out, use
+    /// [`Self::disconnect`] to remove them immediately.
+    pub fn ban_addr(&mut self, addr: SocketAddr) {
+        self.banned_addrs.insert(addr);
+        self.retain_pending(|(_, pending_addr), _| *pending_addr != addr);
+    }
+
+    /// Removes an address ban, returns `true` if the address was banned.
+    pub fn unban_addr(&mut self, addr: SocketAddr) -> bool {
+        self.banned_addrs.remove(&addr)
+    }
+
+    /// Bans a client id, so its connection requests are denied with [`DenialReason::Banned`].
+    ///
+    /// If the client is connected then it is disconnected, and the result is the same as [`Self::disconnect`].
+    pub fn ban_client(&mut self, client_id: u64) -> ServerResult<'_, '_> {
+        self.banned_clients.insert(client_id);
+        self.retain_pending(|_, pending| pending.client_id != client_id);
+        self.disconnect(client_id)
+    }
+
+    /// Removes a client id ban, returns `true` if the client was banned.
+    pub fn unban_client(&mut self, client_id: u64) -> bool {
+        self.banned_clients.remove(&client_id)
+    }
+
+    /// Reserves a client slot for `client_id` until the server's [current time](Self::current_time) reaches `until`.
+    ///
+    /// Reserved slots count against [`max_clients`](Self::max_clients), so other clients are denied with
+    /// [`DenialReason::ServerFull`] if only reserved slots are free. When the reserved client connects it claims its
+    /// slot and the reservation is removed. Expired reservations are removed in [`Self::update`].
+    ///
+    /// Reserving a slot does not disconnect clients, so reservations can exceed the free slots (e.g. after
+    /// [`Self::set_max_clients`]). In that case the reserved clients may still be denied. Reserving a slot again
+    /// replaces the deadline. Does nothing if the client is already connected.
+    pub fn reserve_slot(&mut self, client_id: u64, until: Duration) {
+        if self.is_client_connected(client_id) {
+            return;
+        }
+        self.reserved_slots.insert(client_id, until);
+    }
+
+    /// Removes a client slot reservation made with [`Self::reserve_slot`].
+    ///
+    /// Returns `true` if the client had a reservation.
+    pub fn cancel_slot_reservation(&mut self, client_id: u64) -> bool {
+        self.reserved_slots.remove(&client_id).is_some()
+    }
+
+    /// Returns the number of client slots currently reserved.
+    pub fn reserved_slots(&self) -> usize {
+        self.reserved_slots.len()
+    }
+
+    /// Returns `true` if a client slot is available for `client_id`, accounting for slots reserved by other clients.
+    fn has_free_slot(&self, client_id: u64) -> bool {
+        let reserved_by_others = self.reserved_slots.len() - usize::from(self.reserved_slots.contains_key(&client_id));
+        self.connected_clients() + reserved_by_others < self.max_clients
+    }
+
+    fn is_protocol_id_accepted(&self, protocol_id: u64) -> bool {
+        protocol_id == self.protocol_id || self.accepted_protocol_ids.contains(&protocol_id)
+    }
+
+    /// Returns `true` if secure connect tokens for `token_socket_id` may be used on `socket_id`, see
+    /// [`ServerSocketConfig::shares_connect_tokens`].
+    fn shares_connect_tokens(&self, socket_id: usize, token_socket_id: usize) -> bool {
+        self.secure
+            && self.sockets[socket_id].shares_connect_tokens
+            && self.sockets.get(token_socket_id).is_some_and(|socket| socket.shares_connect_tokens)
+    }
+
+    /// Denies the pending or requesting client at `addr`, and returns the connection denied packet to send to it.
+    ///
+    /// The packet is encrypted with `send_key`, and `code` tells the client why it was denied.
+    fn deny<'a>(
+        &mut self,
+        socket_id: usize,
+        addr: SocketAddr,
+        protocol_id: u64,
+        send_key: &[u8; NETCODE_KEY_BYTES],
+        reason: DenialReason,
+        code: u8,
+    ) -> Result<ServerResult<'a, '_>, NetcodeError> {
+        self.remove_pending(socket_id, addr);
+        let packet = Packet::ConnectionDenied { reason: code };
+        let len = packet.encode(
+            &mut self.out,
+            protocol_id,
+            Some((self.global_sequence, send_key)),
+            self.sockets[socket_id].needs_encryption,
+        )?;
+        self.global_sequence += 1;
+        Ok(ServerResult::ConnectionDenied {
+            socket_id,
+            addr,
+            payload: Some(&mut self.out[..len]),
+            reason,
+        })
+    }
+
+    /// Counts a connection request from `addr`, and returns `false` if the address exceeded the connection rate limit.
+    fn allow_connection_request(&mut self, addr: SocketAddr) -> bool {
+        let Some((max_requests, window)) = self.connection_rate_limit else {
+            return true;
+        };
+        let current_time = self.current_time;
+        if self.connection_request_counts.len() >= NETCODE_MAX_RATE_LIMITED_ADDRESSES && !self.connection_request_counts.contains_key(&addr)
+        {
+            // Evict the address whose window started first, so new addresses are still limited.
+            let oldest = self
+                .connection_request_counts
+                .iter()
+                .min_by_key(|(_, (_, window_start))| *window_start)
+                .map(|(oldest, _)| *oldest);
+            if let Some(oldest) = oldest {
+                log::trace!("Connection rate limit for {} evicted: too many addresses are tracked.", oldest);
+                self.connection_request_counts.remove(&oldest);
+            }
+        }
+        let (count, window_start) = self.connection_request_counts.entry(addr).or_insert((0, current_time));
+        if current_time >= *window_start + window {
+            *count = 0;
+            *window_start = current_time;
+        }
+        *count = count.saturating_add(1);
+        *count <= max_requests
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn handle_connection_request<'a>(
         &mut self,
@@ -301,20 +937,38 @@ impl NetcodeServer {
             return Err(NetcodeError::InvalidVersion);
         }
 
-        if protocol_id != self.protocol_id {
-            return Err(NetcodeError::InvalidProtocolID);
+        if !self.allow_connection_request(addr) {
+            log::trace!(
+                "Connection request dropped: rate limit exceeded (socket id: {}, address: {}).",
+                socket_id,
+                addr
+            );
+            return Ok(ServerResult::None);
         }
 
-        if self.current_time.as_secs() >= expire_timestamp {
-            return Err(NetcodeError::Expired);
+        // Tokens with an unaccepted protocol id or that expired are only decrypted to tell the client why it was
+        // denied when requests are rate limited, since decryption is tried with every connect key.
+        let protocol_id_accepted = self.is_protocol_id_accepted(protocol_id);
+        let expired = self.current_time.as_secs() >= expire_timestamp;
+        if self.connection_rate_limit.is_none() {
+            if !protocol_id_accepted {
+                return Err(NetcodeError::InvalidProtocolID);
+            }
+            if expired {
+                return Err(NetcodeError::Expired);
+            }
         }
-
-        let connect_token = PrivateConnectToken::decode(&data, self.protocol_id, expire_timestamp, &xnonce, &self.connect_key)?;
+        let connect_token = match self.decode_private_connect_token(&data, protocol_id, expire_timestamp, &xnonce) {
+            Ok(connect_token) => connect_token,
+            Err(_) if !protocol_id_accepted => return Err(NetcodeError::InvalidProtocolID),
+            Err(_) if expired => return Err(NetcodeError::Expired),
+            Err(e) => return Err(e),
+        };
 
         if socket_id >= self.sockets.len() {
             return Err(NetcodeError::InvalidSocketId);
         }
-        if socket_id != connect_token.socket_id as usize {
+        if socket_id != connect_token.socket_id as usize && !self.shares_connect_tokens(socket_id, connect_token.socket_id as usize) {
             return Err(NetcodeError::InvalidSocketId);
         }
 
@@ -331,6 +985,40 @@ impl NetcodeServer {
             }
         }
 
+        let denial = match (protocol_id_accepted, expired) {
+            (false, _) => Some((DenialReason::ProtocolMismatch, NETCODE_DENIED_PROTOCOL_MISMATCH)),
+            (true, true) => Some((DenialReason::Expired, NETCODE_DENIED_EXPIRED)),
+            (true, false) => None,
+        };
+        if let Some((reason, code)) = denial {
+            log::debug!(
+                "Connection request denied: {:?} for client {} (protocol id: {}, socket id: {}, address: {}).",
+                reason,
+                connect_token.client_id,
+                protocol_id,
+                socket_id,
+                addr
+            );
+            return self.deny(socket_id, addr, protocol_id, &connect_token.server_to_client_key, reason, code);
+        }
+
+        if self.banned_clients.contains(&connect_token.client_id) {
+            log::debug!(
+                "Connection request denied: client {} is banned (socket id: {}, address: {}).",
+                connect_token.client_id,
+                socket_id,
+                addr
+            );
+            return self.deny(
+                socket_id,
+                addr,
+                protocol_id,
+                &connect_token.server_to_client_key,
+                DenialReason::Banned,
+                NETCODE_DENIED_UNSPECIFIED,
+            );
+        }
+
         if let Some((_, connection)) = find_client_mut_by_addr(&mut self.clients, socket_id, addr) {
             // This branch should be unreachable since connection requests are ignored for already-connected addresses.
 
@@ -357,6 +1045,7 @@ impl NetcodeServer {
                     addr,
                     socket_id,
                     payload: None,
+                    reason: DenialReason::AlreadyConnected,
                 });
             }
         } else if let Some(connection) = find_client_mut_by_id(&mut self.clients, connect_token.client_id) {
@@ -373,9 +1062,45 @@ impl NetcodeServer {
                 addr,
                 socket_id,
                 payload: None,
+                reason: DenialReason::AlreadyConnected,
             });
         }
 
+        if self.draining {
+            log::debug!(
+                "Connection request denied: server is draining (socket id: {}, address: {}).",
+                socket_id,
+                addr
+            );
+            return self.deny(
+                socket_id,
+                addr,
+                protocol_id,
+                &connect_token.server_to_client_key,
+                DenialReason::Draining,
+                NETCODE_DENIED_UNSPECIFIED,
+            );
+        }
+
+        if let Some(ConnectionFilter(filter)) = &mut self.connection_filter {
+            if !self.pending_clients.contains_key(&(socket_id, addr)) && !filter(connect_token.client_id, addr, &connect_token.user_data) {
+                log::debug!(
+                    "Connection request denied: client {} rejected by the connection filter (socket id: {}, address: {}).",
+                    connect_token.client_id,
+                    socket_id,
+                    addr
+                );
+                return self.deny(
+                    socket_id,
+                    addr,
+                    protocol_id,
+                    &connect_token.server_to_client_key,
+                    DenialReason::Filtered,
+                    NETCODE_DENIED_UNSPECIFIED,
+                );
+            }
+        }
+
         if !self.pending_clients.contains_key(&(socket_id, addr)) && self.pending_clients.len() >= NETCODE_MAX_PENDING_CLIENTS {
             log::warn!(
                 "Connection request denied: reached max amount allowed of pending clients ({}).",
@@ -385,9 +1110,28 @@ impl NetcodeServer {
                 addr,
                 socket_id,
                 payload: None,
+                reason: DenialReason::TooManyPendingClients,
             });
         }
 
+        if let Some(max_concurrent_handshakes) = self.sockets[socket_id].max_concurrent_handshakes {
+            if !self.pending_clients.contains_key(&(socket_id, addr))
+                && self.pending_count_on_socket(socket_id) >= max_concurrent_handshakes
+            {
+                log::warn!(
+                    "Connection request denied: reached max amount allowed of pending clients on socket {} ({}).",
+                    socket_id,
+                    max_concurrent_handshakes
+                );
+                return Ok(ServerResult::ConnectionDenied {
+                    addr,
+                    socket_id,
+                    payload: None,
+                    reason: DenialReason::TooManyPendingClients,
+                });
+            }
+        }
+
         let mut mac = [0u8; NETCODE_MAC_BYTES];
         mac.copy_from_slice(&data[NETCODE_CONNECT_TOKEN_PRIVATE_BYTES - NETCODE_MAC_BYTES..]);
         let connect_token_entry = ConnectTokenEntry {
@@ -403,24 +1147,19 @@ impl NetcodeServer {
                 addr,
                 socket_id,
                 payload: None,
+                reason: DenialReason::ConnectTokenInUse,
             });
         }
 
-        if self.clients.iter().flatten().count() >= self.max_clients {
-            self.pending_clients.remove(&(socket_id, addr));
-            let packet = Packet::ConnectionDenied;
-            let len = packet.encode(
-                &mut self.out,
-                self.protocol_id,
-                Some((self.global_sequence, &connect_token.server_to_client_key)),
-                self.sockets[socket_id].needs_encryption,
-            )?;
-            self.global_sequence += 1;
-            return Ok(ServerResult::ConnectionDenied {
+        if !self.has_free_slot(connect_token.client_id) {
+            return self.deny(
                 socket_id,
                 addr,
-                payload: Some(&mut self.out[..len]),
-            });
+                protocol_id,
+                &connect_token.server_to_client_key,
+                DenialReason::ServerFull,
+                NETCODE_DENIED_UNSPECIFIED,
+            );
         }
 
         self.challenge_sequence += 1;
@@ -433,7 +1172,7 @@ impl NetcodeServer {
 
         let len = packet.encode(
             &mut self.out,
-            self.protocol_id,
+            protocol_id,
             Some((self.global_sequence, &connect_token.server_to_client_key)),
             self.sockets[socket_id].needs_encryption,
         )?;
@@ -441,14 +1180,23 @@ impl NetcodeServer {
 
         log::trace!("Connection request from Client {}", connect_token.client_id);
 
+        let randomize_initial_sequence = self.randomize_initial_sequence;
+        if !self.pending_clients.contains_key(&(socket_id, addr)) {
+            self.pending_counts[socket_id] += 1;
+        }
         let pending = self.pending_clients.entry((socket_id, addr)).or_insert_with(|| Connection {
             confirmed: false,
-            sequence: 0,
+            sequence: match randomize_initial_sequence {
+                true => u64::from_le_bytes(generate_random_bytes()) >> 2,
+                false => 0,
+            },
             client_id: connect_token.client_id,
             last_packet_received_time: self.current_time,
             last_packet_send_time: self.current_time,
+            connected_at: self.current_time,
             socket_id,
             addr,
+            protocol_id,
             state: ConnectionState::PendingResponse,
             send_key: connect_token.server_to_client_key,
             receive_key: connect_token.client_to_server_key,
@@ -469,20 +1217,22 @@ impl NetcodeServer {
     }
 
     /// Returns an encoded packet payload to be sent to the client.
+    ///
+    /// The payload can't be larger than the [`ServerSocketConfig::max_payload_bytes`] of the client's socket.
     pub fn generate_payload_packet<'s>(
         &'s mut self,
         client_id: u64,
         payload: &[u8],
     ) -> Result<(usize, SocketAddr, &'s mut [u8]), NetcodeError> {
-        if payload.len() > NETCODE_MAX_PAYLOAD_BYTES {
-            return Err(NetcodeError::PayloadAboveLimit);
-        }
-
         if let Some(client) = find_client_mut_by_id(&mut self.clients, client_id) {
+            if payload.len() > self.sockets[client.socket_id].max_payload_bytes {
+                return Err(NetcodeError::PayloadAboveLimit);
+            }
+
             let packet = Packet::Payload(payload);
             let len = packet.encode(
                 &mut self.out,
-                self.protocol_id,
+                client.protocol_id,
                 Some((client.sequence, &client.send_key)),
                 self.sockets[client.socket_id].needs_encryption,
             )?;
@@ -495,8 +1245,52 @@ impl NetcodeServer {
         Err(NetcodeError::ClientNotFound)
     }
 
+    /// Registers the handler for extension packets of `packet_type` received from connected clients, replacing
+    /// any previous handler for that type.
+    ///
+    /// Returns [`NetcodeError::InvalidPacketType`] if `packet_type` is not one of the
+    /// [`NETCODE_EXTENSION_PACKET_TYPES`](crate::NETCODE_EXTENSION_PACKET_TYPES). See [`PacketExtension`].
+    #[cfg(feature = "extensions")]
+    pub fn register_extension(&mut self, packet_type: u8, extension: impl PacketExtension) -> Result<(), NetcodeError> {
+        self.extensions.register(packet_type, Box::new(extension))
+    }
+
+    /// Returns an encoded extension packet of `packet_type` to be sent to the client. See [`PacketExtension`].
+    ///
+    /// The payload can't be larger than the [`ServerSocketConfig::max_payload_bytes`] of the client's socket.
+    #[cfg(feature = "extensions")]
+    pub fn generate_extension_packet<'s>(
+        &'s mut self,
+        client_id: u64,
+        packet_type: u8,
+        payload: &[u8],
+    ) -> Result<(usize, SocketAddr, &'s mut [u8]), NetcodeError> {
+        if let Some(client) = find_client_mut_by_id(&mut self.clients, client_id) {
+            if payload.len() > self.sockets[client.socket_id].max_payload_bytes {
+                return Err(NetcodeError::PayloadAboveLimit);
+            }
+
+            let len = encode_extension_packet(
+                &mut self.out,
+                packet_type,
+                payload,
+                client.protocol_id,
+                (client.sequence, &client.send_key),
+                self.sockets[client.socket_id].needs_encryption,
+            )?;
+            client.sequence += 1;
+            client.last_packet_send_time = self.current_time;
+
+            return Ok((client.socket_id, client.addr, &mut self.out[..len]));
+        }
+
+        Err(NetcodeError::ClientNotFound)
+    }
+
     /// Process an packet from the especifed address. Returns a server result, check out
     /// [ServerResult].
+    ///
+    /// Returns [`ServerResult::Error`] if `socket_id` does not match any of the server's sockets.
     pub fn process_packet<'a, 's>(&'s mut self, socket_id: usize, addr: SocketAddr, buffer: &'a mut [u8]) -> ServerResult<'a, 's> {
         match self.process_packet_internal(socket_id, addr, buffer) {
             Err(e) => {
@@ -513,15 +1307,50 @@ impl NetcodeServer {
         addr: SocketAddr,
         buffer: &'a mut [u8],
     ) -> Result<ServerResult<'a, 's>, NetcodeError> {
+        if socket_id >= self.sockets.len() {
+            return Err(NetcodeError::InvalidSocketId);
+        }
+
+        if self.banned_addrs.contains(&addr) {
+            log::trace!("Packet dropped: address {} is banned (socket id: {}).", addr, socket_id);
+            return Ok(ServerResult::None);
+        }
+
         if buffer.len() < 2 + NETCODE_MAC_BYTES {
             return Err(NetcodeError::PacketTooSmall);
         }
 
         // Handle connected client
         if let Some((slot, client)) = find_client_mut_by_addr(&mut self.clients, socket_id, addr) {
+            #[cfg(feature = "extensions")]
+            if let Some(packet_type) = extension_packet_type(buffer) {
+                let payload = decode_extension_packet(
+                    buffer,
+                    client.protocol_id,
+                    &client.receive_key,
+                    &mut client.replay_protection,
+                    self.sockets[socket_id].needs_encryption,
+                )?;
+                log::trace!(
+                    "Received extension packet from connected client ({}): {}",
+                    client.client_id,
+                    packet_type
+                );
+
+                client.last_packet_received_time = self.current_time;
+                if client.state == ConnectionState::Connected {
+                    if !client.confirmed {
+                        log::trace!("Confirmed connection for Client {}", client.client_id);
+                        client.confirmed = true;
+                    }
+                    self.extensions.receive(packet_type, client.client_id, payload);
+                }
+                return Ok(ServerResult::None);
+            }
+
             let (_, packet) = Packet::decode(
                 buffer,
-                self.protocol_id,
+                client.protocol_id,
                 Some(&client.receive_key),
                 Some(&mut client.replay_protection),
                 self.sockets[socket_id].needs_encryption,
@@ -535,7 +1364,7 @@ impl NetcodeServer {
             client.last_packet_received_time = self.current_time;
             match client.state {
                 ConnectionState::Connected => match packet {
-                    Packet::Disconnect => {
+                    Packet::Disconnect { .. } => {
                         client.state = ConnectionState::Disconnected;
                         let client_id = client.client_id;
                         self.clients[slot] = None;
@@ -564,6 +1393,31 @@ impl NetcodeServer {
                         }
                         return Ok(ServerResult::None);
                     }
+                    Packet::Response { .. }
+                        if !client.confirmed && self.current_time.saturating_sub(client.connected_at) < self.response_grace =>
+                    {
+                        // The client is still waiting for the keep-alive that completes the handshake (it was lost or
+                        // reordered behind this response), resend it now instead of at the next send interval.
+                        log::trace!("Resending connection keep-alive to Client {}", client.client_id);
+                        let packet = Packet::KeepAlive {
+                            max_clients: self.max_clients as u32,
+                            client_index: slot as u32,
+                            user_data: self.echo_user_data.then_some(&client.user_data),
+                        };
+                        let len = packet.encode(
+                            &mut self.out,
+                            client.protocol_id,
+                            Some((client.sequence, &client.send_key)),
+                            self.sockets[socket_id].needs_encryption,
+                        )?;
+                        client.sequence += 1;
+                        client.last_packet_send_time = self.current_time;
+                        return Ok(ServerResult::PacketToSend {
+                            socket_id,
+                            addr,
+                            payload: &mut self.out[..len],
+                        });
+                    }
                     _ => return Ok(ServerResult::None),
                 },
                 _ => return Ok(ServerResult::None),
@@ -574,7 +1428,7 @@ impl NetcodeServer {
         if let Some(pending) = self.pending_clients.get_mut(&(socket_id, addr)) {
             let (_, packet) = Packet::decode(
                 buffer,
-                self.protocol_id,
+                pending.protocol_id,
                 Some(&pending.receive_key),
                 Some(&mut pending.replay_protection),
                 self.sockets[socket_id].needs_encryption,
@@ -596,7 +1450,7 @@ impl NetcodeServer {
                     token_sequence,
                 } => {
                     let challenge_token = ChallengeToken::decode(token_data, token_sequence, &self.challenge_key)?;
-                    let mut pending = self.pending_clients.remove(&(socket_id, addr)).unwrap();
+                    let mut pending = self.remove_pending(socket_id, addr).unwrap();
                     if find_client_slot_by_id(&self.clients, challenge_token.client_id).is_some() {
                         log::debug!(
                             "Ignored connection response for Client {}, already connected.",
@@ -604,36 +1458,39 @@ impl NetcodeServer {
                         );
                         return Ok(ServerResult::None);
                     }
-                    match self.clients.iter().position(|c| c.is_none()) {
+                    let client_index = match self.draining || !self.has_free_slot(challenge_token.client_id) {
+                        true => None,
+                        false => self.clients.iter().position(|c| c.is_none()),
+                    };
+                    match client_index {
                         None => {
-                            let packet = Packet::ConnectionDenied;
-                            let len = packet.encode(
-                                &mut self.out,
-                                self.protocol_id,
-                                Some((self.global_sequence, &pending.send_key)),
-                                self.sockets[socket_id].needs_encryption,
-                            )?;
-                            // pending.state = ConnectionState::Disconnected;
-                            self.global_sequence += 1;
-                            // pending.last_packet_send_time = self.current_time;
-                            return Ok(ServerResult::ConnectionDenied {
+                            let reason = match self.draining {
+                                true => DenialReason::Draining,
+                                false => DenialReason::ServerFull,
+                            };
+                            return self.deny(
                                 socket_id,
                                 addr,
-                                payload: Some(&mut self.out[..len]),
-                            });
+                                pending.protocol_id,
+                                &pending.send_key,
+                                reason,
+                                NETCODE_DENIED_UNSPECIFIED,
+                            );
                         }
                         Some(client_index) => {
                             pending.state = ConnectionState::Connected;
                             pending.user_data = challenge_token.user_data;
                             pending.last_packet_send_time = self.current_time;
+                            pending.connected_at = self.current_time;
 
                             let packet = Packet::KeepAlive {
                                 max_clients: self.max_clients as u32,
                                 client_index: client_index as u32,
+                                user_data: self.echo_user_data.then_some(&pending.user_data),
                             };
                             let len = packet.encode(
                                 &mut self.out,
-                                self.protocol_id,
+                                pending.protocol_id,
                                 Some((pending.sequence, &pending.send_key)),
                                 self.sockets[socket_id].needs_encryption,
                             )?;
@@ -642,6 +1499,7 @@ impl NetcodeServer {
                             let client_id: u64 = pending.client_id;
                             let user_data: [u8; NETCODE_USER_DATA_BYTES] = pending.user_data;
                             self.clients[client_index] = Some(pending);
+                            self.reserved_slots.remove(&client_id);
 
                             return Ok(ServerResult::ClientConnected {
                                 client_id,
@@ -689,6 +1547,24 @@ impl NetcodeServer {
         self.clients_id_iter().collect()
     }
 
+    /// Returns up to `limit` ids from the connected clients, skipping the first `offset` clients.
+    ///
+    /// Clients are ordered by their client slot, so pages may shift when clients connect or disconnect.
+    pub fn clients_id_paginated(&self, offset: usize, limit: usize) -> Vec<u64> {
+        self.clients_id_iter().skip(offset).take(limit).collect()
+    }
+
+    /// Returns the ids of connected clients that have not been confirmed yet.
+    ///
+    /// A client is confirmed once the server receives its first payload or keepalive packet after connecting. Until
+    /// then the client may not know it is connected, so servers may want to delay sending large initial state.
+    pub fn unconfirmed_clients(&self) -> Vec<u64> {
+        self.clients
+            .iter()
+            .filter_map(|slot| slot.as_ref().filter(|client| !client.confirmed).map(|client| client.client_id))
+            .collect()
+    }
+
     /// Returns the maximum number of clients that can be connected.
     pub fn max_clients(&self) -> usize {
         self.max_clients
@@ -699,6 +1575,8 @@ impl NetcodeServer {
     /// Changing the `max_clients` to a lower value than the current number of connect clients
     /// does not disconnect clients. So [`NetcodeServer::connected_clients()`] can return a
     /// higher value than [`NetcodeServer::max_clients()`].
+    ///
+    /// Slots reserved with [`NetcodeServer::reserve_slot()`] count against `max_clients`.
     pub fn set_max_clients(&mut self, max_clients: usize) {
         let max_clients = max_clients.min(NETCODE_MAX_CLIENTS);
         log::debug!("Netcode max_clients set to {}", max_clients);
@@ -706,12 +1584,50 @@ impl NetcodeServer {
         self.max_clients = max_clients;
     }
 
+    /// Sets the capacity thresholds, see [`ServerConfig::capacity_thresholds`].
+    ///
+    /// Thresholds that the server's load already exceeds will emit [`CapacityEvent::Exceeded`] in the next update.
+    pub fn set_capacity_thresholds(&mut self, capacity_thresholds: Vec<CapacityThreshold>) {
+        self.capacity_thresholds = capacity_thresholds.into_iter().map(|t| (t, false)).collect();
+    }
+
+    /// Returns the next capacity event if available.
+    ///
+    /// Each threshold emits [`CapacityEvent::Exceeded`] once when the load reaches it, and [`CapacityEvent::Recovered`]
+    /// once when the load falls back below it (accounting for hysteresis).
+    pub fn get_capacity_event(&mut self) -> Option<CapacityEvent> {
+        self.capacity_events.pop_front()
+    }
+
+    fn update_capacity_thresholds(&mut self) {
+        if self.capacity_thresholds.is_empty() || self.max_clients == 0 {
+            return;
+        }
+
+        let load = self.connected_clients() as f32 / self.max_clients as f32;
+        for (threshold, exceeded) in self.capacity_thresholds.iter_mut() {
+            if !*exceeded && load >= threshold.fraction {
+                *exceeded = true;
+                log::debug!("Server load {load} exceeded capacity threshold {}", threshold.fraction);
+                self.capacity_events.push_back(CapacityEvent::Exceeded {
+                    fraction: threshold.fraction,
+                });
+            } else if *exceeded && load < threshold.fraction - threshold.hysteresis {
+                *exceeded = false;
+                log::debug!("Server load {load} recovered from capacity threshold {}", threshold.fraction);
+                self.capacity_events.push_back(CapacityEvent::Recovered {
+                    fraction: threshold.fraction,
+                });
+            }
+        }
+    }
+
     /// Returns current number of clients connected.
     pub fn connected_clients(&self) -> usize {
         self.clients.iter().filter(|slot| slot.is_some()).count()
     }
 
-    /// Advance the server current time, and remove any pending connections that have expired.
+    /// Advance the server current time, and remove any pending connections and slot reservations that have expired.
     pub fn update(&mut self, duration: Duration) {
         self.current_time += duration;
 
@@ -722,7 +1638,23 @@ impl NetcodeServer {
             }
         }
 
-        self.pending_clients.retain(|_, c| c.state != ConnectionState::Disconnected);
+        self.retain_pending(|_, c| c.state != ConnectionState::Disconnected);
+
+        let current_time = self.current_time;
+        self.reserved_slots.retain(|client_id, until| {
+            let expired = current_time >= *until;
+            if expired {
+                log::debug!("Reserved slot for Client {} expired.", client_id);
+            }
+            !expired
+        });
+
+        if let Some((_, window)) = self.connection_rate_limit {
+            self.connection_request_counts
+                .retain(|_, (_, window_start)| current_time < *window_start + window);
+        }
+
+        self.update_capacity_thresholds();
     }
 
     /// Updates the client, returns a ServerResult.
@@ -755,15 +1687,16 @@ impl NetcodeServer {
             let socket_id = client.socket_id;
 
             if client.state == ConnectionState::Disconnected {
-                let packet = Packet::Disconnect;
+                let packet = Packet::Disconnect { reason: 0 };
                 let sequence = client.sequence;
                 let send_key = client.send_key;
                 let addr = client.addr;
+                let protocol_id = client.protocol_id;
                 self.clients[slot] = None;
 
                 let len = match packet.encode(
                     &mut self.out,
-                    self.protocol_id,
+                    protocol_id,
                     Some((sequence, &send_key)),
                     self.sockets[socket_id].needs_encryption,
                 ) {
@@ -788,36 +1721,58 @@ impl NetcodeServer {
             }
 
             if client.last_packet_send_time + NETCODE_SEND_RATE <= self.current_time {
-                let packet = Packet::KeepAlive {
-                    client_index: slot as u32,
-                    max_clients: self.max_clients as u32,
-                };
-
-                let len = match packet.encode(
-                    &mut self.out,
-                    self.protocol_id,
-                    Some((client.sequence, &client.send_key)),
-                    self.sockets[socket_id].needs_encryption,
-                ) {
-                    Err(e) => {
-                        log::error!("Failed to encode keep alive packet: {}", e);
-                        return ServerResult::None;
-                    }
-                    Ok(len) => len,
-                };
-                client.sequence += 1;
-                client.last_packet_send_time = self.current_time;
-                return ServerResult::PacketToSend {
-                    socket_id,
-                    addr: client.addr,
-                    payload: &mut self.out[..len],
-                };
+                return self.send_keep_alive(slot);
             }
         }
 
         ServerResult::None
     }
 
+    /// Immediately generates a keep alive packet for the client, regardless of when the last packet
+    /// was sent to it. Returns [`ServerResult::None`] if the client is not connected.
+    ///
+    /// Useful for probing whether a client is still alive without waiting for the next keep alive
+    /// from [`NetcodeServer::update_client`].
+    pub fn send_keepalive_now(&mut self, client_id: u64) -> ServerResult<'_, '_> {
+        match find_client_slot_by_id(&self.clients, client_id) {
+            None => ServerResult::None,
+            Some(slot) => self.send_keep_alive(slot),
+        }
+    }
+
+    fn send_keep_alive(&mut self, slot: usize) -> ServerResult<'_, '_> {
+        let Some(client) = &mut self.clients[slot] else {
+            return ServerResult::None;
+        };
+
+        let packet = Packet::KeepAlive {
+            client_index: slot as u32,
+            max_clients: self.max_clients as u32,
+            user_data: (self.echo_user_data && !client.confirmed).then_some(&client.user_data),
+        };
+
+        let len = match packet.encode(
+            &mut self.out,
+            client.protocol_id,
+            Some((client.sequence, &client.send_key)),
+            self.sockets[client.socket_id].needs_encryption,
+        ) {
+            Err(e) => {
+                log::error!("Failed to encode keep alive packet: {}", e);
+                return ServerResult::None;
+            }
+            Ok(len) => len,
+        };
+        client.sequence += 1;
+        client.last_packet_send_time = self.current_time;
+
+        ServerResult::PacketToSend {
+            socket_id: client.socket_id,
+            addr: client.addr,
+            payload: &mut self.out[..len],
+        }
+    }
+
     pub fn is_client_connected(&self, client_id: u64) -> bool {
         find_client_slot_by_id(&self.clients, client_id).is_some()
     }
@@ -827,37 +1782,64 @@ impl NetcodeServer {
     //       but the library user would need to be aware that he has to run
     //       the same code as Result::ClientDisconnected
     pub fn disconnect(&mut self, client_id: u64) -> ServerResult<'_, '_> {
+        self.disconnect_with_reason(client_id, 0)
+    }
+
+    /// Disconnects a client like [`Self::disconnect`], with an application-defined reason code (e.g. "kicked for
+    /// idling" or "server full").
+    ///
+    /// The client can read the code from [`DisconnectReason::DisconnectedByServer`](crate::DisconnectReason). A code
+    /// of 0 is the same as [`Self::disconnect`].
+    pub fn disconnect_with_reason(&mut self, client_id: u64, reason: u8) -> ServerResult<'_, '_> {
         if let Some(slot) = find_client_slot_by_id(&self.clients, client_id) {
             let client = self.clients[slot].take().unwrap();
-            let packet = Packet::Disconnect;
-
-            let len = match packet.encode(
-                &mut self.out,
-                self.protocol_id,
-                Some((client.sequence, &client.send_key)),
-                self.sockets[client.socket_id].needs_encryption,
-            ) {
-                Err(e) => {
-                    log::error!("Failed to encode disconnect packet: {}", e);
-                    return ServerResult::ClientDisconnected {
-                        client_id,
-                        socket_id: client.socket_id,
-                        addr: client.addr,
-                        payload: None,
-                    };
-                }
-                Ok(len) => len,
-            };
+            let needs_encryption = self.sockets[client.socket_id].needs_encryption;
             return ServerResult::ClientDisconnected {
                 client_id,
                 socket_id: client.socket_id,
                 addr: client.addr,
-                payload: Some(&mut self.out[..len]),
+                payload: encode_disconnect_packet(&client, reason, needs_encryption, &mut self.out),
             };
         }
 
         ServerResult::None
     }
+
+    /// Disconnects multiple clients, returning a [`ServerResult::ClientDisconnected`] for each of them.
+    ///
+    /// Ids that are not connected are skipped. See [`Self::disconnect`].
+    pub fn disconnect_many(&mut self, client_ids: &[u64]) -> Vec<ServerResult<'_, '_>> {
+        let clients: Vec<Connection> = client_ids
+            .iter()
+            .filter_map(|client_id| {
+                let slot = find_client_slot_by_id(&self.clients, *client_id)?;
+                self.clients[slot].take()
+            })
+            .collect();
+
+        self.disconnect_out.resize(clients.len() * NETCODE_MAX_PACKET_BYTES, 0);
+        clients
+            .into_iter()
+            .zip(self.disconnect_out.chunks_mut(NETCODE_MAX_PACKET_BYTES))
+            .map(|(client, out)| ServerResult::ClientDisconnected {
+                client_id: client.client_id,
+                socket_id: client.socket_id,
+                addr: client.addr,
+                payload: encode_disconnect_packet(&client, 0, self.sockets[client.socket_id].needs_encryption, out),
+            })
+            .collect()
+    }
+}
+
+fn encode_disconnect_packet<'s>(client: &Connection, reason: u8, needs_encryption: bool, out: &'s mut [u8]) -> Option<&'s mut [u8]> {
+    let packet = Packet::Disconnect { reason };
+    match packet.encode(out, client.protocol_id, Some((client.sequence, &client.send_key)), needs_encryption) {
+        Err(e) => {
+            log::error!("Failed to encode disconnect packet: {}", e);
+            None
+        }
+        Ok(len) => Some(&mut out[..len]),
+    }
 }
 
 fn find_client_mut_by_id(clients: &mut [Option<Connection>], client_id: u64) -> Option<&mut Connection> {
@@ -884,22 +1866,78 @@ fn find_client_mut_by_addr(clients: &mut [Option<Connection>], socket_id: usize,
 
 #[cfg(test)]
 mod tests {
-    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication};
+    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication, DisconnectReason, HandshakeTimings};
 
     use super::*;
 
     const TEST_KEY: &[u8; NETCODE_KEY_BYTES] = b"an example very very secret key."; // 32-bytes
     const TEST_PROTOCOL_ID: u64 = 7;
 
-    fn new_server() -> NetcodeServer {
-        let config = ServerConfig {
+    fn test_config() -> ServerConfig {
+        ServerConfig {
             current_time: Duration::ZERO,
             max_clients: 16,
             protocol_id: TEST_PROTOCOL_ID,
+            accepted_protocol_ids: Vec::new(),
+            capacity_thresholds: Vec::new(),
+            randomize_initial_sequence: false,
+            connection_rate_limit: None,
             sockets: vec![ServerSocketConfig::new(vec!["127.0.0.1:5000".parse().unwrap()])],
             authentication: ServerAuthentication::Secure { private_key: *TEST_KEY },
+        }
+    }
+
+    fn new_server() -> NetcodeServer {
+        NetcodeServer::new(test_config())
+    }
+
+    #[test]
+    fn new_secure_generated() {
+        let config = ServerConfig {
+            authentication: ServerAuthentication::Unsecure,
+            ..test_config()
         };
-        NetcodeServer::new(config)
+        let (mut server, private_key) = NetcodeServer::new_secure_generated(config);
+        assert_ne!(&private_key, TEST_KEY);
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        for (key, accepted) in [(TEST_KEY, false), (&private_key, true)] {
+            let mut client = new_client_with_addresses(&server, 4, 0, server.addresses(0), key);
+            let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+            let result = server.process_packet(0, client_addr, client_packet);
+            assert_eq!(matches!(result, ServerResult::ConnectionAccepted { .. }), accepted);
+        }
+    }
+
+    #[test]
+    fn decrypt_key_rotation() {
+        let mut server = new_server();
+        let new_key: [u8; NETCODE_KEY_BYTES] = generate_random_bytes();
+        let mut port = 3000;
+        let mut try_connect = |server: &mut NetcodeServer, key: &[u8; NETCODE_KEY_BYTES]| -> bool {
+            port += 1;
+            let client_addr = SocketAddr::from(([127, 0, 0, 1], port));
+            let mut client = new_client_with_addresses(server, port as u64, 0, server.addresses(0), key);
+            let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+            let result = server.process_packet(0, client_addr, client_packet);
+            matches!(result, ServerResult::ConnectionAccepted { .. })
+        };
+
+        assert!(try_connect(&mut server, TEST_KEY));
+        assert!(!try_connect(&mut server, &new_key));
+
+        // Both keys are accepted during the grace period.
+        server.add_decrypt_key(new_key);
+        assert!(try_connect(&mut server, TEST_KEY));
+        assert!(try_connect(&mut server, &new_key));
+
+        assert!(server.retire_decrypt_key(TEST_KEY));
+        assert!(!server.retire_decrypt_key(TEST_KEY));
+        assert!(!try_connect(&mut server, TEST_KEY));
+        assert!(try_connect(&mut server, &new_key));
+
+        assert!(server.retire_decrypt_key(&new_key));
+        assert!(!try_connect(&mut server, &new_key));
     }
 
     #[test]
@@ -1002,6 +2040,344 @@ mod tests {
         assert!(!server.is_client_connected(client_id));
     }
 
+    #[test]
+    fn reordered_connection_response() {
+        // Returns the server's reply to a connection response that arrives after an earlier response connected the
+        // client, and the client's connection.
+        fn reorder_responses(server: &mut NetcodeServer, client_addr: SocketAddr) -> (bool, NetcodeClient) {
+            let mut client = new_client_with_addresses(server, 4, 0, server.addresses(0), TEST_KEY);
+
+            let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+            match server.process_packet(0, client_addr, client_packet) {
+                ServerResult::ConnectionAccepted { payload, .. } => client.process_packet(payload),
+                _ => unreachable!(),
+            };
+
+            // The client resends its response, and the packets are reordered.
+            let (first_response, _) = client.update(Duration::ZERO).unwrap();
+            let mut first_response = first_response.to_vec();
+            let (second_response, _) = client.update(NETCODE_SEND_RATE).unwrap();
+
+            // The keep-alive that completes the handshake is lost.
+            assert!(matches!(
+                server.process_packet(0, client_addr, second_response),
+                ServerResult::ClientConnected { .. }
+            ));
+            assert!(!client.is_connected());
+
+            let resent = match server.process_packet(0, client_addr, &mut first_response) {
+                ServerResult::PacketToSend { payload, .. } => {
+                    client.process_packet(payload);
+                    true
+                }
+                ServerResult::None => false,
+                _ => unreachable!(),
+            };
+            (resent, client)
+        }
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut server = new_server();
+        assert_eq!(server.response_grace(), NETCODE_RESPONSE_GRACE);
+        let (resent, client) = reorder_responses(&mut server, client_addr);
+        assert!(resent);
+        assert!(client.is_connected());
+
+        let mut server = new_server();
+        server.set_response_grace(Duration::ZERO);
+        let (resent, client) = reorder_responses(&mut server, client_addr);
+        assert!(!resent);
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn keepalive_now() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, client_id, client_addr).unwrap();
+
+        // The send rate interval has not elapsed, so no keep alive is scheduled.
+        assert_eq!(server.update_client(client_id), ServerResult::None);
+
+        match server.send_keepalive_now(client_id) {
+            ServerResult::PacketToSend { socket_id, addr, payload } => {
+                assert_eq!(socket_id, 0);
+                assert_eq!(addr, client_addr);
+                assert!(client.process_packet(payload).is_none());
+            }
+            _ => unreachable!(),
+        }
+        assert!(client.is_connected());
+
+        // The send timer was reset.
+        server.update(NETCODE_SEND_RATE / 2);
+        assert_eq!(server.update_client(client_id), ServerResult::None);
+
+        assert_eq!(server.send_keepalive_now(client_id + 1), ServerResult::None);
+    }
+
+    #[test]
+    fn socket_max_payload_bytes() {
+        let mut socket_config = ServerSocketConfig::new(vec!["127.0.0.1:5000".parse().unwrap()]);
+        socket_config.max_payload_bytes = NETCODE_MAX_PAYLOAD_BYTES * 3;
+        let mut server = NetcodeServer::new(ServerConfig {
+            sockets: vec![socket_config, ServerSocketConfig::new(vec!["127.0.0.1:5001".parse().unwrap()])],
+            ..test_config()
+        });
+        let client_id = 4;
+        let mut client = connect_client(&mut server, client_id, "127.0.0.1:3000".parse().unwrap()).unwrap();
+
+        let payload = vec![7u8; NETCODE_MAX_PAYLOAD_BYTES * 3];
+        let (_, _, packet) = server.generate_payload_packet(client_id, &payload).unwrap();
+        assert!(packet.len() > NETCODE_MAX_PACKET_BYTES);
+        assert_eq!(client.process_packet(packet).unwrap(), &payload[..]);
+
+        let payload = vec![7u8; NETCODE_MAX_PAYLOAD_BYTES * 3 + 1];
+        assert!(matches!(
+            server.generate_payload_packet(client_id, &payload),
+            Err(NetcodeError::PayloadAboveLimit)
+        ));
+    }
+
+    #[test]
+    fn accepted_protocol_ids() {
+        let old_protocol_id = TEST_PROTOCOL_ID - 1;
+        let mut server = new_server();
+        server.set_accepted_protocol_ids(vec![old_protocol_id]);
+
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            old_protocol_id,
+            3,
+            client_id,
+            5,
+            0,
+            server.addresses(0),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let mut client = complete_handshake(&mut server, client, client_addr).unwrap();
+        assert_eq!(server.client_protocol_id(client_id), Some(old_protocol_id));
+
+        // Packets are exchanged with the client's protocol id.
+        let payload = [7u8; 300];
+        let (_, _, packet) = server.generate_payload_packet(client_id, &payload).unwrap();
+        assert_eq!(client.process_packet(packet).unwrap(), payload);
+        let (_, packet) = client.generate_payload_packet(&payload).unwrap();
+        match server.process_packet(0, client_addr, packet) {
+            ServerResult::Payload { client_id: id, payload: p } => {
+                assert_eq!(id, client_id);
+                assert_eq!(p, payload);
+            }
+            _ => unreachable!(),
+        }
+
+        // Unknown protocol ids are rejected.
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID + 1,
+            3,
+            client_id + 1,
+            5,
+            0,
+            server.addresses(0),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        let client_addr: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        assert!(matches!(
+            server.process_packet(0, client_addr, &mut client_packet.to_vec()),
+            ServerResult::Error { .. }
+        ));
+
+        // The client is only told about the mismatch when requests are rate limited.
+        server.connection_rate_limit = Some((10, Duration::from_secs(1)));
+        match server.process_packet(0, client_addr, client_packet) {
+            ServerResult::ConnectionDenied { payload, reason, .. } => {
+                assert_eq!(reason, DenialReason::ProtocolMismatch);
+                client.process_packet(payload.unwrap());
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ProtocolMismatch));
+    }
+
+    #[test]
+    fn expired_token_denied() {
+        let mut server = new_server();
+        server.update(Duration::from_secs(5));
+
+        // The client's clock is behind the server's, so it still considers the token valid.
+        let connect_token =
+            ConnectToken::generate(Duration::ZERO, TEST_PROTOCOL_ID, 3, 4, 5, 0, server.addresses(0), None, TEST_KEY).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        assert!(matches!(
+            server.process_packet(0, client_addr, &mut client_packet.to_vec()),
+            ServerResult::Error { .. }
+        ));
+
+        // The client is only told the token expired when requests are rate limited.
+        server.connection_rate_limit = Some((10, Duration::from_secs(1)));
+        match server.process_packet(0, client_addr, client_packet) {
+            ServerResult::ConnectionDenied { payload, reason, .. } => {
+                assert_eq!(reason, DenialReason::Expired);
+                client.process_packet(payload.unwrap());
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectTokenExpired));
+    }
+
+    #[test]
+    fn draining() {
+        let mut server = new_server();
+        assert!(!server.is_draining());
+
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, client_id, client_addr).unwrap();
+
+        server.set_draining(true);
+        assert!(server.is_draining());
+
+        // New connections are denied.
+        let mut new_client = new_client_with_addresses(&server, client_id + 1, 0, server.addresses(0), TEST_KEY);
+        let (client_packet, _) = new_client.update(Duration::ZERO).unwrap();
+        match server.process_packet(0, "127.0.0.1:3001".parse().unwrap(), client_packet) {
+            ServerResult::ConnectionDenied { payload, reason, .. } => {
+                assert_eq!(reason, DenialReason::Draining);
+                new_client.process_packet(payload.unwrap());
+            }
+            _ => unreachable!(),
+        }
+        assert!(new_client.is_disconnected());
+        assert!(!server.is_client_connected(client_id + 1));
+
+        // Existing clients are unaffected.
+        assert!(server.is_client_connected(client_id));
+        let payload = [7u8; 300];
+        let (_, _, packet) = server.generate_payload_packet(client_id, &payload).unwrap();
+        assert_eq!(client.process_packet(packet).unwrap(), payload);
+
+        // Connections are accepted again after draining stops.
+        server.set_draining(false);
+        connect_client(&mut server, client_id + 1, "127.0.0.1:3002".parse().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn clients_id_paginated() {
+        let mut server = new_server();
+        for client_id in 0..5 {
+            let client_addr = SocketAddr::from(([127, 0, 0, 1], 3000 + client_id as u16));
+            connect_client(&mut server, client_id, client_addr).unwrap();
+        }
+
+        assert_eq!(server.clients_id_paginated(0, 2), vec![0, 1]);
+        assert_eq!(server.clients_id_paginated(2, 2), vec![2, 3]);
+        assert_eq!(server.clients_id_paginated(4, 2), vec![4]);
+        assert!(server.clients_id_paginated(5, 2).is_empty());
+        assert!(server.clients_id_paginated(0, 0).is_empty());
+    }
+
+    #[test]
+    fn time_until_timeout() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, client_id, client_addr).unwrap();
+        assert_eq!(server.time_until_timeout(client_id), Some(Duration::from_secs(5)));
+        assert_eq!(server.time_until_timeout(client_id + 1), None);
+
+        server.update(Duration::from_secs(2));
+        assert_eq!(server.time_until_timeout(client_id), Some(Duration::from_secs(3)));
+
+        // Receiving a packet resets the countdown.
+        let (_, client_packet) = client.generate_payload_packet(&[1u8; 10]).unwrap();
+        server.process_packet(0, client_addr, client_packet);
+        assert_eq!(server.time_until_timeout(client_id), Some(Duration::from_secs(5)));
+
+        server.update(Duration::from_secs(5));
+        assert_eq!(server.time_until_timeout(client_id), Some(Duration::ZERO));
+        assert!(server.is_client_connected(client_id));
+
+        server.update(Duration::from_secs(1));
+        assert_eq!(server.time_until_timeout(client_id), Some(Duration::ZERO));
+        server.update_client(client_id);
+        assert!(!server.is_client_connected(client_id));
+        assert_eq!(server.time_until_timeout(client_id), None);
+    }
+
+    #[test]
+    fn client_info() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, client_id, client_addr).unwrap();
+        assert_eq!(server.client_info(client_id + 1), None);
+
+        let info = server.client_info(client_id).unwrap();
+        assert_eq!(info.socket_id, 0);
+        assert_eq!(info.addr, client_addr);
+        assert!(!info.confirmed);
+
+        // Sending a packet advances the sequence.
+        server.update(Duration::from_secs(1));
+        server.generate_payload_packet(client_id, &[1u8; 10]).unwrap();
+        let sent_info = server.client_info(client_id).unwrap();
+        assert_eq!(sent_info.sequence, info.sequence + 1);
+        assert_eq!(sent_info.last_packet_send_time, Duration::from_secs(1));
+
+        // Receiving a packet confirms the client.
+        server.update(Duration::from_secs(1));
+        let (_, client_packet) = client.generate_payload_packet(&[1u8; 10]).unwrap();
+        server.process_packet(0, client_addr, client_packet);
+        let info = server.client_info(client_id).unwrap();
+        assert!(info.confirmed);
+        assert_eq!(info.last_packet_received_time, Duration::from_secs(2));
+
+        server.disconnect(client_id);
+        assert_eq!(server.client_info(client_id), None);
+    }
+
+    #[test]
+    fn capacity_thresholds() {
+        let mut server = new_server();
+        server.set_capacity_thresholds(vec![CapacityThreshold {
+            fraction: 0.25,
+            hysteresis: 0.1,
+        }]);
+        server.update(Duration::ZERO);
+        assert_eq!(server.get_capacity_event(), None);
+
+        for client_id in 0..4 {
+            let client_addr = SocketAddr::from(([127, 0, 0, 1], 3000 + client_id as u16));
+            connect_client(&mut server, client_id, client_addr).unwrap();
+        }
+        server.update(Duration::ZERO);
+        assert_eq!(server.get_capacity_event(), Some(CapacityEvent::Exceeded { fraction: 0.25 }));
+        assert_eq!(server.get_capacity_event(), None);
+
+        // Dropping a client stays within the hysteresis, so no event is emitted.
+        server.disconnect(3);
+        server.update(Duration::ZERO);
+        assert_eq!(server.get_capacity_event(), None);
+
+        server.disconnect(2);
+        server.update(Duration::ZERO);
+        assert_eq!(server.get_capacity_event(), Some(CapacityEvent::Recovered { fraction: 0.25 }));
+        assert_eq!(server.get_capacity_event(), None);
+    }
+
     #[test]
     fn connect_token_already_used() {
         let mut server = new_server();
@@ -1027,4 +2403,668 @@ mod tests {
         connect_token.address = "127.0.0.1:3001".parse().unwrap();
         assert!(!server.find_or_add_connect_token_entry(connect_token));
     }
+
+    #[test]
+    fn client_id_from_addr() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let other_addr: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        assert_eq!(server.client_id_from_addr(0, client_addr), None);
+
+        connect_client(&mut server, client_id, client_addr).unwrap();
+        assert_eq!(server.client_id_from_addr(0, client_addr), Some(client_id));
+        assert_eq!(server.client_id_from_addr(0, other_addr), None);
+        assert_eq!(server.client_id_from_addr(1, client_addr), None);
+
+        server.disconnect(client_id);
+        assert_eq!(server.client_id_from_addr(0, client_addr), None);
+    }
+
+    #[test]
+    fn disconnect_many() {
+        let mut server = new_server();
+        let mut clients: Vec<NetcodeClient> = (0..4)
+            .map(|i| connect_client(&mut server, i, format!("127.0.0.1:{}", 3000 + i).parse().unwrap()).unwrap())
+            .collect();
+
+        // Unknown and repeated ids are skipped.
+        let results = server.disconnect_many(&[1, 3, 9, 1]);
+        assert_eq!(results.len(), 2);
+        for (result, expected_id) in results.into_iter().zip([1, 3]) {
+            match result {
+                ServerResult::ClientDisconnected {
+                    client_id,
+                    addr,
+                    payload: Some(payload),
+                    ..
+                } => {
+                    assert_eq!(client_id, expected_id);
+                    assert_eq!(addr, format!("127.0.0.1:{}", 3000 + client_id).parse().unwrap());
+                    let client = &mut clients[client_id as usize];
+                    assert!(client.process_packet(payload).is_none());
+                    assert!(!client.is_connected());
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        assert_eq!(server.clients_id(), vec![0, 2]);
+        assert!(clients[0].is_connected());
+        assert!(clients[2].is_connected());
+    }
+
+    #[test]
+    fn unconfirmed_clients() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+        connect_client(&mut server, 5, "127.0.0.1:3001".parse().unwrap()).unwrap();
+        assert_eq!(server.unconfirmed_clients(), vec![4, 5]);
+
+        let (_, packet) = client.generate_payload_packet(&[1, 2, 3]).unwrap();
+        assert!(matches!(
+            server.process_packet(0, client_addr, packet),
+            ServerResult::Payload { client_id: 4, .. }
+        ));
+        assert_eq!(server.unconfirmed_clients(), vec![5]);
+        assert_eq!(server.clients_id(), vec![4, 5]);
+    }
+
+    #[test]
+    fn echo_user_data() {
+        fn connect_with_user_data(server: &mut NetcodeServer, user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> NetcodeClient {
+            let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+            let connect_token = ConnectToken::generate(
+                Duration::ZERO,
+                TEST_PROTOCOL_ID,
+                3,
+                4,
+                5,
+                0,
+                server.addresses(0),
+                Some(user_data),
+                TEST_KEY,
+            )
+            .unwrap();
+            let client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+            complete_handshake(server, client, client_addr).unwrap()
+        }
+
+        let user_data = generate_random_bytes();
+
+        let mut server = new_server();
+        assert!(!server.echo_user_data());
+        let client = connect_with_user_data(&mut server, &user_data);
+        assert_eq!(client.confirmed_user_data(), None);
+
+        let mut server = new_server();
+        server.set_echo_user_data(true);
+        let mut client = connect_with_user_data(&mut server, &user_data);
+        assert_eq!(client.confirmed_user_data(), Some(user_data));
+
+        // Keep-alives only carry the user data until the client is confirmed.
+        let ServerResult::PacketToSend { payload, .. } = server.send_keepalive_now(4) else {
+            unreachable!()
+        };
+        let unconfirmed_len = payload.len();
+        let (_, packet) = client.generate_payload_packet(&[1, 2, 3]).unwrap();
+        server.process_packet(0, "127.0.0.1:3000".parse().unwrap(), packet);
+        assert!(server.unconfirmed_clients().is_empty());
+        let ServerResult::PacketToSend { payload, .. } = server.send_keepalive_now(4) else {
+            unreachable!()
+        };
+        assert_eq!(payload.len() + NETCODE_USER_DATA_BYTES, unconfirmed_len);
+    }
+
+    #[test]
+    fn invalid_socket_id() {
+        let mut server = new_server();
+        let client_id = 4;
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        connect_client(&mut server, client_id, client_addr).unwrap();
+
+        let mut client = new_client_with_addresses(&server, client_id + 1, 0, server.addresses(0), TEST_KEY);
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        let mut packet = client_packet.to_vec();
+
+        for socket_id in [1, usize::MAX] {
+            let result = server.process_packet(socket_id, client_addr, &mut packet);
+            assert!(matches!(result, ServerResult::Error { socket_id: id, addr } if id == socket_id && addr == client_addr));
+        }
+        assert!(server.is_client_connected(client_id));
+        assert_eq!(server.connected_clients(), 1);
+    }
+
+    #[test]
+    fn shared_token_across_sockets() {
+        let mut server = new_server();
+        let ipv6_addr: SocketAddr = "[::1]:5000".parse().unwrap();
+        server.add_socket(ServerSocketConfig::new(vec![ipv6_addr]));
+        server.add_socket(ServerSocketConfig::new(vec!["127.0.0.1:6000".parse().unwrap()]));
+
+        // The token is for socket 0 but also lists the address of socket 1.
+        let mut addresses = server.addresses(0);
+        addresses.push(ipv6_addr);
+        let mut client = new_client_with_addresses(&server, 0, 0, addresses, TEST_KEY);
+        let request = client.update(Duration::ZERO).unwrap().0.to_vec();
+        let client_addr: SocketAddr = "[::1]:3000".parse().unwrap();
+
+        // Both sockets must opt in.
+        assert!(matches!(
+            server.process_packet(1, client_addr, &mut request.clone()),
+            ServerResult::Error { socket_id: 1, .. }
+        ));
+        server.set_shares_connect_tokens(1, true);
+        assert!(matches!(
+            server.process_packet(1, client_addr, &mut request.clone()),
+            ServerResult::Error { socket_id: 1, .. }
+        ));
+        server.set_shares_connect_tokens(0, true);
+
+        // Sockets that aren't listed in the token can't be used.
+        server.set_shares_connect_tokens(2, true);
+        assert!(matches!(
+            server.process_packet(2, client_addr, &mut request.clone()),
+            ServerResult::Error { socket_id: 2, .. }
+        ));
+
+        assert!(matches!(
+            server.process_packet(1, client_addr, &mut request.clone()),
+            ServerResult::ConnectionAccepted { socket_id: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn max_concurrent_handshakes() {
+        let mut socket_config = ServerSocketConfig::new(vec!["127.0.0.1:5000".parse().unwrap()]);
+        socket_config.max_concurrent_handshakes = Some(2);
+        let mut server = NetcodeServer::new(ServerConfig {
+            sockets: vec![socket_config, ServerSocketConfig::new(vec!["127.0.0.1:5001".parse().unwrap()])],
+            ..test_config()
+        });
+
+        fn request_connection(server: &mut NetcodeServer, socket_id: usize, client_id: u64) -> Result<(), DenialReason> {
+            let client_addr = SocketAddr::from(([127, 0, 0, 1], 3000 + client_id as u16));
+            match server.process_packet(socket_id, client_addr, &mut connection_request(server, client_id, socket_id)) {
+                ServerResult::ConnectionAccepted { .. } => Ok(()),
+                ServerResult::ConnectionDenied { reason, .. } => Err(reason),
+                _ => unreachable!(),
+            }
+        }
+
+        // Flood the first socket.
+        assert!(request_connection(&mut server, 0, 0).is_ok());
+        assert!(request_connection(&mut server, 0, 1).is_ok());
+        assert!(matches!(
+            request_connection(&mut server, 0, 2),
+            Err(DenialReason::TooManyPendingClients)
+        ));
+
+        // The second socket still accepts handshakes.
+        assert!(request_connection(&mut server, 1, 3).is_ok());
+        assert!(request_connection(&mut server, 1, 4).is_ok());
+        assert!(request_connection(&mut server, 1, 5).is_ok());
+
+        assert_eq!(server.pending_count_on_socket(0), 2);
+        assert_eq!(server.pending_count_on_socket(1), 3);
+        assert_eq!(server.pending_count_on_socket(2), 0);
+
+        server.set_max_concurrent_handshakes(0, None);
+        assert!(request_connection(&mut server, 0, 6).is_ok());
+        assert_eq!(server.pending_count_on_socket(0), 3);
+
+        // Counts drop as pending clients are removed.
+        server.ban_client(6);
+        assert_eq!(server.pending_count_on_socket(0), 2);
+        server.update(Duration::from_secs(4));
+        assert_eq!(server.pending_count_on_socket(0), 0);
+        assert_eq!(server.pending_count_on_socket(1), 0);
+    }
+
+    /// Makes a client with a connect token for the given socket of the server that lists the given server addresses.
+    fn new_client_with_addresses(
+        server: &NetcodeServer,
+        client_id: u64,
+        socket_id: usize,
+        addresses: Vec<SocketAddr>,
+        key: &[u8; NETCODE_KEY_BYTES],
+    ) -> NetcodeClient {
+        let connect_token = ConnectToken::generate(
+            server.current_time(),
+            TEST_PROTOCOL_ID,
+            3,
+            client_id,
+            5,
+            socket_id as u8,
+            addresses,
+            None,
+            key,
+        )
+        .unwrap();
+        NetcodeClient::new(server.current_time(), ClientAuthentication::Secure { connect_token }).unwrap()
+    }
+
+    /// Returns the connection request packet of a new client for the given socket of the server.
+    fn connection_request(server: &NetcodeServer, client_id: u64, socket_id: usize) -> Vec<u8> {
+        let mut client = new_client_with_addresses(server, client_id, socket_id, server.addresses(socket_id), TEST_KEY);
+        client.update(Duration::ZERO).unwrap().0.to_vec()
+    }
+
+    /// Connects a new client to the first socket of the server, or returns why it was denied.
+    fn connect_client(server: &mut NetcodeServer, client_id: u64, client_addr: SocketAddr) -> Result<NetcodeClient, DenialReason> {
+        let client = new_client_with_addresses(server, client_id, 0, server.addresses(0), TEST_KEY);
+        complete_handshake(server, client, client_addr)
+    }
+
+    /// Runs the handshake of the client with the first socket of the server, or returns why it was denied.
+    fn complete_handshake(
+        server: &mut NetcodeServer,
+        mut client: NetcodeClient,
+        client_addr: SocketAddr,
+    ) -> Result<NetcodeClient, DenialReason> {
+        for _ in 0..2 {
+            let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+            match server.process_packet(0, client_addr, client_packet) {
+                ServerResult::ConnectionAccepted { payload, .. } | ServerResult::ClientConnected { payload, .. } => {
+                    client.process_packet(payload);
+                }
+                ServerResult::ConnectionDenied { reason, .. } => return Err(reason),
+                _ => unreachable!(),
+            }
+        }
+        assert!(client.is_connected());
+
+        Ok(client)
+    }
+
+    #[test]
+    fn connection_filter() {
+        let mut server = new_server();
+        server.set_connection_filter(|client_id, _, user_data| client_id != 2 && user_data != &[1; NETCODE_USER_DATA_BYTES]);
+
+        connect_client(&mut server, 1, "127.0.0.1:3001".parse().unwrap()).unwrap();
+        let result = connect_client(&mut server, 2, "127.0.0.1:3002".parse().unwrap());
+        assert_eq!(result.err(), Some(DenialReason::Filtered));
+        assert!(server.pending_clients.is_empty());
+
+        // Denied clients are notified.
+        let user_data = [1u8; NETCODE_USER_DATA_BYTES];
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID,
+            3,
+            3,
+            5,
+            0,
+            server.addresses(0),
+            Some(&user_data),
+            TEST_KEY,
+        )
+        .unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(0, "127.0.0.1:3003".parse().unwrap(), client_packet) {
+            ServerResult::ConnectionDenied { payload, reason, .. } => {
+                assert_eq!(reason, DenialReason::Filtered);
+                client.process_packet(payload.unwrap());
+            }
+            _ => unreachable!(),
+        }
+        assert!(client.is_disconnected());
+
+        server.remove_connection_filter();
+        connect_client(&mut server, 2, "127.0.0.1:3002".parse().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn handshake_timings() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = new_client_with_addresses(&server, 4, 0, server.addresses(0), TEST_KEY);
+        assert_eq!(client.handshake_timings(), None);
+
+        let (client_packet, _) = client.update(Duration::from_millis(10)).unwrap();
+        let ServerResult::ConnectionAccepted { payload, .. } = server.process_packet(0, client_addr, client_packet) else {
+            unreachable!();
+        };
+        assert!(client.update(Duration::from_millis(20)).is_none());
+        client.process_packet(payload);
+        assert_eq!(client.handshake_timings(), None);
+
+        let (client_packet, _) = client.update(Duration::from_millis(15)).unwrap();
+        let ServerResult::ClientConnected { payload, .. } = server.process_packet(0, client_addr, client_packet) else {
+            unreachable!();
+        };
+        assert!(client.update(Duration::from_millis(5)).is_none());
+        client.process_packet(payload);
+        assert!(client.is_connected());
+
+        let timings = client.handshake_timings().unwrap();
+        assert_eq!(
+            timings,
+            HandshakeTimings {
+                request_sent: Duration::from_millis(10),
+                challenge_received: Duration::from_millis(30),
+                response_sent: Duration::from_millis(45),
+                keep_alive_received: Duration::from_millis(50),
+            }
+        );
+        assert_eq!(timings.total(), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn pending_clients() {
+        let mut server = new_server();
+        assert_eq!(server.pending_clients_count(), 0);
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = new_client_with_addresses(&server, 4, 0, server.addresses(0), TEST_KEY);
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        let ServerResult::ConnectionAccepted { payload, .. } = server.process_packet(0, client_addr, client_packet) else {
+            unreachable!();
+        };
+        client.process_packet(payload);
+        assert_eq!(server.pending_clients_count(), 1);
+        assert_eq!(server.pending_clients_iter().collect::<Vec<_>>(), vec![(0, client_addr)]);
+
+        server.update(Duration::from_millis(500));
+        assert_eq!(
+            server.pending_clients_info(),
+            vec![PendingClientInfo {
+                socket_id: 0,
+                addr: client_addr,
+                client_id: 4,
+                time_in_pending: Duration::from_millis(500),
+                time_since_last_packet: Duration::from_millis(500),
+                state: PendingClientState::AwaitingResponse,
+            }]
+        );
+
+        // Pending clients are removed once connected.
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        server.process_packet(0, client_addr, client_packet);
+        assert_eq!(server.pending_clients_count(), 0);
+        assert_eq!(server.pending_clients_iter().count(), 0);
+    }
+
+    #[test]
+    fn reserved_slot_claim() {
+        let mut server = new_server();
+        server.set_max_clients(2);
+        server.reserve_slot(10, Duration::from_secs(30));
+        assert_eq!(server.reserved_slots(), 1);
+
+        connect_client(&mut server, 1, "127.0.0.1:3001".parse().unwrap()).unwrap();
+
+        // Only the reserved slot is free.
+        let result = connect_client(&mut server, 2, "127.0.0.1:3002".parse().unwrap());
+        assert_eq!(result.err(), Some(DenialReason::ServerFull));
+
+        // The reserved client claims its slot.
+        connect_client(&mut server, 10, "127.0.0.1:3010".parse().unwrap()).unwrap();
+        assert!(server.is_client_connected(10));
+        assert_eq!(server.reserved_slots(), 0);
+        assert_eq!(server.connected_clients(), 2);
+
+        // Connected clients can't reserve slots.
+        server.reserve_slot(1, Duration::from_secs(30));
+        assert_eq!(server.reserved_slots(), 0);
+    }
+
+    #[test]
+    fn reserved_slot_expiry() {
+        let mut server = new_server();
+        server.set_max_clients(1);
+        server.reserve_slot(10, Duration::from_secs(5));
+        server.reserve_slot(11, Duration::from_secs(10));
+
+        let result = connect_client(&mut server, 1, "127.0.0.1:3001".parse().unwrap());
+        assert_eq!(result.err(), Some(DenialReason::ServerFull));
+
+        // Slots reserved beyond max_clients don't guarantee a connection.
+        let result = connect_client(&mut server, 10, "127.0.0.1:3010".parse().unwrap());
+        assert_eq!(result.err(), Some(DenialReason::ServerFull));
+        assert!(server.cancel_slot_reservation(11));
+        assert!(!server.cancel_slot_reservation(11));
+
+        // The slot is freed once the reservation expires.
+        server.update(Duration::from_secs(4));
+        assert_eq!(server.reserved_slots(), 1);
+        server.update(Duration::from_secs(1));
+        assert_eq!(server.reserved_slots(), 0);
+        connect_client(&mut server, 1, "127.0.0.1:3002".parse().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn randomize_initial_sequence() {
+        let mut server = NetcodeServer::new(ServerConfig {
+            randomize_initial_sequence: true,
+            ..test_config()
+        });
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+        let initial_sequence = find_client_by_id(&server.clients, 4).unwrap().sequence;
+        assert!(initial_sequence > 1 << 8);
+        assert!(initial_sequence < 1 << 62);
+
+        for _ in 0..3 {
+            let (_, _, packet) = server.generate_payload_packet(4, b"server").unwrap();
+            assert_eq!(client.process_packet(packet), Some(&b"server"[..]));
+
+            let (_, packet) = client.generate_payload_packet(b"client").unwrap();
+            assert_eq!(
+                server.process_packet(0, client_addr, packet),
+                ServerResult::Payload {
+                    client_id: 4,
+                    payload: &b"client"[..]
+                }
+            );
+        }
+        assert!(client.is_connected());
+    }
+
+    #[test]
+    fn ban_list() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+
+        // Banning a connected client disconnects it.
+        let ServerResult::ClientDisconnected { client_id, payload, .. } = server.ban_client(4) else {
+            unreachable!();
+        };
+        assert_eq!(client_id, 4);
+        client.process_packet(payload.unwrap());
+        assert!(client.is_disconnected());
+        assert_eq!(connect_client(&mut server, 4, client_addr).err(), Some(DenialReason::Banned));
+
+        assert!(server.unban_client(4));
+        assert!(!server.unban_client(4));
+        connect_client(&mut server, 4, client_addr).unwrap();
+
+        // Packets from banned addresses are dropped.
+        let banned_addr: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        server.ban_addr(banned_addr);
+        let mut client = new_client_with_addresses(&server, 5, 0, server.addresses(0), TEST_KEY);
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        assert_eq!(server.process_packet(0, banned_addr, client_packet), ServerResult::None);
+        assert_eq!(server.pending_clients_count(), 0);
+
+        assert!(server.unban_addr(banned_addr));
+        connect_client(&mut server, 5, banned_addr).unwrap();
+    }
+
+    #[test]
+    fn disconnect_with_reason() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+
+        let ServerResult::ClientDisconnected { payload, .. } = server.disconnect_with_reason(4, 3) else {
+            unreachable!();
+        };
+        client.process_packet(payload.unwrap());
+        assert_eq!(
+            client.disconnect_reason(),
+            Some(DisconnectReason::DisconnectedByServer { reason: 3 })
+        );
+
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+        let ServerResult::ClientDisconnected { payload, .. } = server.disconnect(4) else {
+            unreachable!();
+        };
+        client.process_packet(payload.unwrap());
+        assert_eq!(
+            client.disconnect_reason(),
+            Some(DisconnectReason::DisconnectedByServer { reason: 0 })
+        );
+    }
+
+    #[test]
+    fn add_socket() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+
+        let socket_addr: SocketAddr = "127.0.0.1:6000".parse().unwrap();
+        let socket_id = server.add_socket(ServerSocketConfig::new(vec![socket_addr]));
+        assert_eq!(socket_id, 1);
+        assert_eq!(server.addresses(socket_id), vec![socket_addr]);
+
+        // Clients can connect to the new socket.
+        let mut new_client = new_client_with_addresses(&server, 5, 1, vec![socket_addr], TEST_KEY);
+        let (client_packet, _) = new_client.update(Duration::ZERO).unwrap();
+        let ServerResult::ConnectionAccepted { socket_id, payload, .. } = server.process_packet(1, client_addr, client_packet) else {
+            unreachable!();
+        };
+        assert_eq!(socket_id, 1);
+        new_client.process_packet(payload);
+        let (client_packet, _) = new_client.update(Duration::ZERO).unwrap();
+        let ServerResult::ClientConnected { payload, .. } = server.process_packet(1, client_addr, client_packet) else {
+            unreachable!();
+        };
+        new_client.process_packet(payload);
+        assert!(new_client.is_connected());
+        assert_eq!(server.client_addr(5), Some((1, client_addr)));
+
+        // Existing connections are not affected.
+        assert_eq!(server.client_addr(4), Some((0, client_addr)));
+        let (_, _, packet) = server.generate_payload_packet(4, b"server").unwrap();
+        assert_eq!(client.process_packet(packet), Some(&b"server"[..]));
+    }
+
+    #[test]
+    fn connection_rate_limit() {
+        let mut server = NetcodeServer::new(ServerConfig {
+            connection_rate_limit: Some((2, Duration::from_secs(1))),
+            ..test_config()
+        });
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut request = connection_request(&server, 1, 0);
+
+        for _ in 0..2 {
+            assert!(matches!(
+                server.process_packet(0, client_addr, &mut request.clone()),
+                ServerResult::ConnectionAccepted { .. }
+            ));
+        }
+        // Requests over the limit are dropped silently.
+        assert_eq!(server.process_packet(0, client_addr, &mut request.clone()), ServerResult::None);
+
+        // Other addresses are limited separately.
+        let other_addr: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        assert!(matches!(
+            server.process_packet(0, other_addr, &mut connection_request(&server, 2, 0)),
+            ServerResult::ConnectionAccepted { .. }
+        ));
+
+        // The limit resets after the window.
+        server.update(Duration::from_secs(1));
+        assert!(server.connection_request_counts.is_empty());
+        let result = server.process_packet(0, client_addr, &mut request);
+        assert!(matches!(result, ServerResult::ConnectionAccepted { .. }));
+
+        // New addresses evict the oldest tracked address when too many addresses are tracked, and are still limited.
+        server.connection_request_counts.clear();
+        let oldest_addr = SocketAddr::from(([10, 0, 0, 1], 0));
+        server.connection_request_counts.insert(oldest_addr, (u32::MAX, Duration::ZERO));
+        for port in 1..NETCODE_MAX_RATE_LIMITED_ADDRESSES as u16 {
+            let addr = SocketAddr::from(([10, 0, 0, 1], port));
+            server.connection_request_counts.insert(addr, (u32::MAX, server.current_time()));
+        }
+        let request = connection_request(&server, 3, 0);
+        for _ in 0..2 {
+            assert!(matches!(
+                server.process_packet(0, other_addr, &mut request.clone()),
+                ServerResult::ConnectionAccepted { .. }
+            ));
+        }
+        assert_eq!(server.process_packet(0, other_addr, &mut request.clone()), ServerResult::None);
+        assert_eq!(server.connection_request_counts.len(), NETCODE_MAX_RATE_LIMITED_ADDRESSES);
+        assert!(!server.connection_request_counts.contains_key(&oldest_addr));
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn extension_packets() {
+        use std::sync::{Arc, Mutex};
+
+        type Received = Vec<(u64, Vec<u8>)>;
+
+        #[derive(Debug, Default, Clone)]
+        struct Recorder(Arc<Mutex<Received>>);
+
+        impl PacketExtension for Recorder {
+            fn receive(&mut self, client_id: u64, payload: &[u8]) {
+                self.0.lock().unwrap().push((client_id, payload.to_vec()));
+            }
+        }
+
+        let mut server = new_server();
+        let server_received = Recorder::default();
+        server.register_extension(8, server_received.clone()).unwrap();
+        // Built-in packet types can't be overridden.
+        assert!(matches!(
+            server.register_extension(crate::PacketType::Disconnect as u8, Recorder::default()),
+            Err(NetcodeError::InvalidPacketType)
+        ));
+
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let mut client = connect_client(&mut server, 4, client_addr).unwrap();
+        let client_received = Recorder::default();
+        client.register_extension(15, client_received.clone()).unwrap();
+
+        // Client to server.
+        let (_, packet) = client.generate_extension_packet(8, b"hello").unwrap();
+        let mut replayed = packet.to_vec();
+        assert_eq!(server.process_packet(0, client_addr, packet), ServerResult::None);
+        assert_eq!(*server_received.0.lock().unwrap(), vec![(4, b"hello".to_vec())]);
+
+        // Extension packets are replay-protected.
+        assert_eq!(
+            server.process_packet(0, client_addr, &mut replayed),
+            ServerResult::Error {
+                socket_id: 0,
+                addr: client_addr
+            }
+        );
+
+        // Packets without a registered extension are dropped.
+        let (_, packet) = client.generate_extension_packet(9, b"dropped").unwrap();
+        assert_eq!(server.process_packet(0, client_addr, packet), ServerResult::None);
+        assert_eq!(server_received.0.lock().unwrap().len(), 1);
+
+        // Server to client.
+        let (_, _, packet) = server.generate_extension_packet(4, 15, b"world").unwrap();
+        assert_eq!(client.process_packet(packet), None);
+        assert_eq!(*client_received.0.lock().unwrap(), vec![(4, b"world".to_vec())]);
+
+        assert!(matches!(
+            server.generate_extension_packet(4, 7, b""),
+            Err(NetcodeError::InvalidPacketType)
+        ));
+    }
 }
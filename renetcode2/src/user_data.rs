@@ -0,0 +1,132 @@
+use std::{error::Error, fmt};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::NETCODE_USER_DATA_BYTES;
+
+/// Typed wrapper for the user data embedded in connect tokens.
+///
+/// Values are serialized with `bincode` into a fixed buffer of [`NETCODE_USER_DATA_BYTES`] bytes, the rest of the
+/// buffer is zeroed. Values that serialize to more than [`NETCODE_USER_DATA_BYTES`] bytes are rejected.
+///
+/// Pass [`Self::as_bytes`] to [`ConnectToken::generate`](crate::ConnectToken::generate), then read the value on the
+/// server from the `user_data` of [`ServerResult::ClientConnected`](crate::ServerResult::ClientConnected) with
+/// [`Self::to_value`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserData([u8; NETCODE_USER_DATA_BYTES]);
+
+impl UserData {
+    /// Serializes a value into user data.
+    pub fn from_value<T: Serialize>(value: &T) -> Result<Self, UserDataError> {
+        let size = bincode::serialized_size(value).map_err(UserDataError::Serialization)?;
+        if size > NETCODE_USER_DATA_BYTES as u64 {
+            return Err(UserDataError::TooLarge { size });
+        }
+
+        let mut bytes = [0u8; NETCODE_USER_DATA_BYTES];
+        bincode::serialize_into(&mut bytes[..], value).map_err(UserDataError::Serialization)?;
+        Ok(Self(bytes))
+    }
+
+    /// Deserializes the value stored in the user data.
+    pub fn to_value<T: DeserializeOwned>(&self) -> Result<T, UserDataError> {
+        bincode::deserialize(&self.0).map_err(UserDataError::Serialization)
+    }
+
+    /// Returns the raw user data.
+    pub fn as_bytes(&self) -> &[u8; NETCODE_USER_DATA_BYTES] {
+        &self.0
+    }
+}
+
+impl From<[u8; NETCODE_USER_DATA_BYTES]> for UserData {
+    fn from(bytes: [u8; NETCODE_USER_DATA_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<UserData> for [u8; NETCODE_USER_DATA_BYTES] {
+    fn from(user_data: UserData) -> Self {
+        user_data.0
+    }
+}
+
+/// Errors from converting values to and from [`UserData`].
+#[derive(Debug)]
+pub enum UserDataError {
+    /// The serialized value is larger than [`NETCODE_USER_DATA_BYTES`].
+    TooLarge { size: u64 },
+    /// The value could not be serialized or deserialized.
+    Serialization(bincode::Error),
+}
+
+impl Error for UserDataError {}
+
+impl fmt::Display for UserDataError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use UserDataError::*;
+
+        match *self {
+            TooLarge { size } => write!(fmt, "user data is {size} bytes, the maximum is {NETCODE_USER_DATA_BYTES}"),
+            Serialization(ref err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{ConnectToken, NETCODE_KEY_BYTES};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Player {
+        name: String,
+        level: u32,
+    }
+
+    #[test]
+    fn round_trip() {
+        let player = Player {
+            name: "player".into(),
+            level: 7,
+        };
+        let user_data = UserData::from_value(&player).unwrap();
+
+        let private_key = [1u8; NETCODE_KEY_BYTES];
+        let addresses = vec!["127.0.0.1:5000".parse().unwrap()];
+        assert!(ConnectToken::generate(
+            Duration::ZERO,
+            0,
+            300,
+            1,
+            15,
+            0,
+            addresses,
+            Some(user_data.as_bytes()),
+            &private_key
+        )
+        .is_ok());
+
+        let user_data = UserData::from(<[u8; NETCODE_USER_DATA_BYTES]>::from(user_data));
+        assert_eq!(user_data.to_value::<Player>().unwrap(), player);
+    }
+
+    #[test]
+    fn too_large() {
+        assert!(UserData::from_value(&vec![0u8; NETCODE_USER_DATA_BYTES - 8]).is_ok());
+        assert!(matches!(
+            UserData::from_value(&vec![0u8; NETCODE_USER_DATA_BYTES - 7]),
+            Err(UserDataError::TooLarge { size }) if size == NETCODE_USER_DATA_BYTES as u64 + 1
+        ));
+    }
+
+    #[test]
+    fn invalid_value() {
+        let user_data = UserData::from_value(&u8::MAX).unwrap();
+        assert!(matches!(user_data.to_value::<bool>(), Err(UserDataError::Serialization(_))));
+    }
+}